@@ -0,0 +1,44 @@
+use serde_json::{json, Value};
+
+/// What a resolved code lens of rust-analyzer does: `test` or `run` for the runnables, `debug`,
+/// `implementation` or `reference`, or `None` for commands it isn't known for.
+pub fn code_lens_kind(lens: &Value) -> Option<&'static str> {
+    let command = &lens["command"];
+    match command["command"].as_str()? {
+        "rust-analyzer.runSingle" => {
+            let runnable = &command["arguments"][0];
+            let is_test = runnable["args"]["cargoArgs"][0] == "test"
+                || runnable["label"]
+                    .as_str()
+                    .is_some_and(|label| label.starts_with("test") || label.starts_with("doctest"));
+            Some(if is_test { "test" } else { "run" })
+        }
+        "rust-analyzer.debugSingle" => Some("debug"),
+        // Both lenses show locations, only their titles tell them apart, e.g. `3 implementations`.
+        "rust-analyzer.showReferences" | "rust-analyzer.gotoLocation" => {
+            let title = command["title"].as_str().unwrap_or_default();
+            Some(if title.contains("implementation") {
+                "implementation"
+            } else {
+                "reference"
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Format a resolved code lens compactly, with the position it's shown at rather than its range.
+pub fn format_code_lens(lens: &Value) -> Value {
+    let command = &lens["command"];
+
+    json!({
+        "kind": code_lens_kind(lens),
+        "range": {
+            "line": lens["range"]["start"]["line"],
+            "character": lens["range"]["start"]["character"]
+        },
+        "title": command["title"],
+        "command": command["command"],
+        "arguments": command.get("arguments").cloned().unwrap_or_else(|| json!([]))
+    })
+}
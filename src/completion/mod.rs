@@ -0,0 +1,75 @@
+use serde_json::{json, Value};
+
+use crate::config::COMPLETION_DOC_CHARS;
+
+/// Names of the LSP `CompletionItemKind`s, the kind number being the index plus one.
+const COMPLETION_ITEM_KINDS: [&str; 25] = [
+    "text",
+    "method",
+    "function",
+    "constructor",
+    "field",
+    "variable",
+    "class",
+    "interface",
+    "module",
+    "property",
+    "unit",
+    "value",
+    "enum",
+    "keyword",
+    "snippet",
+    "color",
+    "file",
+    "reference",
+    "folder",
+    "enum_member",
+    "constant",
+    "struct",
+    "event",
+    "operator",
+    "type_parameter",
+];
+
+/// The name of an LSP `CompletionItemKind`, e.g. `struct` for 22.
+pub fn completion_kind_name(kind: u64) -> Option<&'static str> {
+    let index = usize::try_from(kind).ok()?.checked_sub(1)?;
+    COMPLETION_ITEM_KINDS.get(index).copied()
+}
+
+/// Reduce a completion item to the fields worth showing: `label`, `kind_name`, `detail`,
+/// `documentation`, `insert_text` and `sort_text`.
+///
+/// The documentation, either a string or a `MarkupContent`, becomes plain text of at most
+/// [`COMPLETION_DOC_CHARS`] chars. rust-analyzer usually sends the inserted text as a `textEdit`,
+/// whose new text is used in the absence of `insertText`.
+pub fn structured_completion_item(item: &Value) -> Value {
+    let documentation = match &item["documentation"] {
+        Value::String(text) => Some(text.as_str()),
+        Value::Object(markup) => markup.get("value").and_then(Value::as_str),
+        _ => None,
+    };
+    let insert_text = item["insertText"]
+        .as_str()
+        .or_else(|| item["textEdit"]["newText"].as_str());
+
+    json!({
+        "label": item["label"],
+        "kind_name": item["kind"].as_u64().and_then(completion_kind_name),
+        "detail": item["detail"],
+        "documentation": documentation.map(|text| truncate_chars(text.trim(), COMPLETION_DOC_CHARS)),
+        "insert_text": insert_text,
+        "sort_text": item["sortText"]
+    })
+}
+
+/// Truncate `text` to `max` chars, ending it with an ellipsis if anything was cut.
+fn truncate_chars(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+
+    let mut truncated: String = text.chars().take(max.saturating_sub(1)).collect();
+    truncated.push('…');
+    truncated
+}
@@ -0,0 +1,91 @@
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use super::{CheckCommand, LspTimeouts, ServerOptions};
+
+/// Name of the configuration file, looked up in the workspace root.
+pub const CONFIG_FILE_NAME: &str = ".rust-analyzer-mcp.toml";
+
+/// Settings read from the `.rust-analyzer-mcp.toml` file in the workspace root.
+///
+/// Every setting is optional; unset ones keep their current value when applied.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigFile {
+    /// Path to the rust-analyzer binary. Relative paths are resolved against the workspace root.
+    pub rust_analyzer_path: Option<PathBuf>,
+    pub check_command: Option<CheckCommand>,
+    pub request_timeout_secs: Option<u64>,
+    pub document_open_delay_ms: Option<u64>,
+    pub diagnostic_poll_interval_ms: Option<u64>,
+    pub compact_responses: Option<bool>,
+    pub log_lsp_messages: Option<bool>,
+    pub response_cache_capacity: Option<usize>,
+    pub max_open_documents: Option<usize>,
+    /// Timeouts per request category, from a `[timeouts]` table. Categories missing from the
+    /// table keep their default timeout.
+    pub timeouts: Option<LspTimeouts>,
+}
+
+impl ConfigFile {
+    /// Load the configuration file from `workspace_root`, if there's one.
+    pub fn load(workspace_root: &Path) -> Result<Self> {
+        let path = workspace_root.join(CONFIG_FILE_NAME);
+        let content = match std::fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(anyhow!("Failed to read {}: {}", path.display(), e)),
+        };
+
+        let mut config = Self::parse(&content)
+            .map_err(|e| anyhow!("Failed to parse {}: {}", path.display(), e))?;
+        if let Some(rust_analyzer_path) = &config.rust_analyzer_path {
+            config.rust_analyzer_path = Some(workspace_root.join(rust_analyzer_path));
+        }
+
+        Ok(config)
+    }
+
+    /// Parse the content of a configuration file.
+    pub fn parse(content: &str) -> Result<Self> {
+        Ok(toml::from_str(content)?)
+    }
+
+    /// Override `options` with the settings set in this configuration.
+    pub fn apply(&self, options: &mut ServerOptions) {
+        if let Some(rust_analyzer_path) = &self.rust_analyzer_path {
+            options.rust_analyzer_path = Some(rust_analyzer_path.clone());
+        }
+        if let Some(check_command) = self.check_command {
+            options.config.check_on_save = check_command;
+        }
+        if let Some(secs) = self.request_timeout_secs {
+            options.config.lsp_request_timeout = Duration::from_secs(secs);
+        }
+        if let Some(millis) = self.document_open_delay_ms {
+            options.config.document_open_delay = Duration::from_millis(millis);
+        }
+        if let Some(millis) = self.diagnostic_poll_interval_ms {
+            options.config.diagnostics_poll_interval = Duration::from_millis(millis);
+        }
+        if let Some(compact_responses) = self.compact_responses {
+            options.compact_responses = compact_responses;
+        }
+        if let Some(log_lsp_messages) = self.log_lsp_messages {
+            options.log_lsp_messages = log_lsp_messages;
+        }
+        if let Some(capacity) = self.response_cache_capacity {
+            options.config.response_cache_capacity = capacity;
+        }
+        if let Some(max) = self.max_open_documents {
+            options.config.max_open_documents = max;
+        }
+        if let Some(timeouts) = self.timeouts {
+            options.config.timeouts = timeouts;
+        }
+    }
+}
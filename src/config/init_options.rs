@@ -0,0 +1,58 @@
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+/// Name of the file with extra rust-analyzer initialization options, looked up in the workspace
+/// root.
+pub const INIT_OPTIONS_FILE_NAME: &str = ".ra-mcp.json";
+
+/// Load the user's rust-analyzer initialization options.
+///
+/// They're read from the JSON file pointed to by the `RA_MCP_INIT_OPTIONS` environment variable if
+/// set, or else from the `.ra-mcp.json` file in `workspace_root`, if there's one. The options must
+/// be a JSON object.
+pub fn load_init_options(workspace_root: &Path) -> Result<Option<Value>> {
+    let path = match std::env::var_os("RA_MCP_INIT_OPTIONS").filter(|path| !path.is_empty()) {
+        Some(path) => PathBuf::from(path),
+        None => {
+            let path = workspace_root.join(INIT_OPTIONS_FILE_NAME);
+            if !path.exists() {
+                return Ok(None);
+            }
+            path
+        }
+    };
+
+    let content = std::fs::read_to_string(&path)
+        .map_err(|e| anyhow!("Failed to read {}: {}", path.display(), e))?;
+    let options: Value = serde_json::from_str(&content)
+        .map_err(|e| anyhow!("Failed to parse {}: {}", path.display(), e))?;
+    if !options.is_object() {
+        return Err(anyhow!(
+            "Invalid {}: initialization options must be a JSON object",
+            path.display()
+        ));
+    }
+
+    Ok(Some(options))
+}
+
+/// Deep-merge `overlay` into `base`.
+///
+/// Objects are merged key by key, so keys only set in `base` are preserved. Any other value in
+/// `overlay`, including arrays, replaces the one in `base`.
+pub fn merge_json(base: &mut Value, overlay: &Value) {
+    match (base, overlay) {
+        (Value::Object(base), Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(key) {
+                    Some(base_value) => merge_json(base_value, value),
+                    None => {
+                        base.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay.clone(),
+    }
+}
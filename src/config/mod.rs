@@ -0,0 +1,344 @@
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use serde_json::Value;
+use std::{path::PathBuf, str::FromStr, time::Duration};
+
+mod file;
+mod init_options;
+
+pub use file::{ConfigFile, CONFIG_FILE_NAME};
+pub use init_options::{load_init_options, merge_json, INIT_OPTIONS_FILE_NAME};
+
+/// Default timeout for LSP requests in seconds.
+pub const LSP_REQUEST_TIMEOUT_SECS: u64 = 30;
+
+/// Delay after opening a document to allow rust-analyzer to process it.
+pub const DOCUMENT_OPEN_DELAY_MILLIS: u64 = 200;
+
+/// Default time to wait for diagnostics to be published for a file.
+pub const DIAGNOSTICS_WAIT_MILLIS: u64 = 3000;
+
+/// Interval between checks while waiting for diagnostics.
+pub const DIAGNOSTICS_POLL_INTERVAL_MILLIS: u64 = 500;
+
+/// Maximum time to wait for indexing to finish after a workspace reload.
+pub const WORKSPACE_RELOAD_WAIT_SECS: u64 = 120;
+
+/// Time given to rust-analyzer to start indexing after a workspace reload.
+pub const WORKSPACE_RELOAD_START_MILLIS: u64 = 2000;
+
+/// Default maximum time `rust_analyzer_wait_for_analysis` waits for indexing and cargo check.
+pub const ANALYSIS_WAIT_MILLIS: u64 = 60_000;
+
+/// Interval between checks of rust-analyzer's progress.
+pub const PROGRESS_POLL_INTERVAL_MILLIS: u64 = 250;
+
+/// Maximum number of hovers collected by a single `rust_analyzer_hover_range` call.
+pub const MAX_RANGE_HOVERS: usize = 50;
+
+/// Default number of completion items returned by `rust_analyzer_completion`.
+pub const MAX_COMPLETION_ITEMS: usize = 50;
+
+/// Default globs of the files opened by `rust_analyzer_index_workspace`.
+pub const INDEX_WORKSPACE_GLOBS: [&str; 2] = ["src/**/*.rs", "tests/**/*.rs"];
+
+/// Default number of errors returned by `rust_analyzer_workspace_errors`.
+pub const WORKSPACE_ERRORS_LIMIT: usize = 20;
+
+/// Number of top completion items resolved to fill in their documentation and detail.
+pub const COMPLETION_RESOLVE_LIMIT: usize = 10;
+
+/// Maximum number of chars of documentation kept per item by structured completions.
+pub const COMPLETION_DOC_CHARS: usize = 200;
+
+/// Maximum size of a file read through `resources/read`.
+pub const MAX_RESOURCE_BYTES: u64 = 1024 * 1024;
+
+/// Default number of tool results kept in the response cache.
+pub const RESPONSE_CACHE_CAPACITY: usize = 100;
+
+/// Default number of documents kept open in rust-analyzer.
+pub const MAX_OPEN_DOCUMENTS: usize = 50;
+
+/// How long file changes must settle before rust-analyzer is told about them.
+pub const WATCH_DEBOUNCE_MILLIS: u64 = 200;
+
+/// Largest message accepted from the MCP client, over HTTP or stdio, so a misbehaving client can't
+/// exhaust memory.
+pub const MAX_MESSAGE_SIZE: usize = 16 * 1024 * 1024;
+
+/// The cargo command rust-analyzer runs on save to produce diagnostics.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckCommand {
+    #[default]
+    Check,
+    Clippy,
+    /// Don't run any check on save.
+    None,
+}
+
+impl FromStr for CheckCommand {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "check" => Ok(Self::Check),
+            "clippy" => Ok(Self::Clippy),
+            "none" => Ok(Self::None),
+            _ => Err(anyhow!(
+                "Invalid check command `{}`, expected `check`, `clippy` or `none`",
+                s
+            )),
+        }
+    }
+}
+
+/// Categories of LSP requests, each with its own timeout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeoutCategory {
+    Hover,
+    Completion,
+    /// Definitions, declarations, references and parent modules.
+    Definition,
+    Symbols,
+    Formatting,
+    Diagnostics,
+    /// Starting rust-analyzer and (re)loading the workspace.
+    Workspace,
+}
+
+impl FromStr for TimeoutCategory {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "hover" => Ok(Self::Hover),
+            "completion" => Ok(Self::Completion),
+            "definition" => Ok(Self::Definition),
+            "symbols" => Ok(Self::Symbols),
+            "formatting" => Ok(Self::Formatting),
+            "diagnostics" => Ok(Self::Diagnostics),
+            "workspace" => Ok(Self::Workspace),
+            _ => Err(anyhow!(
+                "Invalid timeout category `{}`, expected `hover`, `completion`, `definition`, \
+                 `symbols`, `formatting`, `diagnostics` or `workspace`",
+                s
+            )),
+        }
+    }
+}
+
+/// Timeouts of the LSP requests per category, in seconds.
+///
+/// Requests outside of these categories use [`ServerConfig::lsp_request_timeout`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LspTimeouts {
+    pub hover_secs: u64,
+    pub completion_secs: u64,
+    pub definition_secs: u64,
+    pub symbols_secs: u64,
+    pub formatting_secs: u64,
+    pub diagnostics_secs: u64,
+    pub workspace_secs: u64,
+}
+
+impl Default for LspTimeouts {
+    fn default() -> Self {
+        Self {
+            hover_secs: 10,
+            completion_secs: 10,
+            definition_secs: LSP_REQUEST_TIMEOUT_SECS,
+            symbols_secs: LSP_REQUEST_TIMEOUT_SECS,
+            formatting_secs: LSP_REQUEST_TIMEOUT_SECS,
+            diagnostics_secs: 60,
+            workspace_secs: 300,
+        }
+    }
+}
+
+impl LspTimeouts {
+    /// The timeout of requests in `category`.
+    pub fn get(&self, category: TimeoutCategory) -> Duration {
+        let secs = match category {
+            TimeoutCategory::Hover => self.hover_secs,
+            TimeoutCategory::Completion => self.completion_secs,
+            TimeoutCategory::Definition => self.definition_secs,
+            TimeoutCategory::Symbols => self.symbols_secs,
+            TimeoutCategory::Formatting => self.formatting_secs,
+            TimeoutCategory::Diagnostics => self.diagnostics_secs,
+            TimeoutCategory::Workspace => self.workspace_secs,
+        };
+
+        Duration::from_secs(secs)
+    }
+
+    /// Set the timeout of requests in `category`.
+    pub fn set(&mut self, category: TimeoutCategory, secs: u64) {
+        let field = match category {
+            TimeoutCategory::Hover => &mut self.hover_secs,
+            TimeoutCategory::Completion => &mut self.completion_secs,
+            TimeoutCategory::Definition => &mut self.definition_secs,
+            TimeoutCategory::Symbols => &mut self.symbols_secs,
+            TimeoutCategory::Formatting => &mut self.formatting_secs,
+            TimeoutCategory::Diagnostics => &mut self.diagnostics_secs,
+            TimeoutCategory::Workspace => &mut self.workspace_secs,
+        };
+        *field = secs;
+    }
+}
+
+/// Tunable timings and analysis settings.
+///
+/// The defaults are the constants above. Each setting can be overridden through an environment
+/// variable, see [`ServerConfig::from_env`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerConfig {
+    /// Timeout for LSP requests outside of the [`LspTimeouts`] categories
+    /// (`RA_MCP_LSP_TIMEOUT_SECS`).
+    pub lsp_request_timeout: Duration,
+    /// Timeouts of the LSP requests per category.
+    pub timeouts: LspTimeouts,
+    /// Delay after opening a document to allow rust-analyzer to process it
+    /// (`RA_MCP_DOCUMENT_OPEN_DELAY_MS`).
+    pub document_open_delay: Duration,
+    /// Default time to wait for diagnostics to be published (`RA_MCP_DIAGNOSTICS_TIMEOUT_MS`).
+    pub diagnostics_poll_timeout: Duration,
+    /// Interval between checks while waiting for diagnostics
+    /// (`RA_MCP_DIAGNOSTICS_POLL_INTERVAL_MS`).
+    pub diagnostics_poll_interval: Duration,
+    /// The command rust-analyzer runs on save (`RA_MCP_CHECK_ON_SAVE`).
+    pub check_on_save: CheckCommand,
+    /// Number of results of read-only tools kept in the response cache, 0 to disable caching
+    /// (`RA_MCP_RESPONSE_CACHE_CAPACITY`).
+    pub response_cache_capacity: usize,
+    /// Number of documents kept open in rust-analyzer before the least recently used one is
+    /// closed (`RA_MCP_MAX_OPEN_DOCUMENTS`).
+    pub max_open_documents: usize,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            lsp_request_timeout: Duration::from_secs(LSP_REQUEST_TIMEOUT_SECS),
+            timeouts: LspTimeouts::default(),
+            document_open_delay: Duration::from_millis(DOCUMENT_OPEN_DELAY_MILLIS),
+            diagnostics_poll_timeout: Duration::from_millis(DIAGNOSTICS_WAIT_MILLIS),
+            diagnostics_poll_interval: Duration::from_millis(DIAGNOSTICS_POLL_INTERVAL_MILLIS),
+            check_on_save: CheckCommand::default(),
+            response_cache_capacity: RESPONSE_CACHE_CAPACITY,
+            max_open_documents: MAX_OPEN_DOCUMENTS,
+        }
+    }
+}
+
+impl ServerConfig {
+    /// Create the configuration from the defaults, overridden by the environment variables set.
+    pub fn from_env() -> Result<Self> {
+        let mut config = Self::default();
+        config.apply_env()?;
+
+        Ok(config)
+    }
+
+    /// Override the settings with the environment variables set.
+    ///
+    /// `RA_MCP_CHECK_ON_SAVE` takes a check command (`check`, `clippy` or `none`), or `true` /
+    /// `false` to enable or disable the default check.
+    pub fn apply_env(&mut self) -> Result<()> {
+        if let Some(secs) = env_number("RA_MCP_LSP_TIMEOUT_SECS")? {
+            self.lsp_request_timeout = Duration::from_secs(secs);
+        }
+        if let Some(millis) = env_number("RA_MCP_DOCUMENT_OPEN_DELAY_MS")? {
+            self.document_open_delay = Duration::from_millis(millis);
+        }
+        if let Some(millis) = env_number("RA_MCP_DIAGNOSTICS_TIMEOUT_MS")? {
+            self.diagnostics_poll_timeout = Duration::from_millis(millis);
+        }
+        if let Some(millis) = env_number("RA_MCP_DIAGNOSTICS_POLL_INTERVAL_MS")? {
+            self.diagnostics_poll_interval = Duration::from_millis(millis);
+        }
+        if let Some(capacity) = env_number("RA_MCP_RESPONSE_CACHE_CAPACITY")? {
+            self.response_cache_capacity = capacity as usize;
+        }
+        if let Some(max) = env_number("RA_MCP_MAX_OPEN_DOCUMENTS")? {
+            self.max_open_documents = max as usize;
+        }
+        if let Some(value) = env_var("RA_MCP_CHECK_ON_SAVE") {
+            self.check_on_save = match value.as_str() {
+                "true" => CheckCommand::Check,
+                "false" => CheckCommand::None,
+                value => value
+                    .parse()
+                    .map_err(|e| anyhow!("Invalid RA_MCP_CHECK_ON_SAVE: {}", e))?,
+            };
+        }
+
+        Ok(())
+    }
+}
+
+fn env_var(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+fn env_number(name: &str) -> Result<Option<u64>> {
+    env_var(name)
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|e| anyhow!("Invalid {} `{}`: {}", name, value, e))
+        })
+        .transpose()
+}
+
+/// Options to configure the server with.
+#[derive(Debug, Clone, Default)]
+pub struct ServerOptions {
+    /// The rust-analyzer binary to use. If unset, it's looked up in `PATH` and `~/.cargo/bin`.
+    pub rust_analyzer_path: Option<PathBuf>,
+    /// Serialize tool results as compact rather than pretty-printed JSON.
+    pub compact_responses: bool,
+    /// Log the raw LSP messages exchanged with rust-analyzer.
+    pub log_lsp_messages: bool,
+    /// Timings and analysis settings.
+    pub config: ServerConfig,
+    /// Extra rust-analyzer initialization options, deep-merged into the defaults.
+    pub init_options: Option<Value>,
+    /// The origins of the web pages allowed to call the HTTP server, besides the ones served from
+    /// localhost, e.g. `https://example.com`.
+    pub allowed_origins: Vec<String>,
+}
+
+impl ServerOptions {
+    /// Override the rust-analyzer binary with `RUST_ANALYZER_PATH`, then with `arg`, the
+    /// `--rust-analyzer-path` command line argument.
+    ///
+    /// A binary given explicitly, including in the config file, must exist: using another one
+    /// found in `PATH` instead would be surprising, so this fails with an error naming the
+    /// setting at fault.
+    pub fn apply_rust_analyzer_path(&mut self, arg: Option<PathBuf>) -> Result<()> {
+        let mut source = "rust_analyzer_path in the config file";
+        if let Some(path) = std::env::var_os("RUST_ANALYZER_PATH").filter(|path| !path.is_empty()) {
+            self.rust_analyzer_path = Some(PathBuf::from(path));
+            source = "RUST_ANALYZER_PATH";
+        }
+        if let Some(path) = arg {
+            self.rust_analyzer_path = Some(path);
+            source = "--rust-analyzer-path";
+        }
+
+        match &self.rust_analyzer_path {
+            Some(path) => which::which(path).map(|_| ()).map_err(|e| {
+                anyhow!(
+                    "rust-analyzer not found at {} (set by {}): {}",
+                    path.display(),
+                    source,
+                    e
+                )
+            }),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use anyhow::{anyhow, Result};
+use log::debug;
+use serde_json::{json, Value};
+use std::{path::Path, process::Stdio, time::Duration};
+use tokio::process::Command;
+
+use super::code_url;
+
+/// Maximum time given to `rustc --explain` and `cargo clippy --explain`.
+const EXPLAIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Explain a rustc error code (e.g. `E0308`) or a clippy lint (e.g. `clippy::needless_return`).
+///
+/// Error codes get rustc's long-form `explanation`. Clippy lints get the `summary` of what they
+/// check from `cargo clippy --explain`, which is null if clippy isn't installed. Both come with the
+/// `url` of their documentation. The commands are run in `workspace_root`, so that they're those of
+/// the toolchain the workspace is pinned to.
+pub async fn explain_code(workspace_root: &Path, code: &str) -> Result<Value> {
+    let url = code_url(&json!(code)).ok_or_else(|| anyhow!("Unknown diagnostic code: {}", code))?;
+
+    if let Some(lint) = code.strip_prefix("clippy::") {
+        let summary =
+            match run_explain(workspace_root, "cargo", &["clippy", "--explain", lint]).await {
+                Ok(explanation) => clippy_summary(&explanation),
+                Err(e) => {
+                    debug!("Failed to explain {}: {}", code, e);
+                    None
+                }
+            };
+
+        return Ok(json!({
+            "code": code,
+            "source": "clippy",
+            "url": url,
+            "summary": summary
+        }));
+    }
+
+    let explanation = run_explain(workspace_root, "rustc", &["--explain", code]).await?;
+
+    Ok(json!({
+        "code": code,
+        "source": "rustc",
+        "url": url,
+        "explanation": explanation
+    }))
+}
+
+async fn run_explain(workspace_root: &Path, program: &str, args: &[&str]) -> Result<String> {
+    let output = Command::new(program)
+        .args(args)
+        .current_dir(workspace_root)
+        .stdin(Stdio::null())
+        .kill_on_drop(true)
+        .output();
+    let output = tokio::time::timeout(EXPLAIN_TIMEOUT, output)
+        .await
+        .map_err(|_| anyhow!("Timed out running {} {}", program, args.join(" ")))?
+        .map_err(|e| anyhow!("Failed to run {}: {}", program, e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let message = if stderr.trim().is_empty() {
+            stdout.trim()
+        } else {
+            stderr.trim()
+        };
+        return Err(anyhow!(
+            "{} {} failed: {}",
+            program,
+            args.join(" "),
+            message
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// The `What it does` section of a clippy lint's documentation, or all of it if it has no such
+/// section.
+pub fn clippy_summary(explanation: &str) -> Option<String> {
+    let mut lines = explanation
+        .lines()
+        .skip_while(|line| !line.starts_with("### What it does"));
+    let summary = match lines.next() {
+        Some(_) => lines
+            .take_while(|line| !line.starts_with("### "))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        None => explanation.to_string(),
+    };
+    let summary = summary.trim();
+
+    (!summary.is_empty()).then(|| summary.to_string())
+}
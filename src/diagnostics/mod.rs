@@ -1,6 +1,74 @@
+use anyhow::{anyhow, Result};
 use serde_json::{json, Value};
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+    fmt::Write,
+    path::Path,
+    str::FromStr,
+};
 
-pub fn format_diagnostics(file_path: &str, result: &Value) -> Value {
+use crate::edits::{char_index, uri_to_path};
+
+mod explain;
+mod rustc_format;
+mod sarif;
+
+pub use explain::{clippy_summary, explain_code};
+pub use rustc_format::{to_rustc_diagnostic, to_rustc_diagnostics};
+pub use sarif::{to_sarif, SarifLog};
+
+/// Maximum number of source lines shown in a diagnostic snippet.
+const MAX_SNIPPET_LINES: usize = 5;
+
+/// Number of example locations listed per group of diagnostics.
+const GROUP_EXAMPLES: usize = 3;
+
+/// What [`format_workspace_diagnostics`] groups the diagnostics by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticGrouping {
+    /// The diagnostic code, e.g. `E0308` or `dead_code`.
+    Code,
+    File,
+    Severity,
+}
+
+impl FromStr for DiagnosticGrouping {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "code" => Ok(Self::Code),
+            "file" => Ok(Self::File),
+            "severity" => Ok(Self::Severity),
+            _ => Err(anyhow!(
+                "Invalid grouping `{}`, expected `code`, `file` or `severity`",
+                s
+            )),
+        }
+    }
+}
+
+/// Format the diagnostics of a file, counting them by severity.
+///
+/// Diagnostics with the same code, message and range (e.g. reported once per macro expansion) are
+/// only listed once, and `summary.deduplicated` says how many were dropped. The
+/// `relatedInformation` of each diagnostic is collapsed into a `related` array of locations and
+/// messages.
+///
+/// Only the diagnostics at least as severe as `min_severity`, an LSP `DiagnosticSeverity`, are
+/// listed. The summary still counts all of them, `summary.shown` being the number listed and
+/// `summary.<severity>_suppressed` the number of those left out, e.g. `warnings_suppressed`.
+///
+/// rustc's own rendering of the diagnostics coming from cargo check, shipped by rust-analyzer in
+/// `data.rendered`, is included as `rendered` with `include_rendered`. By default it only is for
+/// errors and warnings, as it's long and less useful for the others.
+pub fn format_diagnostics(
+    file_path: &str,
+    result: &Value,
+    min_severity: Option<u64>,
+    include_rendered: Option<bool>,
+) -> Value {
     let Some(diag_array) = result.as_array() else {
         return json!({
             "file": file_path,
@@ -9,7 +77,13 @@ pub fn format_diagnostics(file_path: &str, result: &Value) -> Value {
                 "errors": 0,
                 "warnings": 0,
                 "information": 0,
-                "hints": 0
+                "hints": 0,
+                "deduplicated": 0,
+                "shown": 0,
+                "errors_suppressed": 0,
+                "warnings_suppressed": 0,
+                "information_suppressed": 0,
+                "hints_suppressed": 0
             }
         });
     };
@@ -21,7 +95,13 @@ pub fn format_diagnostics(file_path: &str, result: &Value) -> Value {
             "errors": 0,
             "warnings": 0,
             "information": 0,
-            "hints": 0
+            "hints": 0,
+            "deduplicated": 0,
+            "shown": 0,
+            "errors_suppressed": 0,
+            "warnings_suppressed": 0,
+            "information_suppressed": 0,
+            "hints_suppressed": 0
         }
     });
 
@@ -29,8 +109,17 @@ pub fn format_diagnostics(file_path: &str, result: &Value) -> Value {
     let mut warnings = 0;
     let mut information = 0;
     let mut hints = 0;
+    let mut seen = HashSet::new();
+    let mut deduplicated = 0;
+    let mut suppressed = [0; 4];
 
     for diag in diag_array {
+        let key = json!([diag["code"], diag["message"], diag["range"]]).to_string();
+        if !seen.insert(key) {
+            deduplicated += 1;
+            continue;
+        }
+
         // Count by severity.
         if let Some(severity) = diag.get("severity").and_then(|s| s.as_u64()) {
             match severity {
@@ -42,31 +131,531 @@ pub fn format_diagnostics(file_path: &str, result: &Value) -> Value {
             }
         }
 
+        if !meets_severity(diag, min_severity) {
+            if let Some(severity) = diag["severity"].as_u64() {
+                count_suppressed(&mut suppressed, severity);
+            }
+            continue;
+        }
+
         // Add formatted diagnostic.
         let Some(diag_list) = output["diagnostics"].as_array_mut() else {
             continue;
         };
+        let href = code_href(diag);
+        let severity = diag["severity"].as_u64();
+        let rendered = include_rendered
+            .unwrap_or(severity.is_some_and(|severity| severity <= 2))
+            .then(|| diag["data"]["rendered"].as_str())
+            .flatten();
 
-        diag_list.push(json!({
-            "severity": match diag.get("severity").and_then(|s| s.as_u64()) {
-                Some(1) => "error",
-                Some(2) => "warning",
-                Some(3) => "information",
-                Some(4) => "hint",
-                _ => "unknown"
-            },
+        let mut formatted = json!({
+            "severity": severity_name(diag.get("severity").and_then(|s| s.as_u64())),
             "range": diag.get("range").cloned().unwrap_or(json!(null)),
             "message": diag.get("message").and_then(|m| m.as_str()).unwrap_or(""),
             "code": diag.get("code").cloned().unwrap_or(json!(null)),
-            "source": diag.get("source").and_then(|s| s.as_str()).unwrap_or("rust-analyzer"),
-            "relatedInformation": diag.get("relatedInformation").cloned().unwrap_or(json!(null))
-        }));
+            "code_url": href,
+            "code_description": href.as_ref().map(|href| json!({ "href": href })),
+            "source": diagnostic_source(diag),
+            "related": related_information(diag)
+        });
+        if let Some(rendered) = rendered {
+            formatted["rendered"] = json!(rendered);
+        }
+        diag_list.push(formatted);
     }
 
     output["summary"]["errors"] = json!(errors);
     output["summary"]["warnings"] = json!(warnings);
     output["summary"]["information"] = json!(information);
     output["summary"]["hints"] = json!(hints);
+    output["summary"]["deduplicated"] = json!(deduplicated);
+    output["summary"]["shown"] = json!(output["diagnostics"].as_array().map_or(0, Vec::len));
+    add_suppressed(&mut output["summary"], &suppressed);
+
+    output
+}
+
+/// Format diagnostics per URI, counting them by severity.
+///
+/// Like in [`format_diagnostics`], the counts include the diagnostics below `min_severity` but
+/// only the others are listed, `shown` being their number and `<severity>_suppressed` the number
+/// of the others. Files without any are left out.
+///
+/// With `group_by`, the diagnostics are summarized in `groups` instead of being listed in `files`,
+/// the most common first, each with its `count` and the first few occurrences as `examples`.
+pub fn format_workspace_diagnostics(
+    workspace_root: &Path,
+    result: &Value,
+    min_severity: Option<u64>,
+    group_by: Option<DiagnosticGrouping>,
+) -> Value {
+    if !result.is_object() {
+        // Handle unexpected format.
+        if let Some(items) = result.get("items") {
+            return json!({
+                "workspace": workspace_root.display().to_string(),
+                "diagnostics": items,
+                "summary": {
+                    "total_diagnostics": items.as_array().map(|a| a.len()).unwrap_or(0),
+                    "by_severity": {}
+                }
+            });
+        }
+
+        return json!({
+            "workspace": workspace_root.display().to_string(),
+            "diagnostics": result,
+            "summary": {
+                "note": "Unexpected response format from rust-analyzer"
+            }
+        });
+    }
+
+    // Fallback format (diagnostics per URI).
+    let mut output = json!({
+        "workspace": workspace_root.display().to_string(),
+        "files": {},
+        "summary": {
+            "total_files": 0,
+            "total_errors": 0,
+            "total_warnings": 0,
+            "total_information": 0,
+            "total_hints": 0,
+            "shown": 0,
+            "errors_suppressed": 0,
+            "warnings_suppressed": 0,
+            "information_suppressed": 0,
+            "hints_suppressed": 0
+        }
+    });
+
+    let mut total_errors = 0;
+    let mut total_warnings = 0;
+    let mut total_information = 0;
+    let mut total_hints = 0;
+    let mut total_shown = 0;
+    let mut suppressed = [0; 4];
+    let mut file_count = 0;
+
+    let Some(obj) = result.as_object() else {
+        return output;
+    };
+
+    for (uri, diagnostics) in obj {
+        let Some(diag_array) = diagnostics.as_array() else {
+            continue;
+        };
+
+        if diag_array.is_empty() {
+            continue;
+        }
+
+        file_count += 1;
+        let mut file_errors = 0;
+        let mut file_warnings = 0;
+        let mut file_information = 0;
+        let mut file_hints = 0;
+
+        for diag in diag_array {
+            let Some(severity) = diag.get("severity").and_then(|s| s.as_u64()) else {
+                continue;
+            };
+            if !meets_severity(diag, min_severity) {
+                count_suppressed(&mut suppressed, severity);
+            }
+
+            match severity {
+                1 => {
+                    file_errors += 1;
+                    total_errors += 1;
+                }
+                2 => {
+                    file_warnings += 1;
+                    total_warnings += 1;
+                }
+                3 => {
+                    file_information += 1;
+                    total_information += 1;
+                }
+                4 => {
+                    file_hints += 1;
+                    total_hints += 1;
+                }
+                _ => {}
+            }
+        }
+
+        let shown: Vec<&Value> = diag_array
+            .iter()
+            .filter(|diag| meets_severity(diag, min_severity))
+            .collect();
+        if shown.is_empty() {
+            continue;
+        }
+        total_shown += shown.len();
+
+        output["files"][uri] = json!({
+            "diagnostics": shown,
+            "summary": {
+                "errors": file_errors,
+                "warnings": file_warnings,
+                "information": file_information,
+                "hints": file_hints,
+                "shown": shown.len()
+            }
+        });
+    }
+
+    output["summary"]["total_files"] = json!(file_count);
+    output["summary"]["total_errors"] = json!(total_errors);
+    output["summary"]["total_warnings"] = json!(total_warnings);
+    output["summary"]["total_information"] = json!(total_information);
+    output["summary"]["total_hints"] = json!(total_hints);
+    output["summary"]["shown"] = json!(total_shown);
+    add_suppressed(&mut output["summary"], &suppressed);
+
+    if let Some(group_by) = group_by {
+        if let Some(output) = output.as_object_mut() {
+            output.remove("files");
+        }
+        output["groups"] = group_diagnostics(workspace_root, obj, min_severity, group_by);
+    }
 
     output
 }
+
+/// Group the diagnostics per URI at least as severe as `min_severity`, the most common group
+/// first, listing the first occurrences of each by file and position.
+fn group_diagnostics(
+    workspace_root: &Path,
+    result: &serde_json::Map<String, Value>,
+    min_severity: Option<u64>,
+    group_by: DiagnosticGrouping,
+) -> Value {
+    let mut occurrences = Vec::new();
+    for (uri, diagnostics) in result {
+        let file = uri_to_path(uri)
+            .map(|path| {
+                path.strip_prefix(workspace_root)
+                    .unwrap_or(&path)
+                    .display()
+                    .to_string()
+            })
+            .unwrap_or_else(|| uri.clone());
+
+        for diag in diagnostics.as_array().into_iter().flatten() {
+            if !meets_severity(diag, min_severity) {
+                continue;
+            }
+
+            let start = &diag["range"]["start"];
+            occurrences.push((
+                file.clone(),
+                start["line"].as_u64().unwrap_or(0),
+                start["character"].as_u64().unwrap_or(0),
+                diag,
+            ));
+        }
+    }
+    occurrences.sort_by(|a, b| (&a.0, a.1, a.2).cmp(&(&b.0, b.1, b.2)));
+
+    let mut groups: Vec<(Value, usize, Vec<Value>)> = Vec::new();
+    let mut indices = HashMap::new();
+    for (file, line, character, diag) in occurrences {
+        let key = match group_by {
+            // Codes are strings or numbers, or missing.
+            DiagnosticGrouping::Code => match &diag["code"] {
+                Value::Number(code) => json!(code.to_string()),
+                code => code.clone(),
+            },
+            DiagnosticGrouping::File => json!(file),
+            DiagnosticGrouping::Severity => json!(severity_name(diag["severity"].as_u64())),
+        };
+        let index = *indices.entry(key.to_string()).or_insert_with(|| {
+            groups.push((key, 0, Vec::new()));
+            groups.len() - 1
+        });
+
+        let (_, count, examples) = &mut groups[index];
+        *count += 1;
+        if examples.len() < GROUP_EXAMPLES {
+            examples.push(json!({
+                "file": file,
+                "line": line,
+                "character": character,
+                "message": diag["message"]
+            }));
+        }
+    }
+    // Stable, so the groups with as many diagnostics are in the order of their first occurrence.
+    groups.sort_by_key(|(_, count, _)| Reverse(*count));
+
+    groups
+        .into_iter()
+        .map(|(key, count, examples)| {
+            json!({
+                "key": key,
+                "count": count,
+                "examples": examples
+            })
+        })
+        .collect()
+}
+
+/// The name of an LSP `DiagnosticSeverity`, e.g. `error`.
+fn severity_name(severity: Option<u64>) -> &'static str {
+    match severity {
+        Some(1) => "error",
+        Some(2) => "warning",
+        Some(3) => "information",
+        Some(4) => "hint",
+        _ => "unknown",
+    }
+}
+
+/// Count a diagnostic of LSP `DiagnosticSeverity` `severity` left out by a severity filter.
+fn count_suppressed(suppressed: &mut [usize; 4], severity: u64) {
+    if let Some(count) = (severity as usize)
+        .checked_sub(1)
+        .and_then(|index| suppressed.get_mut(index))
+    {
+        *count += 1;
+    }
+}
+
+/// Set the `<severity>_suppressed` counts of a summary.
+fn add_suppressed(summary: &mut Value, suppressed: &[usize; 4]) {
+    let names = ["errors", "warnings", "information", "hints"];
+    for (name, count) in names.iter().zip(suppressed) {
+        summary[format!("{}_suppressed", name)] = json!(count);
+    }
+}
+
+/// List the first `limit` errors of the diagnostics per URI reported for a workspace, sorted by
+/// file and position, with `total_errors` counting all of them.
+pub fn first_errors(workspace_root: &Path, result: &Value, limit: usize) -> Value {
+    let mut errors = Vec::new();
+    for (uri, diagnostics) in result.as_object().into_iter().flatten() {
+        let file = uri_to_path(uri)
+            .map(|path| {
+                path.strip_prefix(workspace_root)
+                    .unwrap_or(&path)
+                    .display()
+                    .to_string()
+            })
+            .unwrap_or_else(|| uri.clone());
+
+        for diag in diagnostics.as_array().into_iter().flatten() {
+            if diag["severity"].as_u64() != Some(1) {
+                continue;
+            }
+
+            let start = &diag["range"]["start"];
+            errors.push((
+                file.clone(),
+                start["line"].as_u64().unwrap_or(0),
+                start["character"].as_u64().unwrap_or(0),
+                diag,
+            ));
+        }
+    }
+    errors.sort_by(|a, b| (&a.0, a.1, a.2).cmp(&(&b.0, b.1, b.2)));
+
+    let total_errors = errors.len();
+    let errors: Vec<Value> = errors
+        .into_iter()
+        .take(limit)
+        .map(|(file, line, character, diag)| {
+            json!({
+                "file": file,
+                "line": line,
+                "character": character,
+                "message": diag["message"],
+                "code": diag["code"]
+            })
+        })
+        .collect();
+
+    json!({
+        "errors": errors,
+        "total_errors": total_errors,
+        "shown": errors.len()
+    })
+}
+
+/// Parse a severity name (`error`, `warning`, `information` or `hint`) into an LSP
+/// `DiagnosticSeverity`, errors being the most severe at 1.
+pub fn parse_severity(name: &str) -> Option<u64> {
+    match name {
+        "error" => Some(1),
+        "warning" => Some(2),
+        "information" => Some(3),
+        "hint" => Some(4),
+        _ => None,
+    }
+}
+
+/// Whether `diag` is at least as severe as `min_severity`. Diagnostics without a severity are
+/// always kept, as there's no telling how severe they are.
+pub fn meets_severity(diag: &Value, min_severity: Option<u64>) -> bool {
+    match (diag["severity"].as_u64(), min_severity) {
+        (Some(severity), Some(min_severity)) => severity <= min_severity,
+        _ => true,
+    }
+}
+
+/// Collapse the `relatedInformation` of a diagnostic into `{ uri, range, message }` entries,
+/// dropping the ones that are repeated.
+fn related_information(diag: &Value) -> Vec<Value> {
+    let Some(related) = diag["relatedInformation"].as_array() else {
+        return Vec::new();
+    };
+
+    let mut seen = HashSet::new();
+    related
+        .iter()
+        .map(|info| {
+            json!({
+                "uri": info["location"]["uri"],
+                "range": info["location"]["range"],
+                "message": info["message"]
+            })
+        })
+        .filter(|info| seen.insert(info.to_string()))
+        .collect()
+}
+
+/// Add a `snippet` with the offending source code to each diagnostic formatted by
+/// [`format_diagnostics`], `content` being the content of the file.
+pub fn add_snippets(formatted: &mut Value, content: &str) {
+    let Some(diagnostics) = formatted["diagnostics"].as_array_mut() else {
+        return;
+    };
+
+    for diag in diagnostics {
+        diag["snippet"] = json!(diagnostic_snippet(content, &diag["range"]));
+    }
+}
+
+/// Render the source lines covered by an LSP range, underlined with carets like rustc does:
+///
+/// ```text
+/// 3 |     let x: i32 = "a";
+///   |                  ^^^
+/// ```
+///
+/// Only the first few lines of a multi-line range are shown. Returns `None` if the range is
+/// malformed or starts past the end of `content`; a range ending past it is clamped.
+pub fn diagnostic_snippet(content: &str, range: &Value) -> Option<String> {
+    let start_line = range["start"]["line"].as_u64()? as usize;
+    let start_character = range["start"]["character"].as_u64()? as usize;
+    let end_line = range["end"]["line"].as_u64()? as usize;
+    let end_character = range["end"]["character"].as_u64()? as usize;
+
+    let lines: Vec<&str> = content.lines().collect();
+    if start_line >= lines.len() || end_line < start_line {
+        return None;
+    }
+    let (end_line, end_character) = if end_line >= lines.len() {
+        (lines.len() - 1, usize::MAX)
+    } else if end_line > start_line && end_character == 0 {
+        // A range ending at the start of a line doesn't cover anything on it.
+        (end_line - 1, usize::MAX)
+    } else {
+        (end_line, end_character)
+    };
+
+    let last_shown = end_line.min(start_line + MAX_SNIPPET_LINES - 1);
+    let width = (last_shown + 1).to_string().len();
+    let mut snippet = String::new();
+    for (i, line) in lines
+        .iter()
+        .enumerate()
+        .take(last_shown + 1)
+        .skip(start_line)
+    {
+        let from = if i == start_line {
+            char_index(line, start_character)
+        } else {
+            0
+        };
+        let to = if i == end_line {
+            char_index(line, end_character)
+        } else {
+            line.chars().count()
+        };
+
+        let _ = writeln!(snippet, "{:>width$} | {}", i + 1, line);
+        // An empty range still gets a caret, but empty lines within a range don't.
+        if to > from || start_line == end_line {
+            // Keep tabs so that the carets line up with the code above them.
+            let indent: String = line
+                .chars()
+                .take(from)
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect();
+            let carets = "^".repeat(to.saturating_sub(from).max(1));
+            let _ = writeln!(snippet, "{:>width$} | {}{}", "", indent, carets);
+        }
+    }
+    if last_shown < end_line {
+        let _ = writeln!(snippet, "{:>width$} | ...", "");
+    }
+    snippet.pop();
+
+    Some(snippet)
+}
+
+/// Determine which tool produced a diagnostic.
+///
+/// rust-analyzer sets `source` to `rustc`, `clippy` or `rust-analyzer`. If it's missing, we infer
+/// it from the diagnostic code instead of blindly attributing everything to rust-analyzer.
+fn diagnostic_source(diag: &Value) -> &str {
+    if let Some(source) = diag.get("source").and_then(|s| s.as_str()) {
+        return source;
+    }
+
+    match diag.get("code").and_then(|c| c.as_str()) {
+        Some(code) if code.starts_with("clippy::") => "clippy",
+        Some(code) if is_rustc_error_code(code) => "rustc",
+        _ => "rust-analyzer",
+    }
+}
+
+/// Get the documentation URL of a diagnostic: the `codeDescription` rust-analyzer provides if any,
+/// or one derived from its code by [`code_url`] otherwise.
+fn code_href(diag: &Value) -> Option<String> {
+    diag["codeDescription"]["href"]
+        .as_str()
+        .map(String::from)
+        .or_else(|| code_url(&diag["code"]))
+}
+
+/// Get the documentation URL for a diagnostic code, if there is one.
+///
+/// rustc error codes (e.g. `E0308`) link to the error index and clippy lints (e.g.
+/// `clippy::redundant_clone`) link to the clippy lint list.
+pub fn code_url(code: &Value) -> Option<String> {
+    let code = code.as_str()?;
+
+    if let Some(lint) = code.strip_prefix("clippy::") {
+        return Some(format!(
+            "https://rust-lang.github.io/rust-clippy/master/index.html#{}",
+            lint
+        ));
+    }
+
+    if is_rustc_error_code(code) {
+        return Some(format!(
+            "https://doc.rust-lang.org/error_codes/{}.html",
+            code
+        ));
+    }
+
+    None
+}
+
+fn is_rustc_error_code(code: &str) -> bool {
+    code.len() == 5 && code.starts_with('E') && code[1..].chars().all(|c| c.is_ascii_digit())
+}
@@ -0,0 +1,199 @@
+use serde_json::{json, Value};
+use std::fmt::Write;
+
+use super::{diagnostic_snippet, meets_severity, related_information};
+use crate::edits::{char_index, position_to_offset};
+
+/// Convert the LSP diagnostics of a file into the JSON diagnostics rustc emits with
+/// `--error-format=json`, which is also what cargo's `--message-format=json` wraps in its
+/// `compiler-message`s.
+///
+/// `file_name` is the path of the file as rustc would show it, i.e. relative to the workspace, and
+/// `content` its content, used to compute the byte offsets and text of the spans. Diagnostics below
+/// `min_severity` are left out.
+pub fn to_rustc_diagnostics(
+    file_name: &str,
+    content: &str,
+    diagnostics: &[Value],
+    min_severity: Option<u64>,
+) -> Vec<Value> {
+    diagnostics
+        .iter()
+        .filter(|diag| meets_severity(diag, min_severity))
+        .map(|diag| to_rustc_diagnostic(file_name, content, diag))
+        .collect()
+}
+
+/// Convert a single LSP diagnostic, see [`to_rustc_diagnostics`].
+pub fn to_rustc_diagnostic(file_name: &str, content: &str, diag: &Value) -> Value {
+    let level = rustc_level(diag["severity"].as_u64());
+    let message = diag["message"].as_str().unwrap_or_default();
+    let code = match &diag["code"] {
+        Value::String(code) => Some(code.clone()),
+        Value::Number(code) => Some(code.to_string()),
+        _ => None,
+    };
+    let span = rustc_span(file_name, content, &diag["range"]);
+
+    // rustc attaches notes like these as children without spans.
+    let notes: Vec<String> = related_information(diag)
+        .iter()
+        .filter_map(|related| related["message"].as_str().map(String::from))
+        .collect();
+    let children: Vec<Value> = notes
+        .iter()
+        .map(|note| {
+            json!({
+                "message": note,
+                "code": null,
+                "level": "note",
+                "spans": [],
+                "children": [],
+                "rendered": null
+            })
+        })
+        .collect();
+
+    json!({
+        "$message_type": "diagnostic",
+        "message": message,
+        "code": code.as_ref().map(|code| json!({ "code": code, "explanation": null })),
+        "level": level,
+        "spans": span.into_iter().collect::<Vec<_>>(),
+        "children": children,
+        "rendered": render(file_name, content, diag, level, code.as_deref(), &notes)
+    })
+}
+
+/// The rustc level matching an LSP `DiagnosticSeverity`.
+fn rustc_level(severity: Option<u64>) -> &'static str {
+    match severity {
+        Some(2) => "warning",
+        Some(3) => "note",
+        Some(4) => "help",
+        _ => "error",
+    }
+}
+
+/// Convert an LSP range into a primary rustc span, whose lines and columns are 1-based and whose
+/// columns count chars rather than UTF-16 code units.
+fn rustc_span(file_name: &str, content: &str, range: &Value) -> Option<Value> {
+    let start_line = range["start"]["line"].as_u64()? as usize;
+    let start_character = range["start"]["character"].as_u64()? as usize;
+    let end_line = range["end"]["line"].as_u64()? as usize;
+    let end_character = range["end"]["character"].as_u64()? as usize;
+
+    let byte_start = position_to_offset(content, start_line as u32, start_character as u32)?;
+    let byte_end =
+        position_to_offset(content, end_line as u32, end_character as u32).unwrap_or(content.len());
+
+    let lines: Vec<&str> = content.lines().collect();
+    let column = |line: usize, character: usize| {
+        lines
+            .get(line)
+            .map_or(1, |text| char_index(text, character) + 1)
+    };
+    let column_start = column(start_line, start_character);
+    let column_end = column(end_line, end_character);
+
+    let text: Vec<Value> = lines
+        .iter()
+        .enumerate()
+        .take(end_line + 1)
+        .skip(start_line)
+        .map(|(i, line)| {
+            json!({
+                "text": line,
+                "highlight_start": if i == start_line { column_start } else { 1 },
+                "highlight_end": if i == end_line {
+                    column_end
+                } else {
+                    line.chars().count() + 1
+                }
+            })
+        })
+        .collect();
+
+    Some(json!({
+        "file_name": file_name,
+        "byte_start": byte_start,
+        "byte_end": byte_end,
+        "line_start": start_line + 1,
+        "line_end": end_line + 1,
+        "column_start": column_start,
+        "column_end": column_end,
+        "is_primary": true,
+        "text": text,
+        "label": null,
+        "suggested_replacement": null,
+        "suggestion_applicability": null,
+        "expansion": null
+    }))
+}
+
+/// Render a diagnostic the way rustc prints it:
+///
+/// ```text
+/// error[E0425]: cannot find value `y` in this scope
+///  --> src/main.rs:2:20
+///   |
+/// 2 |     println!("{}", y);
+///   |                    ^
+///   = note: ...
+/// ```
+fn render(
+    file_name: &str,
+    content: &str,
+    diag: &Value,
+    level: &str,
+    code: Option<&str>,
+    notes: &[String],
+) -> String {
+    let mut rendered = String::from(level);
+    if let Some(code) = code {
+        let _ = write!(rendered, "[{}]", code);
+    }
+    let _ = writeln!(
+        rendered,
+        ": {}",
+        diag["message"].as_str().unwrap_or_default()
+    );
+
+    let range = &diag["range"];
+    let snippet = diagnostic_snippet(content, range);
+    // The gutter is as wide as the line numbers in the snippet.
+    let width = snippet
+        .as_deref()
+        .and_then(|snippet| snippet.find(" | "))
+        .unwrap_or(1);
+    let (Some(line), Some(character)) = (
+        range["start"]["line"].as_u64(),
+        range["start"]["character"].as_u64(),
+    ) else {
+        rendered.push('\n');
+        return rendered;
+    };
+    let column = content
+        .lines()
+        .nth(line as usize)
+        .map_or(1, |text| char_index(text, character as usize) + 1);
+    let _ = writeln!(
+        rendered,
+        "{:width$}--> {}:{}:{}",
+        "",
+        file_name,
+        line + 1,
+        column
+    );
+
+    if let Some(snippet) = snippet {
+        let _ = writeln!(rendered, "{:width$} |", "");
+        let _ = writeln!(rendered, "{}", snippet);
+    }
+    for note in notes {
+        let _ = writeln!(rendered, "{:width$} = note: {}", "", note);
+    }
+    rendered.push('\n');
+
+    rendered
+}
@@ -0,0 +1,215 @@
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use std::path::Path;
+
+use super::{code_href, meets_severity};
+use crate::edits::uri_to_path;
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const SARIF_VERSION: &str = "2.1.0";
+
+/// The base ID the locations of results are relative to, i.e. the workspace root.
+const SOURCE_ROOT: &str = "%SRCROOT%";
+
+/// A SARIF log, as understood by GitHub code scanning among others.
+#[derive(Debug, Serialize)]
+pub struct SarifLog {
+    #[serde(rename = "$schema")]
+    pub schema: &'static str,
+    pub version: &'static str,
+    pub runs: Vec<Run>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Run {
+    pub tool: Tool,
+    pub original_uri_base_ids: Map<String, Value>,
+    pub results: Vec<SarifResult>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Tool {
+    pub driver: Driver,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Driver {
+    pub name: &'static str,
+    pub information_uri: &'static str,
+    pub rules: Vec<Rule>,
+}
+
+/// A rule per diagnostic code, e.g. `E0308` or `clippy::needless_return`.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Rule {
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub help_uri: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SarifResult {
+    /// Diagnostics without a code have no rule.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rule_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rule_index: Option<usize>,
+    pub level: &'static str,
+    pub message: Message,
+    pub locations: Vec<Location>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Message {
+    pub text: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Location {
+    pub physical_location: PhysicalLocation,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PhysicalLocation {
+    pub artifact_location: ArtifactLocation,
+    pub region: Region,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArtifactLocation {
+    pub uri: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uri_base_id: Option<&'static str>,
+}
+
+/// A 1-based region. Like LSP positions, SARIF columns count UTF-16 code units by default.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Region {
+    pub start_line: u64,
+    pub start_column: u64,
+    pub end_line: u64,
+    pub end_column: u64,
+}
+
+/// Convert the diagnostics per URI reported for a workspace into a SARIF 2.1.0 log with a single
+/// run, with a rule per diagnostic code.
+///
+/// The locations of files inside `workspace_root` are relative to it, through the `%SRCROOT%` base
+/// ID. Diagnostics below `min_severity` are left out.
+pub fn to_sarif(workspace_root: &Path, result: &Value, min_severity: Option<u64>) -> SarifLog {
+    let mut files: Vec<(&String, &Value)> = result.as_object().into_iter().flatten().collect();
+    files.sort_by_key(|(uri, _)| *uri);
+
+    let mut rules: Vec<Rule> = Vec::new();
+    let mut results = Vec::new();
+    for (uri, diagnostics) in files {
+        let artifact_location = artifact_location(workspace_root, uri);
+
+        for diag in diagnostics.as_array().into_iter().flatten() {
+            if !meets_severity(diag, min_severity) {
+                continue;
+            }
+
+            let rule_id = match &diag["code"] {
+                Value::String(code) => Some(code.clone()),
+                Value::Number(code) => Some(code.to_string()),
+                _ => None,
+            };
+            let rule_index = rule_id.as_ref().map(|id| {
+                rules
+                    .iter()
+                    .position(|rule| rule.id == *id)
+                    .unwrap_or_else(|| {
+                        rules.push(Rule {
+                            id: id.clone(),
+                            help_uri: code_href(diag),
+                        });
+                        rules.len() - 1
+                    })
+            });
+
+            let range = &diag["range"];
+            // SARIF lines and columns are 1-based.
+            let position =
+                |position: &str, field: &str| range[position][field].as_u64().unwrap_or(0) + 1;
+            results.push(SarifResult {
+                rule_id,
+                rule_index,
+                level: sarif_level(diag["severity"].as_u64()),
+                message: Message {
+                    text: diag["message"].as_str().unwrap_or_default().to_string(),
+                },
+                locations: vec![Location {
+                    physical_location: PhysicalLocation {
+                        artifact_location: artifact_location.clone(),
+                        region: Region {
+                            start_line: position("start", "line"),
+                            start_column: position("start", "character"),
+                            end_line: position("end", "line"),
+                            end_column: position("end", "character"),
+                        },
+                    },
+                }],
+            });
+        }
+    }
+
+    let mut original_uri_base_ids = Map::new();
+    original_uri_base_ids.insert(
+        SOURCE_ROOT.to_string(),
+        json!({ "uri": format!("file://{}/", workspace_root.display()) }),
+    );
+
+    SarifLog {
+        schema: SARIF_SCHEMA,
+        version: SARIF_VERSION,
+        runs: vec![Run {
+            tool: Tool {
+                driver: Driver {
+                    name: "rust-analyzer",
+                    information_uri: "https://rust-analyzer.github.io/",
+                    rules,
+                },
+            },
+            original_uri_base_ids,
+            results,
+        }],
+    }
+}
+
+/// The location of a file, relative to the workspace root if it's inside it.
+fn artifact_location(workspace_root: &Path, uri: &str) -> ArtifactLocation {
+    let relative = uri_to_path(uri).and_then(|path| {
+        path.strip_prefix(workspace_root)
+            .ok()
+            .map(|path| path.display().to_string())
+    });
+
+    match relative {
+        Some(path) => ArtifactLocation {
+            uri: path,
+            uri_base_id: Some(SOURCE_ROOT),
+        },
+        None => ArtifactLocation {
+            uri: uri.to_string(),
+            uri_base_id: None,
+        },
+    }
+}
+
+/// The SARIF level matching an LSP `DiagnosticSeverity`.
+fn sarif_level(severity: Option<u64>) -> &'static str {
+    match severity {
+        Some(2) => "warning",
+        Some(3) | Some(4) => "note",
+        _ => "error",
+    }
+}
@@ -0,0 +1,201 @@
+use anyhow::{bail, Result};
+use serde_json::Value;
+use std::path::PathBuf;
+
+/// Convert an LSP position into a byte offset into `content`.
+///
+/// LSP positions count characters in UTF-16 code units. A character past the end of the line is
+/// clamped to the end of the line, matching how editors treat such positions.
+pub fn position_to_offset(content: &str, line: u32, character: u32) -> Option<usize> {
+    let mut line_start = 0;
+    for _ in 0..line {
+        line_start += content[line_start..].find('\n')? + 1;
+    }
+
+    let line_end = content[line_start..]
+        .find('\n')
+        .map(|i| line_start + i)
+        .unwrap_or(content.len());
+    let line_text = content[line_start..line_end].trim_end_matches('\r');
+
+    Some(line_start + line_offset(line_text, character as usize))
+}
+
+/// Convert a character position in UTF-16 code units, as LSP counts them, into a byte offset into
+/// `line`, clamped to the end of the line.
+pub fn line_offset(line: &str, character: usize) -> usize {
+    let mut units = 0;
+    for (i, c) in line.char_indices() {
+        if units >= character {
+            return i;
+        }
+        units += c.len_utf16();
+    }
+
+    line.len()
+}
+
+/// Like [`line_offset`] but as an index of the chars of `line`, e.g. to line up with it.
+pub fn char_index(line: &str, character: usize) -> usize {
+    line[..line_offset(line, character)].chars().count()
+}
+
+/// Convert an LSP range object into a pair of byte offsets into `content`.
+pub fn range_to_offsets(content: &str, range: &Value) -> Option<(usize, usize)> {
+    let start = position_to_offset(
+        content,
+        range["start"]["line"].as_u64()? as u32,
+        range["start"]["character"].as_u64()? as u32,
+    )?;
+    let end = position_to_offset(
+        content,
+        range["end"]["line"].as_u64()? as u32,
+        range["end"]["character"].as_u64()? as u32,
+    )?;
+
+    (start <= end).then_some((start, end))
+}
+
+/// Get the text covered by an LSP range.
+pub fn range_text<'a>(content: &'a str, range: &Value) -> Option<&'a str> {
+    let (start, end) = range_to_offsets(content, range)?;
+    Some(&content[start..end])
+}
+
+/// Apply LSP text edits to `content`.
+///
+/// The edit ranges all refer to the original content, so the edits are applied from the end of the
+/// content backwards to keep the offsets of the remaining edits valid. Returns `None` if an edit is
+/// malformed, out of bounds or overlaps another one.
+pub fn apply_text_edits(content: &str, edits: &[Value]) -> Option<String> {
+    let mut resolved = Vec::with_capacity(edits.len());
+    for edit in edits {
+        let (start, end) = range_to_offsets(content, &edit["range"])?;
+        let new_text = edit["newText"].as_str()?;
+        resolved.push((start, end, new_text));
+    }
+    // Edits inserting at the same position are applied in the order given.
+    resolved.sort_by_key(|(start, end, _)| (*start, *end));
+
+    let mut result = content.to_string();
+    let mut next_start = content.len();
+    for (start, end, new_text) in resolved.into_iter().rev() {
+        if end > next_start {
+            return None;
+        }
+        result.replace_range(start..end, new_text);
+        next_start = start;
+    }
+
+    Some(result)
+}
+
+/// Check that LSP text edits can be applied to `content`, naming the first one that can't.
+///
+/// Unlike [`apply_text_edits`], which clamps positions past the end of a line like editors do, this
+/// rejects any position outside of `content`. Edits are named by their index in `edits`.
+pub fn validate_text_edits(content: &str, edits: &[Value]) -> Result<()> {
+    let lines: Vec<&str> = content.split('\n').collect();
+
+    let mut ranges = Vec::with_capacity(edits.len());
+    for (i, edit) in edits.iter().enumerate() {
+        if !edit["newText"].is_string() {
+            bail!("Edit {} has no newText", i);
+        }
+
+        let range = &edit["range"];
+        for position in [&range["start"], &range["end"]] {
+            let (Some(line), Some(character)) =
+                (position["line"].as_u64(), position["character"].as_u64())
+            else {
+                bail!("Edit {} has a malformed range: {}", i, range);
+            };
+            let Some(text) = lines.get(line as usize) else {
+                bail!(
+                    "Edit {} is out of bounds: line {} is past the end of the file ({} lines)",
+                    i,
+                    line,
+                    lines.len()
+                );
+            };
+            let length = text.trim_end_matches('\r').encode_utf16().count();
+            if character as usize > length {
+                bail!(
+                    "Edit {} is out of bounds: character {} is past the end of line {} ({} \
+                     characters)",
+                    i,
+                    character,
+                    line,
+                    length
+                );
+            }
+        }
+
+        let Some(offsets) = range_to_offsets(content, range) else {
+            bail!("Edit {} ends before it starts: {}", i, range);
+        };
+        ranges.push((offsets, i));
+    }
+
+    ranges.sort();
+    for pair in ranges.windows(2) {
+        let (((_, end), i), ((start, _), j)) = (pair[0], pair[1]);
+        if end > start {
+            bail!("Edit {} overlaps edit {}", i, j);
+        }
+    }
+
+    Ok(())
+}
+
+/// Flatten a `WorkspaceEdit` into a list of `(uri, text_edits)` pairs.
+///
+/// Both the `changes` map and the `documentChanges` array forms are supported. Resource operations
+/// (create/rename/delete) in `documentChanges` are skipped.
+pub fn workspace_edit_changes(edit: &Value) -> Vec<(String, Vec<Value>)> {
+    let mut changes = Vec::new();
+
+    if let Some(document_changes) = edit.get("documentChanges").and_then(|d| d.as_array()) {
+        for change in document_changes {
+            let Some(uri) = change["textDocument"]["uri"].as_str() else {
+                continue;
+            };
+            let Some(edits) = change.get("edits").and_then(|e| e.as_array()) else {
+                continue;
+            };
+            changes.push((uri.to_string(), edits.clone()));
+        }
+    } else if let Some(map) = edit.get("changes").and_then(|c| c.as_object()) {
+        for (uri, edits) in map {
+            let Some(edits) = edits.as_array() else {
+                continue;
+            };
+            changes.push((uri.clone(), edits.clone()));
+        }
+    }
+
+    changes
+}
+
+/// Convert a `file://` URI into a filesystem path, decoding percent-escapes.
+pub fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let path = uri.strip_prefix("file://")?;
+
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or("");
+            if let Ok(byte) = u8::from_str_radix(hex, 16) {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+
+    String::from_utf8(decoded).ok().map(PathBuf::from)
+}
@@ -1,7 +1,16 @@
+pub mod code_lens;
+pub mod completion;
 pub mod config;
 pub mod diagnostics;
+pub mod edits;
 pub mod lsp;
+pub mod markdown;
 pub mod mcp;
 pub mod protocol;
+pub mod security;
+pub mod sources;
+pub mod symbols;
+pub mod workspace;
 
+pub use config::ServerOptions;
 pub use mcp::RustAnalyzerMCPServer;
@@ -1,37 +1,106 @@
 use anyhow::{anyhow, Result};
-use log::info;
+use log::{debug, info, warn};
+use lru::LruCache;
+use notify::RecommendedWatcher;
 use serde_json::{json, Value};
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+    num::NonZeroUsize,
     path::PathBuf,
     process::Stdio,
-    sync::Arc,
-    time::Duration,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant, SystemTime},
 };
 use tokio::{
-    io::{AsyncWriteExt, BufWriter},
-    process::{Child, Command},
-    sync::{oneshot, Mutex},
+    io::BufWriter,
+    process::{Child, ChildStdin, Command},
+    sync::{mpsc, oneshot, Mutex},
 };
 
 use crate::{
-    config::{DOCUMENT_OPEN_DELAY_MILLIS, LSP_REQUEST_TIMEOUT_SECS},
-    protocol::lsp::LSPRequest,
+    config::{
+        merge_json, CheckCommand, ServerOptions, TimeoutCategory, PROGRESS_POLL_INTERVAL_MILLIS,
+        WATCH_DEBOUNCE_MILLIS,
+    },
+    edits::uri_to_path,
+    protocol::{
+        lsp::LSPRequest,
+        mcp::{ErrorKind, ToolError},
+    },
 };
 
+use super::{
+    connection::{write_message, Connection, PendingRequests},
+    progress::ProgressState,
+    status::ServerStatus,
+    version::INSTALL_HINT,
+    watcher::watch_workspace,
+};
+
+/// State of a document opened in rust-analyzer.
+#[derive(Debug, Clone)]
+pub struct DocumentState {
+    /// The version last sent to rust-analyzer. Incremented on every `didChange`.
+    pub version: i32,
+    /// Modification time of the file on disk when we last synced it.
+    pub mtime: Option<SystemTime>,
+    /// Hash of the content last sent to rust-analyzer, to tell when the file changed on disk.
+    pub content_hash: u64,
+    /// When the document was last opened or used.
+    pub last_access: Instant,
+}
+
+/// The diagnostics rust-analyzer last published for a document.
+#[derive(Debug, Clone, Default)]
+pub struct PublishedDiagnostics {
+    /// The version of the document they were computed for, if rust-analyzer said.
+    pub version: Option<i32>,
+    pub diagnostics: Vec<Value>,
+}
+
 pub struct RustAnalyzerClient {
     pub(super) process: Option<Child>,
     pub(super) request_id: Arc<Mutex<u64>>,
     pub(super) workspace_root: PathBuf,
-    pub(super) stdin: Option<BufWriter<tokio::process::ChildStdin>>,
-    pub(super) pending_requests: Arc<Mutex<HashMap<u64, oneshot::Sender<Value>>>>,
+    pub(super) stdin: Option<Arc<Mutex<BufWriter<ChildStdin>>>>,
+    pub(super) pending_requests: PendingRequests,
     pub(super) initialized: bool,
-    pub(super) open_documents: Arc<Mutex<HashSet<String>>>,
-    pub(super) diagnostics: Arc<Mutex<HashMap<String, Vec<Value>>>>,
+    /// The capabilities rust-analyzer announced in response to `initialize`.
+    pub(super) server_capabilities: Value,
+    /// The documents opened in rust-analyzer, the least recently used ones being closed when
+    /// there are too many.
+    pub(super) open_documents: Arc<Mutex<LruCache<String, DocumentState>>>,
+    pub(super) diagnostics: Arc<Mutex<HashMap<String, PublishedDiagnostics>>>,
+    /// The last version given to a document. It's shared by all documents so that the versions of
+    /// each keep increasing, even when it's closed and opened again.
+    pub(super) last_version: i32,
+    pub(super) progress: Arc<Mutex<ProgressState>>,
+    /// The latest analysis status reported by rust-analyzer.
+    pub(super) server_status: Arc<Mutex<ServerStatus>>,
+    /// The rust-analyzer settings, returned for `workspace/configuration` requests.
+    pub(super) settings: Arc<Mutex<Value>>,
+    /// Number of `workspace/configuration` requests answered by the connection handler.
+    pub(super) configurations_sent: Arc<AtomicU64>,
+    /// Whether rust-analyzer is running, cleared by the connection handler when it exits.
+    pub(super) alive: Arc<AtomicBool>,
+    /// Incremented every time a document is changed through `update_document` and every time the
+    /// watcher tells rust-analyzer about changes made outside of this server.
+    pub(super) edit_generation: Arc<AtomicU64>,
+    /// Number of times rust-analyzer was restarted after exiting unexpectedly.
+    pub(super) restart_count: u32,
+    /// Where to forward rust-analyzer's progress to the MCP client, if anywhere.
+    pub(super) notifications: Option<mpsc::UnboundedSender<Value>>,
+    /// Watches the workspace for changes made outside of this server while rust-analyzer runs.
+    pub(super) watcher: Option<RecommendedWatcher>,
+    pub(super) options: ServerOptions,
 }
 
 impl RustAnalyzerClient {
-    pub fn new(workspace_root: PathBuf) -> Self {
+    pub fn new(workspace_root: PathBuf, options: ServerOptions) -> Self {
         // Ensure the workspace root is absolute.
         let workspace_root = workspace_root.canonicalize().unwrap_or_else(|_| {
             if workspace_root.is_absolute() {
@@ -50,8 +119,22 @@ impl RustAnalyzerClient {
             stdin: None,
             pending_requests: Arc::new(Mutex::new(HashMap::new())),
             initialized: false,
-            open_documents: Arc::new(Mutex::new(HashSet::new())),
+            server_capabilities: Value::Null,
+            open_documents: Arc::new(Mutex::new(LruCache::new(
+                NonZeroUsize::new(options.config.max_open_documents).unwrap_or(NonZeroUsize::MIN),
+            ))),
             diagnostics: Arc::new(Mutex::new(HashMap::new())),
+            last_version: 0,
+            progress: Arc::new(Mutex::new(ProgressState::default())),
+            server_status: Arc::new(Mutex::new(ServerStatus::default())),
+            settings: Arc::new(Mutex::new(Value::Null)),
+            configurations_sent: Arc::new(AtomicU64::new(0)),
+            alive: Arc::new(AtomicBool::new(false)),
+            edit_generation: Arc::new(AtomicU64::new(0)),
+            restart_count: 0,
+            notifications: None,
+            watcher: None,
+            options,
         }
     }
 
@@ -61,14 +144,19 @@ impl RustAnalyzerClient {
             self.workspace_root.display()
         );
 
-        // Clear any existing diagnostics from previous sessions.
+        // Clear any existing diagnostics and progress from previous sessions.
         self.diagnostics.lock().await.clear();
+        *self.progress.lock().await = ProgressState::default();
+        *self.server_status.lock().await = ServerStatus::default();
 
-        // Find rust-analyzer executable.
-        let rust_analyzer_path = find_rust_analyzer()?;
+        // Find rust-analyzer executable, unless one was given explicitly.
+        let rust_analyzer_path = match &self.options.rust_analyzer_path {
+            Some(path) => path.clone(),
+            None => find_rust_analyzer()?,
+        };
         info!("Using rust-analyzer at: {}", rust_analyzer_path.display());
 
-        let mut cmd = Command::new(rust_analyzer_path);
+        let mut cmd = Command::new(&rust_analyzer_path);
         cmd.current_dir(&self.workspace_root)
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
@@ -85,9 +173,13 @@ impl RustAnalyzerClient {
             cmd.env("TMPDIR", tmpdir);
         }
 
-        let mut child = cmd
-            .spawn()
-            .map_err(|e| anyhow!("Failed to start rust-analyzer: {}", e))?;
+        let mut child = cmd.spawn().map_err(|e| {
+            anyhow!(
+                "Failed to start rust-analyzer at {}: {}",
+                rust_analyzer_path.display(),
+                e
+            )
+        })?;
 
         let stdin = child
             .stdin
@@ -102,15 +194,39 @@ impl RustAnalyzerClient {
             .take()
             .ok_or_else(|| anyhow!("Failed to get stderr"))?;
 
-        self.stdin = Some(BufWriter::new(stdin));
+        let stdin = Arc::new(Mutex::new(BufWriter::new(stdin)));
+        self.stdin = Some(Arc::clone(&stdin));
+        self.watcher = match watch_workspace(
+            &self.workspace_root,
+            Arc::clone(&stdin),
+            Arc::clone(&self.open_documents),
+            Arc::clone(&self.edit_generation),
+            Duration::from_millis(WATCH_DEBOUNCE_MILLIS),
+        ) {
+            Ok(watcher) => Some(watcher),
+            Err(e) => {
+                warn!("Failed to watch the workspace for changes: {}", e);
+                None
+            }
+        };
+        *self.settings.lock().await = self.initialization_options();
 
         // Start connection handlers.
-        super::connection::start_handlers(
-            stdout,
-            stderr,
-            Arc::clone(&self.pending_requests),
-            Arc::clone(&self.diagnostics),
-        );
+        let connection = Connection {
+            stdin,
+            pending: Arc::clone(&self.pending_requests),
+            diagnostics: Arc::clone(&self.diagnostics),
+            open_documents: Arc::clone(&self.open_documents),
+            progress: Arc::clone(&self.progress),
+            server_status: Arc::clone(&self.server_status),
+            settings: Arc::clone(&self.settings),
+            configurations_sent: Arc::clone(&self.configurations_sent),
+            log_messages: self.options.log_lsp_messages,
+            alive: Arc::clone(&self.alive),
+            notifications: self.notifications.clone(),
+        };
+        self.alive.store(true, Ordering::SeqCst);
+        super::connection::start_handlers(stdout, stderr, connection);
 
         self.process = Some(child);
 
@@ -119,27 +235,73 @@ impl RustAnalyzerClient {
         self.initialized = true;
 
         // Send workspace/didChangeConfiguration to ensure settings are applied.
+        let _ = self.send_configuration().await;
+
+        info!("rust-analyzer client started and initialized");
+        Ok(())
+    }
+
+    /// Change the command rust-analyzer runs on save, without restarting it.
+    ///
+    /// The new command is run on the workspace right away, even if no document is open. The
+    /// diagnostics of the open documents are cleared until it reports new ones.
+    pub async fn set_check_command(&mut self, check_command: CheckCommand) -> Result<()> {
+        self.options.config.check_on_save = check_command;
+        let configurations_sent = self.configurations_sent.load(Ordering::SeqCst);
+        self.send_configuration().await?;
+
+        let uris: Vec<String> = self
+            .open_documents
+            .lock()
+            .await
+            .iter()
+            .map(|(uri, _)| uri.clone())
+            .collect();
+        {
+            let mut diagnostics = self.diagnostics.lock().await;
+            for uri in &uris {
+                diagnostics.remove(uri);
+            }
+        }
+        if check_command != CheckCommand::None {
+            // rust-analyzer only applies the settings once it asked for them, so checking before
+            // that would run the old command.
+            let timeout = self.options.config.lsp_request_timeout;
+            let applied = tokio::time::timeout(timeout, async {
+                while self.configurations_sent.load(Ordering::SeqCst) == configurations_sent {
+                    tokio::time::sleep(Duration::from_millis(PROGRESS_POLL_INTERVAL_MILLIS)).await;
+                }
+            })
+            .await;
+            if applied.is_err() {
+                warn!("rust-analyzer didn't ask for the new settings, checking anyway");
+            }
+            self.run_flycheck().await?;
+        }
+
+        Ok(())
+    }
+
+    /// Send the settings through `workspace/didChangeConfiguration`.
+    ///
+    /// rust-analyzer ignores the settings in the notification and asks for them with a
+    /// `workspace/configuration` request, which is answered from the shared settings.
+    async fn send_configuration(&mut self) -> Result<()> {
+        let settings = self.initialization_options();
+        *self.settings.lock().await = settings.clone();
+
         let config_params = json!({
             "settings": {
-                "rust-analyzer": {
-                    "checkOnSave": {
-                        "enable": true,
-                        "command": "check",
-                        "allTargets": true
-                    }
-                }
+                "rust-analyzer": settings
             }
         });
-        let _ = self
-            .send_notification("workspace/didChangeConfiguration", Some(config_params))
-            .await;
 
-        info!("rust-analyzer client started and initialized");
-        Ok(())
+        self.send_notification("workspace/didChangeConfiguration", Some(config_params))
+            .await
     }
 
     pub(super) async fn send_notification(
-        &mut self,
+        &self,
         method: &str,
         params: Option<Value>,
     ) -> Result<()> {
@@ -150,28 +312,81 @@ impl RustAnalyzerClient {
         });
 
         let content = serde_json::to_string(&notification)?;
-        let message = format!("Content-Length: {}\r\n\r\n{}", content.len(), content);
 
         info!("Sending LSP notification: {}", method);
+        if self.options.log_lsp_messages {
+            info!("Sent LSP message: {}", content);
+        }
 
-        let Some(stdin) = &mut self.stdin else {
+        let Some(stdin) = &self.stdin else {
             return Err(anyhow!("No stdin available"));
         };
 
-        stdin.write_all(message.as_bytes()).await?;
-        stdin.flush().await?;
+        write_message(stdin, &content).await?;
         Ok(())
     }
 
+    /// Cancel all the requests still waiting for a response from rust-analyzer, sending it a
+    /// `$/cancelRequest` for each so that it stops working on them.
+    ///
+    /// Tool calls are handled one at a time, so when one is cancelled, the pending requests are
+    /// the ones it sent. Returns the number of requests cancelled.
+    pub async fn cancel_pending_requests(&self) -> Result<usize> {
+        let ids: Vec<u64> = self
+            .pending_requests
+            .lock()
+            .await
+            .drain()
+            .map(|(id, _)| id)
+            .collect();
+
+        for id in &ids {
+            info!("Cancelling LSP request {}", id);
+            self.send_notification("$/cancelRequest", Some(json!({ "id": id })))
+                .await?;
+        }
+
+        Ok(ids.len())
+    }
+
+    /// Whether rust-analyzer is running. It's `false` before the client is started and after
+    /// rust-analyzer exited or crashed.
+    pub fn is_alive(&self) -> bool {
+        self.alive.load(Ordering::SeqCst)
+    }
+
+    /// Like [`Self::is_alive`] but also checks the process itself, in case its exit wasn't
+    /// noticed yet.
+    pub fn is_process_running(&mut self) -> bool {
+        let running = self
+            .process
+            .as_mut()
+            .is_some_and(|process| matches!(process.try_wait(), Ok(None)));
+
+        running && self.is_alive()
+    }
+
+    /// The timeout of requests in `category`.
+    pub(super) fn timeout(&self, category: TimeoutCategory) -> Duration {
+        self.options.config.timeouts.get(category)
+    }
+
+    /// Send a request and wait up to `timeout` for its response.
+    ///
+    /// No lock is held while waiting, so any number of requests can be in flight at once; their
+    /// responses are matched by id in the connection handler.
     pub(super) async fn send_request(
-        &mut self,
+        &self,
         method: &str,
         params: Option<Value>,
+        timeout: Duration,
     ) -> Result<Value> {
-        let mut request_id_lock = self.request_id.lock().await;
-        let id = *request_id_lock;
-        *request_id_lock += 1;
-        drop(request_id_lock);
+        let id = {
+            let mut request_id = self.request_id.lock().await;
+            let id = *request_id;
+            *request_id += 1;
+            id
+        };
 
         let request = LSPRequest {
             jsonrpc: "2.0".to_string(),
@@ -181,53 +396,72 @@ impl RustAnalyzerClient {
         };
 
         let content = serde_json::to_string(&request)?;
-        let message = format!("Content-Length: {}\r\n\r\n{}", content.len(), content);
 
         info!("Sending LSP request: {} with params: {:?}", method, params);
+        if self.options.log_lsp_messages {
+            info!("Sent LSP message: {}", content);
+        }
 
-        let Some(stdin) = &mut self.stdin else {
-            return Err(anyhow!("No stdin available"));
+        let Some(stdin) = &self.stdin else {
+            return Err(ToolError::new(ErrorKind::LspRequestFailed, "No stdin available").into());
         };
+        if !self.is_alive() {
+            return Err(ToolError::new(
+                ErrorKind::RustAnalyzerCrashed,
+                "rust-analyzer is not running",
+            )
+            .into());
+        }
 
-        stdin.write_all(message.as_bytes()).await?;
-        stdin.flush().await?;
-
-        // Set up response channel.
+        // Register the response channel before sending, so that a fast response can't arrive
+        // before anyone is waiting for it.
         let (tx, rx) = oneshot::channel();
         self.pending_requests.lock().await.insert(id, tx);
 
+        if let Err(e) = write_message(stdin, &content).await {
+            self.pending_requests.lock().await.remove(&id);
+            return Err(ToolError::new(
+                ErrorKind::LspRequestFailed,
+                format!("Failed to send {} request: {}", method, e),
+            )
+            .into());
+        }
+
         // Wait for response with timeout.
-        tokio::time::timeout(Duration::from_secs(LSP_REQUEST_TIMEOUT_SECS), rx)
-            .await
-            .map_err(|_| anyhow!("Request timeout"))?
-            .map_err(|_| anyhow!("Request cancelled"))
+        match tokio::time::timeout(timeout, rx).await {
+            Ok(Ok(Ok(result))) => Ok(result),
+            Ok(Ok(Err(error))) => {
+                let message = error["message"].as_str().unwrap_or("unknown error");
+                Err(ToolError::new(
+                    ErrorKind::LspRequestFailed,
+                    format!("{} failed: {}", method, message),
+                )
+                .into())
+            }
+            Ok(Err(_)) => {
+                if self.is_alive() {
+                    Err(ToolError::new(ErrorKind::LspRequestFailed, "Request cancelled").into())
+                } else {
+                    Err(ToolError::new(
+                        ErrorKind::RustAnalyzerCrashed,
+                        "rust-analyzer exited before responding",
+                    )
+                    .into())
+                }
+            }
+            Err(_) => {
+                self.pending_requests.lock().await.remove(&id);
+                Err(ToolError::new(ErrorKind::LspTimeout, "Request timeout").into())
+            }
+        }
     }
 
-    async fn initialize(&mut self) -> Result<()> {
-        let init_params = json!({
+    /// The params of the `initialize` request.
+    pub fn initialize_params(&self) -> Value {
+        json!({
             "processId": std::process::id(),
             "rootUri": format!("file://{}", self.workspace_root.display()),
-            "initializationOptions": {
-                "cargo": {
-                    "buildScripts": {
-                        "enable": true
-                    }
-                },
-                "checkOnSave": {
-                    "enable": true,
-                    "command": "check",
-                    "allTargets": true
-                },
-                "diagnostics": {
-                    "enable": true,
-                    "experimental": {
-                        "enable": true
-                    }
-                },
-                "procMacro": {
-                    "enable": true
-                }
-            },
+            "initializationOptions": self.initialization_options(),
             "capabilities": {
                 "textDocument": {
                     "hover": {
@@ -235,14 +469,24 @@ impl RustAnalyzerClient {
                     },
                     "completion": {
                         "completionItem": {
-                            "snippetSupport": true
-                        }
+                            "snippetSupport": true,
+                            "documentationFormat": ["markdown", "plaintext"],
+                            "resolveSupport": {
+                                "properties": ["documentation", "detail"]
+                            }
+                        },
+                        "contextSupport": true
                     },
                     "definition": {
                         "linkSupport": true
                     },
+                    "declaration": {
+                        "linkSupport": true
+                    },
                     "references": {},
-                    "documentSymbol": {},
+                    "documentSymbol": {
+                        "hierarchicalDocumentSymbolSupport": true
+                    },
                     "codeAction": {
                         "codeActionLiteralSupport": {
                             "codeActionKind": {
@@ -267,22 +511,101 @@ impl RustAnalyzerClient {
                             "valueSet": [1, 2]
                         }
                     },
-                    "formatting": {}
+                    "formatting": {},
+                    "rangeFormatting": {},
+                    "codeLens": {},
+                    // rust-analyzer announces its own token types and modifiers in its legend.
+                    "semanticTokens": {
+                        "requests": {
+                            "full": true
+                        },
+                        "tokenTypes": [],
+                        "tokenModifiers": [],
+                        "formats": ["relative"]
+                    }
+                },
+                "window": {
+                    "workDoneProgress": true
+                },
+                "experimental": {
+                    "serverStatusNotification": true,
+                    "hoverRange": true,
+                    // rust-analyzer only shows the code lenses whose commands the client supports.
+                    "commands": {
+                        "commands": [
+                            "rust-analyzer.runSingle",
+                            "rust-analyzer.debugSingle",
+                            "rust-analyzer.showReferences",
+                            "rust-analyzer.gotoLocation"
+                        ]
+                    }
                 },
                 "workspace": {
                     "didChangeConfiguration": {
                         "dynamicRegistration": false
+                    },
+                    "configuration": true,
+                    // The workspace is watched by `watch_workspace` rather than by rust-analyzer.
+                    "didChangeWatchedFiles": {
+                        "dynamicRegistration": true
+                    },
+                    "fileOperations": {
+                        "didCreate": true,
+                        "willRename": true,
+                        "didRename": true
                     }
                 }
             }
+        })
+    }
+
+    /// The rust-analyzer settings: our defaults, with the user's init options merged in.
+    fn initialization_options(&self) -> Value {
+        let mut options = json!({
+            "cargo": {
+                "buildScripts": {
+                    "enable": true
+                }
+            },
+            "checkOnSave": check_on_save_settings(self.options.config.check_on_save),
+            "check": {
+                "command": check_command_name(self.options.config.check_on_save)
+            },
+            "diagnostics": {
+                "enable": true,
+                "experimental": {
+                    "enable": true
+                }
+            },
+            "procMacro": {
+                "enable": true
+            },
+            "rustfmt": {
+                "rangeFormatting": {
+                    "enable": true
+                }
+            }
         });
+        if let Some(init_options) = &self.options.init_options {
+            merge_json(&mut options, init_options);
+        }
 
-        self.send_request("initialize", Some(init_params)).await?;
+        options
+    }
+
+    async fn initialize(&mut self) -> Result<()> {
+        let init_params = self.initialize_params();
+
+        let timeout = self.timeout(TimeoutCategory::Workspace);
+        let result = self
+            .send_request("initialize", Some(init_params), timeout)
+            .await?;
+        self.server_capabilities = result["capabilities"].clone();
         self.send_notification("initialized", Some(json!({})))
             .await?;
 
         // Request workspace reload to trigger cargo check.
-        self.send_request("rust-analyzer/reloadWorkspace", None)
+        self.send_request("rust-analyzer/reloadWorkspace", None, timeout)
             .await
             .ok();
 
@@ -290,13 +613,63 @@ impl RustAnalyzerClient {
     }
 
     pub async fn open_document(&mut self, uri: &str, content: &str) -> Result<()> {
-        // Check if document is already open.
-        {
-            let open_docs = self.open_documents.lock().await;
-            if open_docs.contains(uri) {
-                info!("Document already open: {}", uri);
-                return Ok(());
+        if !self.ensure_open(uri, content).await? {
+            return Ok(());
+        }
+
+        // Send didSave to trigger cargo check.
+        let save_params = json!({
+            "textDocument": {
+                "uri": uri
+            }
+        });
+        self.send_notification("textDocument/didSave", Some(save_params))
+            .await?;
+
+        // Give rust-analyzer time to process the document and run cargo check.
+        tokio::time::sleep(self.options.config.document_open_delay).await;
+
+        Ok(())
+    }
+
+    /// Open a document unless it already is, closing the least recently used one if there are too
+    /// many open.
+    ///
+    /// If the document is already open but its content changed since (e.g. it was edited on disk),
+    /// rust-analyzer gets the new content through [`Self::update_document`].
+    ///
+    /// Returns whether the document was opened, as opposed to already being open.
+    async fn ensure_open(&mut self, uri: &str, content: &str) -> Result<bool> {
+        // Check if document is already open, marking it as recently used if so.
+        let evicted = {
+            let mut open_docs = self.open_documents.lock().await;
+            if let Some(state) = open_docs.get_mut(uri) {
+                state.last_access = Instant::now();
+                if state.content_hash == content_hash(content) {
+                    info!("Document already open: {}", uri);
+                    return Ok(false);
+                }
+                drop(open_docs);
+
+                info!("Document changed since it was opened: {}", uri);
+                self.update_document(uri, content).await?;
+                return Ok(false);
+            }
+
+            if open_docs.len() == open_docs.cap().get() {
+                open_docs.pop_lru().map(|(uri, _)| uri)
+            } else {
+                None
             }
+        };
+
+        // Make room for the document by closing the least recently used one.
+        if let Some(evicted) = evicted {
+            info!("Closing least recently used document: {}", evicted);
+            self.diagnostics.lock().await.remove(&evicted);
+            let params = json!({ "textDocument": { "uri": evicted } });
+            self.send_notification("textDocument/didClose", Some(params))
+                .await?;
         }
 
         // Clear any existing diagnostics for this URI to ensure fresh data.
@@ -305,26 +678,199 @@ impl RustAnalyzerClient {
             diag_lock.remove(uri);
         }
 
+        self.did_open(uri, content).await?;
+
+        Ok(true)
+    }
+
+    /// Open `documents`, given as URI and content pairs, to get them analyzed and collect the
+    /// diagnostics rust-analyzer publishes for them.
+    ///
+    /// Unlike [`Self::open_document`], this doesn't trigger a cargo check or wait a fixed delay per
+    /// document. The documents are opened as many at a time as can stay open, and the diagnostics
+    /// of each batch are collected before the next one evicts it. Documents whose diagnostics
+    /// weren't published in time are left out.
+    pub async fn collect_diagnostics(
+        &mut self,
+        documents: &[(String, String)],
+    ) -> Result<HashMap<String, Vec<Value>>> {
+        let poll_interval = Duration::from_millis(PROGRESS_POLL_INTERVAL_MILLIS);
+
+        // Documents opened while indexing only get their diagnostics once it's done.
+        let deadline = Instant::now() + self.timeout(TimeoutCategory::Workspace);
+        while self.is_indexing().await && Instant::now() < deadline {
+            tokio::time::sleep(poll_interval).await;
+        }
+
+        let batch_size = self.open_documents.lock().await.cap().get();
+        let mut collected = HashMap::new();
+        for batch in documents.chunks(batch_size) {
+            for (uri, content) in batch {
+                self.ensure_open(uri, content).await?;
+            }
+
+            let deadline = Instant::now() + self.timeout(TimeoutCategory::Diagnostics);
+            loop {
+                let diagnostics = self.diagnostics.lock().await;
+                let published = batch.iter().all(|(uri, _)| diagnostics.contains_key(uri));
+                if published || Instant::now() >= deadline {
+                    for (uri, _) in batch {
+                        if let Some(published) = diagnostics.get(uri) {
+                            collected.insert(uri.clone(), published.diagnostics.clone());
+                        }
+                    }
+                    break;
+                }
+                drop(diagnostics);
+
+                tokio::time::sleep(poll_interval).await;
+            }
+        }
+
+        Ok(collected)
+    }
+
+    /// Send `didOpen` for a document and mark it as open.
+    async fn did_open(&mut self, uri: &str, content: &str) -> Result<()> {
         info!("Opening document: {}", uri);
+        let version = self.next_version();
         let params = json!({
             "textDocument": {
                 "uri": uri,
                 "languageId": "rust",
-                "version": 1,
+                "version": version,
                 "text": content
             }
         });
 
-        self.send_notification("textDocument/didOpen", Some(params.clone()))
+        self.send_notification("textDocument/didOpen", Some(params))
             .await?;
 
-        // Mark document as open.
-        {
+        let mtime = file_mtime(uri).await;
+        self.open_documents.lock().await.put(
+            uri.to_string(),
+            DocumentState {
+                version,
+                mtime,
+                content_hash: content_hash(content),
+                last_access: Instant::now(),
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Close a document if it's open, dropping its diagnostics.
+    ///
+    /// Returns whether the document was open.
+    pub async fn close_document(&mut self, uri: &str) -> Result<bool> {
+        if self.open_documents.lock().await.pop(uri).is_none() {
+            return Ok(false);
+        }
+        self.diagnostics.lock().await.remove(uri);
+
+        info!("Closing document: {}", uri);
+        let params = json!({ "textDocument": { "uri": uri } });
+        self.send_notification("textDocument/didClose", Some(params))
+            .await?;
+
+        Ok(true)
+    }
+
+    /// Describe the documents open in rust-analyzer, most recently used first.
+    ///
+    /// Each entry has the document's version and the number of errors and warnings last published
+    /// for it. `has_diagnostics` is false if none were published yet.
+    pub async fn open_documents(&self) -> Value {
+        let open_docs = self.open_documents.lock().await;
+        let diagnostics = self.diagnostics.lock().await;
+
+        let documents: Vec<Value> = open_docs
+            .iter()
+            .map(|(uri, state)| {
+                let diags = diagnostics.get(uri);
+                let count = |severity: u64| {
+                    diags.map_or(0, |published| {
+                        published
+                            .diagnostics
+                            .iter()
+                            .filter(|d| d["severity"].as_u64() == Some(severity))
+                            .count()
+                    })
+                };
+
+                json!({
+                    "uri": uri,
+                    "version": state.version,
+                    "has_diagnostics": diags.is_some(),
+                    "error_count": count(1),
+                    "warning_count": count(2)
+                })
+            })
+            .collect();
+
+        json!(documents)
+    }
+
+    /// The version to give to a document being opened or changed.
+    fn next_version(&mut self) -> i32 {
+        self.last_version += 1;
+        self.last_version
+    }
+
+    /// Whether rust-analyzer published diagnostics for the current content of `uri`, which may be
+    /// none at all.
+    ///
+    /// The diagnostics of a document are dropped when it changes, until new ones are published.
+    pub async fn has_published_diagnostics(&self, uri: &str) -> bool {
+        self.diagnostics.lock().await.contains_key(uri)
+    }
+
+    /// Get the version of the document the diagnostics of `uri` were published for, if they were
+    /// and rust-analyzer said.
+    pub async fn diagnostics_version(&self, uri: &str) -> Option<i32> {
+        self.diagnostics.lock().await.get(uri)?.version
+    }
+
+    /// Number of document changes made through [`Self::update_document`] or picked up by the
+    /// workspace watcher so far.
+    ///
+    /// Analysis results obtained before a change may be outdated, including the ones about other
+    /// files.
+    pub fn edit_generation(&self) -> u64 {
+        self.edit_generation.load(Ordering::SeqCst)
+    }
+
+    /// Notify rust-analyzer that the content of a document changed on disk.
+    ///
+    /// If the document is open, the new content is sent with `didChange` and a bumped version.
+    /// `didSave` is sent either way so that cargo check runs again. The diagnostics of the document
+    /// are dropped until rust-analyzer publishes the ones of the new content.
+    pub async fn update_document(&mut self, uri: &str, content: &str) -> Result<()> {
+        self.edit_generation.fetch_add(1, Ordering::SeqCst);
+        self.diagnostics.lock().await.remove(uri);
+        let next_version = self.next_version();
+        let version = {
             let mut open_docs = self.open_documents.lock().await;
-            open_docs.insert(uri.to_string());
+            open_docs.get_mut(uri).map(|state| {
+                state.version = next_version;
+                state.version
+            })
+        };
+
+        if let Some(version) = version {
+            info!("Updating document: {} (version {})", uri, version);
+            let params = json!({
+                "textDocument": {
+                    "uri": uri,
+                    "version": version
+                },
+                "contentChanges": [{ "text": content }]
+            });
+            self.send_notification("textDocument/didChange", Some(params))
+                .await?;
         }
 
-        // Send didSave to trigger cargo check.
         let save_params = json!({
             "textDocument": {
                 "uri": uri
@@ -333,18 +879,76 @@ impl RustAnalyzerClient {
         self.send_notification("textDocument/didSave", Some(save_params))
             .await?;
 
-        // Give rust-analyzer time to process the document and run cargo check.
-        tokio::time::sleep(Duration::from_millis(DOCUMENT_OPEN_DELAY_MILLIS)).await;
+        let mtime = file_mtime(uri).await;
+        if let Some(state) = self.open_documents.lock().await.get_mut(uri) {
+            state.mtime = mtime;
+            state.content_hash = content_hash(content);
+        }
+
+        Ok(())
+    }
+
+    /// Restart rust-analyzer after it exited unexpectedly, e.g. because it crashed or ran out of
+    /// memory.
+    ///
+    /// Requests still waiting for a response are failed and the documents that were open are
+    /// opened again with their content on disk.
+    pub async fn restart(&mut self) -> Result<()> {
+        // Oldest first, so that they're reopened in the same LRU order.
+        let reopen: Vec<String> = self
+            .open_documents
+            .lock()
+            .await
+            .iter()
+            .rev()
+            .map(|(uri, _)| uri.clone())
+            .collect();
+
+        // There's no point in asking a dead process to shut down.
+        self.initialized = false;
+        self.shutdown().await?;
+        self.pending_requests.lock().await.clear();
+        self.stdin = None;
+
+        self.restart_count += 1;
+        warn!(
+            "Restarting rust-analyzer (restart #{}), reopening {} documents",
+            self.restart_count,
+            reopen.len()
+        );
+        self.start().await?;
+
+        for uri in reopen {
+            let Some(path) = uri_to_path(&uri) else {
+                continue;
+            };
+            match tokio::fs::read_to_string(&path).await {
+                Ok(content) => self.did_open(&uri, &content).await?,
+                Err(e) => debug!("Not reopening {}: {}", uri, e),
+            }
+        }
 
         Ok(())
     }
 
+    /// Forward rust-analyzer's progress, as MCP notifications, to `notifications` once started.
+    pub fn set_notifications(&mut self, notifications: mpsc::UnboundedSender<Value>) {
+        self.notifications = Some(notifications);
+    }
+
+    /// Number of times rust-analyzer was restarted by [`Self::restart`].
+    pub fn restart_count(&self) -> u32 {
+        self.restart_count
+    }
+
     pub async fn shutdown(&mut self) -> Result<()> {
         if self.initialized {
-            let _ = self.send_request("shutdown", None).await;
+            let timeout = self.options.config.lsp_request_timeout;
+            let _ = self.send_request("shutdown", None, timeout).await;
             let _ = self.send_notification("exit", None).await;
         }
 
+        self.watcher = None;
         if let Some(mut process) = self.process.take() {
             // Kill the process and wait for it to actually exit.
             let _ = process.kill().await;
@@ -359,21 +963,61 @@ impl RustAnalyzerClient {
     }
 }
 
-fn find_rust_analyzer() -> Result<PathBuf> {
-    which::which("rust-analyzer").or_else(|_| {
-        // Try common installation locations if not in PATH.
-        let home = std::env::var("HOME").unwrap_or_else(|_| String::from("~"));
-        let cargo_bin = PathBuf::from(home).join(".cargo/bin/rust-analyzer");
-        if cargo_bin.exists() {
-            Ok(cargo_bin)
-        } else {
-            which::which("rust-analyzer")
-        }
-    })
-    .map_err(|e| {
-        anyhow!(
-            "Failed to find rust-analyzer in PATH or ~/.cargo/bin: {}. Please ensure rust-analyzer is installed.",
-            e
-        )
-    })
+fn content_hash(content: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish()
+}
+
+async fn file_mtime(uri: &str) -> Option<SystemTime> {
+    let path = uri_to_path(uri)?;
+    tokio::fs::metadata(path).await.ok()?.modified().ok()
+}
+
+/// The cargo command for `check_command`, as expected by the `check.command` setting.
+///
+/// The command doesn't matter if checking is disabled, in which case the default is used.
+fn check_command_name(check_command: CheckCommand) -> &'static str {
+    match check_command {
+        CheckCommand::Check | CheckCommand::None => "check",
+        CheckCommand::Clippy => "clippy",
+    }
+}
+
+/// The `checkOnSave` settings for `check_command`.
+fn check_on_save_settings(check_command: CheckCommand) -> Value {
+    match check_command {
+        CheckCommand::Check => json!({
+            "enable": true,
+            "command": "check",
+            "allTargets": true
+        }),
+        CheckCommand::Clippy => json!({
+            "enable": true,
+            "command": "clippy",
+            "allTargets": true
+        }),
+        CheckCommand::None => json!({ "enable": false }),
+    }
+}
+
+pub(super) fn find_rust_analyzer() -> Result<PathBuf> {
+    which::which("rust-analyzer")
+        .or_else(|_| {
+            // Try common installation locations if not in PATH.
+            let home = std::env::var("HOME").unwrap_or_else(|_| String::from("~"));
+            let cargo_bin = PathBuf::from(home).join(".cargo/bin/rust-analyzer");
+            if cargo_bin.exists() {
+                Ok(cargo_bin)
+            } else {
+                which::which("rust-analyzer")
+            }
+        })
+        .map_err(|e| {
+            anyhow!(
+                "Failed to find rust-analyzer in PATH or ~/.cargo/bin: {}. {}",
+                e,
+                INSTALL_HINT
+            )
+        })
 }
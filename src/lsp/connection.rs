@@ -1,24 +1,78 @@
-use log::{debug, error, info};
-use serde_json::Value;
-use std::{collections::HashMap, sync::Arc};
+use anyhow::Result;
+use log::{debug, error, info, warn};
+use lru::LruCache;
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+};
 use tokio::{
-    io::{AsyncBufReadExt, AsyncReadExt, BufReader},
-    sync::{oneshot, Mutex},
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader, BufWriter},
+    process::ChildStdin,
+    sync::{mpsc, oneshot, Mutex},
+};
+
+use crate::protocol::{framing::Framing, lsp::LSPResponse};
+
+use super::{
+    client::{DocumentState, PublishedDiagnostics},
+    progress::ProgressState,
+    status::ServerStatus,
 };
 
-use crate::protocol::lsp::LSPResponse;
+/// Send the JSON-RPC message `content` to rust-analyzer over its `stdin`.
+pub async fn write_message(
+    stdin: &Mutex<BufWriter<ChildStdin>>,
+    content: &str,
+) -> std::io::Result<()> {
+    let message = Framing::ContentLength.frame(content);
+    let mut stdin = stdin.lock().await;
+    stdin.write_all(message.as_bytes()).await?;
+    stdin.flush().await
+}
+
+/// The response channels of the requests sent to rust-analyzer, by request id. An error response
+/// is delivered as `Err` with the JSON-RPC error object.
+pub type PendingRequests = Arc<Mutex<HashMap<u64, oneshot::Sender<Result<Value, Value>>>>>;
+
+/// Shared state updated from messages sent by rust-analyzer.
+pub struct Connection {
+    pub stdin: Arc<Mutex<BufWriter<ChildStdin>>>,
+    pub pending: PendingRequests,
+    pub diagnostics: Arc<Mutex<HashMap<String, PublishedDiagnostics>>>,
+    /// The open documents, to tell diagnostics published for an outdated version apart.
+    pub open_documents: Arc<Mutex<LruCache<String, DocumentState>>>,
+    pub progress: Arc<Mutex<ProgressState>>,
+    pub server_status: Arc<Mutex<ServerStatus>>,
+    /// The rust-analyzer settings, returned for `workspace/configuration` requests.
+    pub settings: Arc<Mutex<Value>>,
+    /// Number of `workspace/configuration` requests answered, to tell when rust-analyzer has
+    /// received new settings.
+    pub configurations_sent: Arc<AtomicU64>,
+    /// Log the raw messages received, rather than only in debug logs.
+    pub log_messages: bool,
+    /// Cleared when rust-analyzer's stdout is closed, i.e. when it exited or crashed.
+    pub alive: Arc<AtomicBool>,
+    /// Where to forward rust-analyzer's progress as MCP `$/progress` notifications, if anywhere.
+    ///
+    /// They're written by the MCP server rather than by this task, so that a slow MCP client
+    /// doesn't hold up rust-analyzer's responses.
+    pub notifications: Option<mpsc::UnboundedSender<Value>>,
+}
 
 pub fn start_handlers(
     stdout: tokio::process::ChildStdout,
     stderr: tokio::process::ChildStderr,
-    pending_requests: Arc<Mutex<HashMap<u64, oneshot::Sender<Value>>>>,
-    diagnostics: Arc<Mutex<HashMap<String, Vec<Value>>>>,
+    connection: Connection,
 ) {
     // Log stderr in background.
     tokio::spawn(handle_stderr(stderr));
 
     // Start response handler task.
-    tokio::spawn(handle_stdout(stdout, pending_requests, diagnostics));
+    tokio::spawn(handle_stdout(stdout, connection));
 }
 
 async fn handle_stderr(stderr: tokio::process::ChildStderr) {
@@ -46,11 +100,7 @@ async fn handle_stderr(stderr: tokio::process::ChildStderr) {
     }
 }
 
-async fn handle_stdout(
-    stdout: tokio::process::ChildStdout,
-    pending: Arc<Mutex<HashMap<u64, oneshot::Sender<Value>>>>,
-    diagnostics: Arc<Mutex<HashMap<String, Vec<Value>>>>,
-) {
+async fn handle_stdout(stdout: tokio::process::ChildStdout, connection: Connection) {
     let mut reader = BufReader::new(stdout);
     let mut buffer = String::new();
 
@@ -88,10 +138,20 @@ async fn handle_stdout(
         };
 
         let response_str = String::from_utf8_lossy(&json_buffer);
-        debug!("Received LSP message: {}", response_str);
+        if connection.log_messages {
+            info!("Received LSP message: {}", response_str);
+        } else {
+            debug!("Received LSP message: {}", response_str);
+        }
 
-        handle_lsp_message(&json_buffer, &pending, &diagnostics).await;
+        handle_lsp_message(&json_buffer, &connection).await;
     }
+
+    // rust-analyzer is gone, so no response will ever come. Dropping the senders of the pending
+    // requests makes them fail right away instead of waiting for their timeout.
+    warn!("rust-analyzer stdout closed, it has most likely exited");
+    connection.alive.store(false, Ordering::SeqCst);
+    connection.pending.lock().await.clear();
 }
 
 fn parse_content_length(header: &str) -> Option<usize> {
@@ -100,11 +160,7 @@ fn parse_content_length(header: &str) -> Option<usize> {
         .and_then(|s| s.trim().parse().ok())
 }
 
-async fn handle_lsp_message(
-    json_buffer: &[u8],
-    pending: &Arc<Mutex<HashMap<u64, oneshot::Sender<Value>>>>,
-    diagnostics: &Arc<Mutex<HashMap<String, Vec<Value>>>>,
-) {
+async fn handle_lsp_message(json_buffer: &[u8], connection: &Connection) {
     let Ok(json_value) = serde_json::from_slice::<Value>(json_buffer) else {
         error!(
             "Failed to parse LSP message: {}",
@@ -115,7 +171,14 @@ async fn handle_lsp_message(
 
     // Check if it's a notification (has method but no id).
     if json_value.get("method").is_some() && json_value.get("id").is_none() {
-        handle_notification(json_value, diagnostics).await;
+        handle_notification(json_value, connection).await;
+        return;
+    }
+
+    // Requests from the server have both a method and an id. Their ids are unrelated to ours so
+    // they must not be matched against pending requests.
+    if json_value.get("method").is_some() {
+        handle_server_request(json_value, connection).await;
         return;
     }
 
@@ -128,35 +191,175 @@ async fn handle_lsp_message(
         return;
     };
 
-    let mut pending_lock = pending.lock().await;
+    let mut pending_lock = connection.pending.lock().await;
     let Some(sender) = pending_lock.remove(&id) else {
         return;
     };
 
     if let Some(error) = response.error {
         error!("LSP error for request {}: {}", id, error);
-        let _ = sender.send(serde_json::json!(null));
+        let _ = sender.send(Err(error));
     } else {
         let result = response.result.unwrap_or(serde_json::json!(null));
         info!("Sending result for request {}: {:?}", id, result);
-        let _ = sender.send(result);
+        let _ = sender.send(Ok(result));
     }
 }
 
-async fn handle_notification(
-    json_value: Value,
-    diagnostics: &Arc<Mutex<HashMap<String, Vec<Value>>>>,
-) {
+async fn handle_server_request(request: Value, connection: &Connection) {
+    let method = request["method"].as_str().unwrap_or_default();
+    debug!("Received server request: {}", method);
+
+    let response = match method {
+        "workspace/configuration" => {
+            let settings = connection.settings.lock().await;
+            let items = request["params"]["items"].as_array().cloned();
+            // One result per requested item; we only have settings for the `rust-analyzer` section.
+            let result: Vec<Value> = items
+                .unwrap_or_default()
+                .iter()
+                .map(|item| match item["section"].as_str() {
+                    Some("rust-analyzer") => settings.clone(),
+                    _ => Value::Null,
+                })
+                .collect();
+            json!({
+                "jsonrpc": "2.0",
+                "id": request["id"],
+                "result": result
+            })
+        }
+        "window/workDoneProgress/create" => {
+            forward_progress(
+                connection,
+                json!({
+                    "token": request["params"]["token"],
+                    "kind": "create"
+                }),
+            );
+            json!({
+                "jsonrpc": "2.0",
+                "id": request["id"],
+                "result": null
+            })
+        }
+        "client/registerCapability" | "client/unregisterCapability" => json!({
+            "jsonrpc": "2.0",
+            "id": request["id"],
+            "result": null
+        }),
+        _ => json!({
+            "jsonrpc": "2.0",
+            "id": request["id"],
+            "error": {
+                "code": -32601,
+                "message": format!("Method not found: {}", method)
+            }
+        }),
+    };
+
+    if let Err(e) = write_message(&connection.stdin, &response.to_string()).await {
+        error!("Failed to respond to server request {}: {}", method, e);
+        return;
+    }
+    if method == "workspace/configuration" {
+        connection
+            .configurations_sent
+            .fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+async fn handle_notification(json_value: Value, connection: &Connection) {
     let Some(method) = json_value.get("method").and_then(|m| m.as_str()) else {
         return;
     };
 
     debug!("Received notification: {}", method);
 
-    if method != "textDocument/publishDiagnostics" {
+    match method {
+        "textDocument/publishDiagnostics" => {
+            handle_publish_diagnostics(&json_value, connection).await
+        }
+        "$/progress" => {
+            let Some(params) = json_value.get("params") else {
+                return;
+            };
+            let token = match &params["token"] {
+                Value::String(token) => token.clone(),
+                token => token.to_string(),
+            };
+
+            let mut progress = connection.progress.lock().await;
+            // Only `begin` has a title, so look it up before `end` forgets it.
+            let title = match params["value"]["title"].as_str() {
+                Some(title) => Some(title.to_string()),
+                None => progress.active.get(&token).map(|info| info.title.clone()),
+            };
+            progress.update(params);
+            drop(progress);
+
+            let value = &params["value"];
+            forward_progress(
+                connection,
+                json!({
+                    "token": params["token"],
+                    "kind": value["kind"],
+                    "title": title,
+                    "message": value["message"],
+                    "percentage": value["percentage"]
+                }),
+            );
+        }
+        "experimental/serverStatus" => {
+            let status = ServerStatus::from_params(&json_value["params"]);
+            debug!("rust-analyzer status: {:?}", status);
+            let previous =
+                std::mem::replace(&mut *connection.server_status.lock().await, status.clone());
+            // rust-analyzer repeats its status, e.g. after every change, but only changes matter.
+            if previous != status {
+                let mut params = status.to_json();
+                params["kind"] = json!("status");
+                forward_progress(connection, params);
+            }
+        }
+        "window/showMessage" => {
+            let params = &json_value["params"];
+            let level = match params["type"].as_u64() {
+                Some(1) => "error",
+                Some(2) => "warning",
+                Some(3) => "info",
+                _ => "log",
+            };
+            forward_progress(
+                connection,
+                json!({
+                    "kind": "message",
+                    "level": level,
+                    "message": params["message"]
+                }),
+            );
+        }
+        _ => {}
+    }
+}
+
+/// Forward a `$/progress` notification with `params` to the MCP server, if it wants them.
+fn forward_progress(connection: &Connection, params: Value) {
+    let Some(notifications) = &connection.notifications else {
         return;
+    };
+
+    let notification = json!({
+        "jsonrpc": "2.0",
+        "method": "$/progress",
+        "params": params
+    });
+    if notifications.send(notification).is_err() {
+        debug!("Dropped progress notification, the MCP server is gone");
     }
+}
 
+async fn handle_publish_diagnostics(json_value: &Value, connection: &Connection) {
     let Some(params) = json_value.get("params") else {
         return;
     };
@@ -169,7 +372,39 @@ async fn handle_notification(
         return;
     };
 
-    let mut diag_lock = diagnostics.lock().await;
-    diag_lock.insert(uri.to_string(), diags.clone());
+    // Diagnostics computed before the last change would be taken for those of the new content, or
+    // clobber newer ones arriving late.
+    let version = params
+        .get("version")
+        .and_then(|v| v.as_i64())
+        .and_then(|v| i32::try_from(v).ok());
+    // The open documents are locked before the diagnostics elsewhere, so never while holding them.
+    let current = connection
+        .open_documents
+        .lock()
+        .await
+        .peek(uri)
+        .map(|state| state.version);
+    let mut diag_lock = connection.diagnostics.lock().await;
+    if let Some(version) = version {
+        let stored = diag_lock.get(uri).and_then(|published| published.version);
+        if current.is_some_and(|current| version < current)
+            || stored.is_some_and(|stored| version < stored)
+        {
+            info!(
+                "Ignoring diagnostics for outdated version {} of {}",
+                version, uri
+            );
+            return;
+        }
+    }
+
+    diag_lock.insert(
+        uri.to_string(),
+        PublishedDiagnostics {
+            version,
+            diagnostics: diags.clone(),
+        },
+    );
     info!("Stored {} diagnostics for {}", diags.len(), uri);
 }
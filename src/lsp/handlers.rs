@@ -2,16 +2,40 @@ use anyhow::Result;
 use log::info;
 use serde_json::{json, Value};
 
-use super::client::RustAnalyzerClient;
+use crate::config::TimeoutCategory;
+
+use super::{client::RustAnalyzerClient, status::ServerStatus};
 
 impl RustAnalyzerClient {
-    pub async fn hover(&mut self, uri: &str, line: u32, character: u32) -> Result<Value> {
+    /// Hover a position, or with `end`, the expression between the position and `end` to get its
+    /// type, using rust-analyzer's hover range extension.
+    pub async fn hover(
+        &mut self,
+        uri: &str,
+        line: u32,
+        character: u32,
+        end: Option<(u32, u32)>,
+    ) -> Result<Value> {
+        let start = json!({ "line": line, "character": character });
+        // The extension allows a range in place of the position.
+        let position = match end {
+            Some((end_line, end_character)) => json!({
+                "start": start,
+                "end": { "line": end_line, "character": end_character }
+            }),
+            None => start,
+        };
         let params = json!({
             "textDocument": { "uri": uri },
-            "position": { "line": line, "character": character }
+            "position": position
         });
 
-        self.send_request("textDocument/hover", Some(params)).await
+        self.send_request(
+            "textDocument/hover",
+            Some(params),
+            self.timeout(TimeoutCategory::Hover),
+        )
+        .await
     }
 
     pub async fn definition(&mut self, uri: &str, line: u32, character: u32) -> Result<Value> {
@@ -20,29 +44,175 @@ impl RustAnalyzerClient {
             "position": { "line": line, "character": character }
         });
 
-        self.send_request("textDocument/definition", Some(params))
-            .await
+        self.send_request(
+            "textDocument/definition",
+            Some(params),
+            self.timeout(TimeoutCategory::Definition),
+        )
+        .await
+    }
+
+    pub async fn declaration(&mut self, uri: &str, line: u32, character: u32) -> Result<Value> {
+        let params = json!({
+            "textDocument": { "uri": uri },
+            "position": { "line": line, "character": character }
+        });
+
+        self.send_request(
+            "textDocument/declaration",
+            Some(params),
+            self.timeout(TimeoutCategory::Definition),
+        )
+        .await
     }
 
-    pub async fn references(&mut self, uri: &str, line: u32, character: u32) -> Result<Value> {
+    pub async fn references(
+        &mut self,
+        uri: &str,
+        line: u32,
+        character: u32,
+        include_declaration: bool,
+    ) -> Result<Value> {
         let params = json!({
             "textDocument": { "uri": uri },
             "position": { "line": line, "character": character },
-            "context": { "includeDeclaration": true }
+            "context": { "includeDeclaration": include_declaration }
         });
 
-        self.send_request("textDocument/references", Some(params))
-            .await
+        self.send_request(
+            "textDocument/references",
+            Some(params),
+            self.timeout(TimeoutCategory::Definition),
+        )
+        .await
     }
 
-    pub async fn completion(&mut self, uri: &str, line: u32, character: u32) -> Result<Value> {
+    pub async fn implementation(&mut self, uri: &str, line: u32, character: u32) -> Result<Value> {
         let params = json!({
             "textDocument": { "uri": uri },
             "position": { "line": line, "character": character }
         });
 
-        self.send_request("textDocument/completion", Some(params))
-            .await
+        self.send_request(
+            "textDocument/implementation",
+            Some(params),
+            self.timeout(TimeoutCategory::Definition),
+        )
+        .await
+    }
+
+    pub async fn parent_module(&mut self, uri: &str, line: u32, character: u32) -> Result<Value> {
+        let params = json!({
+            "textDocument": { "uri": uri },
+            "position": { "line": line, "character": character }
+        });
+
+        self.send_request(
+            "experimental/parentModule",
+            Some(params),
+            self.timeout(TimeoutCategory::Definition),
+        )
+        .await
+    }
+
+    pub async fn child_modules(&mut self, uri: &str, line: u32, character: u32) -> Result<Value> {
+        let params = json!({
+            "textDocument": { "uri": uri },
+            "position": { "line": line, "character": character }
+        });
+
+        self.send_request(
+            "experimental/childModules",
+            Some(params),
+            self.timeout(TimeoutCategory::Definition),
+        )
+        .await
+    }
+
+    pub async fn completion(
+        &mut self,
+        uri: &str,
+        line: u32,
+        character: u32,
+        trigger_character: Option<&str>,
+    ) -> Result<Value> {
+        // Trigger kinds from the LSP `CompletionTriggerKind` enumeration.
+        let context = match trigger_character {
+            Some(trigger_character) => json!({
+                "triggerKind": 2,
+                "triggerCharacter": trigger_character
+            }),
+            None => json!({ "triggerKind": 1 }),
+        };
+        let params = json!({
+            "textDocument": { "uri": uri },
+            "position": { "line": line, "character": character },
+            "context": context
+        });
+
+        self.send_request(
+            "textDocument/completion",
+            Some(params),
+            self.timeout(TimeoutCategory::Completion),
+        )
+        .await
+    }
+
+    pub async fn completion_resolve(&self, item: &Value) -> Result<Value> {
+        self.send_request(
+            "completionItem/resolve",
+            Some(item.clone()),
+            self.timeout(TimeoutCategory::Completion),
+        )
+        .await
+    }
+
+    pub async fn code_lens(&self, uri: &str) -> Result<Value> {
+        let params = json!({
+            "textDocument": { "uri": uri }
+        });
+
+        self.send_request(
+            "textDocument/codeLens",
+            Some(params),
+            self.timeout(TimeoutCategory::Symbols),
+        )
+        .await
+    }
+
+    /// Resolve a code lens, e.g. counting the implementations it shows.
+    pub async fn code_lens_resolve(&self, lens: &Value) -> Result<Value> {
+        self.send_request(
+            "codeLens/resolve",
+            Some(lens.clone()),
+            self.timeout(TimeoutCategory::Definition),
+        )
+        .await
+    }
+
+    /// Get the semantic tokens of a document, decoded into `{ line, character, length,
+    /// token_type, modifiers }` entries with [`decode_semantic_tokens`].
+    pub async fn semantic_tokens(&self, uri: &str) -> Result<Value> {
+        let params = json!({
+            "textDocument": { "uri": uri }
+        });
+
+        let result = self
+            .send_request(
+                "textDocument/semanticTokens/full",
+                Some(params),
+                self.timeout(TimeoutCategory::Symbols),
+            )
+            .await?;
+        let data: Vec<u64> = result["data"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_u64)
+            .collect();
+        let legend = &self.server_capabilities["semanticTokensProvider"]["legend"];
+
+        Ok(Value::Array(decode_semantic_tokens(&data, legend)))
     }
 
     pub async fn document_symbols(&mut self, uri: &str) -> Result<Value> {
@@ -50,8 +220,23 @@ impl RustAnalyzerClient {
             "textDocument": { "uri": uri }
         });
 
-        self.send_request("textDocument/documentSymbol", Some(params))
-            .await
+        self.send_request(
+            "textDocument/documentSymbol",
+            Some(params),
+            self.timeout(TimeoutCategory::Symbols),
+        )
+        .await
+    }
+
+    pub async fn workspace_symbols(&mut self, query: &str) -> Result<Value> {
+        let params = json!({ "query": query });
+
+        self.send_request(
+            "workspace/symbol",
+            Some(params),
+            self.timeout(TimeoutCategory::Symbols),
+        )
+        .await
     }
 
     pub async fn formatting(&mut self, uri: &str) -> Result<Value> {
@@ -63,8 +248,40 @@ impl RustAnalyzerClient {
             }
         });
 
-        self.send_request("textDocument/formatting", Some(params))
-            .await
+        self.send_request(
+            "textDocument/formatting",
+            Some(params),
+            self.timeout(TimeoutCategory::Formatting),
+        )
+        .await
+    }
+
+    pub async fn range_formatting(
+        &mut self,
+        uri: &str,
+        start_line: u32,
+        start_char: u32,
+        end_line: u32,
+        end_char: u32,
+    ) -> Result<Value> {
+        let params = json!({
+            "textDocument": { "uri": uri },
+            "range": {
+                "start": { "line": start_line, "character": start_char },
+                "end": { "line": end_line, "character": end_char }
+            },
+            "options": {
+                "tabSize": 4,
+                "insertSpaces": true
+            }
+        });
+
+        self.send_request(
+            "textDocument/rangeFormatting",
+            Some(params),
+            self.timeout(TimeoutCategory::Formatting),
+        )
+        .await
     }
 
     pub async fn diagnostics(&mut self, uri: &str) -> Result<Value> {
@@ -75,9 +292,13 @@ impl RustAnalyzerClient {
             "Available URIs with diagnostics: {:?}",
             diag_lock.keys().collect::<Vec<_>>()
         );
-        if let Some(diags) = diag_lock.get(uri) {
-            info!("Found {} stored diagnostics for {}", diags.len(), uri);
-            return Ok(json!(diags));
+        if let Some(published) = diag_lock.get(uri) {
+            info!(
+                "Found {} stored diagnostics for {}",
+                published.diagnostics.len(),
+                uri
+            );
+            return Ok(json!(published.diagnostics));
         }
         drop(diag_lock);
 
@@ -88,7 +309,11 @@ impl RustAnalyzerClient {
         });
 
         let response = self
-            .send_request("textDocument/diagnostic", Some(params))
+            .send_request(
+                "textDocument/diagnostic",
+                Some(params),
+                self.timeout(TimeoutCategory::Diagnostics),
+            )
             .await?;
 
         // Extract diagnostics from the response.
@@ -99,32 +324,209 @@ impl RustAnalyzerClient {
         }
     }
 
+    /// Request diagnostics for the whole workspace via `workspace/diagnostic`.
+    ///
+    /// The report is converted into a map from document URI to its diagnostics.
     pub async fn workspace_diagnostics(&mut self) -> Result<Value> {
-        // Try workspace/diagnostic if available, otherwise collect from all open documents.
         let params = json!({
             "identifier": "rust-analyzer",
-            "previousResultId": null
+            "previousResultIds": []
         });
 
-        match self
-            .send_request("workspace/diagnostic", Some(params))
-            .await
-        {
-            Ok(response) => Ok(response),
-            Err(_) => {
-                // Fallback: return diagnostics for all open documents.
-                let mut all_diagnostics = json!({});
-                let open_docs = self.open_documents.lock().await.clone();
-
-                for doc_uri in open_docs.iter() {
-                    if let Ok(diag) = self.diagnostics(doc_uri).await {
-                        all_diagnostics[doc_uri] = diag;
-                    }
-                }
-
-                Ok(all_diagnostics)
-            }
+        let response = self
+            .send_request(
+                "workspace/diagnostic",
+                Some(params),
+                self.timeout(TimeoutCategory::Diagnostics),
+            )
+            .await?;
+
+        let Some(reports) = response.get("items").and_then(|i| i.as_array()) else {
+            return Ok(response);
+        };
+
+        let mut all_diagnostics = json!({});
+        for report in reports {
+            let Some(uri) = report.get("uri").and_then(|u| u.as_str()) else {
+                continue;
+            };
+            all_diagnostics[uri] = report.get("items").cloned().unwrap_or(json!([]));
         }
+
+        Ok(all_diagnostics)
+    }
+
+    /// Get the diagnostics collected from `publishDiagnostics` notifications so far.
+    pub async fn workspace_diagnostics_snapshot(&self) -> Value {
+        let diag_lock = self.diagnostics.lock().await;
+        let mut all_diagnostics = json!({});
+        for (uri, published) in diag_lock.iter() {
+            all_diagnostics[uri] = json!(published.diagnostics);
+        }
+
+        all_diagnostics
+    }
+
+    pub async fn memory_usage(&mut self) -> Result<Value> {
+        self.send_request(
+            "rust-analyzer/memoryUsage",
+            None,
+            self.options.config.lsp_request_timeout,
+        )
+        .await
+    }
+
+    /// Get rust-analyzer's status report, describing the loaded workspaces, crates and its
+    /// database, and the crates of the document `uri` if given.
+    pub async fn analyzer_status(&self, uri: Option<&str>) -> Result<Value> {
+        let params = match uri {
+            Some(uri) => json!({ "textDocument": { "uri": uri } }),
+            None => json!({}),
+        };
+
+        self.send_request(
+            "rust-analyzer/analyzerStatus",
+            Some(params),
+            self.options.config.lsp_request_timeout,
+        )
+        .await
+    }
+
+    /// Get the indexing and cargo check progress reported by rust-analyzer.
+    pub async fn progress_status(&self) -> Value {
+        self.progress.lock().await.to_json()
+    }
+
+    /// Get the latest analysis status reported by rust-analyzer.
+    pub async fn server_status(&self) -> ServerStatus {
+        self.server_status.lock().await.clone()
+    }
+
+    /// Whether rust-analyzer is indexing or loading the workspace.
+    pub async fn is_indexing(&self) -> bool {
+        self.progress.lock().await.is_indexing()
+    }
+
+    /// Whether cargo check (flycheck) is running.
+    pub async fn is_checking(&self) -> bool {
+        self.progress.lock().await.is_checking()
+    }
+
+    /// Number of cargo checks (flycheck) that ran to completion.
+    pub async fn checks_completed(&self) -> u64 {
+        self.progress.lock().await.checks_completed
+    }
+
+    /// Run cargo check (flycheck) on the whole workspace.
+    pub async fn run_flycheck(&self) -> Result<()> {
+        self.send_notification(
+            "rust-analyzer/runFlycheck",
+            Some(json!({ "textDocument": null })),
+        )
+        .await
+    }
+
+    /// Cancel the running cargo checks (flycheck).
+    pub async fn cancel_flycheck(&self) -> Result<()> {
+        self.send_notification("rust-analyzer/cancelFlycheck", Some(Value::Null))
+            .await
+    }
+
+    /// Check the state of rust-analyzer and the connection to it.
+    ///
+    /// The status is `ready` once rust-analyzer is initialized and done indexing, `initializing`
+    /// before that and `crashed` if the process isn't running anymore.
+    pub async fn health(&mut self) -> Value {
+        let process_running = self.is_process_running();
+        let indexing = self.is_indexing().await;
+        let status = if !process_running {
+            "crashed"
+        } else if !self.initialized || indexing {
+            "initializing"
+        } else {
+            "ready"
+        };
+
+        let open_documents = self.open_documents.lock().await.len();
+        let pending_requests = self.pending_requests.lock().await.len();
+        let cached_diagnostics: usize = self
+            .diagnostics
+            .lock()
+            .await
+            .values()
+            .map(|published| published.diagnostics.len())
+            .sum();
+
+        json!({
+            "status": status,
+            "checks": {
+                "process_running": process_running,
+                "lsp_initialized": self.initialized,
+                "indexing": indexing
+            },
+            "open_documents": open_documents,
+            "pending_requests": pending_requests,
+            "cached_diagnostics": cached_diagnostics,
+            "restart_count": self.restart_count
+        })
+    }
+
+    pub async fn reload_workspace(&mut self) -> Result<Value> {
+        self.send_request(
+            "rust-analyzer/reloadWorkspace",
+            None,
+            self.timeout(TimeoutCategory::Workspace),
+        )
+        .await
+    }
+
+    pub async fn did_create_files(&mut self, uris: &[String]) -> Result<()> {
+        let files: Vec<Value> = uris.iter().map(|uri| json!({ "uri": uri })).collect();
+        let params = json!({ "files": files });
+
+        self.send_notification("workspace/didCreateFiles", Some(params))
+            .await
+    }
+
+    /// Ask rust-analyzer for the edits needed before renaming a file, e.g. to update `mod`
+    /// declarations and paths when a module file is moved.
+    pub async fn will_rename_files(&mut self, old_uri: &str, new_uri: &str) -> Result<Value> {
+        let params = json!({
+            "files": [{ "oldUri": old_uri, "newUri": new_uri }]
+        });
+
+        let timeout = self.timeout(TimeoutCategory::Workspace);
+        self.send_request("workspace/willRenameFiles", Some(params), timeout)
+            .await
+    }
+
+    pub async fn did_rename_files(&mut self, old_uri: &str, new_uri: &str) -> Result<()> {
+        let params = json!({
+            "files": [{ "oldUri": old_uri, "newUri": new_uri }]
+        });
+
+        self.send_notification("workspace/didRenameFiles", Some(params))
+            .await
+    }
+
+    /// Ask rust-analyzer for the edits renaming the symbol at the given position everywhere it's
+    /// used.
+    pub async fn rename(
+        &mut self,
+        uri: &str,
+        line: u32,
+        character: u32,
+        new_name: &str,
+    ) -> Result<Value> {
+        let params = json!({
+            "textDocument": { "uri": uri },
+            "position": { "line": line, "character": character },
+            "newName": new_name
+        });
+
+        let timeout = self.timeout(TimeoutCategory::Workspace);
+        self.send_request("textDocument/rename", Some(params), timeout)
+            .await
     }
 
     pub async fn code_actions(
@@ -153,8 +555,63 @@ impl RustAnalyzerClient {
             }
         });
 
-        self.send_request("textDocument/codeAction", Some(params))
-            .await
+        self.send_request(
+            "textDocument/codeAction",
+            Some(params),
+            self.options.config.lsp_request_timeout,
+        )
+        .await
+    }
+
+    pub async fn ssr(
+        &mut self,
+        uri: &str,
+        query: &str,
+        parse_only: bool,
+        line: u32,
+        character: u32,
+        selections: Vec<Value>,
+    ) -> Result<Value> {
+        let params = json!({
+            "query": query,
+            "parseOnly": parse_only,
+            "textDocument": { "uri": uri },
+            "position": { "line": line, "character": character },
+            "selections": selections
+        });
+
+        self.send_request(
+            "experimental/ssr",
+            Some(params),
+            self.options.config.lsp_request_timeout,
+        )
+        .await
+    }
+
+    pub async fn move_item(
+        &mut self,
+        uri: &str,
+        start_line: u32,
+        start_char: u32,
+        end_line: u32,
+        end_char: u32,
+        direction: &str,
+    ) -> Result<Value> {
+        let params = json!({
+            "textDocument": { "uri": uri },
+            "range": {
+                "start": { "line": start_line, "character": start_char },
+                "end": { "line": end_line, "character": end_char }
+            },
+            "direction": direction
+        });
+
+        self.send_request(
+            "experimental/moveItem",
+            Some(params),
+            self.options.config.lsp_request_timeout,
+        )
+        .await
     }
 }
 
@@ -187,3 +644,45 @@ fn filter_diagnostics_in_range(diagnostics: &Value, start_line: u32, end_line: u
 
     json!(filtered)
 }
+
+/// Decode the `data` of LSP semantic tokens, naming their types and modifiers after `legend`.
+///
+/// Each token is encoded as 5 integers: its line relative to the previous token's, its start
+/// character relative to the previous token's if on the same line (or absolute otherwise), its
+/// length, the index of its type in the legend's `tokenTypes` and a bit set of its modifiers, bit
+/// `i` standing for the legend's `tokenModifiers[i]`.
+pub fn decode_semantic_tokens(data: &[u64], legend: &Value) -> Vec<Value> {
+    let name = |names: &Value, index: u64| {
+        names[index as usize]
+            .as_str()
+            .unwrap_or("unknown")
+            .to_string()
+    };
+
+    let mut line = 0;
+    let mut character = 0;
+    data.chunks_exact(5)
+        .map(|token| {
+            let (delta_line, delta_start, length, token_type, modifiers) =
+                (token[0], token[1], token[2], token[3], token[4]);
+            if delta_line == 0 {
+                character += delta_start;
+            } else {
+                line += delta_line;
+                character = delta_start;
+            }
+            let modifiers: Vec<String> = (0..u64::BITS as u64)
+                .filter(|bit| modifiers & (1 << bit) != 0)
+                .map(|bit| name(&legend["tokenModifiers"], bit))
+                .collect();
+
+            json!({
+                "line": line,
+                "character": character,
+                "length": length,
+                "token_type": name(&legend["tokenTypes"], token_type),
+                "modifiers": modifiers
+            })
+        })
+        .collect()
+}
@@ -1,5 +1,13 @@
 mod client;
 mod connection;
 mod handlers;
+mod progress;
+mod status;
+mod version;
+mod watcher;
 
 pub use client::RustAnalyzerClient;
+pub use handlers::decode_semantic_tokens;
+pub use status::ServerStatus;
+pub use version::{RustAnalyzerInfo, RustAnalyzerVersion, INSTALL_HINT, MIN_RUST_ANALYZER_VERSION};
+pub use watcher::is_watched;
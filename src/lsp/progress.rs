@@ -0,0 +1,127 @@
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+/// A work done progress reported by rust-analyzer through `$/progress`.
+#[derive(Debug, Clone)]
+pub struct ProgressInfo {
+    pub title: String,
+    pub message: Option<String>,
+    pub percentage: Option<u64>,
+}
+
+/// Tracks the work done progress reported by rust-analyzer.
+#[derive(Debug, Default)]
+pub struct ProgressState {
+    /// In-flight progress, keyed by token.
+    pub active: HashMap<String, ProgressInfo>,
+    /// Whether the last cargo check (flycheck) ran to completion.
+    pub check_completed: bool,
+    /// Number of cargo checks that ran to completion, to tell when a requested one is done.
+    pub checks_completed: u64,
+}
+
+impl ProgressState {
+    /// Update the state from the params of a `$/progress` notification.
+    pub fn update(&mut self, params: &Value) {
+        let token = match &params["token"] {
+            Value::String(token) => token.clone(),
+            token => token.to_string(),
+        };
+        let value = &params["value"];
+        let message = value["message"].as_str().map(String::from);
+        let percentage = value["percentage"].as_u64();
+
+        match value["kind"].as_str() {
+            Some("begin") => {
+                let title = value["title"].as_str().unwrap_or_default().to_string();
+                if is_check(&token, &title) {
+                    self.check_completed = false;
+                }
+                self.active.insert(
+                    token,
+                    ProgressInfo {
+                        title,
+                        message,
+                        percentage,
+                    },
+                );
+            }
+            Some("report") => {
+                if let Some(info) = self.active.get_mut(&token) {
+                    if message.is_some() {
+                        info.message = message;
+                    }
+                    if percentage.is_some() {
+                        info.percentage = percentage;
+                    }
+                }
+            }
+            Some("end") => {
+                if let Some(info) = self.active.remove(&token) {
+                    if is_check(&token, &info.title) {
+                        self.check_completed = true;
+                        self.checks_completed += 1;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Whether rust-analyzer is indexing or loading the workspace.
+    pub fn is_indexing(&self) -> bool {
+        self.active
+            .iter()
+            .any(|(token, info)| !is_check(token, &info.title))
+    }
+
+    /// Whether cargo check is currently running.
+    pub fn is_checking(&self) -> bool {
+        self.active
+            .iter()
+            .any(|(token, info)| is_check(token, &info.title))
+    }
+
+    /// Summarize the state as `indexing`, `checking`, `ready` or `idle`.
+    pub fn status(&self) -> &'static str {
+        if self.is_indexing() {
+            "indexing"
+        } else if self.is_checking() {
+            "checking"
+        } else if self.check_completed {
+            "ready"
+        } else {
+            "idle"
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut progress: Vec<Value> = self
+            .active
+            .iter()
+            .map(|(token, info)| {
+                json!({
+                    "token": token,
+                    "title": info.title,
+                    "message": info.message,
+                    "percentage": info.percentage
+                })
+            })
+            .collect();
+        progress.sort_by(|a, b| a["token"].as_str().cmp(&b["token"].as_str()));
+
+        json!({
+            "status": self.status(),
+            "progress": progress,
+            "check_completed": self.check_completed
+        })
+    }
+}
+
+/// Whether a progress token belongs to cargo check (flycheck) rather than indexing.
+fn is_check(token: &str, title: &str) -> bool {
+    token.contains("flycheck")
+        || token.contains("cargo check")
+        || title.starts_with("cargo check")
+        || title.starts_with("cargo clippy")
+}
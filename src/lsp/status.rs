@@ -0,0 +1,56 @@
+use serde_json::{json, Value};
+
+/// The analysis status rust-analyzer reports through `experimental/serverStatus`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum ServerStatus {
+    /// Loading the workspace or analyzing it, until the first notification.
+    #[default]
+    Loading,
+    /// Done with all pending work (quiescent).
+    Ready,
+    /// The workspace couldn't be loaded, e.g. because of a broken `Cargo.toml`, or needs to be
+    /// reloaded, with rust-analyzer's explanation.
+    Invalid(String),
+}
+
+impl ServerStatus {
+    /// The status described by the params of an `experimental/serverStatus` notification.
+    ///
+    /// Their `health` is `ok`, `warning` or `error`, and `quiescent` tells whether rust-analyzer
+    /// has pending work.
+    pub fn from_params(params: &Value) -> Self {
+        if params["health"] == "error" {
+            let message = params["message"]
+                .as_str()
+                .unwrap_or("rust-analyzer failed to load the workspace");
+            return Self::Invalid(message.to_string());
+        }
+
+        if params["quiescent"].as_bool().unwrap_or(false) {
+            Self::Ready
+        } else {
+            Self::Loading
+        }
+    }
+
+    /// The status as `loading`, `ready` or `invalid`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Loading => "loading",
+            Self::Ready => "ready",
+            Self::Invalid(_) => "invalid",
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        let message = match self {
+            Self::Invalid(message) => Some(message),
+            _ => None,
+        };
+
+        json!({
+            "status": self.name(),
+            "message": message
+        })
+    }
+}
@@ -0,0 +1,134 @@
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::{fmt, path::PathBuf, process::Stdio, time::Duration};
+use tokio::process::Command;
+
+use super::client::find_rust_analyzer;
+
+/// Oldest rust-analyzer release known to support everything the server uses.
+///
+/// This is the first Rust toolchain shipping rust-analyzer as a rustup component. Standalone builds
+/// are versioned `0.x` instead and aren't checked against it.
+pub const MIN_RUST_ANALYZER_VERSION: RustAnalyzerVersion = RustAnalyzerVersion {
+    major: 1,
+    minor: 64,
+    patch: 0,
+};
+
+/// How to install rust-analyzer, for error messages.
+pub const INSTALL_HINT: &str =
+    "Install it with `rustup component add rust-analyzer` or set its path \
+     with --rust-analyzer-path.";
+
+const VERSION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The semantic version of a rust-analyzer release.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct RustAnalyzerVersion {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+}
+
+impl RustAnalyzerVersion {
+    /// Parse the output of `rust-analyzer --version`.
+    ///
+    /// Both rustup builds (`rust-analyzer 1.80.1 (3f5fd8d 2024-08-06)`) and standalone builds
+    /// (`rust-analyzer 0.3.2096-standalone (…)`) are supported. Pre-release and build suffixes are
+    /// ignored.
+    pub fn parse(output: &str) -> Option<Self> {
+        let version = output
+            .trim()
+            .strip_prefix("rust-analyzer")?
+            .split_whitespace()
+            .next()?;
+        let version = version.split(['-', '+']).next()?;
+
+        let mut parts = version.split('.').map(|part| part.parse::<u64>().ok());
+        let version = Self {
+            major: parts.next()??,
+            minor: parts.next()??,
+            patch: parts.next().unwrap_or(Some(0))?,
+        };
+
+        parts.next().is_none().then_some(version)
+    }
+
+    /// Whether this is a standalone build rather than a rustup one.
+    pub fn is_standalone(&self) -> bool {
+        self.major == 0
+    }
+
+    /// Whether this release is new enough for the server.
+    pub fn is_supported(&self) -> bool {
+        self.is_standalone() || *self >= MIN_RUST_ANALYZER_VERSION
+    }
+}
+
+impl fmt::Display for RustAnalyzerVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// The rust-analyzer installation the server uses.
+#[derive(Debug, Clone, Serialize)]
+pub struct RustAnalyzerInfo {
+    pub path: PathBuf,
+    /// The full output of `rust-analyzer --version`.
+    pub version: String,
+    /// The parsed version, if the output could be parsed.
+    pub semver: Option<RustAnalyzerVersion>,
+}
+
+impl RustAnalyzerInfo {
+    /// Find rust-analyzer, unless `path` is given, and ask it for its version.
+    ///
+    /// Fails with an actionable message if rust-analyzer is missing or can't be run. Note that the
+    /// rustup proxy exists even if the component isn't installed, in which case running it fails.
+    pub async fn detect(path: Option<PathBuf>) -> Result<Self> {
+        let path = match path {
+            Some(path) => path,
+            None => find_rust_analyzer()?,
+        };
+
+        let output = Command::new(&path)
+            .arg("--version")
+            .stdin(Stdio::null())
+            .kill_on_drop(true)
+            .output();
+        let output = tokio::time::timeout(VERSION_TIMEOUT, output)
+            .await
+            .map_err(|_| anyhow!("Timed out running {} --version", path.display()))?
+            .map_err(|e| anyhow!("Failed to run {}: {}. {}", path.display(), e, INSTALL_HINT))?;
+        if !output.status.success() {
+            return Err(anyhow!(
+                "{} --version failed: {}. {}",
+                path.display(),
+                String::from_utf8_lossy(&output.stderr).trim(),
+                INSTALL_HINT
+            ));
+        }
+
+        let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        let semver = RustAnalyzerVersion::parse(&version);
+
+        Ok(Self {
+            path,
+            version,
+            semver,
+        })
+    }
+
+    /// A warning to show if the version is too old for the server.
+    pub fn warning(&self) -> Option<String> {
+        let semver = self.semver?;
+        (!semver.is_supported()).then(|| {
+            format!(
+                "rust-analyzer {} is older than {}, the oldest supported version. Some tools may \
+                 not work; update it with `rustup update`.",
+                semver, MIN_RUST_ANALYZER_VERSION
+            )
+        })
+    }
+}
@@ -0,0 +1,201 @@
+use log::{debug, error, warn};
+use lru::LruCache;
+use notify::{event::ModifyKind, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde_json::json;
+use std::{
+    collections::HashMap,
+    path::{Component, Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::{
+    io::BufWriter,
+    process::ChildStdin,
+    sync::{mpsc, Mutex},
+};
+
+use super::{client::DocumentState, connection::write_message};
+
+/// LSP `FileChangeType`s.
+const CREATED: u64 = 1;
+const CHANGED: u64 = 2;
+const DELETED: u64 = 3;
+
+/// Directories whose files rust-analyzer doesn't need to hear about.
+const IGNORED_DIRS: [&str; 2] = ["target", ".git"];
+
+/// Watch the workspace for changes made outside of this server, e.g. by an editor, and tell
+/// rust-analyzer about them with `workspace/didChangeWatchedFiles`.
+///
+/// Bursts of changes are sent at once, when no other change happened for `debounce`. Only Rust
+/// sources and `Cargo.toml`/`Cargo.lock` files are watched, outside of `target/`. Watching stops
+/// when the returned watcher is dropped.
+///
+/// Changes to the content of `open_documents` are left out: rust-analyzer uses their content as it
+/// was last sent rather than the one on disk, which is resynced when they're next used, and the
+/// changes this server makes to them are sent already.
+///
+/// `generation` is incremented every time changes are sent, as they can change the analysis
+/// results of any file.
+pub fn watch_workspace(
+    workspace_root: &Path,
+    stdin: Arc<Mutex<BufWriter<ChildStdin>>>,
+    open_documents: Arc<Mutex<LruCache<String, DocumentState>>>,
+    generation: Arc<AtomicU64>,
+    debounce: Duration,
+) -> notify::Result<RecommendedWatcher> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |event| {
+        let _ = tx.send(event);
+    })?;
+    watcher.watch(workspace_root, RecursiveMode::Recursive)?;
+
+    tokio::spawn(forward_changes(
+        workspace_root.to_path_buf(),
+        rx,
+        stdin,
+        open_documents,
+        generation,
+        debounce,
+    ));
+
+    Ok(watcher)
+}
+
+/// Whether rust-analyzer needs to hear about changes to `path`.
+pub fn is_watched(workspace_root: &Path, path: &Path) -> bool {
+    let Ok(relative) = path.strip_prefix(workspace_root) else {
+        return false;
+    };
+    let ignored = relative.components().any(|component| match component {
+        Component::Normal(name) => IGNORED_DIRS.iter().any(|dir| name == *dir),
+        _ => false,
+    });
+    if ignored {
+        return false;
+    }
+
+    path.extension().is_some_and(|extension| extension == "rs")
+        || path
+            .file_name()
+            .is_some_and(|name| name == "Cargo.toml" || name == "Cargo.lock")
+}
+
+async fn forward_changes(
+    workspace_root: PathBuf,
+    mut events: mpsc::UnboundedReceiver<notify::Result<Event>>,
+    stdin: Arc<Mutex<BufWriter<ChildStdin>>>,
+    open_documents: Arc<Mutex<LruCache<String, DocumentState>>>,
+    generation: Arc<AtomicU64>,
+    debounce: Duration,
+) {
+    let mut changes: HashMap<PathBuf, u64> = HashMap::new();
+
+    loop {
+        let event = if changes.is_empty() {
+            events.recv().await
+        } else {
+            match tokio::time::timeout(debounce, events.recv()).await {
+                Ok(event) => event,
+                Err(_) => {
+                    let mut changes = std::mem::take(&mut changes);
+                    {
+                        let open_documents = open_documents.lock().await;
+                        changes.retain(|path, change| {
+                            *change != CHANGED || !open_documents.contains(&file_uri(path))
+                        });
+                    }
+                    if changes.is_empty() {
+                        continue;
+                    }
+                    if send_changes(&stdin, changes).await.is_err() {
+                        // rust-analyzer is gone.
+                        break;
+                    }
+                    generation.fetch_add(1, Ordering::SeqCst);
+                    continue;
+                }
+            }
+        };
+        // The watcher was dropped.
+        let Some(event) = event else {
+            break;
+        };
+
+        let event = match event {
+            Ok(event) => event,
+            Err(e) => {
+                warn!("File watcher error: {}", e);
+                continue;
+            }
+        };
+        for path in event.paths {
+            if !is_watched(&workspace_root, &path) {
+                continue;
+            }
+            let Some(change) = change_type(&event.kind, &path) else {
+                continue;
+            };
+
+            let change = match changes.get(&path) {
+                // Still new to rust-analyzer.
+                Some(&CREATED) if change == CHANGED => CREATED,
+                // Replaced, e.g. by an editor saving through a temporary file.
+                Some(&DELETED) if change == CREATED => CHANGED,
+                _ => change,
+            };
+            changes.insert(path, change);
+        }
+    }
+}
+
+/// The LSP `FileChangeType` of an event about `path`, if rust-analyzer cares about it.
+fn change_type(kind: &EventKind, path: &Path) -> Option<u64> {
+    match kind {
+        EventKind::Create(_) => Some(CREATED),
+        EventKind::Remove(_) => Some(DELETED),
+        // Renames are reported for both the old and the new path.
+        EventKind::Modify(ModifyKind::Name(_)) => {
+            Some(if path.exists() { CREATED } else { DELETED })
+        }
+        EventKind::Modify(ModifyKind::Metadata(_)) => None,
+        EventKind::Modify(_) => Some(CHANGED),
+        _ => None,
+    }
+}
+
+async fn send_changes(
+    stdin: &Mutex<BufWriter<ChildStdin>>,
+    changes: HashMap<PathBuf, u64>,
+) -> std::io::Result<()> {
+    let changes: Vec<_> = changes
+        .into_iter()
+        .map(|(path, change)| {
+            json!({
+                "uri": file_uri(&path),
+                "type": change
+            })
+        })
+        .collect();
+    debug!("Sending {} watched file changes", changes.len());
+
+    let notification = json!({
+        "jsonrpc": "2.0",
+        "method": "workspace/didChangeWatchedFiles",
+        "params": { "changes": changes }
+    });
+    let written = write_message(stdin, &notification.to_string()).await;
+    if let Err(e) = &written {
+        error!("Failed to send watched file changes: {}", e);
+    }
+
+    written
+}
+
+/// The URI of `path`, as the documents are opened with.
+fn file_uri(path: &Path) -> String {
+    format!("file://{}", path.display())
+}
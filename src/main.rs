@@ -1,22 +1,188 @@
-use anyhow::Result;
-use std::path::PathBuf;
+use anyhow::{anyhow, Result};
+use std::{
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    path::PathBuf,
+};
 
-use rust_analyzer_mcp::RustAnalyzerMCPServer;
+use rust_analyzer_mcp::{
+    config::{load_init_options, CheckCommand, ConfigFile, TimeoutCategory},
+    RustAnalyzerMCPServer, ServerOptions,
+};
 
 #[tokio::main]
 async fn main() -> Result<()> {
     // Initialize logging.
     env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
 
-    // Get workspace path from command line or use current directory.
-    let workspace_path = std::env::args()
-        .nth(1)
-        .map(PathBuf::from)
-        .unwrap_or_else(|| std::env::current_dir().expect("Failed to get current directory"));
+    let args = parse_args()?;
+
+    // Settings are taken from the config file first, then the environment and the command line.
+    let mut options = ServerOptions::default();
+    ConfigFile::load(&args.workspace_path)?.apply(&mut options);
+    options.config.apply_env()?;
+    options.init_options = load_init_options(&args.workspace_path)?;
+    options.apply_rust_analyzer_path(args.rust_analyzer_path)?;
+    if let Some(check_command) = args.check_command {
+        options.config.check_on_save = check_command;
+    }
+    if let Some(max) = args.max_open_documents {
+        options.config.max_open_documents = max;
+    }
+    for (category, secs) in args.timeouts {
+        options.config.timeouts.set(category, secs);
+    }
+    options.allowed_origins = args.allowed_origins;
 
     // Create and run the server.
-    let mut server = RustAnalyzerMCPServer::with_workspace(workspace_path);
-    server.run().await?;
+    let mut server = RustAnalyzerMCPServer::with_options(args.workspace_path, options);
+    match args.http_port {
+        Some(port) => {
+            server
+                .run_http(SocketAddr::new(args.http_host, port))
+                .await?
+        }
+        None => server.run().await?,
+    }
 
     Ok(())
 }
+
+struct Args {
+    workspace_path: PathBuf,
+    rust_analyzer_path: Option<PathBuf>,
+    check_command: Option<CheckCommand>,
+    timeouts: Vec<(TimeoutCategory, u64)>,
+    max_open_documents: Option<usize>,
+    http_port: Option<u16>,
+    http_host: IpAddr,
+    allowed_origins: Vec<String>,
+}
+
+/// Parse the command line:
+/// `rust-analyzer-mcp [--rust-analyzer-path <path>] [--check-command <command>]
+/// [--timeout <category>=<secs>]... [--max-open-documents <n>] [--http-port <port>]
+/// [--http-host <address>] [--allowed-origin <origin>]... [workspace]`.
+///
+/// The workspace defaults to the current directory. The server talks over stdio unless an HTTP
+/// port is given, and only listens on localhost unless told otherwise.
+fn parse_args() -> Result<Args> {
+    let mut workspace_path = None;
+    let mut rust_analyzer_path = None;
+    let mut check_command = None;
+    let mut timeouts = Vec::new();
+    let mut max_open_documents = None;
+    let mut http_port = None;
+    let mut http_host = IpAddr::V4(Ipv4Addr::LOCALHOST);
+    let mut allowed_origins = Vec::new();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--rust-analyzer-path" {
+            let Some(path) = args.next() else {
+                return Err(anyhow!("--rust-analyzer-path requires a value"));
+            };
+            rust_analyzer_path = Some(PathBuf::from(path));
+        } else if let Some(path) = arg.strip_prefix("--rust-analyzer-path=") {
+            rust_analyzer_path = Some(PathBuf::from(path));
+        } else if arg == "--check-command" {
+            let Some(command) = args.next() else {
+                return Err(anyhow!("--check-command requires a value"));
+            };
+            check_command = Some(command.parse()?);
+        } else if let Some(command) = arg.strip_prefix("--check-command=") {
+            check_command = Some(command.parse()?);
+        } else if arg == "--timeout" {
+            let Some(timeout) = args.next() else {
+                return Err(anyhow!("--timeout requires a value"));
+            };
+            timeouts.push(parse_timeout(&timeout)?);
+        } else if let Some(timeout) = arg.strip_prefix("--timeout=") {
+            timeouts.push(parse_timeout(timeout)?);
+        } else if arg == "--max-open-documents" {
+            let Some(max) = args.next() else {
+                return Err(anyhow!("--max-open-documents requires a value"));
+            };
+            max_open_documents = Some(parse_max_open_documents(&max)?);
+        } else if let Some(max) = arg.strip_prefix("--max-open-documents=") {
+            max_open_documents = Some(parse_max_open_documents(max)?);
+        } else if arg == "--http-port" {
+            let Some(port) = args.next() else {
+                return Err(anyhow!("--http-port requires a value"));
+            };
+            http_port = Some(parse_http_port(&port)?);
+        } else if let Some(port) = arg.strip_prefix("--http-port=") {
+            http_port = Some(parse_http_port(port)?);
+        } else if arg == "--http-host" {
+            let Some(host) = args.next() else {
+                return Err(anyhow!("--http-host requires a value"));
+            };
+            http_host = parse_http_host(&host)?;
+        } else if let Some(host) = arg.strip_prefix("--http-host=") {
+            http_host = parse_http_host(host)?;
+        } else if arg == "--allowed-origin" {
+            let Some(origin) = args.next() else {
+                return Err(anyhow!("--allowed-origin requires a value"));
+            };
+            allowed_origins.push(origin);
+        } else if let Some(origin) = arg.strip_prefix("--allowed-origin=") {
+            allowed_origins.push(origin.to_string());
+        } else if arg.starts_with("--") {
+            return Err(anyhow!("Unknown option: {}", arg));
+        } else if workspace_path.is_none() {
+            workspace_path = Some(PathBuf::from(arg));
+        } else {
+            return Err(anyhow!("Unexpected argument: {}", arg));
+        }
+    }
+
+    let workspace_path = match workspace_path {
+        Some(path) => path,
+        None => std::env::current_dir().expect("Failed to get current directory"),
+    };
+
+    Ok(Args {
+        workspace_path,
+        rust_analyzer_path,
+        check_command,
+        timeouts,
+        max_open_documents,
+        http_port,
+        http_host,
+        allowed_origins,
+    })
+}
+
+fn parse_http_port(port: &str) -> Result<u16> {
+    port.parse()
+        .map_err(|e| anyhow!("Invalid --http-port `{}`: {}", port, e))
+}
+
+fn parse_http_host(host: &str) -> Result<IpAddr> {
+    host.parse()
+        .map_err(|e| anyhow!("Invalid --http-host `{}`: {}", host, e))
+}
+
+fn parse_max_open_documents(max: &str) -> Result<usize> {
+    match max.parse() {
+        Ok(max) if max > 0 => Ok(max),
+        _ => Err(anyhow!(
+            "Invalid --max-open-documents `{}`, expected a positive number",
+            max
+        )),
+    }
+}
+
+/// Parse a `<category>=<secs>` timeout, e.g. `hover=5`.
+fn parse_timeout(timeout: &str) -> Result<(TimeoutCategory, u64)> {
+    let Some((category, secs)) = timeout.split_once('=') else {
+        return Err(anyhow!(
+            "Invalid timeout `{}`, expected `<category>=<secs>`",
+            timeout
+        ));
+    };
+    let secs = secs
+        .parse()
+        .map_err(|e| anyhow!("Invalid timeout `{}`: {}", timeout, e))?;
+
+    Ok((category.parse()?, secs))
+}
@@ -0,0 +1,82 @@
+use regex::Regex;
+use serde_json::Value;
+use std::sync::LazyLock;
+
+/// Links, the text of intra-doc links usually being code: `` [`Vec`](https://...) ``.
+static LINK: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\[([^\]]*)\]\([^)]*\)").unwrap());
+static STRONG: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\*\*([^*]+)\*\*|__([^_]+)__").unwrap());
+static EMPHASIS: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\*([^*\s][^*]*)\*").unwrap());
+static INLINE_CODE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"`([^`]+)`").unwrap());
+static HEADING: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^#{1,6}\s+").unwrap());
+
+/// Convert markdown, like that of rust-analyzer's hovers, to plain text.
+///
+/// Code fences are dropped but the code in them is kept as is. Elsewhere, headings, emphasis,
+/// inline code and links are reduced to their text, and rules (rust-analyzer's `---` between the
+/// signature and the documentation) to blank lines. Consecutive blank lines are collapsed.
+pub fn markdown_to_text(markdown: &str) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    let mut in_code = false;
+    for line in markdown.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code = !in_code;
+            continue;
+        }
+
+        let line = if in_code {
+            line.to_string()
+        } else if trimmed.chars().all(|c| c == '-') && trimmed.len() >= 3 {
+            String::new()
+        } else {
+            let line = HEADING.replace(line, "");
+            let line = LINK.replace_all(&line, "$1");
+            let line = INLINE_CODE.replace_all(&line, "$1");
+            let line = STRONG.replace_all(&line, "$1$2");
+            EMPHASIS.replace_all(&line, "$1").into_owned()
+        };
+
+        let blank = line.trim().is_empty();
+        if blank && lines.last().is_none_or(|last| last.is_empty()) {
+            continue;
+        }
+        lines.push(if blank { String::new() } else { line });
+    }
+    while lines.last().is_some_and(|last| last.is_empty()) {
+        lines.pop();
+    }
+
+    lines.join("\n")
+}
+
+/// Collapse the `contents` of an LSP hover into a single markdown string, whichever form they take:
+/// `MarkupContent`, a `MarkedString`, or an array of the latter as older servers send.
+///
+/// Marked strings with a `language` are code blocks, so they're fenced. The items of an array are
+/// separated by blank lines. Returns `None` if there's no content.
+pub fn hover_markdown(hover: &Value) -> Option<String> {
+    fn marked_string(contents: &Value) -> Option<String> {
+        match contents {
+            Value::String(markdown) => Some(markdown.clone()),
+            // `MarkupContent`, whose `kind` is `markdown` or `plaintext`.
+            Value::Object(markup) if markup.contains_key("kind") => {
+                contents["value"].as_str().map(String::from)
+            }
+            Value::Object(_) => {
+                let code = contents["value"].as_str()?;
+                let language = contents["language"].as_str().unwrap_or_default();
+                Some(format!("```{}\n{}\n```", language, code))
+            }
+            _ => None,
+        }
+    }
+
+    match &hover["contents"] {
+        Value::Array(items) => {
+            let markdown: Vec<String> = items.iter().filter_map(marked_string).collect();
+            (!markdown.is_empty()).then(|| markdown.join("\n\n"))
+        }
+        contents => marked_string(contents),
+    }
+}
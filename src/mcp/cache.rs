@@ -0,0 +1,139 @@
+use log::debug;
+use lru::LruCache;
+use serde_json::Value;
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    time::{Instant, SystemTime},
+};
+
+/// Tools whose results only depend on their arguments and the workspace content.
+const CACHEABLE_TOOLS: &[&str] = &[
+    "rust_analyzer_hover",
+    "rust_analyzer_hover_range",
+    "rust_analyzer_definition",
+    "rust_analyzer_declaration",
+    "rust_analyzer_references",
+    "rust_analyzer_parent_module",
+    "rust_analyzer_symbols",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct CacheKey {
+    tool_name: String,
+    args_hash: u64,
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    value: Value,
+    inserted: Instant,
+    /// The file the result is about and its modification time when the result was computed.
+    path: PathBuf,
+    mtime: Option<SystemTime>,
+    /// The client's edit generation when the result was computed.
+    generation: u64,
+}
+
+/// LRU cache of the results of read-only tools.
+///
+/// An entry is only valid as long as the file it's about wasn't modified on disk and no document
+/// was changed, through the server or outside of it, since it was computed, as an edit to one file
+/// can change the results for another.
+pub struct ResponseCache {
+    /// `None` if caching is disabled.
+    entries: Option<LruCache<CacheKey, CacheEntry>>,
+}
+
+impl ResponseCache {
+    /// Create a cache holding up to `capacity` results. A capacity of 0 disables caching.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: NonZeroUsize::new(capacity).map(LruCache::new),
+        }
+    }
+
+    /// Whether the results of `tool_name` can be cached.
+    pub fn is_cacheable(tool_name: &str) -> bool {
+        CACHEABLE_TOOLS.contains(&tool_name)
+    }
+
+    /// Get the cached result of `tool_name` called with `args`, if it's still valid.
+    ///
+    /// `generation` is the client's current edit generation.
+    pub fn get(&mut self, tool_name: &str, args: &Value, generation: u64) -> Option<Value> {
+        let entries = self.entries.as_mut()?;
+        let key = cache_key(tool_name, args);
+
+        let entry = entries.get(&key)?;
+        if entry.generation != generation || file_mtime(&entry.path) != entry.mtime {
+            entries.pop(&key);
+            return None;
+        }
+
+        debug!(
+            "Cache hit for {} ({:?} old)",
+            tool_name,
+            entry.inserted.elapsed()
+        );
+        Some(entry.value.clone())
+    }
+
+    /// Cache the result of `tool_name` called with `args`, which is about the file at `path`.
+    pub fn insert(
+        &mut self,
+        tool_name: &str,
+        args: &Value,
+        path: &Path,
+        generation: u64,
+        value: Value,
+    ) {
+        let Some(entries) = self.entries.as_mut() else {
+            return;
+        };
+
+        entries.put(
+            cache_key(tool_name, args),
+            CacheEntry {
+                value,
+                inserted: Instant::now(),
+                path: path.to_path_buf(),
+                mtime: file_mtime(path),
+                generation,
+            },
+        );
+    }
+
+    /// Drop all the cached results.
+    pub fn clear(&mut self) {
+        if let Some(entries) = self.entries.as_mut() {
+            entries.clear();
+        }
+    }
+
+    /// Number of cached results.
+    pub fn len(&self) -> usize {
+        self.entries.as_ref().map_or(0, |entries| entries.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+fn cache_key(tool_name: &str, args: &Value) -> CacheKey {
+    // Objects are serialized with sorted keys, so equal arguments hash the same.
+    let mut hasher = DefaultHasher::new();
+    args.to_string().hash(&mut hasher);
+
+    CacheKey {
+        tool_name: tool_name.to_string(),
+        args_hash: hasher.finish(),
+    }
+}
+
+fn file_mtime(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).ok()?.modified().ok()
+}
@@ -1,14 +1,47 @@
 use anyhow::{anyhow, Result};
+use futures::future::join_all;
+use glob::Pattern;
 use log::debug;
 use serde_json::{json, Value};
-use std::path::{Path, PathBuf};
+use similar::{ChangeTag, TextDiff};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+use tokio::{fs::OpenOptions, io::AsyncWriteExt};
+use walkdir::{DirEntry, WalkDir};
 
 use crate::{
-    diagnostics::format_diagnostics,
-    protocol::mcp::{ContentItem, ToolResult},
+    code_lens::{code_lens_kind, format_code_lens},
+    completion::structured_completion_item,
+    config::{
+        CheckCommand, ANALYSIS_WAIT_MILLIS, COMPLETION_RESOLVE_LIMIT, INDEX_WORKSPACE_GLOBS,
+        MAX_COMPLETION_ITEMS, MAX_RANGE_HOVERS, PROGRESS_POLL_INTERVAL_MILLIS,
+        WORKSPACE_ERRORS_LIMIT, WORKSPACE_RELOAD_START_MILLIS, WORKSPACE_RELOAD_WAIT_SECS,
+    },
+    diagnostics::{
+        add_snippets, code_url, explain_code, first_errors, format_diagnostics,
+        format_workspace_diagnostics, parse_severity, to_rustc_diagnostics, to_sarif,
+        DiagnosticGrouping,
+    },
+    edits::{
+        apply_text_edits, range_text, range_to_offsets, uri_to_path, validate_text_edits,
+        workspace_edit_changes,
+    },
+    lsp::ServerStatus,
+    markdown::{hover_markdown, markdown_to_text},
+    protocol::mcp::{ContentItem, ErrorKind, MCPError, ToolError, ToolResult},
+    security::validate_edit_path,
+    sources::{context_excerpt, external_crate, source_excerpt},
+    symbols::{
+        derive_at, filter_symbols_by_kind, flatten_symbols, impl_header_at, name_symbol_kinds,
+        parse_impl_header, parse_symbol_kind, symbol_kind_name, MatchQuality, SymbolQuery,
+    },
+    workspace::{is_crate_root, workspace_info, workspace_members},
 };
 
-use super::server::RustAnalyzerMCPServer;
+use super::{cache::ResponseCache, server::RustAnalyzerMCPServer};
 
 /// Helper struct for extracting common tool parameters.
 struct ToolParams;
@@ -16,17 +49,17 @@ struct ToolParams;
 impl ToolParams {
     fn extract_file_path(args: &Value) -> Result<String> {
         let Some(file_path) = args["file_path"].as_str() else {
-            return Err(anyhow!("Missing file_path"));
+            return Err(invalid_arguments("Missing file_path"));
         };
         Ok(file_path.to_string())
     }
 
     fn extract_position(args: &Value) -> Result<(u32, u32)> {
         let Some(line) = args["line"].as_u64() else {
-            return Err(anyhow!("Missing line"));
+            return Err(invalid_arguments("Missing line"));
         };
         let Some(character) = args["character"].as_u64() else {
-            return Err(anyhow!("Missing character"));
+            return Err(invalid_arguments("Missing character"));
         };
         Ok((line as u32, character as u32))
     }
@@ -34,13 +67,124 @@ impl ToolParams {
     fn extract_range(args: &Value) -> Result<(u32, u32, u32, u32)> {
         let (line, character) = Self::extract_position(args)?;
         let Some(end_line) = args["end_line"].as_u64() else {
-            return Err(anyhow!("Missing end_line"));
+            return Err(invalid_arguments("Missing end_line"));
         };
         let Some(end_character) = args["end_character"].as_u64() else {
-            return Err(anyhow!("Missing end_character"));
+            return Err(invalid_arguments("Missing end_character"));
         };
         Ok((line, character, end_line as u32, end_character as u32))
     }
+
+    /// Extract the optional `min_severity` parameter as an LSP `DiagnosticSeverity`.
+    fn extract_min_severity(args: &Value) -> Result<Option<u64>> {
+        let Some(name) = args.get("min_severity").and_then(|s| s.as_str()) else {
+            return Ok(None);
+        };
+
+        parse_severity(name).map(Some).ok_or_else(|| {
+            invalid_arguments(format!(
+                "Invalid min_severity {}: expected error, warning, information or hint",
+                name
+            ))
+        })
+    }
+
+    /// Extract the optional `kinds` parameter as LSP `SymbolKind`s.
+    fn extract_symbol_kinds(args: &Value) -> Result<Option<Vec<u64>>> {
+        let Some(kinds) = args.get("kinds") else {
+            return Ok(None);
+        };
+        let Some(kinds) = kinds.as_array() else {
+            return Err(invalid_arguments(
+                "kinds must be an array of symbol kind names",
+            ));
+        };
+
+        kinds
+            .iter()
+            .map(|kind| {
+                let name = kind.as_str().unwrap_or_default();
+                parse_symbol_kind(name).ok_or_else(|| {
+                    invalid_arguments(format!(
+                        "Invalid symbol kind {}, e.g. struct or function",
+                        kind
+                    ))
+                })
+            })
+            .collect::<Result<Vec<u64>>>()
+            .map(Some)
+    }
+
+    /// Extract the optional `start_line`, `start_character`, `end_line` and `end_character`
+    /// parameters, which must be given all together if at all.
+    fn extract_optional_range(args: &Value) -> Result<Option<(u32, u32, u32, u32)>> {
+        let fields = ["start_line", "start_character", "end_line", "end_character"];
+        let values: Vec<Option<u64>> = fields.iter().map(|f| args[f].as_u64()).collect();
+
+        match values[..] {
+            [None, None, None, None] => Ok(None),
+            [Some(start_line), Some(start_character), Some(end_line), Some(end_character)] => {
+                Ok(Some((
+                    start_line as u32,
+                    start_character as u32,
+                    end_line as u32,
+                    end_character as u32,
+                )))
+            }
+            _ => Err(invalid_arguments(
+                "A range requires all of start_line, start_character, end_line and end_character",
+            )),
+        }
+    }
+
+    /// Extract the optional `offset` (default: 0) and `limit` (default: none) parameters, paging
+    /// through the results of a tool.
+    fn extract_page(args: &Value) -> Result<(usize, Option<usize>)> {
+        let extract = |name: &str| -> Result<Option<usize>> {
+            let Some(value) = args.get(name) else {
+                return Ok(None);
+            };
+            value
+                .as_u64()
+                .map(|value| Some(value as usize))
+                .ok_or_else(|| {
+                    invalid_arguments(format!(
+                        "{} must be a non-negative integer, got {}",
+                        name, value
+                    ))
+                })
+        };
+
+        Ok((extract("offset")?.unwrap_or(0), extract("limit")?))
+    }
+}
+
+/// Keep the `limit` items from `offset` on, telling whether there are more after them.
+///
+/// The items must be sorted deterministically for the pages to be consistent.
+fn paginate<T>(items: Vec<T>, offset: usize, limit: Option<usize>) -> (Vec<T>, bool) {
+    let total = items.len();
+    let page: Vec<T> = items
+        .into_iter()
+        .skip(offset)
+        .take(limit.unwrap_or(usize::MAX))
+        .collect();
+    let has_more = offset.saturating_add(page.len()) < total;
+
+    (page, has_more)
+}
+
+/// An error about the arguments of a tool call.
+fn invalid_arguments(message: impl Into<String>) -> anyhow::Error {
+    ToolError::new(ErrorKind::InvalidArguments, message).into()
+}
+
+/// The message of the error rust-analyzer replied with, if that's what `error` is.
+fn lsp_error_message(error: &anyhow::Error) -> Option<&str> {
+    error
+        .downcast_ref::<ToolError>()
+        .filter(|e| e.kind == ErrorKind::LspRequestFailed)
+        .map(|e| e.message.as_str())
 }
 
 pub async fn handle_tool_call(
@@ -48,26 +192,189 @@ pub async fn handle_tool_call(
     tool_name: &str,
     args: Value,
 ) -> Result<ToolResult> {
-    server.ensure_client_started().await?;
+    // Reporting the version must work even if rust-analyzer can't be started, the health check
+    // reports the state of rust-analyzer without changing it, and the workspace members and the
+    // explanations of diagnostics don't come from rust-analyzer, so they're available before it is.
+    if !matches!(
+        tool_name,
+        "rust_analyzer_version"
+            | "rust_analyzer_health_check"
+            | "rust_analyzer_workspace_members"
+            | "rust_analyzer_explain_diagnostic"
+    ) {
+        server.ensure_client_started().await?;
+    }
+
+    if !ResponseCache::is_cacheable(tool_name) {
+        return dispatch_tool_call(server, tool_name, args).await;
+    }
+
+    let Some(client) = &server.client else {
+        return Err(anyhow!("Client not initialized"));
+    };
+    let generation = client.edit_generation();
+    // Results computed while indexing are likely incomplete, so they aren't cached.
+    let indexing = client.is_indexing().await;
 
+    if let Some(cached) = server.cache.get(tool_name, &args, generation) {
+        return Ok(serde_json::from_value(cached)?);
+    }
+
+    let cache_args = args.clone();
+    let result = dispatch_tool_call(server, tool_name, args).await?;
+    // A document may have changed while computing the result, e.g. the queried one being resynced
+    // with its content on disk, so the result may mix old and new content.
+    let changed = server
+        .client
+        .as_ref()
+        .is_none_or(|client| client.edit_generation() != generation);
+    if !indexing && !changed {
+        if let Some(file_path) = cache_args["file_path"].as_str() {
+            let path = server.resolve_workspace_path(file_path)?;
+            server.cache.insert(
+                tool_name,
+                &cache_args,
+                &path,
+                generation,
+                serde_json::to_value(&result)?,
+            );
+        }
+    }
+
+    Ok(result)
+}
+
+/// Run several tool calls in order, each entry of the result being the result of a call or the
+/// error it failed with.
+async fn handle_batch(server: &mut RustAnalyzerMCPServer, args: Value) -> Result<ToolResult> {
+    let Some(calls) = args["calls"].as_array() else {
+        return Err(invalid_arguments("Missing calls"));
+    };
+
+    let mut results = Vec::with_capacity(calls.len());
+    for call in calls {
+        let Some(tool) = call["tool"].as_str() else {
+            results.push(json!({
+                "tool": call["tool"],
+                "error": MCPError::from_tool_error(&invalid_arguments("Missing tool"))
+            }));
+            continue;
+        };
+        let arguments = call.get("arguments").cloned().unwrap_or_else(|| json!({}));
+
+        let result = if tool == "rust_analyzer_batch" {
+            Err(invalid_arguments("Batches can't be nested"))
+        } else {
+            // Boxed, as this recursion would otherwise make the future infinitely sized.
+            Box::pin(handle_tool_call(server, tool, arguments)).await
+        };
+        results.push(match result {
+            Ok(result) => json!({
+                "tool": tool,
+                "result": tool_result_value(result)
+            }),
+            Err(e) => json!({
+                "tool": tool,
+                "error": MCPError::from_tool_error(&e)
+            }),
+        });
+    }
+
+    Ok(ToolResult {
+        content: vec![ContentItem {
+            content_type: "text".to_string(),
+            text: serde_json::to_string_pretty(&results)?,
+        }],
+    })
+}
+
+/// The content of a tool result as JSON, parsed if it's JSON text as with most tools.
+fn tool_result_value(result: ToolResult) -> Value {
+    let text: String = result.content.into_iter().map(|item| item.text).collect();
+    serde_json::from_str(&text).unwrap_or(Value::String(text))
+}
+
+async fn dispatch_tool_call(
+    server: &mut RustAnalyzerMCPServer,
+    tool_name: &str,
+    args: Value,
+) -> Result<ToolResult> {
     match tool_name {
+        "rust_analyzer_batch" => handle_batch(server, args).await,
         "rust_analyzer_hover" => handle_hover(server, args).await,
+        "rust_analyzer_hover_range" => handle_hover_range(server, args).await,
         "rust_analyzer_definition" => handle_definition(server, args).await,
+        "rust_analyzer_declaration" => handle_declaration(server, args).await,
         "rust_analyzer_references" => handle_references(server, args).await,
+        "rust_analyzer_parent_module" => handle_parent_module(server, args).await,
+        "rust_analyzer_child_modules" => handle_child_modules(server, args).await,
+        "rust_analyzer_impls_for_type" => handle_impls_for_type(server, args).await,
         "rust_analyzer_completion" => handle_completion(server, args).await,
+        "rust_analyzer_completion_resolve" => handle_completion_resolve(server, args).await,
+        "rust_analyzer_code_lens" => handle_code_lens(server, args).await,
+        "rust_analyzer_semantic_tokens" => handle_semantic_tokens(server, args).await,
         "rust_analyzer_symbols" => handle_symbols(server, args).await,
+        "rust_analyzer_goto_symbol" => handle_goto_symbol(server, args).await,
+        "rust_analyzer_workspace_symbols" => handle_workspace_symbols(server, args).await,
         "rust_analyzer_format" => handle_format(server, args).await,
+        "rust_analyzer_format_range" => handle_format_range(server, args).await,
         "rust_analyzer_code_actions" => handle_code_actions(server, args).await,
+        "rust_analyzer_move_item" => handle_move_item(server, args).await,
+        "rust_analyzer_ssr" => handle_ssr(server, args).await,
+        "rust_analyzer_rename" => handle_rename(server, args).await,
+        "rust_analyzer_apply_edits" => handle_apply_edits(server, args).await,
+        "rust_analyzer_create_file" => handle_create_file(server, args).await,
+        "rust_analyzer_rename_file" => handle_rename_file(server, args).await,
+        "rust_analyzer_read_file" => handle_read_file(server, args).await,
+        "rust_analyzer_write_file" => handle_write_file(server, args).await,
+        "rust_analyzer_diff_file" => handle_diff_file(server, args).await,
+        "rust_analyzer_list_files" => handle_list_files(server, args).await,
         "rust_analyzer_set_workspace" => handle_set_workspace(server, args).await,
         "rust_analyzer_diagnostics" => handle_diagnostics(server, args).await,
         "rust_analyzer_workspace_diagnostics" => handle_workspace_diagnostics(server, args).await,
-        _ => Err(anyhow!("Unknown tool: {}", tool_name)),
+        "rust_analyzer_workspace_errors" => handle_workspace_errors(server, args).await,
+        "rust_analyzer_explain_diagnostic" => handle_explain_diagnostic(server, args).await,
+        "rust_analyzer_index_workspace" => handle_index_workspace(server, args).await,
+        "rust_analyzer_status" => handle_status(server, args).await,
+        "rust_analyzer_server_status" => handle_server_status(server).await,
+        "rust_analyzer_wait_for_analysis" => handle_wait_for_analysis(server, args).await,
+        "rust_analyzer_run_check" => handle_run_check(server, args).await,
+        "rust_analyzer_health_check" => handle_health_check(server).await,
+        "rust_analyzer_memory_usage" => handle_memory_usage(server).await,
+        "rust_analyzer_version" => handle_version(server).await,
+        "rust_analyzer_workspace_info" => handle_workspace_info(server).await,
+        "rust_analyzer_workspace_members" => handle_workspace_members(server).await,
+        "rust_analyzer_open_documents" => handle_open_documents(server).await,
+        "rust_analyzer_close_document" => handle_close_document(server, args).await,
+        "rust_analyzer_reload_workspace" => handle_reload_workspace(server).await,
+        "rust_analyzer_set_check_command" => handle_set_check_command(server, args).await,
+        _ => Err(invalid_arguments(format!("Unknown tool: {}", tool_name))),
     }
 }
 
 async fn handle_hover(server: &mut RustAnalyzerMCPServer, args: Value) -> Result<ToolResult> {
     let file_path = ToolParams::extract_file_path(&args)?;
     let (line, character) = ToolParams::extract_position(&args)?;
+    let text = match args["format"].as_str() {
+        None | Some("markdown") => false,
+        Some("text") => true,
+        Some(format) => {
+            return Err(invalid_arguments(format!(
+                "Invalid format `{}`, expected `markdown` or `text`",
+                format
+            )))
+        }
+    };
+    let raw = args["raw"].as_bool().unwrap_or(false);
+    let end = match (args["end_line"].as_u64(), args["end_character"].as_u64()) {
+        (Some(end_line), Some(end_character)) => Some((end_line as u32, end_character as u32)),
+        (None, None) => None,
+        _ => {
+            return Err(invalid_arguments(
+                "A range requires both end_line and end_character",
+            ))
+        }
+    };
 
     let uri = server.open_document_if_needed(&file_path).await?;
 
@@ -75,7 +382,25 @@ async fn handle_hover(server: &mut RustAnalyzerMCPServer, args: Value) -> Result
         return Err(anyhow!("Client not initialized"));
     };
 
-    let result = client.hover(&uri, line, character).await?;
+    let mut result = client.hover(&uri, line, character, end).await?;
+    if text && !raw && !result.is_null() {
+        let range = &result["range"];
+        let position = |position: &str, field: &str| range[position][field].clone();
+        result = json!({
+            "text": hover_markdown(&result).map(|markdown| markdown_to_text(&markdown)),
+            "range": range.is_object().then(|| json!({
+                "start_line": position("start", "line"),
+                "start_character": position("start", "character"),
+                "end_line": position("end", "line"),
+                "end_character": position("end", "character")
+            }))
+        });
+    } else if !raw && !result.is_null() {
+        result = json!({
+            "markdown": hover_markdown(&result),
+            "range": result["range"]
+        });
+    }
 
     Ok(ToolResult {
         content: vec![ContentItem {
@@ -85,9 +410,9 @@ async fn handle_hover(server: &mut RustAnalyzerMCPServer, args: Value) -> Result
     })
 }
 
-async fn handle_definition(server: &mut RustAnalyzerMCPServer, args: Value) -> Result<ToolResult> {
+async fn handle_hover_range(server: &mut RustAnalyzerMCPServer, args: Value) -> Result<ToolResult> {
     let file_path = ToolParams::extract_file_path(&args)?;
-    let (line, character) = ToolParams::extract_position(&args)?;
+    let (line, character, end_line, end_character) = ToolParams::extract_range(&args)?;
 
     let uri = server.open_document_if_needed(&file_path).await?;
 
@@ -95,7 +420,36 @@ async fn handle_definition(server: &mut RustAnalyzerMCPServer, args: Value) -> R
         return Err(anyhow!("Client not initialized"));
     };
 
-    let result = client.definition(&uri, line, character).await?;
+    let symbols = flatten_symbols(&client.document_symbols(&uri).await?);
+    let mut positions: Vec<(u32, u32)> = symbols
+        .iter()
+        .filter_map(|symbol| {
+            let position = (
+                symbol["line"].as_u64()? as u32,
+                symbol["character"].as_u64()? as u32,
+            );
+            (position >= (line, character) && position <= (end_line, end_character))
+                .then_some(position)
+        })
+        .collect();
+    positions.sort();
+    positions.dedup();
+
+    let truncated = positions.len() > MAX_RANGE_HOVERS;
+    positions.truncate(MAX_RANGE_HOVERS);
+
+    let mut hovers = serde_json::Map::new();
+    for (line, character) in positions {
+        let hover = client.hover(&uri, line, character, None).await?;
+        if let Some(text) = hover_markdown(&hover) {
+            hovers.insert(format!("{}:{}", line, character), json!(text));
+        }
+    }
+
+    let result = json!({
+        "hovers": hovers,
+        "truncated": truncated
+    });
 
     Ok(ToolResult {
         content: vec![ContentItem {
@@ -105,9 +459,14 @@ async fn handle_definition(server: &mut RustAnalyzerMCPServer, args: Value) -> R
     })
 }
 
-async fn handle_references(server: &mut RustAnalyzerMCPServer, args: Value) -> Result<ToolResult> {
+async fn handle_definition(server: &mut RustAnalyzerMCPServer, args: Value) -> Result<ToolResult> {
     let file_path = ToolParams::extract_file_path(&args)?;
     let (line, character) = ToolParams::extract_position(&args)?;
+    // `include_snippet` is the former name of `include_source`.
+    let include_source = args["include_source"]
+        .as_bool()
+        .or_else(|| args["include_snippet"].as_bool())
+        .unwrap_or(false);
 
     let uri = server.open_document_if_needed(&file_path).await?;
 
@@ -115,7 +474,18 @@ async fn handle_references(server: &mut RustAnalyzerMCPServer, args: Value) -> R
         return Err(anyhow!("Client not initialized"));
     };
 
-    let result = client.references(&uri, line, character).await?;
+    let mut result = client.definition(&uri, line, character).await?;
+    match &mut result {
+        Value::Array(locations) => {
+            for location in locations {
+                annotate_location(&server.workspace_root, location, include_source).await;
+            }
+        }
+        Value::Object(_) => {
+            annotate_location(&server.workspace_root, &mut result, include_source).await;
+        }
+        _ => {}
+    }
 
     Ok(ToolResult {
         content: vec![ContentItem {
@@ -125,7 +495,62 @@ async fn handle_references(server: &mut RustAnalyzerMCPServer, args: Value) -> R
     })
 }
 
-async fn handle_completion(server: &mut RustAnalyzerMCPServer, args: Value) -> Result<ToolResult> {
+/// Flag a `Location` or `LocationLink` pointing outside of the workspace, e.g. into a dependency or
+/// the standard library, as external along with the crate it belongs to. With `include_source`,
+/// an excerpt of the target's source is embedded too, as the model may not be able to read it.
+async fn annotate_location(workspace_root: &Path, location: &mut Value, include_source: bool) {
+    let Some(path) = location["uri"]
+        .as_str()
+        .or_else(|| location["targetUri"].as_str())
+        .and_then(uri_to_path)
+    else {
+        return;
+    };
+
+    let canonical_root = workspace_root.canonicalize().ok();
+    let external = !path.starts_with(workspace_root)
+        && !canonical_root.is_some_and(|root| path.starts_with(root));
+    location["external"] = json!(external);
+    let krate = external.then(|| external_crate(&path)).flatten();
+    if let Some(krate) = &krate {
+        location["crate"] = json!(krate.name);
+        location["version"] = json!(krate.version);
+        location["source"] = json!(krate.source);
+        location["crate_path"] = json!(krate.path);
+    }
+
+    if !include_source {
+        return;
+    }
+    // The target range of a `LocationLink` covers the whole item, including its doc comment.
+    let range = location
+        .get("targetRange")
+        .unwrap_or(&location["range"])
+        .clone();
+    let (Some(start_line), Some(end_line)) = (
+        range["start"]["line"].as_u64(),
+        range["end"]["line"].as_u64(),
+    ) else {
+        return;
+    };
+    let content = match tokio::fs::read_to_string(&path).await {
+        Ok(content) => content,
+        Err(e) => {
+            let note = match &krate {
+                Some(krate) => krate.missing_sources_note(),
+                None => format!("Can't read {}: {}", path.display(), e),
+            };
+            location["source_snippet"] = Value::Null;
+            location["note"] = json!(note);
+            return;
+        }
+    };
+    if let Some(snippet) = source_excerpt(&content, start_line as usize, end_line as usize) {
+        location["source_snippet"] = json!(snippet);
+    }
+}
+
+async fn handle_declaration(server: &mut RustAnalyzerMCPServer, args: Value) -> Result<ToolResult> {
     let file_path = ToolParams::extract_file_path(&args)?;
     let (line, character) = ToolParams::extract_position(&args)?;
 
@@ -135,7 +560,7 @@ async fn handle_completion(server: &mut RustAnalyzerMCPServer, args: Value) -> R
         return Err(anyhow!("Client not initialized"));
     };
 
-    let result = client.completion(&uri, line, character).await?;
+    let result = client.declaration(&uri, line, character).await?;
 
     Ok(ToolResult {
         content: vec![ContentItem {
@@ -145,19 +570,41 @@ async fn handle_completion(server: &mut RustAnalyzerMCPServer, args: Value) -> R
     })
 }
 
-async fn handle_symbols(server: &mut RustAnalyzerMCPServer, args: Value) -> Result<ToolResult> {
+async fn handle_references(server: &mut RustAnalyzerMCPServer, args: Value) -> Result<ToolResult> {
     let file_path = ToolParams::extract_file_path(&args)?;
+    let (line, character) = ToolParams::extract_position(&args)?;
+    let context_lines = args["context_lines"].as_u64().unwrap_or(0) as usize;
+    let include_declaration = args["include_declaration"].as_bool().unwrap_or(true);
+    let (offset, limit) = ToolParams::extract_page(&args)?;
 
-    debug!("Getting symbols for file: {}", file_path);
     let uri = server.open_document_if_needed(&file_path).await?;
-    debug!("Document opened with URI: {}", uri);
 
     let Some(client) = &mut server.client else {
         return Err(anyhow!("Client not initialized"));
     };
 
-    let result = client.document_symbols(&uri).await?;
-    debug!("Document symbols result: {:?}", result);
+    let mut locations = match client
+        .references(&uri, line, character, include_declaration)
+        .await?
+    {
+        Value::Array(locations) => locations,
+        _ => vec![],
+    };
+    sort_locations(&mut locations);
+    let total = locations.len();
+    let (locations, has_more) = paginate(locations, offset, limit);
+    let count = locations.len();
+    let mut locations = Value::Array(locations);
+    if context_lines > 0 {
+        add_reference_snippets(&mut locations, context_lines).await;
+    }
+
+    let result = json!({
+        "count": count,
+        "total": total,
+        "has_more": has_more,
+        "locations": locations
+    });
 
     Ok(ToolResult {
         content: vec![ContentItem {
@@ -167,8 +614,73 @@ async fn handle_symbols(server: &mut RustAnalyzerMCPServer, args: Value) -> Resu
     })
 }
 
-async fn handle_format(server: &mut RustAnalyzerMCPServer, args: Value) -> Result<ToolResult> {
+/// Sort locations by file and position, dropping the duplicates, e.g. the same reference found
+/// through several macro expansions.
+fn sort_locations(locations: &mut Vec<Value>) {
+    let key = |location: &Value| {
+        let range = &location["range"];
+        (
+            location["uri"].as_str().unwrap_or_default().to_string(),
+            range["start"]["line"].as_u64(),
+            range["start"]["character"].as_u64(),
+            range["end"]["line"].as_u64(),
+            range["end"]["character"].as_u64(),
+        )
+    };
+    locations.sort_by_cached_key(key);
+    locations.dedup_by(|a, b| a["uri"] == b["uri"] && a["range"] == b["range"]);
+}
+
+/// Add the lines around each reference, with `context_lines` lines of context, as a `snippet`.
+///
+/// Each file is only read once, however many references it has.
+async fn add_reference_snippets(references: &mut Value, context_lines: usize) {
+    let Some(references) = references.as_array_mut() else {
+        return;
+    };
+
+    let mut contents: HashMap<String, Option<String>> = HashMap::new();
+    for reference in references {
+        let Some(uri) = reference["uri"].as_str() else {
+            continue;
+        };
+        if !contents.contains_key(uri) {
+            let content = match uri_to_path(uri) {
+                Some(path) => tokio::fs::read_to_string(path).await.ok(),
+                None => None,
+            };
+            contents.insert(uri.to_string(), content);
+        }
+        let Some(Some(content)) = contents.get(uri) else {
+            continue;
+        };
+
+        let range = &reference["range"];
+        let (Some(start_line), Some(end_line)) = (
+            range["start"]["line"].as_u64(),
+            range["end"]["line"].as_u64(),
+        ) else {
+            continue;
+        };
+        if let Some(snippet) = context_excerpt(
+            content,
+            start_line as usize,
+            end_line as usize,
+            context_lines,
+        ) {
+            reference["snippet"] = json!(snippet);
+        }
+    }
+}
+
+async fn handle_parent_module(
+    server: &mut RustAnalyzerMCPServer,
+    args: Value,
+) -> Result<ToolResult> {
     let file_path = ToolParams::extract_file_path(&args)?;
+    // Default to the module of the file itself rather than an inline module within it.
+    let line = args["line"].as_u64().unwrap_or(0) as u32;
+    let character = args["character"].as_u64().unwrap_or(0) as u32;
 
     let uri = server.open_document_if_needed(&file_path).await?;
 
@@ -176,22 +688,78 @@ async fn handle_format(server: &mut RustAnalyzerMCPServer, args: Value) -> Resul
         return Err(anyhow!("Client not initialized"));
     };
 
-    let result = client.formatting(&uri).await?;
+    let result = client.parent_module(&uri, line, character).await?;
+
+    let links = match result {
+        Value::Array(links) => links,
+        Value::Null => vec![],
+        link => vec![link],
+    };
+    let locations: Vec<Value> = links
+        .iter()
+        .filter_map(|link| {
+            // Both `Location` and `LocationLink` are possible, the selection range of the latter
+            // being the name of the module in its `mod` declaration.
+            let target_uri = link["targetUri"]
+                .as_str()
+                .or_else(|| link["uri"].as_str())?;
+            let range = link.get("targetSelectionRange").unwrap_or(&link["range"]);
+            let file = uri_to_path(target_uri).map(|path| {
+                path.strip_prefix(&server.workspace_root)
+                    .unwrap_or(&path)
+                    .display()
+                    .to_string()
+            });
+
+            Some(json!({
+                "file": file,
+                "line": range["start"]["line"],
+                "character": range["start"]["character"],
+                "uri": target_uri
+            }))
+        })
+        .collect();
+    // No locations don't make a crate root, as rust-analyzer has none while it's indexing either,
+    // so crate roots are the ones of the Cargo targets.
+    let path = server.resolve_workspace_path(&file_path)?;
+    let is_crate_root = locations.is_empty() && is_crate_root(&path);
+    let mut output = json!({
+        "locations": locations,
+        "is_crate_root": is_crate_root
+    });
+    if locations.is_empty() {
+        let file = path
+            .strip_prefix(&server.workspace_root)
+            .unwrap_or(&path)
+            .display();
+        let message = if is_crate_root {
+            format!("{} is a crate root; no module declares it", file)
+        } else {
+            format!(
+                "No parent module found for {}; it may not be declared by any module, or \
+                 rust-analyzer may still be indexing",
+                file
+            )
+        };
+        output["message"] = json!(message);
+    }
 
     Ok(ToolResult {
         content: vec![ContentItem {
             content_type: "text".to_string(),
-            text: serde_json::to_string_pretty(&result)?,
+            text: serde_json::to_string_pretty(&output)?,
         }],
     })
 }
 
-async fn handle_code_actions(
+async fn handle_child_modules(
     server: &mut RustAnalyzerMCPServer,
     args: Value,
 ) -> Result<ToolResult> {
     let file_path = ToolParams::extract_file_path(&args)?;
-    let (line, character, end_line, end_character) = ToolParams::extract_range(&args)?;
+    // Default to the module of the file itself rather than an inline module within it.
+    let line = args["line"].as_u64().unwrap_or(0) as u32;
+    let character = args["character"].as_u64().unwrap_or(0) as u32;
 
     let uri = server.open_document_if_needed(&file_path).await?;
 
@@ -199,227 +767,2065 @@ async fn handle_code_actions(
         return Err(anyhow!("Client not initialized"));
     };
 
-    let result = client
-        .code_actions(&uri, line, character, end_line, end_character)
-        .await?;
+    let result = client.child_modules(&uri, line, character).await?;
+    let links = match result {
+        Value::Array(links) => links,
+        Value::Null => vec![],
+        link => vec![link],
+    };
+    if links.is_empty() {
+        let output = json!({ "modules": [], "count": 0 });
+        return Ok(ToolResult {
+            content: vec![ContentItem {
+                content_type: "text".to_string(),
+                text: serde_json::to_string_pretty(&output)?,
+            }],
+        });
+    }
+
+    // The `mod` declarations are found among the symbols of the file.
+    let symbols = flatten_symbols(&client.document_symbols(&uri).await?);
+    let content = tokio::fs::read_to_string(server.resolve_workspace_path(&file_path)?)
+        .await
+        .map_err(|e| ToolError::file_read(&file_path, e))?;
+
+    let mut modules = Vec::new();
+    for link in &links {
+        // Both `Location` and `LocationLink` are possible.
+        let (Some(target_uri), range) = (
+            link["targetUri"].as_str().or_else(|| link["uri"].as_str()),
+            link.get("targetSelectionRange").unwrap_or(&link["range"]),
+        ) else {
+            continue;
+        };
+
+        let inline = target_uri == uri;
+        let name = if inline {
+            range_text(&content, range).map(String::from)
+        } else {
+            uri_to_path(target_uri).as_deref().and_then(module_name)
+        };
+        let declaration = name.as_deref().and_then(|name| {
+            symbols
+                .iter()
+                .find(|s| s["kind"] == 2 && s["name"] == name)
+                .map(|s| json!({ "line": s["line"], "character": s["character"] }))
+        });
+        let file = (!inline)
+            .then(|| uri_to_path(target_uri))
+            .flatten()
+            .map(|path| {
+                path.strip_prefix(&server.workspace_root)
+                    .unwrap_or(&path)
+                    .display()
+                    .to_string()
+            });
+
+        modules.push(json!({
+            "name": name,
+            "inline": inline,
+            "declaration": declaration,
+            "file": file,
+            "location": {
+                "uri": target_uri,
+                "range": range
+            }
+        }));
+    }
+
+    let output = json!({
+        "modules": modules,
+        "count": modules.len()
+    });
 
     Ok(ToolResult {
         content: vec![ContentItem {
             content_type: "text".to_string(),
-            text: serde_json::to_string_pretty(&result)?,
+            text: serde_json::to_string_pretty(&output)?,
         }],
     })
 }
 
-async fn handle_set_workspace(
+/// The name of the module backed by a file, e.g. `foo` for both `foo.rs` and `foo/mod.rs`.
+fn module_name(path: &Path) -> Option<String> {
+    let stem = path.file_stem()?.to_str()?;
+    if stem != "mod" {
+        return Some(stem.to_string());
+    }
+
+    path.parent()?.file_name()?.to_str().map(String::from)
+}
+
+async fn handle_impls_for_type(
     server: &mut RustAnalyzerMCPServer,
     args: Value,
 ) -> Result<ToolResult> {
-    let Some(workspace_path) = args["workspace_path"].as_str() else {
-        return Err(anyhow!("Missing workspace_path"));
+    let file_path = ToolParams::extract_file_path(&args)?;
+    let (line, character) = ToolParams::extract_position(&args)?;
+
+    let uri = server.open_document_if_needed(&file_path).await?;
+
+    let Some(client) = &mut server.client else {
+        return Err(anyhow!("Client not initialized"));
     };
 
-    // Shutdown existing client.
-    if let Some(client) = &mut server.client {
-        client.shutdown().await?;
+    let result = client.implementation(&uri, line, character).await?;
+    let locations = match result {
+        Value::Array(locations) => locations,
+        Value::Null => vec![],
+        location => vec![location],
+    };
+
+    let mut contents = HashMap::new();
+    let mut impls = Vec::new();
+    for location in &locations {
+        // Both `Location` and `LocationLink` point at the implementing type in the impl header.
+        let (Some(uri), range) = (
+            location["uri"]
+                .as_str()
+                .or_else(|| location["targetUri"].as_str()),
+            location
+                .get("targetSelectionRange")
+                .unwrap_or(&location["range"]),
+        ) else {
+            continue;
+        };
+        let Some(path) = uri_to_path(uri) else {
+            continue;
+        };
+
+        if !contents.contains_key(&path) {
+            let content = tokio::fs::read_to_string(&path).await.ok();
+            contents.insert(path.clone(), content);
+        }
+        let Some(content) = contents[&path].as_deref() else {
+            continue;
+        };
+        let Some((start, end)) = range_to_offsets(content, range) else {
+            continue;
+        };
+
+        let file = path
+            .strip_prefix(&server.workspace_root)
+            .unwrap_or(&path)
+            .display()
+            .to_string();
+        let impl_line = &range["start"]["line"];
+        if let Some((trait_name, self_type)) = derive_at(content, start, end) {
+            impls.push(json!({
+                "trait": trait_name,
+                "self_type": self_type,
+                "file": file,
+                "line": impl_line,
+                "derived": true
+            }));
+            continue;
+        }
+
+        // Skip anything else than impl blocks, e.g. implementations of trait items.
+        let Some(header) = impl_header_at(content, start) else {
+            continue;
+        };
+        let Some((trait_name, self_type)) = parse_impl_header(header) else {
+            continue;
+        };
+        impls.push(json!({
+            "trait": trait_name,
+            "self_type": self_type,
+            "file": file,
+            "line": impl_line,
+            "derived": false,
+            "header": header.split_whitespace().collect::<Vec<_>>().join(" ")
+        }));
     }
-    server.client = None;
+    impls.sort_by_key(|i| (i["file"].as_str().map(String::from), i["line"].as_u64()));
 
-    // Set new workspace with proper absolute path handling.
-    let workspace_root = PathBuf::from(workspace_path);
-    server.workspace_root = workspace_root.canonicalize().unwrap_or_else(|_| {
-        if workspace_root.is_absolute() {
-            workspace_root.clone()
-        } else {
-            std::env::current_dir()
-                .unwrap_or_else(|_| PathBuf::from("."))
-                .join(&workspace_root)
+    let trait_impls = impls.iter().filter(|i| !i["trait"].is_null()).count();
+    let result = json!({
+        "impls": impls,
+        "summary": {
+            "total": impls.len(),
+            "trait_impls": trait_impls,
+            "inherent_impls": impls.len() - trait_impls
         }
     });
 
-    // Start the new client automatically.
-    server.ensure_client_started().await?;
-
     Ok(ToolResult {
         content: vec![ContentItem {
             content_type: "text".to_string(),
-            text: format!("Workspace set to: {}", server.workspace_root.display()),
+            text: serde_json::to_string_pretty(&result)?,
         }],
     })
 }
 
-async fn handle_diagnostics(server: &mut RustAnalyzerMCPServer, args: Value) -> Result<ToolResult> {
+async fn handle_completion(server: &mut RustAnalyzerMCPServer, args: Value) -> Result<ToolResult> {
     let file_path = ToolParams::extract_file_path(&args)?;
+    let (line, character) = ToolParams::extract_position(&args)?;
+    let trigger_character = args["trigger_character"].as_str();
+    let max_items = args["max_items"]
+        .as_u64()
+        .map(|max| max as usize)
+        .unwrap_or(MAX_COMPLETION_ITEMS);
+    let full = args["full"].as_bool().unwrap_or(false);
+    let structured = args["structured"].as_bool().unwrap_or(false);
+    let resolve_top_n = args["resolve_top_n"]
+        .as_u64()
+        .map(|n| n as usize)
+        .unwrap_or(COMPLETION_RESOLVE_LIMIT);
 
     let uri = server.open_document_if_needed(&file_path).await?;
 
-    // Poll for diagnostics - rust-analyzer needs time to run cargo check.
-    // For files with expected errors (like diagnostics_test.rs), poll longer.
-    let should_poll = file_path.contains("diagnostics_test") || file_path.contains("simple_error");
-
     let Some(client) = &mut server.client else {
         return Err(anyhow!("Client not initialized"));
     };
 
-    let mut result = json!([]);
-    if should_poll {
-        let start = std::time::Instant::now();
-        let timeout = tokio::time::Duration::from_secs(8); // Less than test timeout.
-        let poll_interval = tokio::time::Duration::from_millis(500);
+    let result = client
+        .completion(&uri, line, character, trigger_character)
+        .await?;
 
-        while start.elapsed() < timeout {
-            result = client.diagnostics(&uri).await?;
-            let Some(diag_array) = result.as_array() else {
-                tokio::time::sleep(poll_interval).await;
-                continue;
+    // Completions come either as a list or as a bare array of items.
+    let (mut items, is_incomplete) = match result {
+        Value::Array(items) => (items, false),
+        Value::Object(mut list) => {
+            let is_incomplete = list["isIncomplete"].as_bool().unwrap_or(false);
+            match list.remove("items") {
+                Some(Value::Array(items)) => (items, is_incomplete),
+                _ => (vec![], is_incomplete),
+            }
+        }
+        result => {
+            // rust-analyzer has no completions until it analyzed the workspace, which shouldn't be
+            // taken for there being none.
+            let ready =
+                client.server_status().await == ServerStatus::Ready && !client.is_indexing().await;
+            let result = if result.is_null() && !ready {
+                json!({
+                    "status": "indexing",
+                    "message": "rust-analyzer is still analyzing the workspace, try again once \
+                                rust_analyzer_wait_for_analysis returns"
+                })
+            } else {
+                result
             };
 
-            if !diag_array.is_empty() {
-                // We got diagnostics, stop polling.
-                break;
-            }
-            tokio::time::sleep(poll_interval).await;
+            return Ok(ToolResult {
+                content: vec![ContentItem {
+                    content_type: "text".to_string(),
+                    text: serde_json::to_string_pretty(&result)?,
+                }],
+            });
+        }
+    };
+
+    items.sort_by(|a, b| {
+        let key = |item: &Value| {
+            item["sortText"]
+                .as_str()
+                .or_else(|| item["label"].as_str())
+                .unwrap_or_default()
+                .to_string()
+        };
+        key(a).cmp(&key(b))
+    });
+
+    let total_items = items.len();
+    let truncated = total_items > max_items;
+    items.truncate(max_items);
+
+    // Resolve the top items so they come with their documentation, full detail and the edits
+    // adding any import they need. The requests are independent, so they're all sent at once.
+    let client = &*client;
+    let unresolved = items
+        .iter_mut()
+        .take(resolve_top_n)
+        .filter(|item| needs_resolve(item));
+    join_all(unresolved.map(|item| async move {
+        match client.completion_resolve(item).await {
+            Ok(resolved) if resolved.is_object() => *item = resolved,
+            Ok(_) => {}
+            Err(e) => debug!("Failed to resolve completion item: {}", e),
         }
+    }))
+    .await;
+
+    let result = if structured {
+        json!({
+            "is_incomplete": is_incomplete || truncated,
+            "items": items.iter().map(structured_completion_item).collect::<Vec<_>>()
+        })
     } else {
-        // For clean files, just wait a bit and check once.
-        tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
-        result = client.diagnostics(&uri).await?;
-    }
+        if !full {
+            for item in items.iter_mut() {
+                if let Some(item) = item.as_object_mut() {
+                    item.remove("data");
+                    item.remove("additionalTextEdits");
+                }
+            }
+        }
 
-    let diagnostics = format_diagnostics(&file_path, &result);
+        json!({
+            "isIncomplete": is_incomplete || truncated,
+            "truncated": truncated,
+            "total_items": total_items,
+            "items": items
+        })
+    };
 
     Ok(ToolResult {
         content: vec![ContentItem {
             content_type: "text".to_string(),
-            text: serde_json::to_string_pretty(&diagnostics)?,
+            text: serde_json::to_string_pretty(&result)?,
         }],
     })
 }
 
-async fn handle_workspace_diagnostics(
+async fn handle_completion_resolve(
     server: &mut RustAnalyzerMCPServer,
-    _args: Value,
+    args: Value,
 ) -> Result<ToolResult> {
-    let Some(client) = &mut server.client else {
+    let item = &args["item"];
+    if !item.is_object() || item.get("label").is_none() {
+        return Err(invalid_arguments(
+            "Missing item: pass a completion item as returned with `full` set",
+        ));
+    }
+
+    let Some(client) = &server.client else {
         return Err(anyhow!("Client not initialized"));
     };
 
-    let result = client.workspace_diagnostics().await?;
-
-    // Format workspace diagnostics.
-    let formatted = format_workspace_diagnostics(&server.workspace_root, &result);
+    let resolved = client.completion_resolve(item).await?;
 
     Ok(ToolResult {
         content: vec![ContentItem {
             content_type: "text".to_string(),
-            text: serde_json::to_string_pretty(&formatted)?,
+            text: serde_json::to_string_pretty(&resolved)?,
         }],
     })
 }
 
-fn format_workspace_diagnostics(workspace_root: &Path, result: &Value) -> Value {
-    if !result.is_object() {
-        // Handle unexpected format.
-        if let Some(items) = result.get("items") {
-            return json!({
-                "workspace": workspace_root.display().to_string(),
-                "diagnostics": items,
-                "summary": {
-                    "total_diagnostics": items.as_array().map(|a| a.len()).unwrap_or(0),
-                    "by_severity": {}
-                }
-            });
+async fn handle_code_lens(server: &mut RustAnalyzerMCPServer, args: Value) -> Result<ToolResult> {
+    let file_path = ToolParams::extract_file_path(&args)?;
+    let kind = args["kind"].as_str();
+    if let Some(kind) = kind {
+        if !["test", "run", "debug", "implementation", "reference"].contains(&kind) {
+            return Err(invalid_arguments(format!(
+                "Unknown code lens kind: {}",
+                kind
+            )));
         }
-
-        return json!({
-            "workspace": workspace_root.display().to_string(),
-            "diagnostics": result,
-            "summary": {
-                "note": "Unexpected response format from rust-analyzer"
-            }
-        });
     }
 
-    // Fallback format (diagnostics per URI).
-    let mut output = json!({
-        "workspace": workspace_root.display().to_string(),
-        "files": {},
-        "summary": {
-            "total_files": 0,
-            "total_errors": 0,
-            "total_warnings": 0,
-            "total_information": 0,
-            "total_hints": 0
-        }
-    });
+    let uri = server.open_document_if_needed(&file_path).await?;
 
-    let mut total_errors = 0;
-    let mut total_warnings = 0;
-    let mut total_information = 0;
-    let mut total_hints = 0;
-    let mut file_count = 0;
+    let Some(client) = &server.client else {
+        return Err(anyhow!("Client not initialized"));
+    };
 
-    let Some(obj) = result.as_object() else {
-        return output;
+    let mut lenses = match client.code_lens(&uri).await? {
+        Value::Array(lenses) => lenses,
+        _ => vec![],
     };
 
-    for (uri, diagnostics) in obj {
-        let Some(diag_array) = diagnostics.as_array() else {
-            continue;
+    // Lenses that are expensive to compute, e.g. counting implementations, only get their command
+    // once resolved. The requests are independent, so they're all sent at once.
+    join_all(
+        lenses
+            .iter_mut()
+            .filter(|lens| lens.get("command").is_none())
+            .map(|lens| async move {
+                match client.code_lens_resolve(lens).await {
+                    Ok(resolved) if resolved.is_object() => *lens = resolved,
+                    Ok(_) => {}
+                    Err(e) => debug!("Failed to resolve code lens: {}", e),
+                }
+            }),
+    )
+    .await;
+
+    let lenses: Vec<Value> = lenses
+        .iter()
+        .filter(|lens| kind.is_none() || code_lens_kind(lens) == kind)
+        .map(format_code_lens)
+        .collect();
+
+    Ok(ToolResult {
+        content: vec![ContentItem {
+            content_type: "text".to_string(),
+            text: serde_json::to_string_pretty(&lenses)?,
+        }],
+    })
+}
+
+/// Whether resolving a completion item would add to it.
+fn needs_resolve(item: &Value) -> bool {
+    let needs_import = item["data"]["imports"]
+        .as_array()
+        .is_some_and(|imports| !imports.is_empty());
+
+    item.get("documentation").is_none() || item.get("detail").is_none() || needs_import
+}
+
+async fn handle_semantic_tokens(
+    server: &mut RustAnalyzerMCPServer,
+    args: Value,
+) -> Result<ToolResult> {
+    let file_path = ToolParams::extract_file_path(&args)?;
+
+    let uri = server.open_document_if_needed(&file_path).await?;
+
+    let Some(client) = &server.client else {
+        return Err(anyhow!("Client not initialized"));
+    };
+
+    let tokens = client.semantic_tokens(&uri).await?;
+
+    Ok(ToolResult {
+        content: vec![ContentItem {
+            content_type: "text".to_string(),
+            text: serde_json::to_string_pretty(&tokens)?,
+        }],
+    })
+}
+
+async fn handle_symbols(server: &mut RustAnalyzerMCPServer, args: Value) -> Result<ToolResult> {
+    let file_path = ToolParams::extract_file_path(&args)?;
+    let flat = args["flat"].as_bool().unwrap_or(false);
+    let kinds = ToolParams::extract_symbol_kinds(&args)?;
+
+    debug!("Getting symbols for file: {}", file_path);
+    let uri = server.open_document_if_needed(&file_path).await?;
+    debug!("Document opened with URI: {}", uri);
+
+    let Some(client) = &mut server.client else {
+        return Err(anyhow!("Client not initialized"));
+    };
+
+    let result = client.document_symbols(&uri).await?;
+    debug!("Document symbols result: {:?}", result);
+    let result = if flat {
+        json!(flatten_symbols(&result))
+    } else {
+        result
+    };
+    let mut result = match kinds {
+        Some(kinds) => filter_symbols_by_kind(&result, &kinds),
+        None => result,
+    };
+    name_symbol_kinds(&mut result);
+
+    Ok(ToolResult {
+        content: vec![ContentItem {
+            content_type: "text".to_string(),
+            text: serde_json::to_string_pretty(&result)?,
+        }],
+    })
+}
+
+async fn handle_goto_symbol(server: &mut RustAnalyzerMCPServer, args: Value) -> Result<ToolResult> {
+    let Some(name) = args["name"].as_str() else {
+        return Err(invalid_arguments("Missing name"));
+    };
+
+    let Some(client) = &mut server.client else {
+        return Err(anyhow!("Client not initialized"));
+    };
+
+    // rust-analyzer only searches for types by default, `#` extends the search to all symbols.
+    let symbols = client.workspace_symbols(&format!("{}#", name)).await?;
+    let matches: Vec<&Value> = symbols
+        .as_array()
+        .map(|symbols| {
+            symbols
+                .iter()
+                .filter(|symbol| symbol["name"].as_str() == Some(name))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let Some(best_rank) = matches.iter().map(|symbol| symbol_rank(symbol)).min() else {
+        return Err(anyhow!("No symbol named `{}` found in the workspace", name));
+    };
+    let best: Vec<&Value> = matches
+        .into_iter()
+        .filter(|symbol| symbol_rank(symbol) == best_rank)
+        .collect();
+    if best.len() > 1 {
+        let candidates: Vec<String> = best
+            .iter()
+            .map(|symbol| {
+                let location = &symbol["location"];
+                format!(
+                    "{}{} ({}:{})",
+                    symbol["containerName"]
+                        .as_str()
+                        .map(|c| format!("{}::", c))
+                        .unwrap_or_default(),
+                    name,
+                    location["uri"].as_str().unwrap_or_default(),
+                    location["range"]["start"]["line"]
+                )
+            })
+            .collect();
+        return Err(anyhow!(
+            "Symbol name `{}` is ambiguous, candidates:\n{}",
+            name,
+            candidates.join("\n")
+        ));
+    }
+
+    let symbol = best[0].clone();
+    let location = &symbol["location"];
+    let (Some(uri), Some(line), Some(character)) = (
+        location["uri"].as_str(),
+        location["range"]["start"]["line"].as_u64(),
+        location["range"]["start"]["character"].as_u64(),
+    ) else {
+        return Err(anyhow!("Symbol `{}` has no usable location", name));
+    };
+    let Some(path) = uri_to_path(uri) else {
+        return Err(anyhow!("Unsupported symbol URI: {}", uri));
+    };
+
+    let uri = server
+        .open_document_if_needed(&path.to_string_lossy())
+        .await?;
+    let Some(client) = &mut server.client else {
+        return Err(anyhow!("Client not initialized"));
+    };
+    let hover = client
+        .hover(&uri, line as u32, character as u32, None)
+        .await?;
+
+    let result = json!({
+        "name": name,
+        "kind": symbol["kind"].as_u64().and_then(symbol_kind_name),
+        "container_name": symbol["containerName"],
+        "location": location,
+        "hover": hover
+    });
+
+    Ok(ToolResult {
+        content: vec![ContentItem {
+            content_type: "text".to_string(),
+            text: serde_json::to_string_pretty(&result)?,
+        }],
+    })
+}
+
+async fn handle_workspace_symbols(
+    server: &mut RustAnalyzerMCPServer,
+    args: Value,
+) -> Result<ToolResult> {
+    let Some(query) = args["query"].as_str() else {
+        return Err(invalid_arguments("Missing query"));
+    };
+    let query = SymbolQuery::parse(query)
+        .map_err(|e| invalid_arguments(format!("Invalid regex {}: {}", query, e)))?;
+    let (offset, limit) = ToolParams::extract_page(&args)?;
+
+    let Some(client) = &mut server.client else {
+        return Err(anyhow!("Client not initialized"));
+    };
+
+    let symbols = client.workspace_symbols(query.lsp_query()).await?;
+    let mut matches: Vec<(MatchQuality, &Value)> = symbols
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|symbol| {
+            let quality = query.match_quality(symbol["name"].as_str()?)?;
+            Some((quality, symbol))
+        })
+        .collect();
+    // Best matches first, keeping rust-analyzer's order otherwise.
+    matches.sort_by_key(|(quality, _)| *quality);
+    let total = matches.len();
+    let (matches, has_more) = paginate(matches, offset, limit);
+
+    let symbols: Vec<Value> = matches
+        .into_iter()
+        .map(|(quality, symbol)| {
+            json!({
+                "name": symbol["name"],
+                "kind": symbol["kind"].as_u64().and_then(symbol_kind_name),
+                "container_name": symbol["containerName"],
+                "location": symbol["location"],
+                "match": quality.name()
+            })
+        })
+        .collect();
+    let result = json!({
+        "symbols": symbols,
+        "total": total,
+        "has_more": has_more
+    });
+
+    Ok(ToolResult {
+        content: vec![ContentItem {
+            content_type: "text".to_string(),
+            text: serde_json::to_string_pretty(&result)?,
+        }],
+    })
+}
+
+/// Rank a symbol for `rust_analyzer_goto_symbol`, preferring types over functions over the rest.
+fn symbol_rank(symbol: &Value) -> u8 {
+    // Kinds from the LSP `SymbolKind` enumeration.
+    match symbol["kind"].as_u64() {
+        // Class, Enum, Interface, Struct, TypeParameter
+        Some(5 | 10 | 11 | 23 | 26) => 0,
+        // Method, Function
+        Some(6 | 12) => 1,
+        _ => 2,
+    }
+}
+
+async fn handle_format(server: &mut RustAnalyzerMCPServer, args: Value) -> Result<ToolResult> {
+    let file_path = ToolParams::extract_file_path(&args)?;
+    let range = ToolParams::extract_optional_range(&args)?;
+    let apply = args["apply"].as_bool().unwrap_or(false);
+
+    let uri = server.open_document_if_needed(&file_path).await?;
+
+    let Some(client) = &mut server.client else {
+        return Err(anyhow!("Client not initialized"));
+    };
+
+    let result = match range {
+        Some((start_line, start_character, end_line, end_character)) => {
+            client
+                .range_formatting(&uri, start_line, start_character, end_line, end_character)
+                .await?
+        }
+        None => client.formatting(&uri).await?,
+    };
+
+    let result = if apply {
+        let edits = result.as_array().cloned().unwrap_or_default();
+        if !edits.is_empty() {
+            let Some(path) = uri_to_path(&uri) else {
+                return Err(anyhow!("Unsupported file URI: {}", uri));
+            };
+            let content = tokio::fs::read_to_string(&path)
+                .await
+                .map_err(|e| ToolError::file_read(&file_path, e))?;
+            let Some(formatted) = apply_text_edits(&content, &edits) else {
+                return Err(anyhow!("Failed to apply formatting edits to {}", file_path));
+            };
+            server.start_writing();
+            tokio::fs::write(&path, &formatted)
+                .await
+                .map_err(|e| anyhow!("Failed to write file {}: {}", file_path, e))?;
+            let Some(client) = &mut server.client else {
+                return Err(anyhow!("Client not initialized"));
+            };
+            client.update_document(&uri, &formatted).await?;
+        }
+
+        json!({
+            "applied": !edits.is_empty(),
+            "edits_count": edits.len()
+        })
+    } else {
+        result
+    };
+
+    Ok(ToolResult {
+        content: vec![ContentItem {
+            content_type: "text".to_string(),
+            text: serde_json::to_string_pretty(&result)?,
+        }],
+    })
+}
+
+async fn handle_format_range(
+    server: &mut RustAnalyzerMCPServer,
+    args: Value,
+) -> Result<ToolResult> {
+    let file_path = ToolParams::extract_file_path(&args)?;
+    let Some((start_line, start_character, end_line, end_character)) =
+        ToolParams::extract_optional_range(&args)?
+    else {
+        return Err(invalid_arguments(
+            "Missing range: start_line, start_character, end_line and end_character are required",
+        ));
+    };
+
+    let uri = server.open_document_if_needed(&file_path).await?;
+
+    let Some(client) = &mut server.client else {
+        return Err(anyhow!("Client not initialized"));
+    };
+
+    let result = client
+        .range_formatting(&uri, start_line, start_character, end_line, end_character)
+        .await?;
+
+    Ok(ToolResult {
+        content: vec![ContentItem {
+            content_type: "text".to_string(),
+            text: serde_json::to_string_pretty(&result)?,
+        }],
+    })
+}
+
+async fn handle_code_actions(
+    server: &mut RustAnalyzerMCPServer,
+    args: Value,
+) -> Result<ToolResult> {
+    let file_path = ToolParams::extract_file_path(&args)?;
+    let (line, character, end_line, end_character) = ToolParams::extract_range(&args)?;
+
+    let uri = server.open_document_if_needed(&file_path).await?;
+
+    let Some(client) = &mut server.client else {
+        return Err(anyhow!("Client not initialized"));
+    };
+
+    let result = client
+        .code_actions(&uri, line, character, end_line, end_character)
+        .await?;
+
+    Ok(ToolResult {
+        content: vec![ContentItem {
+            content_type: "text".to_string(),
+            text: serde_json::to_string_pretty(&result)?,
+        }],
+    })
+}
+
+async fn handle_move_item(server: &mut RustAnalyzerMCPServer, args: Value) -> Result<ToolResult> {
+    let file_path = ToolParams::extract_file_path(&args)?;
+    let (line, character, end_line, end_character) = ToolParams::extract_range(&args)?;
+    let direction = match args["direction"].as_str() {
+        Some("up") => "Up",
+        Some("down") => "Down",
+        Some(direction) => {
+            return Err(invalid_arguments(format!(
+                "Invalid direction `{}`, expected `up` or `down`",
+                direction
+            )))
+        }
+        None => return Err(invalid_arguments("Missing direction")),
+    };
+
+    let uri = server.open_document_if_needed(&file_path).await?;
+
+    let Some(client) = &mut server.client else {
+        return Err(anyhow!("Client not initialized"));
+    };
+
+    let result = client
+        .move_item(&uri, line, character, end_line, end_character, direction)
+        .await?;
+
+    Ok(ToolResult {
+        content: vec![ContentItem {
+            content_type: "text".to_string(),
+            text: serde_json::to_string_pretty(&result)?,
+        }],
+    })
+}
+
+async fn handle_ssr(server: &mut RustAnalyzerMCPServer, args: Value) -> Result<ToolResult> {
+    let file_path = ToolParams::extract_file_path(&args)?;
+    let (line, character) = ToolParams::extract_position(&args)?;
+    let Some(query) = args["query"].as_str() else {
+        return Err(invalid_arguments("Missing query"));
+    };
+    let parse_only = args["parse_only"].as_bool().unwrap_or(false);
+
+    let mut selections = Vec::new();
+    if let Some(ranges) = args["selections"].as_array() {
+        for range in ranges {
+            let (start_line, start_char, end_line, end_char) = ToolParams::extract_range(range)?;
+            selections.push(json!({
+                "start": { "line": start_line, "character": start_char },
+                "end": { "line": end_line, "character": end_char }
+            }));
+        }
+    }
+
+    let uri = server.open_document_if_needed(&file_path).await?;
+
+    let Some(client) = &mut server.client else {
+        return Err(anyhow!("Client not initialized"));
+    };
+
+    let mut output = json!({
+        "query": query,
+        "parse_only": parse_only,
+        "valid": true,
+        "files": [],
+        "total_edits": 0
+    });
+
+    // rust-analyzer replies with an error for malformed rules, which is the answer to give rather
+    // than a failure of the tool.
+    let result = match client
+        .ssr(&uri, query, parse_only, line, character, selections)
+        .await
+    {
+        Ok(result) => result,
+        Err(e) => match lsp_error_message(&e) {
+            Some(message) => {
+                output["valid"] = json!(false);
+                output["error"] = json!(message);
+                return Ok(ToolResult {
+                    content: vec![ContentItem {
+                        content_type: "text".to_string(),
+                        text: serde_json::to_string_pretty(&output)?,
+                    }],
+                });
+            }
+            None => return Err(e),
+        },
+    };
+
+    let mut files = Vec::new();
+    let mut total_edits = 0;
+    for (uri, edits) in workspace_edit_changes(&result) {
+        let path = uri_to_path(&uri);
+        let content = match &path {
+            Some(path) => tokio::fs::read_to_string(path).await.ok(),
+            None => None,
+        };
+        let file = path
+            .as_ref()
+            .map(|p| {
+                p.strip_prefix(&server.workspace_root)
+                    .unwrap_or(p)
+                    .display()
+                    .to_string()
+            })
+            .unwrap_or_else(|| uri.clone());
+
+        let edits: Vec<Value> = edits
+            .iter()
+            .map(|edit| {
+                let before = content
+                    .as_deref()
+                    .and_then(|c| range_text(c, &edit["range"]));
+                json!({
+                    "range": edit["range"],
+                    "before": before,
+                    "after": edit["newText"]
+                })
+            })
+            .collect();
+
+        total_edits += edits.len();
+        files.push(json!({
+            "file": file,
+            "uri": uri,
+            "edits": edits
+        }));
+    }
+    output["files"] = json!(files);
+    output["total_edits"] = json!(total_edits);
+
+    Ok(ToolResult {
+        content: vec![ContentItem {
+            content_type: "text".to_string(),
+            text: serde_json::to_string_pretty(&output)?,
+        }],
+    })
+}
+
+async fn handle_rename(server: &mut RustAnalyzerMCPServer, args: Value) -> Result<ToolResult> {
+    let file_path = ToolParams::extract_file_path(&args)?;
+    let (line, character) = ToolParams::extract_position(&args)?;
+    let Some(new_name) = args["new_name"].as_str() else {
+        return Err(invalid_arguments("Missing new_name"));
+    };
+    let apply = args["apply"].as_bool().unwrap_or(false);
+
+    let uri = server.open_document_if_needed(&file_path).await?;
+
+    let Some(client) = &mut server.client else {
+        return Err(anyhow!("Client not initialized"));
+    };
+
+    // rust-analyzer replies with an error, telling why, if there's nothing to rename at the
+    // position or the new name isn't a valid identifier.
+    let edit = client.rename(&uri, line, character, new_name).await?;
+    if edit.is_null() {
+        return Err(anyhow!(
+            "Nothing to rename at {}:{}:{}",
+            file_path,
+            line,
+            character
+        ));
+    }
+
+    let result = if apply {
+        let files = apply_workspace_edit(server, &edit).await?;
+        let total_edits: u64 = files.iter().filter_map(|f| f["edits"].as_u64()).sum();
+        // Results about any of the changed files are now stale.
+        server.cache.clear();
+
+        json!({
+            "new_name": new_name,
+            "applied": true,
+            "files": files,
+            "total_edits": total_edits
+        })
+    } else {
+        json!({
+            "new_name": new_name,
+            "applied": false,
+            "edit": edit
+        })
+    };
+
+    Ok(ToolResult {
+        content: vec![ContentItem {
+            content_type: "text".to_string(),
+            text: serde_json::to_string_pretty(&result)?,
+        }],
+    })
+}
+
+async fn handle_apply_edits(server: &mut RustAnalyzerMCPServer, args: Value) -> Result<ToolResult> {
+    if !args["changes"].is_object() && !args["documentChanges"].is_array() {
+        return Err(invalid_arguments("Missing changes or documentChanges"));
+    }
+
+    let files = apply_workspace_edit(server, &args).await?;
+    let total_edits: u64 = files.iter().filter_map(|f| f["edits"].as_u64()).sum();
+    server.cache.clear();
+
+    let result = json!({
+        "applied": true,
+        "files": files,
+        "total_edits": total_edits
+    });
+
+    Ok(ToolResult {
+        content: vec![ContentItem {
+            content_type: "text".to_string(),
+            text: serde_json::to_string_pretty(&result)?,
+        }],
+    })
+}
+
+/// Write the changes of a `WorkspaceEdit` to disk and notify rust-analyzer about them.
+///
+/// Files are given by `file://` URI or by path relative to the workspace root. All the files are
+/// checked and edited in memory before anything is written, so an edit to a file outside the
+/// workspace or under `target/`, or one that doesn't apply, leaves every file untouched. Returns
+/// the number of edits and changed lines per file.
+async fn apply_workspace_edit(
+    server: &mut RustAnalyzerMCPServer,
+    edit: &Value,
+) -> Result<Vec<Value>> {
+    let mut updates = Vec::new();
+    for (uri, edits) in workspace_edit_changes(edit) {
+        let file_path = match uri_to_path(&uri) {
+            Some(path) => path.to_string_lossy().into_owned(),
+            None if !uri.contains("://") => uri.clone(),
+            None => return Err(anyhow!("Unsupported file URI: {}", uri)),
         };
+        let path = validate_edit_path(&server.workspace_root, &file_path)?;
+        let content = tokio::fs::read_to_string(&path)
+            .await
+            .map_err(|e| ToolError::file_read(&path.display().to_string(), e))?;
+        validate_text_edits(&content, &edits)
+            .map_err(|e| invalid_arguments(format!("Invalid edits for {}: {}", file_path, e)))?;
+        let Some(new_content) = apply_text_edits(&content, &edits) else {
+            return Err(anyhow!("Failed to apply edits to {}", path.display()));
+        };
+        let changed_lines = TextDiff::from_lines(content.as_str(), new_content.as_str())
+            .iter_all_changes()
+            .filter(|change| change.tag() == ChangeTag::Insert)
+            .count();
+
+        updates.push((path, edits.len(), changed_lines, new_content));
+    }
+
+    server.start_writing();
+    let Some(client) = &mut server.client else {
+        return Err(anyhow!("Client not initialized"));
+    };
+
+    let mut files = Vec::new();
+    for (path, edits, changed_lines, new_content) in updates {
+        tokio::fs::write(&path, &new_content)
+            .await
+            .map_err(|e| anyhow!("Failed to write file {}: {}", path.display(), e))?;
+        // Use the same URI form as when opening documents so open ones get the new content.
+        let uri = format!("file://{}", path.display());
+        client.update_document(&uri, &new_content).await?;
+
+        let file = path
+            .strip_prefix(&server.workspace_root)
+            .unwrap_or(&path)
+            .display()
+            .to_string();
+        files.push(json!({
+            "file": file,
+            "edits": edits,
+            "changed_lines": changed_lines
+        }));
+    }
+
+    Ok(files)
+}
+
+async fn handle_create_file(server: &mut RustAnalyzerMCPServer, args: Value) -> Result<ToolResult> {
+    let file_path = ToolParams::extract_file_path(&args)?;
+    let open_document = args["open_document"].as_bool().unwrap_or(false);
+
+    let absolute_path = server.resolve_workspace_path(&file_path)?;
+
+    let content = match args["content"].as_str() {
+        Some(content) => content.to_string(),
+        None => {
+            let module = absolute_path
+                .file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            format!("//! The `{}` module.\n", module)
+        }
+    };
+
+    server.start_writing();
+    if let Some(parent) = absolute_path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| anyhow!("Failed to create directory {}: {}", parent.display(), e))?;
+    }
+    // Checking for an existing file and creating it in one go, so that a file created meanwhile
+    // isn't overwritten.
+    let mut file = match OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&absolute_path)
+        .await
+    {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+            return Err(anyhow!("File already exists: {}", file_path));
+        }
+        Err(e) => return Err(anyhow!("Failed to create file {}: {}", file_path, e)),
+    };
+    file.write_all(content.as_bytes())
+        .await
+        .map_err(|e| anyhow!("Failed to write file {}: {}", file_path, e))?;
+
+    let absolute_path = absolute_path.canonicalize().unwrap_or(absolute_path);
+    let uri = format!("file://{}", absolute_path.display());
+
+    let Some(client) = &mut server.client else {
+        return Err(anyhow!("Client not initialized"));
+    };
+    client.did_create_files(&[uri]).await?;
+
+    if open_document {
+        server.open_document_if_needed(&file_path).await?;
+    }
+
+    let result = json!({
+        "created_path": file_path,
+        "absolute_path": absolute_path.display().to_string()
+    });
+
+    Ok(ToolResult {
+        content: vec![ContentItem {
+            content_type: "text".to_string(),
+            text: serde_json::to_string_pretty(&result)?,
+        }],
+    })
+}
+
+async fn handle_rename_file(server: &mut RustAnalyzerMCPServer, args: Value) -> Result<ToolResult> {
+    let Some(old_path) = args["old_path"].as_str() else {
+        return Err(invalid_arguments("Missing old_path"));
+    };
+    let Some(new_path) = args["new_path"].as_str() else {
+        return Err(invalid_arguments("Missing new_path"));
+    };
+    let apply = args["apply"].as_bool().unwrap_or(false);
+
+    let old_absolute = server.resolve_workspace_path(old_path)?;
+    let new_absolute = server.resolve_workspace_path(new_path)?;
+    if !old_absolute.is_file() {
+        return Err(ToolError::new(
+            ErrorKind::FileNotFound,
+            format!("File not found: {}", old_path),
+        )
+        .with_path(old_path)
+        .into());
+    }
+    if new_absolute.exists() {
+        return Err(anyhow!("File already exists: {}", new_path));
+    }
+    let old_uri = format!("file://{}", old_absolute.display());
+    let new_uri = format!("file://{}", new_absolute.display());
+
+    let Some(client) = &mut server.client else {
+        return Err(anyhow!("Client not initialized"));
+    };
+
+    let edit = client.will_rename_files(&old_uri, &new_uri).await?;
+    if !apply {
+        let result = json!({
+            "old_path": old_path,
+            "new_path": new_path,
+            "edit": edit
+        });
+
+        return Ok(ToolResult {
+            content: vec![ContentItem {
+                content_type: "text".to_string(),
+                text: serde_json::to_string_pretty(&result)?,
+            }],
+        });
+    }
+
+    // The edits refer to the files as they are before the rename, so apply them first.
+    let files = apply_workspace_edit(server, &edit).await?;
+    let edits_count: u64 = files.iter().filter_map(|f| f["edits"].as_u64()).sum();
+
+    let Some(client) = &mut server.client else {
+        return Err(anyhow!("Client not initialized"));
+    };
+    client.close_document(&old_uri).await?;
+    if let Some(parent) = new_absolute.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| anyhow!("Failed to create directory {}: {}", parent.display(), e))?;
+    }
+    tokio::fs::rename(&old_absolute, &new_absolute)
+        .await
+        .map_err(|e| anyhow!("Failed to rename {} to {}: {}", old_path, new_path, e))?;
+    client.did_rename_files(&old_uri, &new_uri).await?;
+    // Results about the old path are now stale even if no other file changed.
+    server.cache.clear();
+
+    let result = json!({
+        "old_path": old_path,
+        "new_path": new_path,
+        "applied": true,
+        "files_changed": files.len(),
+        "edits_count": edits_count
+    });
+
+    Ok(ToolResult {
+        content: vec![ContentItem {
+            content_type: "text".to_string(),
+            text: serde_json::to_string_pretty(&result)?,
+        }],
+    })
+}
+
+async fn handle_read_file(server: &mut RustAnalyzerMCPServer, args: Value) -> Result<ToolResult> {
+    let file_path = ToolParams::extract_file_path(&args)?;
+
+    let absolute_path = server.resolve_workspace_path(&file_path)?;
+    let content = tokio::fs::read_to_string(&absolute_path)
+        .await
+        .map_err(|e| ToolError::file_read(&file_path, e))?;
+
+    let result = json!({
+        "content": content,
+        "lines": content.lines().count(),
+        "bytes": content.len(),
+        "encoding": "utf-8"
+    });
+
+    Ok(ToolResult {
+        content: vec![ContentItem {
+            content_type: "text".to_string(),
+            text: serde_json::to_string_pretty(&result)?,
+        }],
+    })
+}
+
+async fn handle_write_file(server: &mut RustAnalyzerMCPServer, args: Value) -> Result<ToolResult> {
+    let file_path = ToolParams::extract_file_path(&args)?;
+    let Some(content) = args["content"].as_str() else {
+        return Err(invalid_arguments("Missing content"));
+    };
+
+    let absolute_path = server.resolve_workspace_path(&file_path)?;
+    let existed = absolute_path.exists();
+
+    server.start_writing();
+    tokio::fs::write(&absolute_path, content)
+        .await
+        .map_err(|e| anyhow!("Failed to write file {}: {}", file_path, e))?;
+
+    let uri = format!("file://{}", absolute_path.display());
+
+    let Some(client) = &mut server.client else {
+        return Err(anyhow!("Client not initialized"));
+    };
+
+    if !existed {
+        client.did_create_files(std::slice::from_ref(&uri)).await?;
+    }
+    client.update_document(&uri, content).await?;
+
+    let result = json!({
+        "written": true,
+        "created": !existed,
+        "bytes": content.len()
+    });
+
+    Ok(ToolResult {
+        content: vec![ContentItem {
+            content_type: "text".to_string(),
+            text: serde_json::to_string_pretty(&result)?,
+        }],
+    })
+}
+
+async fn handle_diff_file(server: &mut RustAnalyzerMCPServer, args: Value) -> Result<ToolResult> {
+    let file_path = ToolParams::extract_file_path(&args)?;
+    let Some(new_content) = args["new_content"].as_str() else {
+        return Err(invalid_arguments("Missing new_content"));
+    };
+
+    let absolute_path = server.resolve_workspace_path(&file_path)?;
+    let current_content = tokio::fs::read_to_string(&absolute_path)
+        .await
+        .map_err(|e| ToolError::file_read(&file_path, e))?;
+
+    let diff = TextDiff::from_lines(current_content.as_str(), new_content);
+
+    let mut insertions = 0;
+    let mut deletions = 0;
+    let mut changed_lines = Vec::new();
+    for change in diff.iter_all_changes() {
+        match change.tag() {
+            ChangeTag::Insert => {
+                insertions += 1;
+                changed_lines.extend(change.new_index());
+            }
+            ChangeTag::Delete => deletions += 1,
+            ChangeTag::Equal => {}
+        }
+    }
+
+    let unified = diff
+        .unified_diff()
+        .header(&format!("a/{}", file_path), &format!("b/{}", file_path))
+        .to_string();
+
+    let result = json!({
+        "diff": unified,
+        "insertions": insertions,
+        "deletions": deletions,
+        "changed_lines": changed_lines
+    });
+
+    Ok(ToolResult {
+        content: vec![ContentItem {
+            content_type: "text".to_string(),
+            text: serde_json::to_string_pretty(&result)?,
+        }],
+    })
+}
+
+async fn handle_list_files(server: &mut RustAnalyzerMCPServer, args: Value) -> Result<ToolResult> {
+    let pattern = args["pattern"]
+        .as_str()
+        .map(Pattern::new)
+        .transpose()
+        .map_err(|e| invalid_arguments(format!("Invalid pattern: {}", e)))?;
+    let include_test_files = args["include_test_files"].as_bool().unwrap_or(true);
+
+    let root = server.workspace_root.clone();
+    let files = tokio::task::spawn_blocking(move || {
+        list_rust_files(&root, pattern.as_ref(), include_test_files)
+    })
+    .await?;
+
+    let result = json!({
+        "count": files.len(),
+        "files": files
+    });
+
+    Ok(ToolResult {
+        content: vec![ContentItem {
+            content_type: "text".to_string(),
+            text: serde_json::to_string_pretty(&result)?,
+        }],
+    })
+}
+
+/// Recursively list the `.rs` files under `root`, relative to it, skipping `target` and hidden
+/// directories.
+fn list_rust_files(
+    root: &Path,
+    pattern: Option<&Pattern>,
+    include_test_files: bool,
+) -> Vec<String> {
+    let is_skipped_dir = |entry: &DirEntry| {
+        let name = entry.file_name().to_string_lossy();
+        entry.depth() > 0
+            && entry.file_type().is_dir()
+            && (name == "target" || name.starts_with('.'))
+    };
+
+    let mut files: Vec<String> = WalkDir::new(root)
+        .into_iter()
+        .filter_entry(|entry| !is_skipped_dir(entry))
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            entry.file_type().is_file() && entry.path().extension().is_some_and(|ext| ext == "rs")
+        })
+        .filter_map(|entry| {
+            let relative = entry.path().strip_prefix(root).ok()?;
+            if !include_test_files && is_test_file(relative) {
+                return None;
+            }
+            if pattern.is_some_and(|pattern| !pattern.matches_path(relative)) {
+                return None;
+            }
+            Some(relative.to_string_lossy().into_owned())
+        })
+        .collect();
+    files.sort();
+
+    files
+}
+
+/// Whether a workspace-relative path looks like a test file.
+fn is_test_file(path: &Path) -> bool {
+    let in_tests_dir = path.components().any(|c| c.as_os_str() == "tests");
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy())
+        .unwrap_or_default();
+
+    in_tests_dir || name.ends_with("_test.rs") || name.starts_with("test_")
+}
+
+async fn handle_set_workspace(
+    server: &mut RustAnalyzerMCPServer,
+    args: Value,
+) -> Result<ToolResult> {
+    let Some(workspace_path) = args["workspace_path"].as_str() else {
+        return Err(invalid_arguments("Missing workspace_path"));
+    };
+
+    // Shutdown existing client.
+    if let Some(client) = &mut server.client {
+        client.shutdown().await?;
+    }
+    server.client = None;
+    server.cache.clear();
+
+    // Set new workspace with proper absolute path handling.
+    let workspace_root = PathBuf::from(workspace_path);
+    server.workspace_root = workspace_root.canonicalize().unwrap_or_else(|_| {
+        if workspace_root.is_absolute() {
+            workspace_root.clone()
+        } else {
+            std::env::current_dir()
+                .unwrap_or_else(|_| PathBuf::from("."))
+                .join(&workspace_root)
+        }
+    });
+
+    // Start the new client automatically.
+    server.ensure_client_started().await?;
+    server.notify_lists_changed().await;
+
+    Ok(ToolResult {
+        content: vec![ContentItem {
+            content_type: "text".to_string(),
+            text: format!("Workspace set to: {}", server.workspace_root.display()),
+        }],
+    })
+}
+
+async fn handle_diagnostics(server: &mut RustAnalyzerMCPServer, args: Value) -> Result<ToolResult> {
+    let file_path = ToolParams::extract_file_path(&args)?;
+
+    let uri = server.open_document_if_needed(&file_path).await?;
+
+    // Wait for rust-analyzer to publish the diagnostics of the file. If the file changed on disk,
+    // its old diagnostics were dropped when syncing it, so this waits for the new ones.
+    let wait = args["wait_ms"]
+        .as_u64()
+        .map(Duration::from_millis)
+        .unwrap_or(server.options.config.diagnostics_poll_timeout);
+    let poll_interval = server.options.config.diagnostics_poll_interval;
+    let with_snippets = args["with_snippets"].as_bool().unwrap_or(false);
+    let include_rendered = args["include_rendered"].as_bool();
+    let min_severity = ToolParams::extract_min_severity(&args)?;
+    let rustc_format = match args["format"].as_str() {
+        None | Some("compact") => false,
+        Some("rustc") => true,
+        Some(format) => {
+            return Err(invalid_arguments(format!(
+                "Invalid format {}: expected compact or rustc",
+                format
+            )))
+        }
+    };
+
+    let Some(client) = &mut server.client else {
+        return Err(anyhow!("Client not initialized"));
+    };
+
+    let start = Instant::now();
+    let timed_out = loop {
+        if client.has_published_diagnostics(&uri).await {
+            break false;
+        }
+        let elapsed = start.elapsed();
+        if elapsed >= wait {
+            break true;
+        }
+
+        tokio::time::sleep(poll_interval.min(wait - elapsed)).await;
+    };
+    // Without published diagnostics, they're pulled instead.
+    let result = client.diagnostics(&uri).await?;
+    let version = client.diagnostics_version(&uri).await;
+
+    if rustc_format {
+        let path = server.resolve_workspace_path(&file_path)?;
+        let content = tokio::fs::read_to_string(&path)
+            .await
+            .map_err(|e| ToolError::file_read(&file_path, e))?;
+        let file_name = path
+            .strip_prefix(&server.workspace_root)
+            .unwrap_or(&path)
+            .display()
+            .to_string();
+        let messages = to_rustc_diagnostics(
+            &file_name,
+            &content,
+            result.as_array().map(Vec::as_slice).unwrap_or_default(),
+            min_severity,
+        );
+
+        // One message per line, like `--message-format=json`.
+        let lines = messages
+            .iter()
+            .map(serde_json::to_string)
+            .collect::<Result<Vec<String>, _>>()?;
+        return Ok(ToolResult {
+            content: vec![ContentItem {
+                content_type: "text".to_string(),
+                text: lines.join("\n"),
+            }],
+        });
+    }
+
+    let mut diagnostics = format_diagnostics(&file_path, &result, min_severity, include_rendered);
+    // The version of the document the diagnostics are about, to match them with edits.
+    diagnostics["version"] = json!(version);
+    // Nothing was published in time, so the diagnostics may be missing or incomplete.
+    diagnostics["timed_out"] = json!(timed_out);
+    if with_snippets {
+        let path = server.resolve_workspace_path(&file_path)?;
+        let content = tokio::fs::read_to_string(&path)
+            .await
+            .map_err(|e| ToolError::file_read(&file_path, e))?;
+        add_snippets(&mut diagnostics, &content);
+    }
+
+    Ok(ToolResult {
+        content: vec![ContentItem {
+            content_type: "text".to_string(),
+            text: serde_json::to_string_pretty(&diagnostics)?,
+        }],
+    })
+}
+
+async fn handle_workspace_diagnostics(
+    server: &mut RustAnalyzerMCPServer,
+    args: Value,
+) -> Result<ToolResult> {
+    let min_severity = ToolParams::extract_min_severity(&args)?;
+    let sarif = match args["format"].as_str() {
+        None | Some("compact") => false,
+        Some("sarif") => true,
+        Some(format) => {
+            return Err(invalid_arguments(format!(
+                "Invalid format {}: expected compact or sarif",
+                format
+            )))
+        }
+    };
+    let group_by = args["group_by"]
+        .as_str()
+        .map(str::parse::<DiagnosticGrouping>)
+        .transpose()
+        .map_err(|e| invalid_arguments(e.to_string()))?;
+
+    let (result, partial) = collect_workspace_diagnostics(server).await?;
+
+    if sarif {
+        let log = to_sarif(&server.workspace_root, &result, min_severity);
+        return Ok(ToolResult {
+            content: vec![ContentItem {
+                content_type: "text".to_string(),
+                text: serde_json::to_string_pretty(&log)?,
+            }],
+        });
+    }
+
+    // Format workspace diagnostics.
+    let mut formatted =
+        format_workspace_diagnostics(&server.workspace_root, &result, min_severity, group_by);
+    if partial {
+        formatted["partial"] = json!(true);
+    }
+
+    Ok(ToolResult {
+        content: vec![ContentItem {
+            content_type: "text".to_string(),
+            text: serde_json::to_string_pretty(&formatted)?,
+        }],
+    })
+}
 
-        if diag_array.is_empty() {
-            continue;
+async fn handle_workspace_errors(
+    server: &mut RustAnalyzerMCPServer,
+    args: Value,
+) -> Result<ToolResult> {
+    let limit = args["limit"]
+        .as_u64()
+        .map(|limit| limit as usize)
+        .unwrap_or(WORKSPACE_ERRORS_LIMIT);
+
+    let (result, partial) = collect_workspace_diagnostics(server).await?;
+    let mut errors = first_errors(&server.workspace_root, &result, limit);
+    if partial {
+        errors["partial"] = json!(true);
+    }
+
+    Ok(ToolResult {
+        content: vec![ContentItem {
+            content_type: "text".to_string(),
+            text: serde_json::to_string_pretty(&errors)?,
+        }],
+    })
+}
+
+async fn handle_explain_diagnostic(
+    server: &mut RustAnalyzerMCPServer,
+    args: Value,
+) -> Result<ToolResult> {
+    let Some(code) = args["code"].as_str() else {
+        return Err(invalid_arguments("Missing code"));
+    };
+    if code_url(&json!(code)).is_none() {
+        return Err(invalid_arguments(format!(
+            "Unknown diagnostic code `{}`: expected a rustc error code like `E0308` or a clippy \
+             lint like `clippy::needless_return`",
+            code
+        )));
+    }
+
+    let explanation = match server.explanations.get(code) {
+        Some(explanation) => explanation.clone(),
+        None => {
+            let explanation = explain_code(&server.workspace_root, code).await?;
+            server
+                .explanations
+                .insert(code.to_string(), explanation.clone());
+            explanation
         }
+    };
+
+    Ok(ToolResult {
+        content: vec![ContentItem {
+            content_type: "text".to_string(),
+            text: serde_json::to_string_pretty(&explanation)?,
+        }],
+    })
+}
 
-        file_count += 1;
-        let mut file_errors = 0;
-        let mut file_warnings = 0;
-        let mut file_information = 0;
-        let mut file_hints = 0;
+async fn handle_index_workspace(
+    server: &mut RustAnalyzerMCPServer,
+    args: Value,
+) -> Result<ToolResult> {
+    let min_severity = ToolParams::extract_min_severity(&args)?;
+    let globs = match args.get("globs") {
+        None => INDEX_WORKSPACE_GLOBS
+            .iter()
+            .map(|glob| glob.to_string())
+            .collect(),
+        Some(Value::Array(globs)) => globs
+            .iter()
+            .map(|glob| {
+                glob.as_str()
+                    .map(String::from)
+                    .ok_or_else(|| invalid_arguments("globs must be an array of strings"))
+            })
+            .collect::<Result<Vec<String>>>()?,
+        Some(_) => return Err(invalid_arguments("globs must be an array of strings")),
+    };
+    let patterns = globs
+        .iter()
+        .map(|glob| Pattern::new(glob))
+        .collect::<Result<Vec<Pattern>, _>>()
+        .map_err(|e| invalid_arguments(format!("Invalid glob: {}", e)))?;
+
+    let root = server.workspace_root.clone();
+    let files = tokio::task::spawn_blocking(move || find_source_files(&root, &patterns)).await?;
+
+    let mut documents = Vec::with_capacity(files.len());
+    for file in &files {
+        let path = server.workspace_root.join(file);
+        let content = tokio::fs::read_to_string(&path)
+            .await
+            .map_err(|e| ToolError::file_read(file, e))?;
+        documents.push((format!("file://{}", path.display()), content));
+    }
 
-        for diag in diag_array {
-            let Some(severity) = diag.get("severity").and_then(|s| s.as_u64()) else {
-                continue;
-            };
+    let Some(client) = &mut server.client else {
+        return Err(anyhow!("Client not initialized"));
+    };
+    let collected = client.collect_diagnostics(&documents).await?;
 
-            match severity {
-                1 => {
-                    file_errors += 1;
-                    total_errors += 1;
-                }
-                2 => {
-                    file_warnings += 1;
-                    total_warnings += 1;
-                }
-                3 => {
-                    file_information += 1;
-                    total_information += 1;
-                }
-                4 => {
-                    file_hints += 1;
-                    total_hints += 1;
-                }
-                _ => {}
-            }
+    // Include what was published for other files too, e.g. by cargo check.
+    let mut diagnostics = client.workspace_diagnostics_snapshot().await;
+    let analyzed_files = collected.len();
+    for (uri, diags) in collected {
+        diagnostics[uri] = json!(diags);
+    }
+
+    let mut result =
+        format_workspace_diagnostics(&server.workspace_root, &diagnostics, min_severity, None);
+    result["indexed_files"] = json!(files.len());
+    result["analyzed_files"] = json!(analyzed_files);
+
+    Ok(ToolResult {
+        content: vec![ContentItem {
+            content_type: "text".to_string(),
+            text: serde_json::to_string_pretty(&result)?,
+        }],
+    })
+}
+
+/// List the `.rs` files under `root` matching any of `patterns`, relative to it and sorted.
+///
+/// Files ignored by `.gitignore` and the like are skipped, and so are hidden and `target`
+/// directories.
+pub(super) fn find_source_files(root: &Path, patterns: &[Pattern]) -> Vec<String> {
+    let mut files: Vec<String> = ignore::WalkBuilder::new(root)
+        .require_git(false)
+        .filter_entry(|entry| entry.depth() == 0 || entry.file_name() != "target")
+        .build()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            entry.file_type().is_some_and(|t| t.is_file())
+                && entry.path().extension().is_some_and(|ext| ext == "rs")
+        })
+        .filter_map(|entry| {
+            let relative = entry.path().strip_prefix(root).ok()?;
+            patterns
+                .iter()
+                .any(|pattern| pattern.matches_path(relative))
+                .then(|| relative.to_string_lossy().into_owned())
+        })
+        .collect();
+    files.sort();
+
+    files
+}
+
+/// Get the diagnostics of the whole workspace per URI, and whether they're partial.
+///
+/// A live report is preferred but if there's none, e.g. when the request times out on a cold
+/// workspace, whatever has been published so far is used.
+async fn collect_workspace_diagnostics(
+    server: &mut RustAnalyzerMCPServer,
+) -> Result<(Value, bool)> {
+    let Some(client) = &mut server.client else {
+        return Err(anyhow!("Client not initialized"));
+    };
+
+    Ok(match client.workspace_diagnostics().await {
+        Ok(result) if !result.is_null() => (result, false),
+        Ok(_) => (client.workspace_diagnostics_snapshot().await, true),
+        Err(e) => {
+            debug!(
+                "workspace/diagnostic failed, using collected diagnostics: {}",
+                e
+            );
+            (client.workspace_diagnostics_snapshot().await, true)
         }
+    })
+}
 
-        output["files"][uri] = json!({
-            "diagnostics": diagnostics,
-            "summary": {
-                "errors": file_errors,
-                "warnings": file_warnings,
-                "information": file_information,
-                "hints": file_hints
-            }
+async fn handle_status(server: &mut RustAnalyzerMCPServer, args: Value) -> Result<ToolResult> {
+    // Scoping the report to a file implies wanting it.
+    let uri = match args["file_path"].as_str() {
+        Some(file_path) => Some(format!(
+            "file://{}",
+            server.resolve_workspace_path(file_path)?.display()
+        )),
+        None => None,
+    };
+    let detailed = uri.is_some() || args["detailed"].as_bool().unwrap_or(false);
+
+    let Some(client) = &server.client else {
+        return Err(anyhow!("Client not initialized"));
+    };
+
+    let mut result = client.progress_status().await;
+    result["restart_count"] = json!(client.restart_count());
+    if detailed {
+        result["analyzer_status"] = client.analyzer_status(uri.as_deref()).await?;
+    }
+
+    Ok(ToolResult {
+        content: vec![ContentItem {
+            content_type: "text".to_string(),
+            text: serde_json::to_string_pretty(&result)?,
+        }],
+    })
+}
+
+async fn handle_server_status(server: &mut RustAnalyzerMCPServer) -> Result<ToolResult> {
+    let Some(client) = &server.client else {
+        return Err(anyhow!("Client not initialized"));
+    };
+
+    let status = client.server_status().await;
+
+    Ok(ToolResult {
+        content: vec![ContentItem {
+            content_type: "text".to_string(),
+            text: serde_json::to_string_pretty(&status.to_json())?,
+        }],
+    })
+}
+
+/// Wait until rust-analyzer has loaded the workspace and is done indexing it and running cargo
+/// check, as told by its `$/progress` and `experimental/serverStatus` notifications.
+async fn handle_wait_for_analysis(
+    server: &mut RustAnalyzerMCPServer,
+    args: Value,
+) -> Result<ToolResult> {
+    let timeout =
+        Duration::from_millis(args["timeout_ms"].as_u64().unwrap_or(ANALYSIS_WAIT_MILLIS));
+
+    let Some(client) = &server.client else {
+        return Err(anyhow!("Client not initialized"));
+    };
+
+    let poll_interval = Duration::from_millis(PROGRESS_POLL_INTERVAL_MILLIS);
+    let start = Instant::now();
+    let ready = loop {
+        let done = match client.server_status().await {
+            // Waiting won't fix a broken workspace.
+            ServerStatus::Invalid(_) => break false,
+            ServerStatus::Loading => false,
+            ServerStatus::Ready => !client.is_indexing().await && !client.is_checking().await,
+        };
+        if done {
+            break true;
+        }
+        if start.elapsed() >= timeout {
+            break false;
+        }
+        tokio::time::sleep(poll_interval).await;
+    };
+
+    let result = json!({
+        "ready": ready,
+        "waited_ms": start.elapsed().as_millis() as u64,
+        "server_status": client.server_status().await.to_json(),
+        "progress": client.progress_status().await
+    });
+
+    Ok(ToolResult {
+        content: vec![ContentItem {
+            content_type: "text".to_string(),
+            text: serde_json::to_string_pretty(&result)?,
+        }],
+    })
+}
+
+async fn handle_run_check(server: &mut RustAnalyzerMCPServer, args: Value) -> Result<ToolResult> {
+    let cancel = args["cancel"].as_bool().unwrap_or(false);
+    let timeout =
+        Duration::from_millis(args["timeout_ms"].as_u64().unwrap_or(ANALYSIS_WAIT_MILLIS));
+
+    let Some(client) = &server.client else {
+        return Err(anyhow!("Client not initialized"));
+    };
+
+    if cancel {
+        client.cancel_flycheck().await?;
+        let result = json!({ "cancelled": true });
+
+        return Ok(ToolResult {
+            content: vec![ContentItem {
+                content_type: "text".to_string(),
+                text: serde_json::to_string_pretty(&result)?,
+            }],
         });
     }
 
-    output["summary"]["total_files"] = json!(file_count);
-    output["summary"]["total_errors"] = json!(total_errors);
-    output["summary"]["total_warnings"] = json!(total_warnings);
-    output["summary"]["total_information"] = json!(total_information);
-    output["summary"]["total_hints"] = json!(total_hints);
+    // A check already running when requesting ours would end before it, so count completions
+    // rather than watching for the end of any check.
+    let completed_before = client.checks_completed().await;
+    client.run_flycheck().await?;
+
+    let poll_interval = Duration::from_millis(PROGRESS_POLL_INTERVAL_MILLIS);
+    let start = Instant::now();
+    let completed = loop {
+        if client.checks_completed().await > completed_before && !client.is_checking().await {
+            break true;
+        }
+        if start.elapsed() >= timeout {
+            break false;
+        }
+        tokio::time::sleep(poll_interval).await;
+    };
+
+    let diagnostics = format_workspace_diagnostics(
+        &server.workspace_root,
+        &client.workspace_diagnostics_snapshot().await,
+        None,
+        None,
+    );
+    let files: serde_json::Map<String, Value> = diagnostics["files"]
+        .as_object()
+        .into_iter()
+        .flatten()
+        .map(|(uri, file)| {
+            (
+                uri.clone(),
+                json!({
+                    "errors": file["summary"]["errors"],
+                    "warnings": file["summary"]["warnings"]
+                }),
+            )
+        })
+        .collect();
+    let result = json!({
+        "completed": completed,
+        "waited_ms": start.elapsed().as_millis() as u64,
+        "total_errors": diagnostics["summary"]["total_errors"],
+        "total_warnings": diagnostics["summary"]["total_warnings"],
+        "files": files
+    });
+
+    Ok(ToolResult {
+        content: vec![ContentItem {
+            content_type: "text".to_string(),
+            text: serde_json::to_string_pretty(&result)?,
+        }],
+    })
+}
+
+async fn handle_health_check(server: &mut RustAnalyzerMCPServer) -> Result<ToolResult> {
+    let result = match &mut server.client {
+        Some(client) => client.health().await,
+        // rust-analyzer is started by the first tool call.
+        None => json!({
+            "status": "not_started",
+            "checks": {
+                "process_running": false,
+                "lsp_initialized": false,
+                "indexing": false
+            },
+            "open_documents": 0,
+            "pending_requests": 0,
+            "cached_diagnostics": 0,
+            "restart_count": 0
+        }),
+    };
+
+    Ok(ToolResult {
+        content: vec![ContentItem {
+            content_type: "text".to_string(),
+            text: serde_json::to_string_pretty(&result)?,
+        }],
+    })
+}
+
+async fn handle_memory_usage(server: &mut RustAnalyzerMCPServer) -> Result<ToolResult> {
+    let Some(client) = &mut server.client else {
+        return Err(anyhow!("Client not initialized"));
+    };
+
+    let result = client.memory_usage().await?;
+    // The breakdown is preformatted text, which reads much better unquoted.
+    let text = match result {
+        Value::String(text) => text,
+        result => serde_json::to_string_pretty(&result)?,
+    };
+
+    Ok(ToolResult {
+        content: vec![ContentItem {
+            content_type: "text".to_string(),
+            text,
+        }],
+    })
+}
+
+async fn handle_open_documents(server: &mut RustAnalyzerMCPServer) -> Result<ToolResult> {
+    let Some(client) = &server.client else {
+        return Err(anyhow!("Client not initialized"));
+    };
+
+    let documents = client.open_documents().await;
+    let result = json!({
+        "count": documents.as_array().map_or(0, |docs| docs.len()),
+        "documents": documents
+    });
+
+    Ok(ToolResult {
+        content: vec![ContentItem {
+            content_type: "text".to_string(),
+            text: serde_json::to_string_pretty(&result)?,
+        }],
+    })
+}
+
+async fn handle_close_document(
+    server: &mut RustAnalyzerMCPServer,
+    args: Value,
+) -> Result<ToolResult> {
+    let file_path = ToolParams::extract_file_path(&args)?;
+
+    let absolute_path = server.resolve_workspace_path(&file_path)?;
+    let uri = format!("file://{}", absolute_path.display());
+
+    let Some(client) = &mut server.client else {
+        return Err(anyhow!("Client not initialized"));
+    };
+
+    let closed = client.close_document(&uri).await?;
+    // Reopening the document should give fresh results rather than cached ones.
+    if closed {
+        server.cache.clear();
+    }
+
+    let result = json!({
+        "file_path": file_path,
+        "closed": closed
+    });
+
+    Ok(ToolResult {
+        content: vec![ContentItem {
+            content_type: "text".to_string(),
+            text: serde_json::to_string_pretty(&result)?,
+        }],
+    })
+}
+
+async fn handle_workspace_members(server: &mut RustAnalyzerMCPServer) -> Result<ToolResult> {
+    let members = workspace_members(&server.workspace_root)?;
+
+    Ok(ToolResult {
+        content: vec![ContentItem {
+            content_type: "text".to_string(),
+            text: serde_json::to_string_pretty(&members)?,
+        }],
+    })
+}
+
+async fn handle_workspace_info(server: &mut RustAnalyzerMCPServer) -> Result<ToolResult> {
+    let mut info = workspace_info(&server.workspace_root)?;
+
+    // The status is a rust-analyzer extension, so it's only included if available.
+    if let Some(client) = &server.client {
+        info["analyzer_status"] = match client.analyzer_status(None).await {
+            Ok(status) => status,
+            Err(e) => {
+                debug!("Failed to get rust-analyzer status: {}", e);
+                Value::Null
+            }
+        };
+    }
+
+    Ok(ToolResult {
+        content: vec![ContentItem {
+            content_type: "text".to_string(),
+            text: serde_json::to_string_pretty(&info)?,
+        }],
+    })
+}
+
+async fn handle_version(server: &mut RustAnalyzerMCPServer) -> Result<ToolResult> {
+    let info = server.rust_analyzer_info().await?;
+    let text = match info.warning() {
+        Some(warning) => format!("{}\n\nWarning: {}", info.version, warning),
+        None => info.version,
+    };
+
+    Ok(ToolResult {
+        content: vec![ContentItem {
+            content_type: "text".to_string(),
+            text,
+        }],
+    })
+}
+
+async fn handle_reload_workspace(server: &mut RustAnalyzerMCPServer) -> Result<ToolResult> {
+    let Some(client) = &mut server.client else {
+        return Err(anyhow!("Client not initialized"));
+    };
+
+    client.reload_workspace().await?;
+    server.cache.clear();
+
+    // Indexing doesn't necessarily start right away, so give it a moment before waiting for it
+    // to end.
+    let poll_interval = Duration::from_millis(PROGRESS_POLL_INTERVAL_MILLIS);
+    let start = Instant::now();
+    while !client.is_indexing().await
+        && start.elapsed() < Duration::from_millis(WORKSPACE_RELOAD_START_MILLIS)
+    {
+        tokio::time::sleep(poll_interval).await;
+    }
+
+    let wait = Duration::from_secs(WORKSPACE_RELOAD_WAIT_SECS);
+    while client.is_indexing().await && start.elapsed() < wait {
+        tokio::time::sleep(poll_interval).await;
+    }
+
+    let result = json!({
+        "reloaded": true,
+        "indexing_complete": !client.is_indexing().await,
+        "status": client.progress_status().await
+    });
+
+    Ok(ToolResult {
+        content: vec![ContentItem {
+            content_type: "text".to_string(),
+            text: serde_json::to_string_pretty(&result)?,
+        }],
+    })
+}
+
+async fn handle_set_check_command(
+    server: &mut RustAnalyzerMCPServer,
+    args: Value,
+) -> Result<ToolResult> {
+    let Some(command) = args["command"].as_str() else {
+        return Err(invalid_arguments("Missing command"));
+    };
+    let check_command: CheckCommand = command.parse()?;
+
+    let Some(client) = &mut server.client else {
+        return Err(anyhow!("Client not initialized"));
+    };
+
+    client.set_check_command(check_command).await?;
+    // Keep the command if the client gets restarted for another workspace.
+    server.options.config.check_on_save = check_command;
 
-    output
+    Ok(ToolResult {
+        content: vec![ContentItem {
+            content_type: "text".to_string(),
+            text: format!("Check command set to: {}", command),
+        }],
+    })
 }
@@ -0,0 +1,298 @@
+use anyhow::Result;
+use http_body_util::{combinators::BoxBody, BodyExt, Full, Limited, StreamBody};
+use hyper::{
+    body::{Bytes, Frame, Incoming},
+    header::{self, HeaderValue},
+    http::uri::Authority,
+    server::conn::http1,
+    service::service_fn,
+    Method, Request, Response, StatusCode, Uri,
+};
+use hyper_util::rt::TokioIo;
+use log::{debug, error, info};
+use serde_json::{json, Value};
+use std::{
+    convert::Infallible,
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+};
+use tokio::{
+    net::TcpListener,
+    sync::{broadcast, Mutex},
+};
+
+use super::{output::McpOutput, server::RustAnalyzerMCPServer};
+use crate::config::MAX_MESSAGE_SIZE;
+
+type Body = BoxBody<Bytes, Infallible>;
+
+/// Notifications buffered for each SSE client, beyond which a slow client misses the oldest.
+const SSE_CHANNEL_CAPACITY: usize = 256;
+
+impl RustAnalyzerMCPServer {
+    /// Serve JSON-RPC messages POSTed to `/` on `addr` until interrupted, sending the notifications
+    /// to the clients holding a `GET /` SSE stream.
+    ///
+    /// Connections are handled concurrently but requests are handled one at a time, like on stdio.
+    /// Requests must be addressed to `addr`, and browsers may only send them from the origins
+    /// allowed by [`AccessPolicy`].
+    pub async fn run_http(mut self, addr: SocketAddr) -> Result<()> {
+        let listener = TcpListener::bind(addr).await?;
+        let addr = listener.local_addr()?;
+        info!("Starting rust-analyzer MCP server on http://{}", addr);
+        let policy = Arc::new(AccessPolicy {
+            addr,
+            allowed_origins: self.options.allowed_origins.clone(),
+        });
+
+        let (notifications, _) = broadcast::channel(SSE_CHANNEL_CAPACITY);
+        let mut progress = self.set_output(McpOutput::Sse(notifications.clone()));
+        let sse = notifications.clone();
+        tokio::spawn(async move {
+            while let Some(notification) = progress.recv().await {
+                // Dropped if no client is listening.
+                let _ = sse.send(notification);
+            }
+        });
+        let server = Arc::new(Mutex::new(self));
+        let shutdown = tokio::signal::ctrl_c();
+        tokio::pin!(shutdown);
+
+        loop {
+            let (stream, peer) = tokio::select! {
+                accepted = listener.accept() => match accepted {
+                    Ok(accepted) => accepted,
+                    Err(e) => {
+                        error!("Failed to accept connection: {}", e);
+                        continue;
+                    }
+                },
+                _ = &mut shutdown => {
+                    info!("Received shutdown signal");
+                    break;
+                }
+            };
+
+            debug!("Accepted connection from {}", peer);
+            let server = Arc::clone(&server);
+            let notifications = notifications.clone();
+            let policy = Arc::clone(&policy);
+            tokio::spawn(async move {
+                let service = service_fn(move |request| {
+                    handle_http_request(
+                        Arc::clone(&server),
+                        notifications.clone(),
+                        Arc::clone(&policy),
+                        request,
+                    )
+                });
+                if let Err(e) = http1::Builder::new()
+                    .serve_connection(TokioIo::new(stream), service)
+                    .await
+                {
+                    debug!("Connection from {} failed: {}", peer, e);
+                }
+            });
+        }
+
+        server.lock().await.shutdown().await;
+
+        Ok(())
+    }
+}
+
+/// Who may call the server.
+///
+/// The server has no authentication and its tools write files, so a web page must not be able to
+/// call it: neither through the browser of a local user, which sends the page's `Origin`, nor by
+/// rebinding its own domain name to the server's address, which leaves that name in `Host`.
+struct AccessPolicy {
+    /// The address the server listens on.
+    addr: SocketAddr,
+    /// The origins allowed besides the ones of pages served from localhost.
+    allowed_origins: Vec<String>,
+}
+
+impl AccessPolicy {
+    /// Whether `host`, the `Host` header, names the address the server listens on, or `localhost`
+    /// if that's a loopback address. Any name is accepted when listening on all addresses.
+    fn allows_host(&self, host: &str) -> bool {
+        let Ok(authority) = host.parse::<Authority>() else {
+            return false;
+        };
+        if authority.port_u16().unwrap_or(80) != self.addr.port() {
+            return false;
+        }
+
+        let ip = self.addr.ip();
+        match authority.host().trim_matches(['[', ']']).parse::<IpAddr>() {
+            Ok(host) => ip.is_unspecified() || host == ip,
+            Err(_) => {
+                ip.is_unspecified()
+                    || (ip.is_loopback() && authority.host().eq_ignore_ascii_case("localhost"))
+            }
+        }
+    }
+
+    /// Whether a web page from `origin`, the `Origin` header, may call the server: if it's served
+    /// from localhost or its origin is one of `allowed_origins`.
+    fn allows_origin(&self, origin: &str) -> bool {
+        if self.allowed_origins.iter().any(|allowed| allowed == origin) {
+            return true;
+        }
+
+        let Ok(uri) = origin.parse::<Uri>() else {
+            return false;
+        };
+        let Some(host) = uri.host() else {
+            return false;
+        };
+        matches!(uri.scheme_str(), Some("http" | "https"))
+            && (host.eq_ignore_ascii_case("localhost")
+                || host
+                    .trim_matches(['[', ']'])
+                    .parse::<IpAddr>()
+                    .is_ok_and(|ip| ip.is_loopback()))
+    }
+}
+
+async fn handle_http_request(
+    server: Arc<Mutex<RustAnalyzerMCPServer>>,
+    notifications: broadcast::Sender<Value>,
+    policy: Arc<AccessPolicy>,
+    request: Request<Incoming>,
+) -> Result<Response<Body>, Infallible> {
+    let host = request
+        .headers()
+        .get(header::HOST)
+        .and_then(|host| host.to_str().ok());
+    if !host.is_some_and(|host| policy.allows_host(host)) {
+        debug!("Rejected request for host {:?}", host);
+        return Ok(empty_response(StatusCode::MISDIRECTED_REQUEST));
+    }
+    // Only browsers send an `Origin`, which other clients don't need to.
+    let origin = request.headers().get(header::ORIGIN).cloned();
+    if let Some(origin) = &origin {
+        if !origin
+            .to_str()
+            .is_ok_and(|origin| policy.allows_origin(origin))
+        {
+            debug!("Rejected request from origin {:?}", origin);
+            return Ok(empty_response(StatusCode::FORBIDDEN));
+        }
+    }
+
+    let response = match (request.method(), request.uri().path()) {
+        // CORS preflight from browser-based clients.
+        (&Method::OPTIONS, _) => empty_response(StatusCode::NO_CONTENT),
+        (&Method::POST, "/") => handle_post(server, request).await,
+        (&Method::GET, "/") => handle_sse(&notifications),
+        (_, "/") => empty_response(StatusCode::METHOD_NOT_ALLOWED),
+        _ => empty_response(StatusCode::NOT_FOUND),
+    };
+
+    Ok(match origin {
+        Some(origin) => with_cors_headers(response, origin),
+        None => response,
+    })
+}
+
+async fn handle_post(
+    server: Arc<Mutex<RustAnalyzerMCPServer>>,
+    request: Request<Incoming>,
+) -> Response<Body> {
+    let body = match Limited::new(request.into_body(), MAX_MESSAGE_SIZE)
+        .collect()
+        .await
+    {
+        Ok(body) => body.to_bytes(),
+        Err(e) => {
+            debug!("Failed to read request body: {}", e);
+            return empty_response(StatusCode::PAYLOAD_TOO_LARGE);
+        }
+    };
+
+    let message = match serde_json::from_slice::<Value>(&body) {
+        Ok(message) => message,
+        Err(e) => {
+            let error = json!({
+                "jsonrpc": "2.0",
+                "id": null,
+                "error": {
+                    "code": -32700,
+                    "message": format!("Parse error: {}", e)
+                }
+            });
+            return json_response(StatusCode::BAD_REQUEST, &error);
+        }
+    };
+
+    match server.lock().await.handle_message(message).await {
+        Some(response) => json_response(StatusCode::OK, &response),
+        // Batches of notifications don't get a response.
+        None => empty_response(StatusCode::ACCEPTED),
+    }
+}
+
+/// Stream the notifications sent from now on as SSE `message` events, until the client disconnects.
+fn handle_sse(notifications: &broadcast::Sender<Value>) -> Response<Body> {
+    let events = futures::stream::unfold(notifications.subscribe(), |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(notification) => {
+                    let event = format!("event: message\ndata: {}\n\n", notification);
+                    return Some((Ok(Frame::data(Bytes::from(event))), receiver));
+                }
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    debug!("SSE client missed {} notifications", missed);
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    });
+
+    let mut response = Response::new(StreamBody::new(events).boxed());
+    let headers = response.headers_mut();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("text/event-stream"),
+    );
+    headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+
+    response
+}
+
+fn json_response(status: StatusCode, body: &Value) -> Response<Body> {
+    let mut response = Response::new(Full::new(Bytes::from(body.to_string())).boxed());
+    *response.status_mut() = status;
+    response.headers_mut().insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/json"),
+    );
+
+    response
+}
+
+fn empty_response(status: StatusCode) -> Response<Body> {
+    let mut response = Response::new(Full::new(Bytes::new()).boxed());
+    *response.status_mut() = status;
+
+    response
+}
+
+/// Allow the page from `origin`, which was checked, to read `response`.
+fn with_cors_headers(mut response: Response<Body>, origin: HeaderValue) -> Response<Body> {
+    let headers = response.headers_mut();
+    headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin);
+    headers.insert(header::VARY, HeaderValue::from_static("Origin"));
+    headers.insert(
+        header::ACCESS_CONTROL_ALLOW_METHODS,
+        HeaderValue::from_static("GET, POST, OPTIONS"),
+    );
+    headers.insert(
+        header::ACCESS_CONTROL_ALLOW_HEADERS,
+        HeaderValue::from_static("Content-Type"),
+    );
+
+    response
+}
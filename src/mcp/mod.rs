@@ -1,5 +1,14 @@
+mod cache;
 mod handlers;
+mod http;
+mod output;
+mod prompts;
+mod resources;
 mod server;
 mod tools;
 
+pub use cache::ResponseCache;
+pub use output::McpOutput;
+pub use prompts::{get_prompt, get_prompts};
+pub use resources::{list_resources, read_resource};
 pub use server::RustAnalyzerMCPServer;
@@ -0,0 +1,45 @@
+use anyhow::Result;
+use serde_json::Value;
+use std::sync::{Arc, OnceLock};
+use tokio::{
+    io::{AsyncWriteExt, BufWriter, Stdout},
+    sync::{broadcast, Mutex},
+};
+
+use crate::protocol::framing::Framing;
+
+/// Where the MCP server sends its notifications, e.g. the progress forwarded from rust-analyzer.
+#[derive(Clone)]
+pub enum McpOutput {
+    /// The stdout of the server when serving over stdio, shared with the responses so that their
+    /// lines don't interleave. Messages are framed like the first one the client sent, once
+    /// it's known.
+    Stdio {
+        writer: Arc<Mutex<BufWriter<Stdout>>>,
+        framing: Arc<OnceLock<Framing>>,
+    },
+    /// The SSE streams of the clients connected when serving over HTTP.
+    Sse(broadcast::Sender<Value>),
+}
+
+impl McpOutput {
+    /// Send `message` to the client(s).
+    ///
+    /// Over SSE, it's dropped if no client is listening.
+    pub async fn send(&self, message: &Value) -> Result<()> {
+        match self {
+            Self::Stdio { writer, framing } => {
+                let framing = framing.get().copied().unwrap_or_default();
+                let framed = framing.frame(&message.to_string());
+                let mut writer = writer.lock().await;
+                writer.write_all(framed.as_bytes()).await?;
+                writer.flush().await?;
+            }
+            Self::Sse(sender) => {
+                let _ = sender.send(message.clone());
+            }
+        }
+
+        Ok(())
+    }
+}
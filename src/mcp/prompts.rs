@@ -0,0 +1,116 @@
+use anyhow::Result;
+use serde_json::{json, Value};
+
+use crate::protocol::mcp::{ErrorKind, PromptArgument, PromptDefinition, ToolError};
+
+/// A prompt template, whose `{argument}` placeholders are replaced by the arguments of
+/// `prompts/get`.
+struct Prompt {
+    name: &'static str,
+    description: &'static str,
+    /// The arguments, as name, description and whether it's required.
+    arguments: &'static [(&'static str, &'static str, bool)],
+    template: &'static str,
+}
+
+const PROMPTS: &[Prompt] = &[
+    Prompt {
+        name: "fix-all-errors",
+        description: "Fix all the compiler errors of the workspace, one file at a time",
+        arguments: &[],
+        template: "\
+Fix all the compiler errors in this Rust workspace using the rust-analyzer tools:
+
+1. Call `rust_analyzer_wait_for_analysis` so that the errors are complete.
+2. Call `rust_analyzer_workspace_errors` to find the files with errors and the first ones to fix.
+3. For each of these files, call `rust_analyzer_diagnostics` to get all of its errors with their \
+exact ranges.
+4. For each error, call `rust_analyzer_code_actions` on its range. If a quick fix matches the \
+error, apply its edit with `rust_analyzer_apply_edits`, otherwise edit the code yourself.
+5. Call `rust_analyzer_workspace_errors` again, and repeat until there are no errors left.
+
+Fix the root cause of an error rather than silencing it, and don't change unrelated code.",
+    },
+    Prompt {
+        name: "explain-symbol",
+        description: "Explain what a symbol is, where it's defined and how it's used",
+        arguments: &[
+            ("file_path", "Path to the Rust file", true),
+            ("line", "Line number of the symbol (0-based)", true),
+            (
+                "character",
+                "Character position of the symbol (0-based)",
+                true,
+            ),
+        ],
+        template: "\
+Explain the symbol at line {line}, character {character} of {file_path} using the rust-analyzer \
+tools:
+
+1. Call `rust_analyzer_hover` at that position to get its type, signature and documentation.
+2. Call `rust_analyzer_definition` at that position, with `include_source`, to read its \
+definition.
+3. Call `rust_analyzer_references` at that position to see where it's used.
+
+Then explain what the symbol is and does, where it's defined and how the rest of the code uses it.",
+    },
+];
+
+/// The prompts listed by `prompts/list`.
+pub fn get_prompts() -> Vec<PromptDefinition> {
+    PROMPTS
+        .iter()
+        .map(|prompt| PromptDefinition {
+            name: prompt.name.to_string(),
+            description: prompt.description.to_string(),
+            arguments: prompt
+                .arguments
+                .iter()
+                .map(|(name, description, required)| PromptArgument {
+                    name: name.to_string(),
+                    description: description.to_string(),
+                    required: *required,
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+/// The result of `prompts/get`: the prompt `name` as a user message, with its placeholders
+/// replaced by `arguments`.
+pub fn get_prompt(name: &str, arguments: &Value) -> Result<Value> {
+    let Some(prompt) = PROMPTS.iter().find(|prompt| prompt.name == name) else {
+        return Err(ToolError::new(
+            ErrorKind::InvalidArguments,
+            format!("Unknown prompt: {}", name),
+        )
+        .into());
+    };
+
+    let mut text = prompt.template.to_string();
+    for (argument, _, required) in prompt.arguments {
+        let value = match &arguments[argument] {
+            Value::String(value) => value.clone(),
+            Value::Null => {
+                if *required {
+                    return Err(ToolError::new(
+                        ErrorKind::InvalidArguments,
+                        format!("Missing argument `{}` of prompt {}", argument, name),
+                    )
+                    .into());
+                }
+                String::new()
+            }
+            value => value.to_string(),
+        };
+        text = text.replace(&format!("{{{}}}", argument), &value);
+    }
+
+    Ok(json!({
+        "description": prompt.description,
+        "messages": [{
+            "role": "user",
+            "content": { "type": "text", "text": text }
+        }]
+    }))
+}
@@ -0,0 +1,86 @@
+use anyhow::Result;
+use glob::Pattern;
+use serde_json::{json, Value};
+use std::path::Path;
+
+use crate::{
+    config::MAX_RESOURCE_BYTES,
+    edits::uri_to_path,
+    protocol::mcp::{ErrorKind, ToolError},
+    security::validate_workspace_path,
+};
+
+use super::handlers::find_source_files;
+
+const RUST_MIME_TYPE: &str = "text/x-rust";
+
+/// The result of `resources/list`: the Rust sources of the workspace, as `file://` URIs named
+/// after their path relative to the workspace root.
+///
+/// Files skipped by `rust_analyzer_index_workspace` (ignored ones and build output) are left out.
+pub fn list_resources(workspace_root: &Path) -> Value {
+    let all_sources = Pattern::new("**/*.rs").expect("valid glob");
+    let resources: Vec<Value> = find_source_files(workspace_root, &[all_sources])
+        .into_iter()
+        .map(|relative| {
+            let path = workspace_root.join(&relative);
+            let mut resource = json!({
+                "uri": format!("file://{}", path.display()),
+                "name": relative,
+                "mimeType": RUST_MIME_TYPE
+            });
+            if let Ok(metadata) = path.metadata() {
+                resource["size"] = json!(metadata.len());
+            }
+            resource
+        })
+        .collect();
+
+    json!({ "resources": resources })
+}
+
+/// The result of `resources/read` for a `file://` URI inside the workspace.
+///
+/// Files larger than [`MAX_RESOURCE_BYTES`] are refused rather than truncated.
+pub fn read_resource(workspace_root: &Path, uri: &str) -> Result<Value> {
+    let Some(path) = uri_to_path(uri) else {
+        return Err(ToolError::new(
+            ErrorKind::InvalidArguments,
+            format!("Not a file:// URI: {}", uri),
+        )
+        .into());
+    };
+    let file_path = path.to_string_lossy();
+    let path = validate_workspace_path(workspace_root, &file_path)?;
+
+    let size = path
+        .metadata()
+        .map_err(|e| ToolError::file_read(&file_path, e))?
+        .len();
+    if size > MAX_RESOURCE_BYTES {
+        return Err(ToolError::new(
+            ErrorKind::InvalidArguments,
+            format!(
+                "File is too large to read as a resource ({} bytes, at most {}): {}",
+                size, MAX_RESOURCE_BYTES, file_path
+            ),
+        )
+        .with_path(file_path.as_ref())
+        .into());
+    }
+    let text = std::fs::read_to_string(&path).map_err(|e| ToolError::file_read(&file_path, e))?;
+
+    let mime_type = if path.extension().is_some_and(|extension| extension == "rs") {
+        RUST_MIME_TYPE
+    } else {
+        "text/plain"
+    };
+
+    Ok(json!({
+        "contents": [{
+            "uri": uri,
+            "mimeType": mime_type,
+            "text": text
+        }]
+    }))
+}
@@ -1,20 +1,58 @@
 use anyhow::Result;
-use log::{debug, error, info};
-use serde_json::json;
-use std::{path::PathBuf, sync::Arc};
+use futures::FutureExt;
+use log::{debug, error, info, warn};
+use serde_json::{json, Value};
+use std::{
+    any::Any,
+    collections::{HashMap, VecDeque},
+    panic::AssertUnwindSafe,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, OnceLock,
+    },
+};
 use tokio::{
-    io::{AsyncBufReadExt, AsyncWriteExt, BufReader, BufWriter},
-    sync::Mutex,
+    io::{BufReader, BufWriter},
+    sync::{mpsc, Mutex},
 };
+use tokio_util::sync::CancellationToken;
 
 use crate::{
-    lsp::RustAnalyzerClient,
-    protocol::mcp::{MCPError, MCPRequest, MCPResponse},
+    config::ServerOptions,
+    lsp::{RustAnalyzerClient, RustAnalyzerInfo},
+    protocol::{
+        framing::read_framed_message,
+        mcp::{MCPError, MCPRequest, MCPResponse, ToolError, ToolResult},
+    },
+    security::validate_workspace_path,
 };
 
+use super::{cache::ResponseCache, output::McpOutput};
+
+/// The cancellation tokens of the in-flight tool calls, by request ID.
+///
+/// Request IDs can be numbers or strings, so they're keyed by their JSON text, which also keeps
+/// `1` and `"1"` apart.
+pub type CancellationMap = Arc<Mutex<HashMap<String, CancellationToken>>>;
+
 pub struct RustAnalyzerMCPServer {
     pub(super) client: Option<RustAnalyzerClient>,
     pub(super) workspace_root: PathBuf,
+    pub(super) options: ServerOptions,
+    pub(super) cache: ResponseCache,
+    /// The detected rust-analyzer installation, once detection succeeded.
+    pub(super) rust_analyzer_info: Option<RustAnalyzerInfo>,
+    /// The explanations of the diagnostic codes explained so far, which never change.
+    pub(super) explanations: HashMap<String, Value>,
+    /// Where notifications are sent, and rust-analyzer's progress is forwarded: stdout over stdio,
+    /// the SSE streams over HTTP.
+    pub(super) output: Option<McpOutput>,
+    /// Where rust-analyzer's progress is forwarded to, to be sent to `output` by whoever owns it.
+    pub(super) notifications: Option<mpsc::UnboundedSender<Value>>,
+    /// Set once the request being handled started writing files, after which it can't be
+    /// cancelled anymore.
+    pub(super) writing: Arc<AtomicBool>,
 }
 
 impl Default for RustAnalyzerMCPServer {
@@ -25,13 +63,17 @@ impl Default for RustAnalyzerMCPServer {
 
 impl RustAnalyzerMCPServer {
     pub fn new() -> Self {
-        Self {
-            client: None,
-            workspace_root: std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")),
-        }
+        Self::with_options(
+            std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")),
+            ServerOptions::default(),
+        )
     }
 
     pub fn with_workspace(workspace_root: PathBuf) -> Self {
+        Self::with_options(workspace_root, ServerOptions::default())
+    }
+
+    pub fn with_options(workspace_root: PathBuf, options: ServerOptions) -> Self {
         // Ensure the workspace root is absolute.
         let workspace_root = workspace_root.canonicalize().unwrap_or_else(|_| {
             // If canonicalize fails, try to make it absolute.
@@ -47,28 +89,72 @@ impl RustAnalyzerMCPServer {
         Self {
             client: None,
             workspace_root,
+            cache: ResponseCache::new(options.config.response_cache_capacity),
+            rust_analyzer_info: None,
+            explanations: HashMap::new(),
+            output: None,
+            notifications: None,
+            writing: Arc::new(AtomicBool::new(false)),
+            options,
+        }
+    }
+
+    /// Detect the rust-analyzer installation, reusing the result of a previous detection.
+    ///
+    /// Failures aren't remembered so that installing rust-analyzer doesn't require a restart.
+    pub(super) async fn rust_analyzer_info(&mut self) -> Result<RustAnalyzerInfo> {
+        if let Some(info) = &self.rust_analyzer_info {
+            return Ok(info.clone());
+        }
+
+        let info = RustAnalyzerInfo::detect(self.options.rust_analyzer_path.clone()).await?;
+        info!("Detected {} at {}", info.version, info.path.display());
+        if let Some(warning) = info.warning() {
+            warn!("{}", warning);
         }
+        self.rust_analyzer_info = Some(info.clone());
+
+        Ok(info)
     }
 
+    /// Start rust-analyzer if it's not running.
+    ///
+    /// If it exited or crashed since it was started, it's restarted with the documents that were
+    /// open reopened.
     pub(super) async fn ensure_client_started(&mut self) -> Result<()> {
-        if self.client.is_none() {
-            let mut client = RustAnalyzerClient::new(self.workspace_root.clone());
-            client.start().await?;
-            self.client = Some(client);
+        if let Some(client) = &mut self.client {
+            if client.is_alive() {
+                return Ok(());
+            }
+
+            warn!("rust-analyzer is not running anymore, restarting it");
+            self.cache.clear();
+            return client.restart().await;
         }
+
+        let mut client = RustAnalyzerClient::new(self.workspace_root.clone(), self.options.clone());
+        if let Some(notifications) = &self.notifications {
+            client.set_notifications(notifications.clone());
+        }
+        client.start().await?;
+        self.client = Some(client);
+
         Ok(())
     }
 
+    /// Resolve `file_path` against the workspace root, making sure it doesn't escape it.
+    ///
+    /// See [`validate_workspace_path`].
+    pub(super) fn resolve_workspace_path(&self, file_path: &str) -> Result<PathBuf> {
+        validate_workspace_path(&self.workspace_root, file_path)
+    }
+
     pub(super) async fn open_document_if_needed(&mut self, file_path: &str) -> Result<String> {
-        let absolute_path = self.workspace_root.join(file_path);
-        // Ensure we have an absolute path for the URI.
-        let absolute_path = absolute_path
-            .canonicalize()
-            .unwrap_or_else(|_| absolute_path.clone());
+        let absolute_path = self.resolve_workspace_path(file_path)?;
         let uri = format!("file://{}", absolute_path.display());
         let content = tokio::fs::read_to_string(&absolute_path)
             .await
-            .map_err(|e| anyhow::anyhow!("Failed to read file {}: {}", file_path, e))?;
+            .map_err(|e| ToolError::file_read(file_path, e))?;
 
         let Some(client) = &mut self.client else {
             return Err(anyhow::anyhow!("Client not initialized"));
@@ -78,13 +164,83 @@ impl RustAnalyzerMCPServer {
         Ok(uri)
     }
 
+    /// Tell the client that the tools and resources may have changed, e.g. because the workspace
+    /// did, with `notifications/tools/list_changed` and `notifications/resources/list_changed`.
+    pub(super) async fn notify_lists_changed(&self) {
+        let Some(output) = &self.output else {
+            return;
+        };
+
+        for method in [
+            "notifications/tools/list_changed",
+            "notifications/resources/list_changed",
+        ] {
+            let notification = json!({
+                "jsonrpc": "2.0",
+                "method": method
+            });
+            if let Err(e) = output.send(&notification).await {
+                error!("Failed to send {}: {}", method, e);
+            }
+        }
+    }
+
+    /// Make the request being handled non-cancellable, before it starts writing files.
+    ///
+    /// Cancelling it halfway through would leave some of the files written but not others, or
+    /// rust-analyzer unaware of the changes.
+    pub(super) fn start_writing(&self) {
+        self.writing.store(true, Ordering::SeqCst);
+    }
+
+    /// Send notifications to `output`.
+    ///
+    /// rust-analyzer's progress comes out of the returned receiver instead, for the caller to send
+    /// to `output`, so that rust-analyzer's responses aren't held up by writing to a slow client.
+    pub(super) fn set_output(&mut self, output: McpOutput) -> mpsc::UnboundedReceiver<Value> {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        if let Some(client) = &mut self.client {
+            client.set_notifications(sender.clone());
+        }
+        self.notifications = Some(sender);
+        self.output = Some(output);
+
+        receiver
+    }
+
     pub async fn run(&mut self) -> Result<()> {
         info!("Starting rust-analyzer MCP server");
 
         let stdin = tokio::io::stdin();
         let stdout = tokio::io::stdout();
-        let mut reader = BufReader::new(stdin);
-        let mut writer = BufWriter::new(stdout);
+        // Reading a message isn't cancel-safe, unlike receiving one, so it's done by its own task.
+        let (sender, mut messages) = mpsc::unbounded_channel();
+        // Responses are framed like the first message of the client.
+        let framing = Arc::new(OnceLock::new());
+        let client_framing = Arc::clone(&framing);
+        tokio::spawn(async move {
+            let mut reader = BufReader::new(stdin);
+            loop {
+                match read_framed_message(&mut reader).await {
+                    Ok(Some((message, framing))) => {
+                        let _ = client_framing.set(framing);
+                        if sender.send(message).is_err() {
+                            break;
+                        }
+                    }
+                    Ok(None) => break, // EOF
+                    Err(e) => {
+                        error!("Error reading from stdin: {}", e);
+                        break;
+                    }
+                }
+            }
+        });
+        let writer = McpOutput::Stdio {
+            writer: Arc::new(Mutex::new(BufWriter::new(stdout))),
+            framing,
+        };
+        let mut notifications = self.set_output(writer.clone());
 
         // Handle shutdown signals.
         let running = Arc::new(Mutex::new(true));
@@ -96,68 +252,216 @@ impl RustAnalyzerMCPServer {
             *running_clone.lock().await = false;
         });
 
+        let cancellations: CancellationMap = Arc::default();
+        // Messages read while handling a request, to be handled after it.
+        let mut queued = VecDeque::new();
+        let mut eof = false;
+
         loop {
             // Check if we should stop.
             if !*running.lock().await {
                 break;
             }
 
-            let mut line = String::new();
-            let bytes_read = match reader.read_line(&mut line).await {
-                Ok(n) => n,
-                Err(e) => {
-                    error!("Error reading from stdin: {}", e);
-                    break;
-                }
+            let message = match queued.pop_front() {
+                Some(message) => message,
+                None if eof => break,
+                None => tokio::select! {
+                    message = messages.recv() => match message {
+                        Some(message) => match parse_message(&message) {
+                            Some(message) => message,
+                            None => continue,
+                        },
+                        None => break, // EOF
+                    },
+                    Some(notification) = notifications.recv() => {
+                        writer.send(&notification).await?;
+                        continue;
+                    }
+                },
             };
 
-            if bytes_read == 0 {
-                break; // EOF
+            // Nothing is in flight, so there's nothing left to cancel.
+            if cancelled_request_id(&message).is_some() {
+                if let Some(ack) = cancel_ack(&message) {
+                    writer.send(&ack).await?;
+                }
+                continue;
+            }
+
+            // Keep reading while a tool call is in flight, so it can be cancelled.
+            let id = cancellable_request_id(&message);
+            let token = CancellationToken::new();
+            let mut cancelled = false;
+            let mut uncancellable = false;
+            self.writing.store(false, Ordering::SeqCst);
+            let writing = Arc::clone(&self.writing);
+            if let Some(id) = &id {
+                cancellations
+                    .lock()
+                    .await
+                    .insert(id.to_string(), token.clone());
+            }
+            let response = {
+                let handling = self.handle_message(message);
+                tokio::pin!(handling);
+                loop {
+                    tokio::select! {
+                        response = &mut handling => break response,
+                        _ = token.cancelled(), if !uncancellable => {
+                            if writing.load(Ordering::SeqCst) {
+                                info!("Not cancelling request {:?}, which is writing files", id);
+                                uncancellable = true;
+                                continue;
+                            }
+                            info!("Cancelled request {:?}", id);
+                            cancelled = true;
+                            break id.clone().map(cancelled_response);
+                        }
+                        message = messages.recv(), if !eof => match message {
+                            Some(message) => {
+                                let Some(message) = parse_message(&message) else {
+                                    continue;
+                                };
+                                match cancelled_request_id(&message) {
+                                    Some(id) => {
+                                        cancel(&message, &id, &cancellations, &mut queued, &writer)
+                                            .await?
+                                    }
+                                    None => queued.push_back(message),
+                                }
+                            }
+                            None => eof = true,
+                        },
+                        Some(notification) = notifications.recv() => {
+                            writer.send(&notification).await?;
+                        }
+                    }
+                }
+            };
+            if let Some(id) = &id {
+                cancellations.lock().await.remove(&id.to_string());
             }
 
-            let line = line.trim();
-            if line.is_empty() {
-                continue;
+            // The cancelled call was dropped, but rust-analyzer is still working on its requests.
+            if cancelled {
+                if let Some(client) = &self.client {
+                    if let Err(e) = client.cancel_pending_requests().await {
+                        warn!("Failed to cancel rust-analyzer requests: {}", e);
+                    }
+                }
             }
 
-            let Ok(request) = serde_json::from_str::<MCPRequest>(line) else {
-                debug!("Failed to parse request: {}", line);
+            // The progress reported while handling the request comes first.
+            while let Ok(notification) = notifications.try_recv() {
+                writer.send(&notification).await?;
+            }
+            let Some(response) = response else {
                 continue;
             };
-
-            debug!("Received request: {}", request.method);
-            let response = self.handle_request(request).await;
-            let response_json = serde_json::to_string(&response)?;
-            writer.write_all(response_json.as_bytes()).await?;
-            writer.write_all(b"\n").await?;
-            writer.flush().await?;
+            writer.send(&response).await?;
         }
 
-        // Cleanup.
+        self.shutdown().await;
+
+        Ok(())
+    }
+
+    /// Stop rust-analyzer before exiting.
+    pub(super) async fn shutdown(&mut self) {
         info!("Shutting down");
         if let Some(client) = &mut self.client {
             let _ = client.shutdown().await;
         }
+    }
 
-        Ok(())
+    /// Handle a JSON-RPC message, either a single request or a batch.
+    ///
+    /// Returns the response to send back, if any.
+    pub(super) async fn handle_message(&mut self, message: Value) -> Option<Value> {
+        match message {
+            Value::Array(batch) => {
+                let responses = self.handle_batch(batch).await;
+                // A batch of notifications gets no response at all.
+                (!responses.is_empty()).then(|| json!(responses))
+            }
+            message => {
+                let request = match serde_json::from_value::<MCPRequest>(message) {
+                    Ok(request) => request,
+                    Err(e) => {
+                        debug!("Failed to parse request: {}", e);
+                        return None;
+                    }
+                };
+
+                debug!("Received request: {}", request.method);
+                let response = self.handle_request(request).await;
+                Some(json!(response))
+            }
+        }
+    }
+
+    /// Handle a JSON-RPC batch, returning the responses to the requests in it.
+    ///
+    /// The elements are handled in order. Notifications are handled too but don't get a response.
+    async fn handle_batch(&mut self, batch: Vec<Value>) -> Vec<MCPResponse> {
+        if batch.is_empty() {
+            return vec![invalid_request()];
+        }
+
+        let mut responses = Vec::new();
+        for message in batch {
+            let Ok(request) = serde_json::from_value::<MCPRequest>(message) else {
+                responses.push(invalid_request());
+                continue;
+            };
+
+            debug!("Received batched request: {}", request.method);
+            let is_notification = request.id.is_none();
+            let response = self.handle_request(request).await;
+            if !is_notification {
+                responses.push(response);
+            }
+        }
+
+        responses
     }
 
     async fn handle_request(&mut self, request: MCPRequest) -> MCPResponse {
         match request.method.as_str() {
-            "initialize" => MCPResponse::Success {
-                jsonrpc: "2.0".to_string(),
-                id: request.id,
-                result: json!({
-                    "protocolVersion": "2024-11-05",
-                    "serverInfo": {
-                        "name": "rust-analyzer-mcp",
-                        "version": "0.1.0"
-                    },
-                    "capabilities": {
-                        "tools": {}
+            "initialize" => {
+                // Report problems with rust-analyzer upfront rather than on the first tool call.
+                let rust_analyzer = match self.rust_analyzer_info().await {
+                    Ok(info) => json!({
+                        "path": info.path,
+                        "version": info.version,
+                        "semver": info.semver.map(|v| v.to_string()),
+                        "warning": info.warning()
+                    }),
+                    Err(e) => {
+                        warn!("rust-analyzer is not usable: {}", e);
+                        json!({ "error": e.to_string() })
                     }
-                }),
-            },
+                };
+
+                MCPResponse::Success {
+                    jsonrpc: "2.0".to_string(),
+                    id: request.id,
+                    result: json!({
+                        "protocolVersion": "2024-11-05",
+                        "serverInfo": {
+                            "name": "rust-analyzer-mcp",
+                            "version": "0.1.0",
+                            "rust_analyzer": rust_analyzer
+                        },
+                        "capabilities": {
+                            "tools": { "listChanged": true },
+                            "resources": { "listChanged": true },
+                            "prompts": { "listChanged": false }
+                        }
+                    }),
+                }
+            }
             "tools/list" => MCPResponse::Success {
                 jsonrpc: "2.0".to_string(),
                 id: request.id,
@@ -195,22 +499,117 @@ impl RustAnalyzerMCPServer {
                     .cloned()
                     .unwrap_or_else(|| json!({}));
 
-                match super::handlers::handle_tool_call(self, tool_name, args).await {
+                // A panicking handler fails its request rather than taking the server down.
+                let handling = super::handlers::handle_tool_call(self, tool_name, args);
+                let result = match AssertUnwindSafe(handling).catch_unwind().await {
+                    Ok(result) => result,
+                    Err(panic) => {
+                        error!(
+                            "Tool {} panicked: {}",
+                            tool_name,
+                            panic_message(panic.as_ref())
+                        );
+                        return MCPResponse::Error {
+                            jsonrpc: "2.0".to_string(),
+                            id: request.id,
+                            error: MCPError {
+                                code: -32603,
+                                message: "Internal server error (panic)".to_string(),
+                                data: None,
+                            },
+                        };
+                    }
+                };
+
+                match result {
+                    Ok(mut result) => {
+                        if self.options.compact_responses {
+                            compact_tool_result(&mut result);
+                        }
+                        MCPResponse::Success {
+                            jsonrpc: "2.0".to_string(),
+                            id: request.id,
+                            result: serde_json::to_value(result).unwrap(),
+                        }
+                    }
+                    Err(e) => {
+                        error!("Tool call error: {}", e);
+                        MCPResponse::Error {
+                            jsonrpc: "2.0".to_string(),
+                            id: request.id,
+                            error: MCPError::from_tool_error(&e),
+                        }
+                    }
+                }
+            }
+            "prompts/list" => MCPResponse::Success {
+                jsonrpc: "2.0".to_string(),
+                id: request.id,
+                result: json!({
+                    "prompts": super::prompts::get_prompts()
+                }),
+            },
+            "prompts/get" => {
+                let params = request.params.unwrap_or_else(|| json!({}));
+                let Some(name) = params["name"].as_str() else {
+                    return MCPResponse::Error {
+                        jsonrpc: "2.0".to_string(),
+                        id: request.id,
+                        error: MCPError {
+                            code: -32602,
+                            message: "Missing prompt name".to_string(),
+                            data: None,
+                        },
+                    };
+                };
+
+                match super::prompts::get_prompt(name, &params["arguments"]) {
+                    Ok(result) => MCPResponse::Success {
+                        jsonrpc: "2.0".to_string(),
+                        id: request.id,
+                        result,
+                    },
+                    Err(e) => MCPResponse::Error {
+                        jsonrpc: "2.0".to_string(),
+                        id: request.id,
+                        error: MCPError::from_tool_error(&e),
+                    },
+                }
+            }
+            "resources/list" => MCPResponse::Success {
+                jsonrpc: "2.0".to_string(),
+                id: request.id,
+                result: super::resources::list_resources(&self.workspace_root),
+            },
+            "resources/read" => {
+                let Some(uri) = request
+                    .params
+                    .as_ref()
+                    .and_then(|params| params["uri"].as_str())
+                else {
+                    return MCPResponse::Error {
+                        jsonrpc: "2.0".to_string(),
+                        id: request.id,
+                        error: MCPError {
+                            code: -32602,
+                            message: "Missing resource uri".to_string(),
+                            data: None,
+                        },
+                    };
+                };
+
+                match super::resources::read_resource(&self.workspace_root, uri) {
                     Ok(result) => MCPResponse::Success {
                         jsonrpc: "2.0".to_string(),
                         id: request.id,
-                        result: serde_json::to_value(result).unwrap(),
+                        result,
                     },
                     Err(e) => {
-                        error!("Tool call error: {}", e);
+                        error!("Resource read error: {}", e);
                         MCPResponse::Error {
                             jsonrpc: "2.0".to_string(),
                             id: request.id,
-                            error: MCPError {
-                                code: -1,
-                                message: e.to_string(),
-                                data: None,
-                            },
+                            error: MCPError::from_tool_error(&e),
                         }
                     }
                 }
@@ -227,3 +626,136 @@ impl RustAnalyzerMCPServer {
         }
     }
 }
+
+/// The message a panic was raised with, if it's a string as with `panic!` and `unwrap`.
+fn panic_message(panic: &(dyn Any + Send)) -> &str {
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = panic.downcast_ref::<String>() {
+        message
+    } else {
+        "unknown panic"
+    }
+}
+
+/// Parse a message read from stdin as JSON-RPC, skipping blank and malformed ones.
+fn parse_message(line: &str) -> Option<Value> {
+    let line = line.trim();
+    if line.is_empty() {
+        return None;
+    }
+
+    match serde_json::from_str::<Value>(line) {
+        Ok(message) => Some(message),
+        Err(_) => {
+            debug!("Failed to parse request: {}", line);
+            None
+        }
+    }
+}
+
+/// The ID of the request a message asks to cancel, if it's a cancellation notification.
+///
+/// The LSP-style `$/cancelRequest`, MCP's `notifications/cancelled` and a `cancel` request are
+/// accepted.
+fn cancelled_request_id(message: &Value) -> Option<Value> {
+    let id = match message["method"].as_str()? {
+        "$/cancelRequest" | "cancel" => &message["params"]["id"],
+        "notifications/cancelled" => &message["params"]["requestId"],
+        _ => return None,
+    };
+
+    request_id(id)
+}
+
+/// The ID of a message if it's a tool call that can be cancelled.
+fn cancellable_request_id(message: &Value) -> Option<Value> {
+    if message["method"] != "tools/call" {
+        return None;
+    }
+
+    request_id(&message["id"])
+}
+
+/// A JSON-RPC request ID, which is either a number or a string.
+fn request_id(id: &Value) -> Option<Value> {
+    (id.is_number() || id.is_string()).then(|| id.clone())
+}
+
+/// Handle `message`, cancelling the request `id`, while another request is in flight.
+///
+/// The in-flight request is cancelled through its token. A request still waiting in `queued` is
+/// dropped and answered right away.
+async fn cancel(
+    message: &Value,
+    id: &Value,
+    cancellations: &CancellationMap,
+    queued: &mut VecDeque<Value>,
+    writer: &McpOutput,
+) -> Result<()> {
+    if let Some(token) = cancellations.lock().await.get(&id.to_string()) {
+        token.cancel();
+    } else if let Some(index) = queued
+        .iter()
+        .position(|queued| request_id(&queued["id"]).as_ref() == Some(id))
+    {
+        info!("Cancelled queued request {}", id);
+        queued.remove(index);
+        writer.send(&cancelled_response(id.clone())).await?;
+    }
+
+    if let Some(ack) = cancel_ack(message) {
+        writer.send(&ack).await?;
+    }
+
+    Ok(())
+}
+
+/// The response to a cancellation sent as a request rather than a notification, i.e. with an ID.
+fn cancel_ack(message: &Value) -> Option<Value> {
+    let id = request_id(&message["id"])?;
+
+    Some(json!(MCPResponse::Success {
+        jsonrpc: "2.0".to_string(),
+        id: Some(id),
+        result: json!({}),
+    }))
+}
+
+/// The response to a request cancelled by the client.
+fn cancelled_response(id: Value) -> Value {
+    json!(MCPResponse::Error {
+        jsonrpc: "2.0".to_string(),
+        id: Some(id),
+        error: MCPError {
+            code: -32800,
+            message: "Request cancelled".to_string(),
+            data: None,
+        },
+    })
+}
+
+/// The response to a batch element that isn't a valid request, or to an empty batch.
+fn invalid_request() -> MCPResponse {
+    MCPResponse::Error {
+        jsonrpc: "2.0".to_string(),
+        id: Some(Value::Null),
+        error: MCPError {
+            code: -32600,
+            message: "Invalid Request".to_string(),
+            data: None,
+        },
+    }
+}
+
+/// Re-serialize the JSON text content of a tool result without pretty-printing.
+fn compact_tool_result(result: &mut ToolResult) {
+    for item in &mut result.content {
+        let Ok(value) = serde_json::from_str::<serde_json::Value>(&item.text) else {
+            continue;
+        };
+        if let Ok(text) = serde_json::to_string(&value) {
+            item.text = text;
+        }
+    }
+}
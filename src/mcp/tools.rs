@@ -12,14 +12,63 @@ pub fn get_tools() -> Vec<ToolDefinition> {
                 "properties": {
                     "file_path": { "type": "string", "description": "Path to the Rust file" },
                     "line": { "type": "number", "description": "Line number (0-based)" },
-                    "character": { "type": "number", "description": "Character position (0-based)" }
+                    "character": { "type": "number", "description": "Character position (0-based)" },
+                    "format": { "type": "string", "enum": ["markdown", "text"], "description": "`markdown` for `{markdown, range}`, `text` for `{text, range}` with the markdown stripped (default: markdown)" },
+                    "raw": { "type": "boolean", "description": "Return the LSP hover as rust-analyzer sent it instead, ignoring `format` (default: false)" },
+                    "end_line": { "type": "number", "description": "End line (0-based) of an expression starting at `line` and `character`, to get its type, e.g. that of a whole chained call" },
+                    "end_character": { "type": "number", "description": "End character position (0-based, exclusive) of the expression" }
                 },
                 "required": ["file_path", "line", "character"]
             }),
         },
+        ToolDefinition {
+            name: "rust_analyzer_goto_symbol".to_string(),
+            description: "Find a symbol in the workspace by its exact name and get its location \
+                          and hover information"
+                .to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string", "description": "Exact name of the symbol (e.g. a type name)" }
+                },
+                "required": ["name"]
+            }),
+        },
+        ToolDefinition {
+            name: "rust_analyzer_workspace_symbols".to_string(),
+            description: "Search the symbols of the workspace by name, or by regex with a query \
+                          like `/^test_/`"
+                .to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string", "description": "Text to search for, matched by rust-analyzer, or a regex between slashes (e.g. `/Trait$/`)" },
+                    "offset": { "type": "number", "description": "Number of symbols to skip, to page through them (default: 0)" },
+                    "limit": { "type": "number", "description": "Maximum number of symbols to return (default: all)" }
+                },
+                "required": ["query"]
+            }),
+        },
         ToolDefinition {
             name: "rust_analyzer_definition".to_string(),
             description: "Go to definition of a symbol at a specific position".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "file_path": { "type": "string", "description": "Path to the Rust file" },
+                    "line": { "type": "number", "description": "Line number (0-based)" },
+                    "character": { "type": "number", "description": "Character position (0-based)" },
+                    "include_source": { "type": "boolean", "description": "Include the source lines around each definition in `source_snippet`, e.g. to see the signature of a dependency's item (default: false)" },
+                    "include_snippet": { "type": "boolean", "description": "Deprecated alias of include_source" }
+                },
+                "required": ["file_path", "line", "character"]
+            }),
+        },
+        ToolDefinition {
+            name: "rust_analyzer_declaration".to_string(),
+            description: "Go to declaration of a symbol at a specific position (differs from \
+                          definition for re-exports and extern items)"
+                .to_string(),
             input_schema: json!({
                 "type": "object",
                 "properties": {
@@ -38,11 +87,77 @@ pub fn get_tools() -> Vec<ToolDefinition> {
                 "properties": {
                     "file_path": { "type": "string", "description": "Path to the Rust file" },
                     "line": { "type": "number", "description": "Line number (0-based)" },
-                    "character": { "type": "number", "description": "Character position (0-based)" }
+                    "character": { "type": "number", "description": "Character position (0-based)" },
+                    "context_lines": { "type": "number", "description": "Include the source of each reference in a `snippet`, with this many lines before and after it (default: 0, no snippet)" },
+                    "include_declaration": { "type": "boolean", "description": "Include the declaration of the symbol among its references (default: true)" },
+                    "offset": { "type": "number", "description": "Number of references to skip, to page through them (default: 0)" },
+                    "limit": { "type": "number", "description": "Maximum number of references to return (default: all)" }
+                },
+                "required": ["file_path", "line", "character"]
+            }),
+        },
+        ToolDefinition {
+            name: "rust_analyzer_parent_module".to_string(),
+            description: "Find where a file's module is declared (its `mod` declaration in the \
+                          parent module), or that the file is a crate root"
+                .to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "file_path": { "type": "string", "description": "Path to the Rust file" },
+                    "line": { "type": "number", "description": "Line number (0-based) within an inline module to find its declaration instead (default: 0)" },
+                    "character": { "type": "number", "description": "Character position (0-based) (default: 0)" }
+                },
+                "required": ["file_path"]
+            }),
+        },
+        ToolDefinition {
+            name: "rust_analyzer_child_modules".to_string(),
+            description: "List the modules declared in a file, with their `mod` declaration and \
+                          the file backing them, to get an overview of a crate's module tree"
+                .to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "file_path": { "type": "string", "description": "Path to the Rust file" },
+                    "line": { "type": "number", "description": "Line number (0-based) within an inline module to list its children instead (default: 0)" },
+                    "character": { "type": "number", "description": "Character position (0-based) within an inline module (default: 0)" }
+                },
+                "required": ["file_path"]
+            }),
+        },
+        ToolDefinition {
+            name: "rust_analyzer_impls_for_type".to_string(),
+            description: "List every impl block (inherent and trait) of the type at a position, \
+                          with the trait name and location"
+                .to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "file_path": { "type": "string", "description": "Path to the Rust file" },
+                    "line": { "type": "number", "description": "Line number of the type name (0-based)" },
+                    "character": { "type": "number", "description": "Character position of the type name (0-based)" }
                 },
                 "required": ["file_path", "line", "character"]
             }),
         },
+        ToolDefinition {
+            name: "rust_analyzer_hover_range".to_string(),
+            description: "Get hover information for every symbol declared in a range of a Rust \
+                          file"
+                .to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "file_path": { "type": "string", "description": "Path to the Rust file" },
+                    "line": { "type": "number", "description": "Start line number (0-based)" },
+                    "character": { "type": "number", "description": "Start character position (0-based)" },
+                    "end_line": { "type": "number", "description": "End line number (0-based)" },
+                    "end_character": { "type": "number", "description": "End character position (0-based)" }
+                },
+                "required": ["file_path", "line", "character", "end_line", "end_character"]
+            }),
+        },
         ToolDefinition {
             name: "rust_analyzer_completion".to_string(),
             description: "Get code completion suggestions at a specific position".to_string(),
@@ -51,11 +166,55 @@ pub fn get_tools() -> Vec<ToolDefinition> {
                 "properties": {
                     "file_path": { "type": "string", "description": "Path to the Rust file" },
                     "line": { "type": "number", "description": "Line number (0-based)" },
-                    "character": { "type": "number", "description": "Character position (0-based)" }
+                    "character": { "type": "number", "description": "Character position (0-based)" },
+                    "trigger_character": { "type": "string", "description": "Character that triggered the completion, e.g. `.` or `:` (optional)" },
+                    "max_items": { "type": "number", "description": "Maximum number of items to return (default: 50)" },
+                    "full": { "type": "boolean", "description": "Keep the `data` and `additionalTextEdits` fields of the items (default: false)" },
+                    "structured": { "type": "boolean", "description": "Return `{is_incomplete, items}` with only the label, kind name, detail, plain-text documentation, insert text and sort text of each item (default: false)" },
+                    "resolve_top_n": { "type": "number", "description": "Number of top items to resolve with their documentation and import edits (default: 10)" }
                 },
                 "required": ["file_path", "line", "character"]
             }),
         },
+        ToolDefinition {
+            name: "rust_analyzer_completion_resolve".to_string(),
+            description: "Resolve a completion item to get its documentation and the edits adding the imports it needs"
+                .to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "item": { "type": "object", "description": "A completion item returned by rust_analyzer_completion with `full` set, including its `data`" }
+                },
+                "required": ["item"]
+            }),
+        },
+        ToolDefinition {
+            name: "rust_analyzer_code_lens".to_string(),
+            description: "Get the code lenses of a Rust file: the tests and binaries that can be \
+                          run, and the number of implementations of traits and types"
+                .to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "file_path": { "type": "string", "description": "Path to the Rust file" },
+                    "kind": { "type": "string", "enum": ["test", "run", "debug", "implementation", "reference"], "description": "Only return the lenses of this kind (default: all)" }
+                },
+                "required": ["file_path"]
+            }),
+        },
+        ToolDefinition {
+            name: "rust_analyzer_semantic_tokens".to_string(),
+            description: "Get the semantic tokens of a Rust file: the kind of every identifier and \
+                          keyword (e.g. a mutable variable, a macro or an unsafe call)"
+                .to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "file_path": { "type": "string", "description": "Path to the Rust file" }
+                },
+                "required": ["file_path"]
+            }),
+        },
         ToolDefinition {
             name: "rust_analyzer_symbols".to_string(),
             description: "Get document symbols (functions, structs, etc.) for a Rust file"
@@ -63,22 +222,46 @@ pub fn get_tools() -> Vec<ToolDefinition> {
             input_schema: json!({
                 "type": "object",
                 "properties": {
-                    "file_path": { "type": "string", "description": "Path to the Rust file" }
+                    "file_path": { "type": "string", "description": "Path to the Rust file" },
+                    "kinds": { "type": "array", "items": { "type": "string" }, "description": "Only return symbols of these kinds, e.g. [\"struct\", \"enum\", \"trait\"]" }
                 },
                 "required": ["file_path"]
             }),
         },
         ToolDefinition {
             name: "rust_analyzer_format".to_string(),
-            description: "Format a Rust file using rust-analyzer".to_string(),
+            description: "Format a Rust file, or only a range of it, using rust-analyzer"
+                .to_string(),
             input_schema: json!({
                 "type": "object",
                 "properties": {
-                    "file_path": { "type": "string", "description": "Path to the Rust file" }
+                    "file_path": { "type": "string", "description": "Path to the Rust file" },
+                    "start_line": { "type": "number", "description": "Start line of the range to format (0-based, optional)" },
+                    "start_character": { "type": "number", "description": "Start character of the range to format (0-based, optional)" },
+                    "end_line": { "type": "number", "description": "End line of the range to format (0-based, optional)" },
+                    "end_character": { "type": "number", "description": "End character of the range to format (0-based, optional)" },
+                    "apply": { "type": "boolean", "description": "Write the formatted content to disk instead of returning the edits (default: false)" }
                 },
                 "required": ["file_path"]
             }),
         },
+        ToolDefinition {
+            name: "rust_analyzer_format_range".to_string(),
+            description: "Format only a range of a Rust file, e.g. the code just edited, and get \
+                          the resulting edits"
+                .to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "file_path": { "type": "string", "description": "Path to the Rust file" },
+                    "start_line": { "type": "number", "description": "Start line of the range to format (0-based)" },
+                    "start_character": { "type": "number", "description": "Start character of the range to format (0-based)" },
+                    "end_line": { "type": "number", "description": "End line of the range to format (0-based)" },
+                    "end_character": { "type": "number", "description": "End character of the range to format (0-based)" }
+                },
+                "required": ["file_path", "start_line", "start_character", "end_line", "end_character"]
+            }),
+        },
         ToolDefinition {
             name: "rust_analyzer_code_actions".to_string(),
             description: "Get available code actions for a range in a Rust file".to_string(),
@@ -94,6 +277,169 @@ pub fn get_tools() -> Vec<ToolDefinition> {
                 "required": ["file_path", "line", "character", "end_line", "end_character"]
             }),
         },
+        ToolDefinition {
+            name: "rust_analyzer_move_item".to_string(),
+            description: "Compute the edits to move the item (function, enum variant, field, ...) \
+                          in a range above or below its sibling"
+                .to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "file_path": { "type": "string", "description": "Path to the Rust file" },
+                    "line": { "type": "number", "description": "Start line number (0-based)" },
+                    "character": { "type": "number", "description": "Start character position (0-based)" },
+                    "end_line": { "type": "number", "description": "End line number (0-based)" },
+                    "end_character": { "type": "number", "description": "End character position (0-based)" },
+                    "direction": { "type": "string", "enum": ["up", "down"], "description": "Direction to move the item in" }
+                },
+                "required": ["file_path", "line", "character", "end_line", "end_character", "direction"]
+            }),
+        },
+        ToolDefinition {
+            name: "rust_analyzer_ssr".to_string(),
+            description: "Structural search and replace (e.g. `foo($a, $b) ==>> bar($b, $a)`), \
+                          returning the resulting edits grouped per file"
+                .to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "file_path": { "type": "string", "description": "Path to the Rust file providing the resolution context" },
+                    "line": { "type": "number", "description": "Line number of the context position (0-based)" },
+                    "character": { "type": "number", "description": "Character position of the context position (0-based)" },
+                    "query": { "type": "string", "description": "SSR rule in the form `search ==>> replacement`" },
+                    "parse_only": { "type": "boolean", "description": "Only validate the query without computing edits (default: false)" },
+                    "selections": {
+                        "type": "array",
+                        "description": "Restrict replacements to these ranges",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "line": { "type": "number" },
+                                "character": { "type": "number" },
+                                "end_line": { "type": "number" },
+                                "end_character": { "type": "number" }
+                            },
+                            "required": ["line", "character", "end_line", "end_character"]
+                        }
+                    }
+                },
+                "required": ["file_path", "line", "character", "query"]
+            }),
+        },
+        ToolDefinition {
+            name: "rust_analyzer_rename".to_string(),
+            description: "Rename the symbol at a position everywhere it's used, optionally \
+                          applying the edits to the files"
+                .to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "file_path": { "type": "string", "description": "Path to the Rust file" },
+                    "line": { "type": "number", "description": "Line number (0-based)" },
+                    "character": { "type": "number", "description": "Character position (0-based)" },
+                    "new_name": { "type": "string", "description": "New name of the symbol" },
+                    "apply": { "type": "boolean", "description": "Write the edits to the files instead of returning them (default: false)" }
+                },
+                "required": ["file_path", "line", "character", "new_name"]
+            }),
+        },
+        ToolDefinition {
+            name: "rust_analyzer_apply_edits".to_string(),
+            description: "Apply a WorkspaceEdit, as returned by the other tools, to the files and \
+                          notify rust-analyzer"
+                .to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "changes": {
+                        "type": "object",
+                        "description": "Text edits per file URI or path relative to the workspace root"
+                    },
+                    "documentChanges": {
+                        "type": "array",
+                        "description": "Text edits per versioned document, used instead of `changes` if given"
+                    }
+                }
+            }),
+        },
+        ToolDefinition {
+            name: "rust_analyzer_create_file".to_string(),
+            description: "Create a new Rust source file and notify rust-analyzer about it"
+                .to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "file_path": { "type": "string", "description": "Path of the new file, relative to the workspace root" },
+                    "content": { "type": "string", "description": "Initial file content (default: a module doc comment)" },
+                    "open_document": { "type": "boolean", "description": "Open the new file in rust-analyzer (default: false)" }
+                },
+                "required": ["file_path"]
+            }),
+        },
+        ToolDefinition {
+            name: "rust_analyzer_rename_file".to_string(),
+            description: "Rename or move a file, computing the edits needed to keep module declarations and paths valid"
+                .to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "old_path": { "type": "string", "description": "Current path of the file, relative to the workspace root" },
+                    "new_path": { "type": "string", "description": "New path of the file, relative to the workspace root" },
+                    "apply": { "type": "boolean", "description": "Move the file and apply the edits instead of returning them (default: false)" }
+                },
+                "required": ["old_path", "new_path"]
+            }),
+        },
+        ToolDefinition {
+            name: "rust_analyzer_read_file".to_string(),
+            description: "Read a file from the workspace".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "file_path": { "type": "string", "description": "Path to the file, relative to the workspace root" }
+                },
+                "required": ["file_path"]
+            }),
+        },
+        ToolDefinition {
+            name: "rust_analyzer_write_file".to_string(),
+            description: "Write content to a file in the workspace and notify rust-analyzer so \
+                          diagnostics are refreshed"
+                .to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "file_path": { "type": "string", "description": "Path to the file, relative to the workspace root" },
+                    "content": { "type": "string", "description": "The new file content" }
+                },
+                "required": ["file_path", "content"]
+            }),
+        },
+        ToolDefinition {
+            name: "rust_analyzer_diff_file".to_string(),
+            description: "Preview the unified diff between a file and new content without writing \
+                          anything"
+                .to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "file_path": { "type": "string", "description": "Path to the file, relative to the workspace root" },
+                    "new_content": { "type": "string", "description": "The proposed new file content" }
+                },
+                "required": ["file_path", "new_content"]
+            }),
+        },
+        ToolDefinition {
+            name: "rust_analyzer_list_files".to_string(),
+            description: "List the Rust source files in the workspace".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "pattern": { "type": "string", "description": "Glob pattern to filter the paths with, relative to the workspace root (e.g. `src/**/*.rs`)" },
+                    "include_test_files": { "type": "boolean", "description": "Whether to include test files (default: true)" }
+                }
+            }),
+        },
         ToolDefinition {
             name: "rust_analyzer_set_workspace".to_string(),
             description: "Set the workspace root directory for rust-analyzer".to_string(),
@@ -112,7 +458,12 @@ pub fn get_tools() -> Vec<ToolDefinition> {
             input_schema: json!({
                 "type": "object",
                 "properties": {
-                    "file_path": { "type": "string", "description": "Path to the Rust file" }
+                    "file_path": { "type": "string", "description": "Path to the Rust file" },
+                    "wait_ms": { "type": "number", "description": "Maximum time to wait for diagnostics to be published, in milliseconds (default: 3000)" },
+                    "with_snippets": { "type": "boolean", "description": "Include the offending source lines, underlined, in a `snippet` field (default: false)" },
+                    "min_severity": { "type": "string", "enum": ["error", "warning", "information", "hint"], "description": "Only list diagnostics at least this severe (default: all)" },
+                    "include_rendered": { "type": "boolean", "description": "Include rustc's rendering of the diagnostics from cargo check, with their notes and suggestions, in a `rendered` field (default: only for errors and warnings)" },
+                    "format": { "type": "string", "enum": ["compact", "rustc"], "description": "Output format: `compact` or `rustc` for rustc's JSON diagnostics, one per line (default: compact)" }
                 },
                 "required": ["file_path"]
             }),
@@ -120,10 +471,221 @@ pub fn get_tools() -> Vec<ToolDefinition> {
         ToolDefinition {
             name: "rust_analyzer_workspace_diagnostics".to_string(),
             description: "Get all compiler diagnostics across the entire workspace".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "min_severity": { "type": "string", "enum": ["error", "warning", "information", "hint"], "description": "Only list diagnostics at least this severe (default: all)" },
+                    "format": { "type": "string", "enum": ["compact", "sarif"], "description": "Output format: `compact` or `sarif` for a SARIF 2.1.0 log, e.g. for GitHub code scanning (default: compact)" },
+                    "group_by": { "type": "string", "enum": ["code", "file", "severity"], "description": "Summarize the diagnostics in groups with their count and a few examples rather than listing them all, e.g. by `code` for `42 × dead_code` (default: no grouping)" }
+                }
+            }),
+        },
+        ToolDefinition {
+            name: "rust_analyzer_index_workspace".to_string(),
+            description: "Open all the source files of the workspace to get their diagnostics, \
+                          including files never opened before"
+                .to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "globs": { "type": "array", "items": { "type": "string" }, "description": "Globs of the files to open, relative to the workspace (default: [\"src/**/*.rs\", \"tests/**/*.rs\"])" },
+                    "min_severity": { "type": "string", "enum": ["error", "warning", "information", "hint"], "description": "Only list diagnostics at least this severe (default: all)" }
+                }
+            }),
+        },
+        ToolDefinition {
+            name: "rust_analyzer_explain_diagnostic".to_string(),
+            description: "Explain a diagnostic code in depth: rustc's long-form explanation of an \
+                          error code, or the documentation of a clippy lint"
+                .to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "code": { "type": "string", "description": "The `code` of a diagnostic, e.g. `E0308` or `clippy::needless_return`" }
+                },
+                "required": ["code"]
+            }),
+        },
+        ToolDefinition {
+            name: "rust_analyzer_workspace_errors".to_string(),
+            description: "Get the first errors across the workspace, sorted by file and line, to \
+                          decide where to start fixing"
+                .to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "limit": { "type": "number", "description": "Maximum number of errors to return (default: 20)" }
+                }
+            }),
+        },
+        ToolDefinition {
+            name: "rust_analyzer_status".to_string(),
+            description: "Get rust-analyzer's indexing and cargo check progress, to know when \
+                          diagnostics are ready. Call this with `detailed` when other tools return \
+                          null, to tell whether rust-analyzer is still indexing or failed to load \
+                          the workspace"
+                .to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "detailed": { "type": "boolean", "description": "Include rust-analyzer's own status report: the loaded workspaces, the state of its virtual file system and of the build scripts (default: false)" },
+                    "file_path": { "type": "string", "description": "Scope the detailed report to the crates of this file, implying `detailed`" }
+                }
+            }),
+        },
+        ToolDefinition {
+            name: "rust_analyzer_server_status".to_string(),
+            description: "Get rust-analyzer's analysis status: loading, ready or invalid (the \
+                          workspace failed to load)"
+                .to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {}
+            }),
+        },
+        ToolDefinition {
+            name: "rust_analyzer_wait_for_analysis".to_string(),
+            description: "Wait until rust-analyzer has loaded the workspace and finished indexing \
+                          it and running cargo check, so that other tools return complete results"
+                .to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "timeout_ms": { "type": "number", "description": "Maximum time to wait, in milliseconds (default: 60000)" }
+                }
+            }),
+        },
+        ToolDefinition {
+            name: "rust_analyzer_run_check".to_string(),
+            description: "Run cargo check on the workspace now and wait for it to finish, \
+                          returning the number of errors and warnings per file"
+                .to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "cancel": { "type": "boolean", "description": "Cancel the running check instead of starting one (default: false)" },
+                    "timeout_ms": { "type": "number", "description": "Maximum time to wait for the check, in milliseconds (default: 60000)" }
+                }
+            }),
+        },
+        ToolDefinition {
+            name: "rust_analyzer_health_check".to_string(),
+            description: "Check whether rust-analyzer is running and ready, without starting it"
+                .to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {}
+            }),
+        },
+        ToolDefinition {
+            name: "rust_analyzer_memory_usage".to_string(),
+            description: "Get rust-analyzer's memory usage, broken down per query".to_string(),
             input_schema: json!({
                 "type": "object",
                 "properties": {}
             }),
         },
+        ToolDefinition {
+            name: "rust_analyzer_open_documents".to_string(),
+            description: "List the documents open in rust-analyzer with their error and warning \
+                          counts"
+                .to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {}
+            }),
+        },
+        ToolDefinition {
+            name: "rust_analyzer_close_document".to_string(),
+            description: "Close a document in rust-analyzer, freeing its memory and dropping its \
+                          cached diagnostics"
+                .to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "file_path": { "type": "string", "description": "Path to the Rust file" }
+                },
+                "required": ["file_path"]
+            }),
+        },
+        ToolDefinition {
+            name: "rust_analyzer_workspace_info".to_string(),
+            description: "Get an overview of the workspace: its crates with their version, edition \
+                          and dependencies, and the pinned toolchain"
+                .to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {}
+            }),
+        },
+        ToolDefinition {
+            name: "rust_analyzer_workspace_members".to_string(),
+            description: "List the crates of the Cargo workspace with their version, edition and \
+                          path, without waiting for rust-analyzer"
+                .to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {}
+            }),
+        },
+        ToolDefinition {
+            name: "rust_analyzer_version".to_string(),
+            description: "Get the version of the rust-analyzer used by the server".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {}
+            }),
+        },
+        ToolDefinition {
+            name: "rust_analyzer_reload_workspace".to_string(),
+            description: "Reload the workspace (e.g. after editing Cargo.toml) and wait for \
+                          rust-analyzer to finish indexing it"
+                .to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {}
+            }),
+        },
+        ToolDefinition {
+            name: "rust_analyzer_set_check_command".to_string(),
+            description: "Change the cargo command rust-analyzer runs on save to produce \
+                          diagnostics, without restarting it"
+                .to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "command": {
+                        "type": "string",
+                        "enum": ["check", "clippy", "none"],
+                        "description": "The command to run: check, clippy or none to disable checking"
+                    }
+                },
+                "required": ["command"]
+            }),
+        },
+        ToolDefinition {
+            name: "rust_analyzer_batch".to_string(),
+            description: "Run several tool calls in one request, e.g. hover, definition and \
+                          references at the same position"
+                .to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "calls": {
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "tool": { "type": "string", "description": "Name of the tool to call" },
+                                "arguments": { "type": "object", "description": "Arguments of the tool" }
+                            },
+                            "required": ["tool"]
+                        },
+                        "description": "The tool calls to run, in order"
+                    }
+                },
+                "required": ["calls"]
+            }),
+        },
     ]
 }
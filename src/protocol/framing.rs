@@ -0,0 +1,105 @@
+use std::io;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt};
+
+use crate::config::MAX_MESSAGE_SIZE;
+
+const CONTENT_LENGTH_HEADER: &str = "Content-Length:";
+
+/// How JSON-RPC messages are delimited on stdio.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Framing {
+    /// One message per line.
+    #[default]
+    NewlineDelimited,
+    /// Each message preceded by LSP-style `Content-Length` headers.
+    ContentLength,
+}
+
+impl Framing {
+    /// Frame `message` to be written out.
+    pub fn frame(self, message: &str) -> String {
+        match self {
+            Self::NewlineDelimited => format!("{}\n", message),
+            Self::ContentLength => format!("Content-Length: {}\r\n\r\n{}", message.len(), message),
+        }
+    }
+}
+
+/// Read the next JSON-RPC message sent by the MCP client from `reader`, without its framing.
+///
+/// Messages are usually newline-delimited, but some clients frame them with LSP-style
+/// `Content-Length` headers instead, so both are accepted, telling them apart by the first line of
+/// each message. Blank lines between messages are skipped. Returns `None` at the end of the input.
+///
+/// A `Content-Length` above [`MAX_MESSAGE_SIZE`] is rejected with [`io::ErrorKind::InvalidData`].
+pub async fn read_message<R: AsyncBufRead + Unpin>(reader: &mut R) -> io::Result<Option<String>> {
+    Ok(read_framed_message(reader)
+        .await?
+        .map(|(message, _)| message))
+}
+
+/// Like [`read_message`], also returning the framing of the message.
+pub async fn read_framed_message<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+) -> io::Result<Option<(String, Framing)>> {
+    let mut line = String::new();
+    let length = loop {
+        line.clear();
+        if reader.read_line(&mut line).await? == 0 {
+            return Ok(None);
+        }
+
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        match parse_content_length(trimmed) {
+            Some(length) => break length?,
+            None => return Ok(Some((trimmed.to_string(), Framing::NewlineDelimited))),
+        }
+    };
+
+    // Skip the other headers, e.g. `Content-Type`, up to the empty line ending them.
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).await? == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        if line.trim().is_empty() {
+            break;
+        }
+    }
+
+    if length > MAX_MESSAGE_SIZE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "Content-Length {} is above the limit of {} bytes",
+                length, MAX_MESSAGE_SIZE
+            ),
+        ));
+    }
+
+    let mut content = vec![0; length];
+    reader.read_exact(&mut content).await?;
+
+    String::from_utf8(content)
+        .map(|content| Some((content, Framing::ContentLength)))
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// The length given by `line` if it's a `Content-Length` header, whose name is case-insensitive.
+fn parse_content_length(line: &str) -> Option<io::Result<usize>> {
+    let name = line.get(..CONTENT_LENGTH_HEADER.len())?;
+    if !name.eq_ignore_ascii_case(CONTENT_LENGTH_HEADER) {
+        return None;
+    }
+
+    let value = line[CONTENT_LENGTH_HEADER.len()..].trim();
+    Some(value.parse().map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Invalid Content-Length `{}`: {}", value, e),
+        )
+    }))
+}
@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{json, Value};
+use std::fmt;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct MCPRequest {
@@ -33,6 +34,137 @@ pub struct MCPError {
     pub data: Option<Value>,
 }
 
+impl MCPError {
+    /// The error of a failed tool call, categorized from the [`ToolError`] it carries, if any.
+    ///
+    /// `data` holds the `kind` of the error, the `path` involved (if any) and a `hint` on what to
+    /// do about it.
+    pub fn from_tool_error(error: &anyhow::Error) -> Self {
+        let tool_error = error.chain().find_map(|e| e.downcast_ref::<ToolError>());
+        let kind = match tool_error {
+            Some(tool_error) => tool_error.kind,
+            None => {
+                let not_found = error
+                    .chain()
+                    .filter_map(|e| e.downcast_ref::<std::io::Error>())
+                    .any(|e| e.kind() == std::io::ErrorKind::NotFound);
+                if not_found {
+                    ErrorKind::FileNotFound
+                } else {
+                    ErrorKind::InternalError
+                }
+            }
+        };
+
+        let mut data = json!({
+            "kind": kind,
+            "hint": kind.hint()
+        });
+        if let Some(path) = tool_error.and_then(|e| e.path.as_ref()) {
+            data["path"] = json!(path);
+        }
+
+        Self {
+            code: kind.code(),
+            message: error.to_string(),
+            data: Some(data),
+        }
+    }
+}
+
+/// The category of a failed tool call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ErrorKind {
+    FileNotFound,
+    PathEscapesWorkspace,
+    LspTimeout,
+    LspRequestFailed,
+    RustAnalyzerCrashed,
+    InvalidArguments,
+    InternalError,
+}
+
+impl ErrorKind {
+    /// The JSON-RPC error code of the category.
+    ///
+    /// Invalid arguments and internal errors use the codes reserved for them by JSON-RPC, the
+    /// others are in the range reserved for implementation-defined server errors.
+    pub fn code(self) -> i32 {
+        match self {
+            Self::FileNotFound => -32001,
+            Self::PathEscapesWorkspace => -32002,
+            Self::LspTimeout => -32003,
+            Self::LspRequestFailed => -32004,
+            Self::RustAnalyzerCrashed => -32005,
+            Self::InvalidArguments => -32602,
+            Self::InternalError => -32603,
+        }
+    }
+
+    /// What to do about an error of the category.
+    pub fn hint(self) -> &'static str {
+        match self {
+            Self::FileNotFound => {
+                "Check the path, relative paths are resolved against the workspace"
+            }
+            Self::PathEscapesWorkspace => "Only files inside the workspace can be accessed",
+            Self::LspTimeout => {
+                "rust-analyzer may still be indexing, check rust_analyzer_status and retry"
+            }
+            Self::LspRequestFailed => "Retry the request",
+            Self::RustAnalyzerCrashed => {
+                "rust-analyzer is restarted on the next tool call, retry the request"
+            }
+            Self::InvalidArguments => "Check the arguments against the tool's input schema",
+            Self::InternalError => "Check the server logs for details",
+        }
+    }
+}
+
+/// A tool call error of a known category, carried by the [`anyhow::Error`] a tool call fails
+/// with.
+#[derive(Debug)]
+pub struct ToolError {
+    pub kind: ErrorKind,
+    pub message: String,
+    /// The file the error is about, if any.
+    pub path: Option<String>,
+}
+
+impl ToolError {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+            path: None,
+        }
+    }
+
+    pub fn with_path(mut self, path: impl Into<String>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    /// The error of reading `path` failing with `error`.
+    pub fn file_read(path: &str, error: std::io::Error) -> Self {
+        let kind = if error.kind() == std::io::ErrorKind::NotFound {
+            ErrorKind::FileNotFound
+        } else {
+            ErrorKind::InternalError
+        };
+
+        Self::new(kind, format!("Failed to read file {}: {}", path, error)).with_path(path)
+    }
+}
+
+impl fmt::Display for ToolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for ToolError {}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ToolDefinition {
     pub name: String,
@@ -41,6 +173,20 @@ pub struct ToolDefinition {
     pub input_schema: Value,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PromptDefinition {
+    pub name: String,
+    pub description: String,
+    pub arguments: Vec<PromptArgument>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PromptArgument {
+    pub name: String,
+    pub description: String,
+    pub required: bool,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ToolResult {
     pub content: Vec<ContentItem>,
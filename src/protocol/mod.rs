@@ -1,5 +1,9 @@
+pub mod framing;
 pub mod lsp;
 pub mod mcp;
 
 pub use lsp::{LSPRequest, LSPResponse};
-pub use mcp::{ContentItem, MCPError, MCPRequest, MCPResponse, ToolDefinition, ToolResult};
+pub use mcp::{
+    ContentItem, ErrorKind, MCPError, MCPRequest, MCPResponse, ToolDefinition, ToolError,
+    ToolResult,
+};
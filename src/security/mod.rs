@@ -0,0 +1,82 @@
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+use crate::protocol::mcp::{ErrorKind, ToolError};
+
+/// Resolve `file_path` against `workspace_root`, making sure it doesn't escape it.
+///
+/// Absolute paths are accepted as long as they're inside the workspace. Symlinks are resolved
+/// before the check, so a link pointing outside the workspace is rejected too. The file and its
+/// parent directories don't need to exist, but the part of the path that doesn't exist yet can't
+/// contain `..`.
+pub fn validate_workspace_path(workspace_root: &Path, file_path: &str) -> Result<PathBuf> {
+    let path = workspace_root.join(file_path);
+
+    // Canonicalize the deepest existing ancestor and append the rest to it.
+    let mut existing = path.as_path();
+    let mut missing = Vec::new();
+    let resolved = loop {
+        match existing.canonicalize() {
+            Ok(resolved) => break resolved,
+            Err(_) => {
+                let (Some(parent), Some(name)) = (existing.parent(), existing.file_name()) else {
+                    return Err(ToolError::new(
+                        ErrorKind::InvalidArguments,
+                        format!("Invalid file path: {}", file_path),
+                    )
+                    .with_path(file_path)
+                    .into());
+                };
+                missing.push(name);
+                existing = parent;
+            }
+        }
+    };
+    let resolved = missing
+        .into_iter()
+        .rev()
+        .fold(resolved, |resolved, name| resolved.join(Path::new(name)));
+
+    let workspace_root = workspace_root
+        .canonicalize()
+        .unwrap_or_else(|_| workspace_root.to_path_buf());
+    if !resolved.starts_with(&workspace_root) {
+        return Err(ToolError::new(
+            ErrorKind::PathEscapesWorkspace,
+            format!(
+                "Path escapes the workspace {}: {}",
+                workspace_root.display(),
+                file_path
+            ),
+        )
+        .with_path(file_path)
+        .into());
+    }
+
+    Ok(resolved)
+}
+
+/// Like [`validate_workspace_path`] but for files the server is about to modify on behalf of
+/// rust-analyzer, which also excludes build output under `target/`.
+pub fn validate_edit_path(workspace_root: &Path, file_path: &str) -> Result<PathBuf> {
+    let resolved = validate_workspace_path(workspace_root, file_path)?;
+
+    let workspace_root = workspace_root
+        .canonicalize()
+        .unwrap_or_else(|_| workspace_root.to_path_buf());
+    let in_target = resolved
+        .strip_prefix(&workspace_root)
+        .ok()
+        .and_then(|relative| relative.components().next())
+        .is_some_and(|first| first.as_os_str() == "target");
+    if in_target {
+        return Err(ToolError::new(
+            ErrorKind::InvalidArguments,
+            format!("Refusing to edit build output: {}", file_path),
+        )
+        .with_path(file_path)
+        .into());
+    }
+
+    Ok(resolved)
+}
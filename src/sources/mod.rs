@@ -0,0 +1,158 @@
+use std::{
+    fmt::Write,
+    path::{Component, Path},
+};
+
+/// Number of lines shown before and after the range of a source excerpt.
+const EXCERPT_CONTEXT_LINES: usize = 2;
+
+/// Maximum number of lines in a source excerpt.
+const MAX_EXCERPT_LINES: usize = 12;
+
+/// A crate whose sources live outside of the workspace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExternalCrate {
+    pub name: String,
+    /// The version of crates from a registry.
+    pub version: Option<String>,
+    /// Where the crate comes from: `registry`, `git` or `sysroot` (the standard library).
+    pub source: &'static str,
+    /// The path of the file within the crate, e.g. `src/de/mod.rs`.
+    pub path: String,
+}
+
+/// Figure out which crate a source file outside of the workspace belongs to, from the layout of
+/// the cargo registry (`registry/src/<index>/<name>-<version>/`), of git checkouts
+/// (`git/checkouts/<name>-<hash>/<rev>/`) or of the sysroot
+/// (`lib/rustlib/src/rust/library/<name>/`).
+pub fn external_crate(path: &Path) -> Option<ExternalCrate> {
+    let components: Vec<&str> = path
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => name.to_str(),
+            _ => None,
+        })
+        .collect();
+    let rest = |from: usize| components.get(from..).map(|rest| rest.join("/"));
+
+    for (i, window) in components.windows(2).enumerate() {
+        match window {
+            ["registry", "src"] => {
+                let (name, version) = split_version(components.get(i + 3)?)?;
+                return Some(ExternalCrate {
+                    name: name.to_string(),
+                    version: Some(version.to_string()),
+                    source: "registry",
+                    path: rest(i + 4)?,
+                });
+            }
+            ["git", "checkouts"] => {
+                let (name, _hash) = components.get(i + 2)?.rsplit_once('-')?;
+                return Some(ExternalCrate {
+                    name: name.to_string(),
+                    version: None,
+                    source: "git",
+                    path: rest(i + 4)?,
+                });
+            }
+            ["rustlib", "src"] if components.get(i + 2..i + 4) == Some(&["rust", "library"]) => {
+                return Some(ExternalCrate {
+                    name: components.get(i + 4)?.to_string(),
+                    version: None,
+                    source: "sysroot",
+                    path: rest(i + 5)?,
+                });
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+impl ExternalCrate {
+    /// Why the sources of the crate can't be read, and how to get them.
+    pub fn missing_sources_note(&self) -> String {
+        match self.source {
+            // The sources of the standard library only ship with the rust-src component.
+            "sysroot" => {
+                "rust-src component required (`rustup component add rust-src`)".to_string()
+            }
+            _ => format!(
+                "The sources of {} aren't downloaded, `cargo fetch` downloads them",
+                self.name
+            ),
+        }
+    }
+}
+
+/// Split a registry directory name like `serde-1.0.200` into the crate name and its version.
+///
+/// Crate names can contain dashes and digits too (e.g. `md-5-0.10.6`), so the version is the
+/// longest suffix after a dash that starts with a `major.minor.patch` version.
+fn split_version(dir: &str) -> Option<(&str, &str)> {
+    dir.match_indices('-')
+        .map(|(i, _)| (&dir[..i], &dir[i + 1..]))
+        .find(|(name, version)| !name.is_empty() && is_version(version))
+}
+
+fn is_version(version: &str) -> bool {
+    let core = version.split(['-', '+']).next().unwrap_or_default();
+    let parts: Vec<&str> = core.split('.').collect();
+    parts.len() == 3
+        && parts
+            .iter()
+            .all(|part| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// Show the 0-based lines `start_line` to `end_line` of `content` with a few lines of context
+/// around them, each prefixed with its 1-based number:
+///
+/// ```text
+/// 41 | /// Serialize this value into the given Serde serializer.
+/// 42 | fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+/// 43 | where
+/// ```
+///
+/// Long ranges are cut off. Returns `None` if `start_line` is past the end of `content`.
+pub fn source_excerpt(content: &str, start_line: usize, end_line: usize) -> Option<String> {
+    let first = start_line.saturating_sub(EXCERPT_CONTEXT_LINES);
+    let last =
+        (end_line.max(start_line) + EXCERPT_CONTEXT_LINES).min(first + MAX_EXCERPT_LINES - 1);
+
+    numbered_lines(content, start_line, first, last)
+}
+
+/// Like [`source_excerpt`] but with `context_lines` lines before and after the range, which
+/// isn't cut off.
+pub fn context_excerpt(
+    content: &str,
+    start_line: usize,
+    end_line: usize,
+    context_lines: usize,
+) -> Option<String> {
+    let first = start_line.saturating_sub(context_lines);
+    let last = end_line.max(start_line).saturating_add(context_lines);
+
+    numbered_lines(content, start_line, first, last)
+}
+
+/// The 0-based lines `first` to `last` of `content`, clamped to it, prefixed with their 1-based
+/// numbers. `None` if `start_line` is past the end of `content`.
+fn numbered_lines(content: &str, start_line: usize, first: usize, last: usize) -> Option<String> {
+    let lines: Vec<&str> = content.lines().collect();
+    if start_line >= lines.len() {
+        return None;
+    }
+
+    let last = last.min(lines.len() - 1);
+    let width = (last + 1).to_string().len();
+
+    let mut excerpt = String::new();
+    for (i, line) in lines.iter().enumerate().take(last + 1).skip(first) {
+        let _ = writeln!(excerpt, "{:>width$} | {}", i + 1, line);
+    }
+    excerpt.pop();
+
+    Some(excerpt)
+}
@@ -0,0 +1,293 @@
+use serde_json::{json, Value};
+
+mod query;
+
+pub use query::{MatchQuality, SymbolQuery};
+
+/// Names of the LSP `SymbolKind`s, the kind number being the index plus one.
+const SYMBOL_KINDS: [&str; 26] = [
+    "file",
+    "module",
+    "namespace",
+    "package",
+    "class",
+    "method",
+    "property",
+    "field",
+    "constructor",
+    "enum",
+    "interface",
+    "function",
+    "variable",
+    "constant",
+    "string",
+    "number",
+    "boolean",
+    "array",
+    "object",
+    "key",
+    "null",
+    "enum_member",
+    "struct",
+    "event",
+    "operator",
+    "type_parameter",
+];
+
+/// The name of an LSP `SymbolKind`, e.g. `struct` for 23.
+pub fn symbol_kind_name(kind: u64) -> Option<&'static str> {
+    let index = usize::try_from(kind).ok()?.checked_sub(1)?;
+    SYMBOL_KINDS.get(index).copied()
+}
+
+/// Parse a symbol kind name, as returned by [`symbol_kind_name`], into an LSP `SymbolKind`.
+///
+/// `trait` is accepted too, rust-analyzer reporting traits as interfaces.
+pub fn parse_symbol_kind(name: &str) -> Option<u64> {
+    if name == "trait" {
+        return parse_symbol_kind("interface");
+    }
+
+    SYMBOL_KINDS
+        .iter()
+        .position(|kind| *kind == name)
+        .map(|index| index as u64 + 1)
+}
+
+/// Replace the LSP `SymbolKind` numbers of symbols by their names, e.g. `"kind": "struct"` rather
+/// than `"kind": 23`, in `DocumentSymbol` trees as well as in flat lists.
+///
+/// Kinds without a name, from a newer LSP version, are left as numbers.
+pub fn name_symbol_kinds(symbols: &mut Value) {
+    let Some(symbols) = symbols.as_array_mut() else {
+        return;
+    };
+
+    for symbol in symbols {
+        if let Some(name) = symbol["kind"].as_u64().and_then(symbol_kind_name) {
+            symbol["kind"] = json!(name);
+        }
+        if let Some(children) = symbol.get_mut("children") {
+            name_symbol_kinds(children);
+        }
+    }
+}
+
+/// Keep only the symbols of the given LSP `SymbolKind`s.
+///
+/// Works on flat lists as well as on `DocumentSymbol` trees, where a symbol of another kind is
+/// kept if any of its descendants is, with only its matching descendants as children.
+pub fn filter_symbols_by_kind(symbols: &Value, kinds: &[u64]) -> Value {
+    let Some(symbols) = symbols.as_array() else {
+        return symbols.clone();
+    };
+
+    let filtered: Vec<Value> = symbols
+        .iter()
+        .filter_map(|symbol| {
+            let mut symbol = symbol.clone();
+            let has_children = symbol
+                .get("children")
+                .is_some_and(|children| children.is_array());
+            if has_children {
+                symbol["children"] = filter_symbols_by_kind(&symbol["children"], kinds);
+            }
+
+            let matches = symbol["kind"]
+                .as_u64()
+                .is_some_and(|kind| kinds.contains(&kind));
+            let has_matching_children =
+                has_children && symbol["children"].as_array().is_some_and(|c| !c.is_empty());
+            (matches || has_matching_children).then_some(symbol)
+        })
+        .collect();
+
+    json!(filtered)
+}
+
+/// Flatten a `textDocument/documentSymbol` response into a list of symbols with their paths.
+///
+/// Each entry has the symbol's `name`, `kind`, 0-based `line` and `character`, its `container` path
+/// (if any) and its full `path` (e.g. `Calculator::add`). Both the hierarchical `DocumentSymbol`
+/// and the flat `SymbolInformation` forms of the response are supported. Impl blocks don't add
+/// their own segment to the path; their items are listed under the implementing type instead.
+pub fn flatten_symbols(symbols: &Value) -> Vec<Value> {
+    let mut flat = Vec::new();
+    let Some(symbols) = symbols.as_array() else {
+        return flat;
+    };
+
+    for symbol in symbols {
+        if symbol.get("location").is_some() {
+            // `SymbolInformation` already is flat, with the parent's name as container.
+            let container = symbol["containerName"].as_str().map(container_segment);
+            push_symbol(&mut flat, symbol, container, &symbol["location"]["range"]);
+        } else {
+            flatten_document_symbol(&mut flat, symbol, None);
+        }
+    }
+
+    flat
+}
+
+fn flatten_document_symbol(flat: &mut Vec<Value>, symbol: &Value, container: Option<String>) {
+    let range = symbol.get("selectionRange").unwrap_or(&symbol["range"]);
+    let name = symbol["name"].as_str().unwrap_or_default();
+
+    let children_container = if is_impl(name) {
+        // Items of an impl belong to the implementing type.
+        Some(join_path(container.as_deref(), &container_segment(name)))
+    } else {
+        push_symbol(flat, symbol, container.clone(), range);
+        Some(join_path(container.as_deref(), name))
+    };
+
+    if let Some(children) = symbol["children"].as_array() {
+        for child in children {
+            flatten_document_symbol(flat, child, children_container.clone());
+        }
+    }
+}
+
+fn push_symbol(flat: &mut Vec<Value>, symbol: &Value, container: Option<String>, range: &Value) {
+    let name = symbol["name"].as_str().unwrap_or_default();
+    if is_impl(name) {
+        return;
+    }
+
+    flat.push(json!({
+        "name": name,
+        "kind": symbol["kind"],
+        "line": range["start"]["line"],
+        "character": range["start"]["character"],
+        "path": join_path(container.as_deref(), name),
+        "container": container
+    }));
+}
+
+fn join_path(container: Option<&str>, name: &str) -> String {
+    match container {
+        Some(container) => format!("{}::{}", container, name),
+        None => name.to_string(),
+    }
+}
+
+fn is_impl(name: &str) -> bool {
+    name == "impl" || name.starts_with("impl ") || name.starts_with("impl<")
+}
+
+/// The path segment a container contributes, i.e. the implementing type for impl blocks.
+fn container_segment(name: &str) -> String {
+    match parse_impl_header(name) {
+        // For trait impls, the items belong to the type the trait is implemented for.
+        Some((_, self_ty)) => self_ty,
+        None => name.to_string(),
+    }
+}
+
+/// Split the header of an impl block, e.g. `impl<T: Clone> Display for Wrapper<T>`, into the
+/// implemented trait, if any, and the implementing type.
+///
+/// The header may span several lines and include a `where` clause and the opening brace of the
+/// body, which are ignored. Returns `None` if `header` isn't the header of an impl block.
+pub fn parse_impl_header(header: &str) -> Option<(Option<String>, String)> {
+    let header = header.split_whitespace().collect::<Vec<_>>().join(" ");
+    let header = header.strip_prefix("unsafe ").unwrap_or(&header);
+    if !is_impl(header) {
+        return None;
+    }
+
+    let mut rest = &header["impl".len()..];
+    // Skip the impl's generic parameters.
+    if rest.starts_with('<') {
+        let mut depth = 0;
+        for (i, c) in rest.char_indices() {
+            match c {
+                '<' => depth += 1,
+                '>' => depth -= 1,
+                _ => {}
+            }
+            if depth == 0 {
+                rest = &rest[i + 1..];
+                break;
+            }
+        }
+    }
+    if let Some(end) = rest.find('{') {
+        rest = &rest[..end];
+    }
+    if let Some(end) = rest.find(" where ") {
+        rest = &rest[..end];
+    }
+    let rest = rest.trim().trim_end_matches(" where");
+
+    let mut depth = 0;
+    for (i, c) in rest.char_indices() {
+        match c {
+            '<' | '(' | '[' => depth += 1,
+            '>' | ')' | ']' => depth -= 1,
+            ' ' if depth == 0 && rest[i..].starts_with(" for ") => {
+                let trait_name = rest[..i].trim().to_string();
+                let self_ty = rest[i + " for ".len()..].trim().to_string();
+                return Some((Some(trait_name), self_ty));
+            }
+            _ => {}
+        }
+    }
+
+    Some((None, rest.to_string()))
+}
+
+/// Find the header of the impl block whose implementing type starts at byte `offset` of
+/// `content`, as `textDocument/implementation` points at it.
+///
+/// The header runs from the `impl` keyword (or `unsafe` before it) to the opening brace of the
+/// body, so it may span several lines.
+pub fn impl_header_at(content: &str, offset: usize) -> Option<&str> {
+    let before = content.get(..offset)?;
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    let (start, _) = before.rmatch_indices("impl").find(|(i, _)| {
+        let preceded = before[..*i].chars().next_back().is_some_and(is_ident);
+        let followed = before[i + "impl".len()..]
+            .chars()
+            .next()
+            .is_some_and(is_ident);
+        !preceded && !followed
+    })?;
+    // The implementing type must be in the header, not in the body of an earlier impl.
+    if before[start..].contains(['{', ';']) {
+        return None;
+    }
+    let start = match before[..start].trim_end().strip_suffix("unsafe") {
+        Some(prefix) => prefix.len(),
+        None => start,
+    };
+
+    let end = content[offset..]
+        .find(['{', ';'])
+        .map_or(content.len(), |i| offset + i);
+
+    Some(content[start..end].trim_end())
+}
+
+/// Find the trait derived at byte range `start..end` of `content` and the type deriving it.
+///
+/// `textDocument/implementation` points at the trait in the `#[derive]` attribute for derived
+/// impls, as there's no impl block.
+pub fn derive_at(content: &str, start: usize, end: usize) -> Option<(String, String)> {
+    let before = content.get(..start)?;
+    let attribute = &before[before.rfind("#[")?..];
+    if !attribute.starts_with("#[derive(") || attribute.contains(']') {
+        return None;
+    }
+    let trait_name = content.get(start..end)?.trim().to_string();
+
+    // The attribute is on the item that follows, possibly after other attributes.
+    let self_type = content[end..]
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|word| !word.is_empty())
+        .skip_while(|word| !matches!(*word, "struct" | "enum" | "union"))
+        .nth(1)?;
+
+    Some((trait_name, self_type.to_string()))
+}
@@ -0,0 +1,91 @@
+use regex::Regex;
+
+/// A query for workspace symbols: text that rust-analyzer matches itself, or a `/regex/` that is
+/// matched here against all the symbols it returns.
+#[derive(Debug, Clone)]
+pub enum SymbolQuery {
+    Text(String),
+    Regex(Regex),
+}
+
+/// How well a symbol name matches a query, best first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MatchQuality {
+    /// The whole name matches.
+    Exact,
+    /// The name starts with the query (case-insensitively for text).
+    Prefix,
+    /// The name contains the query (case-insensitively for text).
+    Contains,
+    /// rust-analyzer's fuzzy matching found the name, or a regex only matches an empty string in
+    /// it, like `/^/`.
+    Fuzzy,
+}
+
+impl SymbolQuery {
+    /// Parse a query, one starting and ending with `/` being a regex.
+    pub fn parse(query: &str) -> Result<Self, regex::Error> {
+        match query
+            .strip_prefix('/')
+            .and_then(|query| query.strip_suffix('/'))
+        {
+            Some(pattern) => Regex::new(pattern).map(Self::Regex),
+            None => Ok(Self::Text(query.to_string())),
+        }
+    }
+
+    /// The query to send with `workspace/symbol`.
+    ///
+    /// Regexes need all the symbols, so they're matched against those found by an empty query.
+    /// `#` makes rust-analyzer search all symbols rather than only types.
+    pub fn lsp_query(&self) -> &str {
+        match self {
+            Self::Text(query) => query,
+            Self::Regex(_) => "#",
+        }
+    }
+
+    /// How well `name` matches the query, `None` if it doesn't match at all.
+    pub fn match_quality(&self, name: &str) -> Option<MatchQuality> {
+        match self {
+            Self::Text(query) => {
+                // rust-analyzer's query syntax: `#` searches all symbols and `*` dependencies too.
+                let query = query.trim_end_matches(['#', '*']);
+                let lowercase_name = name.to_lowercase();
+                let lowercase_query = query.to_lowercase();
+                Some(if name == query {
+                    MatchQuality::Exact
+                } else if lowercase_name.starts_with(&lowercase_query) {
+                    MatchQuality::Prefix
+                } else if lowercase_name.contains(&lowercase_query) {
+                    MatchQuality::Contains
+                } else {
+                    MatchQuality::Fuzzy
+                })
+            }
+            Self::Regex(regex) => {
+                let found = regex.find(name)?;
+                Some(if found.start() == 0 && found.end() == name.len() {
+                    MatchQuality::Exact
+                } else if found.is_empty() {
+                    MatchQuality::Fuzzy
+                } else if found.start() == 0 {
+                    MatchQuality::Prefix
+                } else {
+                    MatchQuality::Contains
+                })
+            }
+        }
+    }
+}
+
+impl MatchQuality {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Exact => "exact",
+            Self::Prefix => "prefix",
+            Self::Contains => "contains",
+            Self::Fuzzy => "fuzzy",
+        }
+    }
+}
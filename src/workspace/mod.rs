@@ -0,0 +1,273 @@
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use toml::Table;
+
+/// Describe the Cargo workspace at `root`: its crates with their version, edition and
+/// dependencies, and the toolchain it's pinned to, if any.
+///
+/// Both single-package projects and workspaces (with glob patterns in `members`) are supported.
+/// Fields inherited from the workspace (`version.workspace = true` and the like) are resolved.
+pub fn workspace_info(root: &Path) -> Result<Value> {
+    let root = root
+        .canonicalize()
+        .map_err(|e| anyhow!("Failed to resolve {}: {}", root.display(), e))?;
+    let manifest = read_manifest(&root.join("Cargo.toml"))?;
+    let workspace = manifest.get("workspace").and_then(|w| w.as_table());
+
+    let mut member_dirs = Vec::new();
+    if manifest.contains_key("package") {
+        member_dirs.push(root.clone());
+    }
+    if let Some(workspace) = workspace {
+        let excluded: Vec<PathBuf> = string_array(workspace.get("exclude"))
+            .map(|path| root.join(path))
+            .collect();
+        for pattern in string_array(workspace.get("members")) {
+            let pattern = root.join(pattern);
+            let paths = glob::glob(&pattern.to_string_lossy())
+                .map_err(|e| anyhow!("Invalid workspace member `{}`: {}", pattern.display(), e))?;
+            for dir in paths.flatten().filter_map(|dir| dir.canonicalize().ok()) {
+                if dir.join("Cargo.toml").is_file()
+                    && !member_dirs.contains(&dir)
+                    && !excluded.iter().any(|excluded| dir.starts_with(excluded))
+                {
+                    member_dirs.push(dir);
+                }
+            }
+        }
+    }
+
+    let members = member_dirs
+        .iter()
+        .map(|dir| member_info(&root, dir, workspace))
+        .collect::<Result<Vec<_>>>()?;
+
+    let rust_edition = manifest
+        .get("package")
+        .and_then(|package| package.as_table())
+        .map(|package| package_field(package, "edition", workspace))
+        .unwrap_or_else(|| workspace_package_field(workspace, "edition"));
+
+    Ok(json!({
+        "workspace_root": root.display().to_string(),
+        "rust_edition": rust_edition,
+        "toolchain": toolchain(&root),
+        "members": members
+    }))
+}
+
+/// List the crates of the Cargo workspace at `root` with their version, edition and path, i.e.
+/// [`workspace_info`] without their dependencies and the toolchain.
+pub fn workspace_members(root: &Path) -> Result<Value> {
+    let info = workspace_info(root)?;
+    let members: Vec<Value> = info["members"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|member| {
+            json!({
+                "name": member["name"],
+                "version": member["version"],
+                "edition": member["edition"],
+                "path": member["path"]
+            })
+        })
+        .collect();
+
+    Ok(json!({
+        "workspace_root": info["workspace_root"],
+        "total": members.len(),
+        "members": members
+    }))
+}
+
+/// Whether `file` is the root of a target of the Cargo package it belongs to, i.e. a file no module
+/// declares: its library (`src/lib.rs` or `[lib] path`), a binary (`src/main.rs`, in `src/bin/`
+/// or a `[[bin]] path`), an example, test or bench (in their directory or at their `path`) or its
+/// build script.
+pub fn is_crate_root(file: &Path) -> bool {
+    // The package is the one of the nearest manifest.
+    let Some(dir) = file
+        .ancestors()
+        .skip(1)
+        .find(|dir| dir.join("Cargo.toml").is_file())
+    else {
+        return false;
+    };
+    let Ok(manifest) = read_manifest(&dir.join("Cargo.toml")) else {
+        return false;
+    };
+    let Some(package) = manifest.get("package").and_then(|p| p.as_table()) else {
+        return false;
+    };
+    let Ok(relative) = file.strip_prefix(dir) else {
+        return false;
+    };
+
+    let declared = |path: &str| Path::new(path.trim_start_matches("./")) == relative;
+    let lib = manifest.get("lib").and_then(|lib| lib.get("path"));
+    let targets = ["bin", "example", "test", "bench"]
+        .iter()
+        .filter_map(|kind| manifest.get(*kind).and_then(|t| t.as_array()))
+        .flatten()
+        .filter_map(|target| target.get("path"));
+    let build = package.get("build");
+    if lib
+        .into_iter()
+        .chain(targets)
+        .chain(build)
+        .filter_map(|path| path.as_str())
+        .any(declared)
+    {
+        return true;
+    }
+
+    // The targets Cargo discovers by their location.
+    let components: Vec<&str> = relative
+        .components()
+        .filter_map(|component| component.as_os_str().to_str())
+        .collect();
+    match components.as_slice() {
+        ["src", "lib.rs" | "main.rs"] | ["build.rs"] => true,
+        ["src", "bin", file] | ["examples" | "tests" | "benches", file] => file.ends_with(".rs"),
+        ["src", "bin", _, "main.rs"] | ["examples" | "tests" | "benches", _, "main.rs"] => true,
+        _ => false,
+    }
+}
+
+fn member_info(root: &Path, dir: &Path, workspace: Option<&Table>) -> Result<Value> {
+    let manifest = read_manifest(&dir.join("Cargo.toml"))?;
+    let Some(package) = manifest.get("package").and_then(|p| p.as_table()) else {
+        return Err(anyhow!(
+            "{} has no [package]",
+            dir.join("Cargo.toml").display()
+        ));
+    };
+
+    let path = dir.strip_prefix(root).unwrap_or(dir);
+    let path = if path.as_os_str().is_empty() {
+        ".".to_string()
+    } else {
+        path.display().to_string()
+    };
+    // Cargo defaults to the 2015 edition when none is given.
+    let edition = package_field(package, "edition", workspace);
+    let edition = if edition.is_null() {
+        json!("2015")
+    } else {
+        edition
+    };
+
+    let workspace_deps = workspace
+        .and_then(|w| w.get("dependencies"))
+        .and_then(|d| d.as_table());
+    let dependencies: Vec<Value> = manifest
+        .get("dependencies")
+        .and_then(|d| d.as_table())
+        .map(|deps| {
+            deps.iter()
+                .map(|(name, spec)| dependency_info(name, spec, workspace_deps))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(json!({
+        "name": package.get("name").and_then(|n| n.as_str()),
+        "version": package_field(package, "version", workspace),
+        "path": path,
+        "edition": edition,
+        "dependencies": dependencies
+    }))
+}
+
+/// Describe a dependency, given either as a version string or as a table. Dependencies inherited
+/// from the workspace get their version from it, and the features of both.
+fn dependency_info(name: &str, spec: &toml::Value, workspace_deps: Option<&Table>) -> Value {
+    let table = match spec {
+        toml::Value::String(version) => {
+            return json!({ "name": name, "version": version, "features": [] })
+        }
+        toml::Value::Table(table) => table,
+        _ => return json!({ "name": name, "version": null, "features": [] }),
+    };
+
+    let mut version = table.get("version").and_then(|v| v.as_str());
+    let mut features: Vec<&str> = string_array(table.get("features")).collect();
+    let inherited = table.get("workspace").and_then(|w| w.as_bool()) == Some(true);
+    if let Some(workspace_spec) = workspace_deps
+        .and_then(|deps| deps.get(name))
+        .filter(|_| inherited)
+    {
+        match workspace_spec {
+            toml::Value::String(workspace_version) => version = Some(workspace_version),
+            toml::Value::Table(workspace_table) => {
+                version = workspace_table.get("version").and_then(|v| v.as_str());
+                features.extend(string_array(workspace_table.get("features")));
+            }
+            _ => {}
+        }
+    }
+
+    json!({ "name": name, "version": version, "features": features })
+}
+
+/// Get a `[package]` field, looking it up in `[workspace.package]` if it's inherited.
+fn package_field(package: &Table, field: &str, workspace: Option<&Table>) -> Value {
+    match package.get(field) {
+        Some(toml::Value::String(value)) => json!(value),
+        Some(toml::Value::Table(table))
+            if table.get("workspace").and_then(|w| w.as_bool()) == Some(true) =>
+        {
+            workspace_package_field(workspace, field)
+        }
+        _ => Value::Null,
+    }
+}
+
+fn workspace_package_field(workspace: Option<&Table>, field: &str) -> Value {
+    workspace
+        .and_then(|w| w.get("package"))
+        .and_then(|p| p.get(field))
+        .and_then(|v| v.as_str())
+        .map_or(Value::Null, |value| json!(value))
+}
+
+/// The toolchain the project is pinned to by `rust-toolchain.toml` or the legacy `rust-toolchain`
+/// file, which is either TOML too or just the channel name.
+fn toolchain(root: &Path) -> Value {
+    for file in ["rust-toolchain.toml", "rust-toolchain"] {
+        let Ok(content) = std::fs::read_to_string(root.join(file)) else {
+            continue;
+        };
+
+        let channel = match content.parse::<Table>() {
+            Ok(toml) => toml
+                .get("toolchain")
+                .and_then(|t| t.get("channel"))
+                .and_then(|c| c.as_str())
+                .map(str::to_string),
+            Err(_) => Some(content.trim().to_string()).filter(|c| !c.is_empty()),
+        };
+        return json!(channel);
+    }
+
+    Value::Null
+}
+
+fn read_manifest(path: &Path) -> Result<Table> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| anyhow!("Failed to read {}: {}", path.display(), e))?;
+
+    content
+        .parse()
+        .map_err(|e| anyhow!("Failed to parse {}: {}", path.display(), e))
+}
+
+fn string_array(value: Option<&toml::Value>) -> impl Iterator<Item = &str> {
+    value
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|v| v.as_str())
+}
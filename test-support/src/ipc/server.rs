@@ -77,8 +77,7 @@ pub fn start_server(workspace_path: &Path, project_type: &str) -> Result<()> {
     stdin.write_all(b"\n")?;
     stdin.flush()?;
 
-    let mut line = String::new();
-    stdout.read_line(&mut line)?;
+    let line = read_response(&mut stdout)?;
 
     let response: Value = serde_json::from_str(&line)?;
     if response.get("error").is_some() {
@@ -156,8 +155,7 @@ pub fn start_server(workspace_path: &Path, project_type: &str) -> Result<()> {
                     stdin.flush()?;
 
                     // Read response from rust-analyzer
-                    let mut response_line = String::new();
-                    stdout.read_line(&mut response_line)?;
+                    let response_line = read_response(&mut stdout)?;
 
                     // Forward response to client
                     stream.write_all(response_line.as_bytes())?;
@@ -224,8 +222,7 @@ fn wait_for_ready(
         stdin.write_all(b"\n")?;
         stdin.flush()?;
 
-        let mut line = String::new();
-        stdout.read_line(&mut line)?;
+        let line = read_response(stdout)?;
 
         let response: Value = serde_json::from_str(&line)?;
 
@@ -267,6 +264,22 @@ fn wait_for_ready(
     Ok(())
 }
 
+/// Read the next response line from the MCP server, skipping notifications like `$/progress`.
+fn read_response(stdout: &mut BufReader<std::process::ChildStdout>) -> Result<String> {
+    loop {
+        let mut line = String::new();
+        if stdout.read_line(&mut line)? == 0 {
+            return Err(anyhow::anyhow!("MCP server closed its output"));
+        }
+
+        let is_notification = serde_json::from_str::<Value>(&line)
+            .is_ok_and(|message| message.get("method").is_some() && message.get("id").is_none());
+        if !is_notification {
+            return Ok(line);
+        }
+    }
+}
+
 pub fn socket_path(project_type: &str) -> PathBuf {
     let socket_dir = std::env::temp_dir().join("rust-analyzer-mcp-sockets");
     let _ = fs::create_dir_all(&socket_dir);
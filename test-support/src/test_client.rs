@@ -26,6 +26,8 @@ pub struct MCPTestClient {
     stdout: Mutex<BufReader<tokio::process::ChildStdout>>,
     request_id: AtomicU64,
     shutdown: AtomicBool,
+    /// Notifications received from the server while waiting for responses.
+    notifications: Mutex<Vec<Value>>,
     /// Optional isolated project that will be cleaned up when client is dropped.
     _isolated_project: Option<IsolatedProject>,
 }
@@ -151,6 +153,7 @@ impl MCPTestClient {
             stdout: Mutex::new(stdout),
             request_id: AtomicU64::new(1),
             shutdown: AtomicBool::new(false),
+            notifications: Mutex::new(Vec::new()),
             _isolated_project: isolated_project,
         })
     }
@@ -216,6 +219,7 @@ impl MCPTestClient {
             stdout: Mutex::new(stdout),
             request_id: AtomicU64::new(1),
             shutdown: AtomicBool::new(false),
+            notifications: Mutex::new(Vec::new()),
             _isolated_project: isolated_project,
         })
     }
@@ -237,6 +241,27 @@ impl MCPTestClient {
         Ok(())
     }
 
+    /// The process id of the MCP server, if it's still running.
+    pub async fn server_pid(&self) -> Option<u32> {
+        self.process.lock().await.as_ref().and_then(|p| p.id())
+    }
+
+    /// Kill the rust-analyzer process spawned by the MCP server, simulating a crash.
+    pub async fn kill_rust_analyzer(&self) -> Result<()> {
+        let Some(pid) = self.server_pid().await else {
+            anyhow::bail!("MCP server is not running");
+        };
+
+        let status = std::process::Command::new("pkill")
+            .args(["-KILL", "-P", &pid.to_string(), "rust-analyzer"])
+            .status()?;
+        if !status.success() {
+            anyhow::bail!("No rust-analyzer process found for MCP server {}", pid);
+        }
+
+        Ok(())
+    }
+
     /// Send a request and wait for response with timeout
     pub async fn send_request(&self, method: &str, params: Option<Value>) -> Result<Value> {
         self.send_request_with_timeout(method, params, timeouts::request())
@@ -272,16 +297,9 @@ impl MCPTestClient {
         }
 
         // Read response with timeout
-        let response_line = timeout(timeout_duration, async {
-            let mut line = String::new();
-            let mut stdout = self.stdout.lock().await;
-            stdout.read_line(&mut line).await?;
-            Ok::<String, anyhow::Error>(line)
-        })
-        .await
-        .map_err(|_| anyhow::anyhow!("Request timeout after {:?}", timeout_duration))??;
-
-        let response: Value = serde_json::from_str(&response_line)?;
+        let response = timeout(timeout_duration, self.read_response())
+            .await
+            .map_err(|_| anyhow::anyhow!("Request timeout after {:?}", timeout_duration))??;
 
         // Check for errors
         if let Some(error) = response.get("error") {
@@ -291,6 +309,55 @@ impl MCPTestClient {
         Ok(response.get("result").cloned().unwrap_or(json!(null)))
     }
 
+    /// Send a raw JSON-RPC message, e.g. a batch, and return the raw response.
+    pub async fn send_message(&self, message: &Value) -> Result<Value> {
+        self.write_message(message).await?;
+        self.next_response().await
+    }
+
+    /// Send a raw JSON-RPC message without waiting for a response.
+    pub async fn write_message(&self, message: &Value) -> Result<()> {
+        let message_str = serde_json::to_string(message)?;
+        let mut stdin = self.stdin.lock().await;
+        stdin.write_all(message_str.as_bytes()).await?;
+        stdin.write_all(b"\n").await?;
+        stdin.flush().await?;
+
+        Ok(())
+    }
+
+    /// Wait for the next raw response from the server.
+    pub async fn next_response(&self) -> Result<Value> {
+        let timeout_duration = timeouts::request();
+        timeout(timeout_duration, self.read_response())
+            .await
+            .map_err(|_| anyhow::anyhow!("Request timeout after {:?}", timeout_duration))?
+    }
+
+    /// Read the next response, setting aside the notifications sent before it.
+    async fn read_response(&self) -> Result<Value> {
+        let mut stdout = self.stdout.lock().await;
+        loop {
+            let mut line = String::new();
+            if stdout.read_line(&mut line).await? == 0 {
+                anyhow::bail!("MCP server closed its output");
+            }
+
+            let message: Value = serde_json::from_str(&line)?;
+            if message.get("method").is_some() && message.get("id").is_none() {
+                self.notifications.lock().await.push(message);
+                continue;
+            }
+
+            return Ok(message);
+        }
+    }
+
+    /// The notifications received from the server so far.
+    pub async fn notifications(&self) -> Vec<Value> {
+        self.notifications.lock().await.clone()
+    }
+
     /// Initialize the MCP server
     pub async fn initialize(&self) -> Result<Value> {
         self.send_request(
@@ -312,9 +379,18 @@ impl MCPTestClient {
     pub async fn initialize_and_wait(&self) -> Result<()> {
         self.initialize().await?;
 
-        // rust-analyzer returns null while indexing, so we need to poll.
+        // Let the server wait for indexing to end, as reported by rust-analyzer's progress.
         let start = std::time::Instant::now();
         let timeout = timeouts::init_wait();
+        let _ = self
+            .call_tool_with_timeout(
+                "rust_analyzer_wait_for_analysis",
+                json!({ "timeout_ms": timeout.as_millis() as u64 }),
+                timeout + timeouts::request(),
+            )
+            .await;
+
+        // Symbols can still be null right after indexing, so poll them too.
         let poll_interval = timeouts::init_poll();
 
         loop {
@@ -358,6 +434,20 @@ impl MCPTestClient {
     }
 
     async fn check_symbols_ready(&self) -> bool {
+        // rust-analyzer tells when it's done loading the workspace, which also starts it.
+        let Ok(response) = self
+            .call_tool("rust_analyzer_server_status", json!({}))
+            .await
+        else {
+            return false;
+        };
+        let status = response["content"][0]["text"]
+            .as_str()
+            .and_then(|text| serde_json::from_str::<Value>(text).ok());
+        if status.is_some_and(|status| status["status"] == "loading") {
+            return false;
+        }
+
         // Use lib.rs as it exists in all test projects
         let Ok(response) = self
             .call_tool("rust_analyzer_symbols", json!({"file_path": "src/lib.rs"}))
@@ -58,8 +58,8 @@ impl<'a> WorkspaceReadiness<'a> {
         self.open_all_critical_files().await?;
         eprintln!("[WorkspaceReadiness] Opened all critical files");
 
-        self.wait_for_symbols().await?;
-        eprintln!("[WorkspaceReadiness] Symbols are available");
+        self.wait_for_health().await?;
+        eprintln!("[WorkspaceReadiness] rust-analyzer is ready");
 
         self.wait_for_imports_resolved().await?;
         eprintln!("[WorkspaceReadiness] Module imports are resolved");
@@ -86,57 +86,29 @@ impl<'a> WorkspaceReadiness<'a> {
         Ok(())
     }
 
-    /// Wait for symbols to be available in all critical files.
-    async fn wait_for_symbols(&self) -> Result<()> {
+    /// Wait for the health check to report rust-analyzer as initialized and done indexing.
+    async fn wait_for_health(&self) -> Result<()> {
         let start = std::time::Instant::now();
 
         while start.elapsed() < self.timeout {
-            let mut all_ready = true;
-
-            for file_path in &self.critical_files {
-                let response = self
-                    .client
-                    .call_tool("rust_analyzer_symbols", json!({"file_path": file_path}))
-                    .await?;
-
-                let Some(content) = response.get("content") else {
-                    all_ready = false;
-                    break;
-                };
-                let Some(text) = content[0].get("text") else {
-                    all_ready = false;
-                    break;
-                };
-                let Some(text_str) = text.as_str() else {
-                    all_ready = false;
-                    break;
-                };
-
-                if text_str == "null" || text_str == "[]" {
-                    all_ready = false;
-                    break;
-                }
-
-                let Ok(symbols) = serde_json::from_str::<Vec<Value>>(text_str) else {
-                    all_ready = false;
-                    break;
-                };
-
-                if symbols.is_empty() {
-                    all_ready = false;
-                    break;
-                }
-            }
+            let response = self
+                .client
+                .call_tool("rust_analyzer_health_check", json!({}))
+                .await?;
+            let Some(text) = response["content"][0]["text"].as_str() else {
+                anyhow::bail!("Unexpected health check response: {}", response);
+            };
+            let health: Value = serde_json::from_str(text)?;
 
-            if all_ready {
-                return Ok(());
+            match health["status"].as_str() {
+                Some("ready") => return Ok(()),
+                Some("crashed") => anyhow::bail!("rust-analyzer crashed: {}", health),
+                _ => sleep(Duration::from_millis(500)).await,
             }
-
-            sleep(Duration::from_millis(500)).await;
         }
 
         Err(anyhow::anyhow!(
-            "Timeout waiting for symbols to be available after {:?}",
+            "Timeout waiting for rust-analyzer to be ready after {:?}",
             self.timeout
         ))
     }
@@ -0,0 +1,143 @@
+use anyhow::Result;
+use serde_json::{json, Value};
+use test_support::{IsolatedProject, MCPTestClient};
+
+#[tokio::test]
+async fn test_config_file_settings_take_effect() -> Result<()> {
+    let project = IsolatedProject::new()?;
+    std::fs::write(
+        project.file_path(".rust-analyzer-mcp.toml"),
+        "compact_responses = true\ncheck_command = \"none\"\nrequest_timeout_secs = 60\n",
+    )?;
+
+    let client = MCPTestClient::start(project.path()).await?;
+    client.initialize().await?;
+
+    let response = client
+        .call_tool("rust_analyzer_list_files", json!({}))
+        .await?;
+    let text = response["content"][0]["text"].as_str().unwrap();
+
+    // Compact responses aren't pretty-printed
+    assert!(
+        !text.contains('\n'),
+        "Response should be compact, got: {}",
+        text
+    );
+    let listing: Value = serde_json::from_str(text)?;
+    assert!(listing["files"]
+        .as_array()
+        .unwrap()
+        .contains(&json!("src/lib.rs")));
+
+    client.shutdown().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_without_config_file() -> Result<()> {
+    let project = IsolatedProject::new()?;
+
+    let client = MCPTestClient::start(project.path()).await?;
+    client.initialize().await?;
+
+    let response = client
+        .call_tool("rust_analyzer_list_files", json!({}))
+        .await?;
+    let text = response["content"][0]["text"].as_str().unwrap();
+
+    // Responses are pretty-printed by default
+    assert!(
+        text.contains('\n'),
+        "Response should be pretty, got: {}",
+        text
+    );
+
+    client.shutdown().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_max_open_documents() -> Result<()> {
+    let project = IsolatedProject::new()?;
+    std::fs::write(
+        project.file_path(".rust-analyzer-mcp.toml"),
+        // Without the cache, the last call has to go through rust-analyzer again.
+        "max_open_documents = 2\nresponse_cache_capacity = 0\n",
+    )?;
+
+    let client = MCPTestClient::start(project.path()).await?;
+    client.initialize_and_wait().await?;
+
+    // Opening more files than the limit closes the least recently used ones, which must be
+    // reopened transparently when used again.
+    let files = ["src/lib.rs", "src/types.rs", "src/utils.rs", "src/lib.rs"];
+    for file in files {
+        let path = project.file_path(file);
+        let response = client
+            .call_tool(
+                "rust_analyzer_symbols",
+                json!({ "file_path": path.to_str().unwrap() }),
+            )
+            .await?;
+        let symbols: Value =
+            serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+        assert!(
+            symbols
+                .as_array()
+                .is_some_and(|symbols| !symbols.is_empty()),
+            "Expected symbols for {}, got: {}",
+            file,
+            symbols
+        );
+    }
+
+    client.shutdown().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_max_open_documents_closes_least_recently_used() -> Result<()> {
+    let project = IsolatedProject::new()?;
+    std::fs::write(
+        project.file_path(".rust-analyzer-mcp.toml"),
+        "max_open_documents = 2\nresponse_cache_capacity = 0\n",
+    )?;
+
+    let client = MCPTestClient::start(project.path()).await?;
+    client.initialize().await?;
+
+    // One more file than can stay open.
+    for file in ["src/lib.rs", "src/types.rs", "src/utils.rs"] {
+        let path = project.file_path(file);
+        client
+            .call_tool(
+                "rust_analyzer_symbols",
+                json!({ "file_path": path.to_str().unwrap() }),
+            )
+            .await?;
+    }
+
+    let response = client
+        .call_tool("rust_analyzer_open_documents", json!({}))
+        .await?;
+    let result: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    assert_eq!(result["count"], 2, "Got: {}", result);
+    let is_open = |file: &str| {
+        result["documents"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|doc| doc["uri"].as_str().is_some_and(|uri| uri.ends_with(file)))
+    };
+    assert!(!is_open("src/lib.rs"), "Got: {}", result);
+    assert!(is_open("src/types.rs"), "Got: {}", result);
+    assert!(is_open("src/utils.rs"), "Got: {}", result);
+
+    client.shutdown().await?;
+
+    Ok(())
+}
@@ -98,6 +98,37 @@ async fn test_file_diagnostics() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_file_diagnostics_wait_ms() -> Result<()> {
+    let mut client = IpcClient::get_or_create("test-project-diagnostics").await?;
+    let workspace_path = client.workspace_path();
+    let errors_path = workspace_path.join("src/errors.rs");
+
+    // A single call with a long enough wait should be enough to get the diagnostics
+    let response = client
+        .call_tool(
+            "rust_analyzer_diagnostics",
+            json!({
+                "file_path": errors_path.to_str().unwrap(),
+                "wait_ms": 8000
+            }),
+        )
+        .await?;
+
+    assert_tool_response(&response);
+    let content = response["content"][0]["text"].as_str().unwrap();
+    let parsed: serde_json::Value = serde_json::from_str(content).unwrap();
+
+    let diagnostics = parsed["diagnostics"].as_array().unwrap();
+    assert!(
+        !diagnostics.is_empty(),
+        "Should have diagnostics for file with errors. Got: {}",
+        serde_json::to_string_pretty(&parsed).unwrap()
+    );
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_file_diagnostics_clean_file() -> Result<()> {
     // Use test-project-diagnostics which has a clean file
@@ -238,6 +269,95 @@ async fn test_workspace_diagnostics() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_workspace_errors() -> Result<()> {
+    let mut client = IpcClient::get_or_create("test-project-diagnostics").await?;
+    let workspace_path = client.workspace_path();
+    let errors_path = workspace_path.join("src/errors.rs");
+
+    // Wait for the errors of errors.rs to be published.
+    let max_attempts = if std::env::var("CI").is_ok() { 20 } else { 10 };
+    for _ in 0..max_attempts {
+        let response = client
+            .call_tool(
+                "rust_analyzer_diagnostics",
+                json!({
+                    "file_path": errors_path.to_str().unwrap(),
+                    "min_severity": "error"
+                }),
+            )
+            .await?;
+        let content = response["content"][0]["text"].as_str().unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(content).unwrap();
+        if parsed["summary"]["errors"].as_u64().unwrap_or(0) > 0 {
+            break;
+        }
+
+        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+    }
+
+    let response = client
+        .call_tool("rust_analyzer_workspace_errors", json!({ "limit": 1 }))
+        .await?;
+
+    assert_tool_response(&response);
+    let content = response["content"][0]["text"].as_str().unwrap();
+    let parsed: serde_json::Value = serde_json::from_str(content).unwrap();
+
+    // errors.rs has several errors but only the first one is listed.
+    assert!(
+        parsed["total_errors"].as_u64().unwrap() > 1,
+        "Got: {}",
+        parsed
+    );
+    let errors = parsed["errors"].as_array().unwrap();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(parsed["shown"], 1);
+    assert_eq!(errors[0]["file"], "src/errors.rs");
+    assert!(errors[0]["line"].is_number());
+    assert!(errors[0]["message"].is_string());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_index_workspace() -> Result<()> {
+    let project = test_support::IsolatedProject::new_diagnostics()?;
+    // Ignored files aren't opened.
+    std::fs::write(project.path().join(".gitignore"), "src/generated.rs\n")?;
+    std::fs::write(
+        project.path().join("src/generated.rs"),
+        "fn generated() {}\n",
+    )?;
+    let client = test_support::MCPTestClient::start(project.path()).await?;
+    client.initialize().await?;
+
+    // No file was opened before, errors.rs gets analyzed by indexing the workspace.
+    let response = client
+        .call_tool(
+            "rust_analyzer_index_workspace",
+            json!({ "min_severity": "error" }),
+        )
+        .await?;
+
+    assert_tool_response(&response);
+    let content = response["content"][0]["text"].as_str().unwrap();
+    let parsed: serde_json::Value = serde_json::from_str(content).unwrap();
+
+    assert_eq!(parsed["indexed_files"], 4, "Got: {}", parsed);
+    let files = parsed["files"].as_object().unwrap();
+    let (_, errors) = files
+        .iter()
+        .find(|(uri, _)| uri.ends_with("src/errors.rs"))
+        .expect("No diagnostics for errors.rs");
+    assert!(errors["summary"]["errors"].as_u64().unwrap() > 0);
+    assert!(!files.keys().any(|uri| uri.ends_with("src/clean.rs")));
+
+    client.shutdown().await?;
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_diagnostics_invalid_file() -> Result<()> {
     // Can use either project, using regular one
@@ -360,3 +480,282 @@ async fn test_diagnostics_severity_levels() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_clippy_diagnostics_after_switching_check_command() -> Result<()> {
+    let project = test_support::IsolatedProject::new()?;
+    let lib_path = project.file_path("src/lib.rs");
+
+    let client = test_support::MCPTestClient::start(project.path()).await?;
+    client.initialize_and_wait().await?;
+
+    // Open the file first so that it gets checked again after the switch
+    client
+        .call_tool(
+            "rust_analyzer_diagnostics",
+            json!({ "file_path": lib_path.to_str().unwrap() }),
+        )
+        .await?;
+    client
+        .call_tool(
+            "rust_analyzer_set_check_command",
+            json!({ "command": "clippy" }),
+        )
+        .await?;
+
+    // `handle_option` has a `match` that clippy wants to be an `if let` (`single_match`)
+    let max_attempts = if std::env::var("CI").is_ok() { 20 } else { 10 };
+    let mut diagnostics = vec![];
+    for _ in 0..max_attempts {
+        let response = client
+            .call_tool(
+                "rust_analyzer_diagnostics",
+                json!({
+                    "file_path": lib_path.to_str().unwrap(),
+                    "wait_ms": 5000
+                }),
+            )
+            .await?;
+        assert_tool_response(&response);
+        let content = response["content"][0]["text"].as_str().unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(content)?;
+        diagnostics = parsed["diagnostics"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default();
+
+        if diagnostics.iter().any(is_clippy_diagnostic) {
+            break;
+        }
+        tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+    }
+
+    client.shutdown().await?;
+
+    assert!(
+        diagnostics.iter().any(is_clippy_diagnostic),
+        "Expected a clippy diagnostic, got: {}",
+        serde_json::to_string_pretty(&diagnostics).unwrap()
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_diagnostics_wait() -> Result<()> {
+    let project = test_support::IsolatedProject::new_diagnostics()?;
+    let clean_path = project.file_path("src/clean.rs");
+
+    let client = test_support::MCPTestClient::start(project.path()).await?;
+    client.initialize_and_wait().await?;
+
+    // A clean file's empty diagnostics are published too, so this doesn't wait for the deadline.
+    let start = std::time::Instant::now();
+    let response = client
+        .call_tool(
+            "rust_analyzer_diagnostics",
+            json!({
+                "file_path": clean_path.to_str().unwrap(),
+                "wait_ms": 30000
+            }),
+        )
+        .await?;
+    assert_tool_response(&response);
+    let parsed: serde_json::Value =
+        serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    assert_eq!(parsed["timed_out"], false, "Got: {}", parsed);
+    assert_eq!(parsed["summary"]["errors"], 0, "Got: {}", parsed);
+    assert!(start.elapsed() < std::time::Duration::from_secs(30));
+
+    // Without waiting, the diagnostics of a file just changed are most likely not published yet,
+    // which is reported rather than returning the outdated ones.
+    let content = std::fs::read_to_string(&clean_path)?;
+    std::fs::write(&clean_path, format!("{}\n// Changed\n", content))?;
+    let response = client
+        .call_tool(
+            "rust_analyzer_diagnostics",
+            json!({
+                "file_path": clean_path.to_str().unwrap(),
+                "wait_ms": 0
+            }),
+        )
+        .await?;
+    assert_tool_response(&response);
+    let parsed: serde_json::Value =
+        serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    assert!(parsed["timed_out"].is_boolean(), "Got: {}", parsed);
+
+    client.shutdown().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_diagnostics_after_editing_file() -> Result<()> {
+    let project = test_support::IsolatedProject::new_diagnostics()?;
+    let clean_path = project.file_path("src/clean.rs");
+
+    let client = test_support::MCPTestClient::start(project.path()).await?;
+    client.initialize_and_wait().await?;
+
+    let response = client
+        .call_tool(
+            "rust_analyzer_diagnostics",
+            json!({
+                "file_path": clean_path.to_str().unwrap(),
+                "wait_ms": 2000
+            }),
+        )
+        .await?;
+    assert_tool_response(&response);
+    let parsed: serde_json::Value =
+        serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    assert_eq!(parsed["summary"]["errors"], 0, "Got: {}", parsed);
+    let first_version = parsed["version"].as_i64();
+
+    // Break the file behind the server's back, like an agent editing it would.
+    let content = std::fs::read_to_string(&clean_path)?;
+    std::fs::write(
+        &clean_path,
+        format!(
+            "{}\npub fn broken() -> i32 {{\n    \"not a number\"\n}}\n",
+            content
+        ),
+    )?;
+
+    let max_attempts = if std::env::var("CI").is_ok() { 20 } else { 10 };
+    let mut parsed = serde_json::Value::Null;
+    for _ in 0..max_attempts {
+        let response = client
+            .call_tool(
+                "rust_analyzer_diagnostics",
+                json!({
+                    "file_path": clean_path.to_str().unwrap(),
+                    "min_severity": "error",
+                    "wait_ms": 5000
+                }),
+            )
+            .await?;
+        assert_tool_response(&response);
+        parsed = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+        if parsed["summary"]["errors"].as_u64().unwrap_or(0) > 0 {
+            break;
+        }
+        tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+    }
+
+    client.shutdown().await?;
+
+    assert!(
+        parsed["summary"]["errors"].as_u64().unwrap_or(0) > 0,
+        "Expected the new error to be reported, got: {}",
+        parsed
+    );
+    // They're about the edited version of the document.
+    if let (Some(first), Some(edited)) = (first_version, parsed["version"].as_i64()) {
+        assert!(edited > first, "Got versions {} and {}", first, edited);
+    }
+
+    Ok(())
+}
+
+fn is_clippy_diagnostic(diagnostic: &serde_json::Value) -> bool {
+    diagnostic["source"].as_str() == Some("clippy")
+        || diagnostic["code"]
+            .as_str()
+            .is_some_and(|code| code.starts_with("clippy::"))
+}
+
+#[tokio::test]
+async fn test_run_check() -> Result<()> {
+    let project = test_support::IsolatedProject::new_diagnostics()?;
+
+    let client = test_support::MCPTestClient::start(project.path()).await?;
+    client.initialize_and_wait().await?;
+
+    let response = client
+        .call_tool_with_timeout(
+            "rust_analyzer_run_check",
+            json!({ "timeout_ms": 120000 }),
+            std::time::Duration::from_secs(150),
+        )
+        .await?;
+    assert_tool_response(&response);
+    let parsed: serde_json::Value =
+        serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    assert_eq!(parsed["completed"], true, "Got: {}", parsed);
+    assert!(
+        parsed["total_errors"].as_u64().unwrap_or(0) > 0,
+        "Got: {}",
+        parsed
+    );
+    assert!(parsed["files"]
+        .as_object()
+        .is_some_and(|files| files.keys().any(|uri| uri.ends_with("src/errors.rs"))));
+
+    let response = client
+        .call_tool("rust_analyzer_run_check", json!({ "cancel": true }))
+        .await?;
+    assert_tool_response(&response);
+    let parsed: serde_json::Value =
+        serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    assert_eq!(parsed["cancelled"], true);
+
+    client.shutdown().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_diagnostics_rendered() -> Result<()> {
+    let project = test_support::IsolatedProject::new_diagnostics()?;
+    let errors_path = project.file_path("src/errors.rs");
+
+    let client = test_support::MCPTestClient::start(project.path()).await?;
+    client.initialize_and_wait().await?;
+    // The rendering comes from cargo check, so make sure it ran.
+    client
+        .call_tool_with_timeout(
+            "rust_analyzer_run_check",
+            json!({ "timeout_ms": 120000 }),
+            std::time::Duration::from_secs(150),
+        )
+        .await?;
+
+    let response = client
+        .call_tool(
+            "rust_analyzer_diagnostics",
+            json!({ "file_path": errors_path.to_str().unwrap() }),
+        )
+        .await?;
+    assert_tool_response(&response);
+    let parsed: serde_json::Value =
+        serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    let diagnostics = parsed["diagnostics"].as_array().unwrap();
+    assert!(
+        diagnostics.iter().any(|diag| diag["severity"] == "error"
+            && diag["rendered"]
+                .as_str()
+                .is_some_and(|rendered| rendered.starts_with("error[E0308]"))),
+        "Got: {}",
+        parsed
+    );
+
+    let response = client
+        .call_tool(
+            "rust_analyzer_diagnostics",
+            json!({ "file_path": errors_path.to_str().unwrap(), "include_rendered": false }),
+        )
+        .await?;
+    let parsed: serde_json::Value =
+        serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    assert!(parsed["diagnostics"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .all(|diag| diag.get("rendered").is_none()));
+
+    client.shutdown().await?;
+
+    Ok(())
+}
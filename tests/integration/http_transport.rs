@@ -0,0 +1,215 @@
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+use std::{process::Stdio, time::Duration};
+use test_support::IsolatedProject;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+    process::{Child, Command},
+};
+
+/// Start the server on a free local port with the extra `args`, returning the process and the port.
+async fn start_http_server(project: &IsolatedProject, args: &[&str]) -> Result<(Child, u16)> {
+    let port = std::net::TcpListener::bind("127.0.0.1:0")?
+        .local_addr()?
+        .port();
+    let server = Command::new(assert_cmd::cargo::cargo_bin("rust-analyzer-mcp"))
+        .arg("--http-port")
+        .arg(port.to_string())
+        .args(args)
+        .arg(project.path())
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()?;
+
+    for _ in 0..50 {
+        if TcpStream::connect(("127.0.0.1", port)).await.is_ok() {
+            return Ok((server, port));
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    Err(anyhow!(
+        "HTTP server didn't start listening on port {}",
+        port
+    ))
+}
+
+/// Send a raw HTTP request and return the status code, headers and body of the response.
+async fn send(port: u16, method: &str, body: &str) -> Result<(u16, String, String)> {
+    let host = format!("Host: localhost:{}", port);
+    send_with_headers(port, method, &[&host], body).await
+}
+
+/// Like [`send`], with the given `headers` lines instead of the default `Host`.
+async fn send_with_headers(
+    port: u16,
+    method: &str,
+    headers: &[&str],
+    body: &str,
+) -> Result<(u16, String, String)> {
+    let mut stream = TcpStream::connect(("127.0.0.1", port)).await?;
+    let request = format!(
+        "{} / HTTP/1.1\r\n{}\r\nContent-Type: application/json\r\n\
+         Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+        method,
+        headers.join("\r\n"),
+        body.len(),
+        body
+    );
+    stream.write_all(request.as_bytes()).await?;
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await?;
+    let (head, body) = response
+        .split_once("\r\n\r\n")
+        .ok_or_else(|| anyhow!("Malformed response: {}", response))?;
+    let status = head
+        .split_whitespace()
+        .nth(1)
+        .and_then(|status| status.parse().ok())
+        .ok_or_else(|| anyhow!("Malformed status line: {}", head))?;
+
+    Ok((status, head.to_lowercase(), body.to_string()))
+}
+
+#[tokio::test]
+async fn test_http_transport() -> Result<()> {
+    let project = IsolatedProject::new()?;
+    let (mut server, port) = start_http_server(&project, &[]).await?;
+
+    let request = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": "2024-11-05",
+            "capabilities": {},
+            "clientInfo": { "name": "test-client", "version": "1.0.0" }
+        }
+    });
+    let (status, headers, body) = send(port, "POST", &request.to_string()).await?;
+    assert_eq!(status, 200);
+    assert!(!headers.contains("access-control-allow-origin"));
+    let response: Value = serde_json::from_str(&body)?;
+    assert_eq!(response["id"], 1);
+    assert_eq!(
+        response["result"]["serverInfo"]["name"],
+        "rust-analyzer-mcp"
+    );
+
+    let request = json!({ "jsonrpc": "2.0", "id": 2, "method": "tools/list" });
+    let (status, _, body) = send(port, "POST", &request.to_string()).await?;
+    assert_eq!(status, 200);
+    let response: Value = serde_json::from_str(&body)?;
+    assert_eq!(response["id"], 2);
+    assert!(response["result"]["tools"].is_array());
+
+    // A batch of notifications is accepted without a response body.
+    let batch = json!([{ "jsonrpc": "2.0", "method": "notifications/initialized" }]);
+    let (status, _, body) = send(port, "POST", &batch.to_string()).await?;
+    assert_eq!(status, 202);
+    assert!(body.is_empty());
+
+    let (status, _, body) = send(port, "POST", "not json").await?;
+    assert_eq!(status, 400);
+    let response: Value = serde_json::from_str(&body)?;
+    assert_eq!(response["error"]["code"], -32700);
+
+    // CORS preflight.
+    let host = format!("Host: localhost:{}", port);
+    let origin = "Origin: http://localhost:3000";
+    let (status, headers, _) = send_with_headers(port, "OPTIONS", &[&host, origin], "").await?;
+    assert_eq!(status, 204);
+    assert!(headers.contains("access-control-allow-origin: http://localhost:3000"));
+    assert!(headers.contains("access-control-allow-methods: get, post, options"));
+
+    server.kill().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_http_sse_stream() -> Result<()> {
+    let project = IsolatedProject::new()?;
+    let (mut server, port) = start_http_server(&project, &[]).await?;
+
+    // The stream stays open, so only read up to the end of the headers.
+    let mut stream = TcpStream::connect(("127.0.0.1", port)).await?;
+    stream
+        .write_all(
+            format!(
+                "GET / HTTP/1.1\r\nHost: 127.0.0.1:{}\r\nAccept: text/event-stream\r\n\r\n",
+                port
+            )
+            .as_bytes(),
+        )
+        .await?;
+    let mut head = Vec::new();
+    let mut buf = [0; 1024];
+    while !head.windows(4).any(|window| window == b"\r\n\r\n") {
+        let read = tokio::time::timeout(Duration::from_secs(10), stream.read(&mut buf)).await??;
+        if read == 0 {
+            return Err(anyhow!("Connection closed before the SSE headers"));
+        }
+        head.extend_from_slice(&buf[..read]);
+    }
+    let head = String::from_utf8_lossy(&head).to_lowercase();
+    assert!(head.starts_with("http/1.1 200"), "{}", head);
+    assert!(head.contains("content-type: text/event-stream"), "{}", head);
+
+    server.kill().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_http_access_policy() -> Result<()> {
+    let project = IsolatedProject::new()?;
+    let (mut server, port) =
+        start_http_server(&project, &["--allowed-origin", "https://app.example.com"]).await?;
+    let request = json!({ "jsonrpc": "2.0", "id": 1, "method": "tools/list" }).to_string();
+    let host = format!("Host: localhost:{}", port);
+
+    // Pages served from localhost and the allowed origins can call the server.
+    for origin in [
+        "http://localhost:8080",
+        "http://127.0.0.1",
+        "https://app.example.com",
+    ] {
+        let origin_header = format!("Origin: {}", origin);
+        let (status, headers, _) =
+            send_with_headers(port, "POST", &[&host, &origin_header], &request).await?;
+        assert_eq!(status, 200, "{} should be allowed", origin);
+        assert!(headers.contains(&format!("access-control-allow-origin: {}", origin)));
+    }
+
+    // Other pages can't.
+    let (status, headers, _) = send_with_headers(
+        port,
+        "POST",
+        &[&host, "Origin: https://evil.example.com"],
+        &request,
+    )
+    .await?;
+    assert_eq!(status, 403);
+    assert!(!headers.contains("access-control-allow-origin"));
+
+    // Neither can requests for another host, e.g. a name rebound to the server's address.
+    let (status, _, _) = send_with_headers(
+        port,
+        "POST",
+        &[&format!("Host: evil.example.com:{}", port)],
+        &request,
+    )
+    .await?;
+    assert_eq!(status, 421);
+    let (status, _, _) = send_with_headers(port, "POST", &["Host: localhost:1"], &request).await?;
+    assert_eq!(status, 421);
+
+    server.kill().await?;
+
+    Ok(())
+}
@@ -1,6 +1,6 @@
 use anyhow::Result;
 use serde_json::{json, Value};
-use std::path::Path;
+use std::{path::Path, time::Duration};
 
 // Import test support library
 use test_support::{is_ci, timeouts, IpcClient};
@@ -100,7 +100,10 @@ async fn test_all_lsp_tools() -> Result<()> {
 
 #[tokio::test]
 async fn test_workspace_change() -> Result<()> {
-    let mut client = IpcClient::get_or_create("test-project").await?;
+    // Use a dedicated server, as the shared one must stay on its workspace for the other tests
+    let project = test_support::IsolatedProject::new()?;
+    let client = test_support::MCPTestClient::start(project.path()).await?;
+    client.initialize().await?;
 
     // Create a second isolated project to switch to
     let second_project = test_support::IsolatedProject::new()?;
@@ -124,6 +127,8 @@ async fn test_workspace_change() -> Result<()> {
         }
     }
 
+    client.shutdown().await?;
+
     Ok(())
 }
 
@@ -214,314 +219,2936 @@ async fn test_error_handling_invalid_positions() -> Result<()> {
     Ok(())
 }
 
-// Helper functions for test_all_lsp_tools
+#[tokio::test]
+async fn test_declaration_vs_definition() -> Result<()> {
+    let mut client = IpcClient::get_or_create("test-project").await?;
+    let workspace_path = client.workspace_path().to_path_buf();
+    let lib_path = workspace_path.join("src/lib.rs");
 
-async fn test_symbols(client: &mut IpcClient, workspace_path: &Path) -> Result<()> {
-    let main_path = workspace_path.join("src/main.rs");
-    let response = client
-        .call_tool(
-            "rust_analyzer_symbols",
-            json!({
-                "file_path": main_path.to_str().unwrap()
-            }),
-        )
+    // `Config` in `pub use types::Config;` on line 4 (0-indexed line 3)
+    let args = json!({
+        "file_path": lib_path.to_str().unwrap(),
+        "line": 3,
+        "character": 16
+    });
+
+    let declaration = client
+        .call_tool("rust_analyzer_declaration", args.clone())
         .await?;
+    let definition = client.call_tool("rust_analyzer_definition", args).await?;
 
-    let Some(content) = response.get("content") else {
-        return Err(anyhow::anyhow!("No content in symbols response"));
-    };
+    let declaration: Value =
+        serde_json::from_str(declaration["content"][0]["text"].as_str().unwrap())?;
+    let definition: Value =
+        serde_json::from_str(definition["content"][0]["text"].as_str().unwrap())?;
 
-    let Some(text) = content[0].get("text") else {
-        return Err(anyhow::anyhow!("No text in symbols response"));
-    };
+    // null during initialization is normal for LSP
+    if declaration.is_null() || definition.is_null() {
+        eprintln!("Got null declaration/definition (rust-analyzer may still be indexing)");
+        return Ok(());
+    }
 
-    let Some(text_str) = text.as_str() else {
-        return Err(anyhow::anyhow!("Text is not a string"));
+    let target_uri = |locations: &Value| {
+        let location = &locations[0];
+        location
+            .get("targetUri")
+            .or_else(|| location.get("uri"))
+            .and_then(|u| u.as_str())
+            .map(String::from)
     };
 
-    let symbols: Vec<Value> = serde_json::from_str(text_str)?;
-    assert!(!symbols.is_empty(), "Should have symbols in main.rs");
-
-    let symbol_names: Vec<String> = symbols
-        .iter()
-        .filter_map(|s| s.get("name")?.as_str().map(String::from))
-        .collect();
+    let declaration_uri = target_uri(&declaration).expect("Declaration should have a target");
+    let definition_uri = target_uri(&definition).expect("Definition should have a target");
 
+    // The re-export resolves to the struct in types.rs either way
     assert!(
-        symbol_names.contains(&"main".to_string()),
-        "Should have main function"
-    );
-    assert!(
-        symbol_names.contains(&"greet".to_string()),
-        "Should have greet function"
-    );
-    assert!(
-        symbol_names.contains(&"Calculator".to_string()),
-        "Should have Calculator struct"
+        declaration_uri.ends_with("src/types.rs"),
+        "Declaration should point into types.rs, got: {}",
+        declaration_uri
     );
+    assert_eq!(declaration_uri, definition_uri);
 
     Ok(())
 }
 
-async fn test_definition(client: &mut IpcClient, workspace_path: &Path) -> Result<bool> {
+#[tokio::test]
+async fn test_flat_symbols() -> Result<()> {
+    let mut client = IpcClient::get_or_create("test-project").await?;
+    let workspace_path = client.workspace_path().to_path_buf();
     let main_path = workspace_path.join("src/main.rs");
+
     let response = client
         .call_tool(
-            "rust_analyzer_definition",
+            "rust_analyzer_symbols",
             json!({
                 "file_path": main_path.to_str().unwrap(),
-                "line": 1,
-                "character": 18
+                "flat": true
             }),
         )
         .await?;
+    let symbols: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    let symbols = symbols.as_array().expect("Flat symbols should be an array");
 
-    let Some(content) = response.get("content") else {
-        return Ok(false);
-    };
-
-    if !content.is_array() || content[0].is_null() {
-        return Ok(false);
-    }
-
-    let Some(text) = content[0].get("text") else {
-        return Ok(false);
-    };
-
-    let Some(text_str) = text.as_str() else {
-        return Ok(false);
-    };
-
-    // null or empty array during initialization is normal for LSP.
-    // We just check that we got a valid response.
-    if text_str == "null" || text_str == "[]" {
-        // This is a valid response during initialization.
-        return Ok(true);
+    // Empty during initialization is normal for LSP
+    if symbols.is_empty() {
+        eprintln!("Got no symbols (rust-analyzer may still be indexing)");
+        return Ok(());
     }
 
-    // Try to parse as array
-    let Ok(definitions) = serde_json::from_str::<Vec<Value>>(text_str) else {
-        return Ok(false);
-    };
+    let add = symbols
+        .iter()
+        .find(|s| s["path"] == "Calculator::add")
+        .unwrap_or_else(|| panic!("`Calculator::add` should be listed, got: {:?}", symbols));
+    assert_eq!(add["name"], "add");
+    assert_eq!(add["container"], "Calculator");
+    assert!(add["line"].is_number());
 
-    Ok(!definitions.is_empty())
+    Ok(())
 }
 
-async fn test_references(client: &mut IpcClient, workspace_path: &Path) -> Result<bool> {
+#[tokio::test]
+async fn test_completion_after_dot() -> Result<()> {
+    let mut client = IpcClient::get_or_create("test-project").await?;
+    let workspace_path = client.workspace_path().to_path_buf();
     let main_path = workspace_path.join("src/main.rs");
+
+    // Right after `calc.` in `let result = calc.add(2, 3);` (0-indexed line 5)
     let response = client
         .call_tool(
-            "rust_analyzer_references",
+            "rust_analyzer_completion",
             json!({
                 "file_path": main_path.to_str().unwrap(),
-                "line": 9,
-                "character": 4
+                "line": 5,
+                "character": 22,
+                "trigger_character": "."
             }),
         )
         .await?;
-
-    let Some(content) = response.get("content") else {
-        return Ok(false);
-    };
-
-    let Some(text) = content[0].get("text") else {
-        return Ok(false);
-    };
-
-    if text.as_str() == Some("null") {
-        return Ok(false);
+    let completions: Value =
+        serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+
+    let items = completions
+        .get("items")
+        .unwrap_or(&completions)
+        .as_array()
+        .cloned()
+        .unwrap_or_default();
+    // Empty during initialization is normal for LSP
+    if items.is_empty() {
+        eprintln!("Got no completions (rust-analyzer may still be indexing)");
+        return Ok(());
     }
 
-    let Some(text_str) = text.as_str() else {
-        return Ok(false);
-    };
+    let add = items
+        .iter()
+        .find(|item| item["label"].as_str().is_some_and(|l| l.starts_with("add")))
+        .unwrap_or_else(|| panic!("`add` should be completed, got: {:?}", items));
+    let detail = add["detail"].as_str().unwrap_or_default();
+    assert!(
+        detail.contains("fn("),
+        "`add` should come with its signature, got: {:?}",
+        add
+    );
 
-    let references: Vec<Value> = serde_json::from_str(text_str)?;
-    Ok(!references.is_empty())
+    Ok(())
 }
 
-async fn test_hover(client: &mut IpcClient, workspace_path: &Path) -> Result<bool> {
+#[tokio::test]
+async fn test_completion_max_items() -> Result<()> {
+    let mut client = IpcClient::get_or_create("test-project").await?;
+    let workspace_path = client.workspace_path().to_path_buf();
     let main_path = workspace_path.join("src/main.rs");
+
+    // Start of `println!` in `main` (0-indexed line 2), where everything in scope completes
     let response = client
         .call_tool(
-            "rust_analyzer_hover",
+            "rust_analyzer_completion",
             json!({
                 "file_path": main_path.to_str().unwrap(),
-                "line": 4,
-                "character": 15
+                "line": 2,
+                "character": 4,
+                "max_items": 5
             }),
         )
         .await?;
-
-    let Some(content) = response.get("content") else {
-        return Ok(false);
-    };
-
-    let Some(text) = content[0].get("text") else {
-        return Ok(false);
-    };
-
-    if text.as_str() == Some("null") {
-        return Ok(false);
+    let completions: Value =
+        serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    if completions["status"] == "indexing" {
+        eprintln!("Got no completions, rust-analyzer is still indexing");
+        return Ok(());
     }
 
-    let Some(text_str) = text.as_str() else {
-        return Ok(false);
-    };
+    let items = completions["items"].as_array().unwrap();
+    let total_items = completions["total_items"].as_u64().unwrap() as usize;
+    assert!(items.len() <= 5, "Got {} items", items.len());
+    assert_eq!(completions["truncated"], total_items > 5);
+    if total_items > 5 {
+        assert_eq!(completions["isIncomplete"], true);
+    }
+    // Heavyweight fields are stripped by default
+    assert!(items
+        .iter()
+        .all(|item| item.get("data").is_none() && item.get("additionalTextEdits").is_none()));
 
-    let hover: Value = serde_json::from_str(text_str)?;
-    Ok(hover.get("contents").is_some())
+    Ok(())
 }
 
-async fn test_completion(client: &mut IpcClient, workspace_path: &Path) -> Result<()> {
+#[tokio::test]
+async fn test_code_lens() -> Result<()> {
+    let mut client = IpcClient::get_or_create("test-project").await?;
+    let workspace_path = client.workspace_path().to_path_buf();
     let main_path = workspace_path.join("src/main.rs");
+
     let response = client
         .call_tool(
-            "rust_analyzer_completion",
-            json!({
-                "file_path": main_path.to_str().unwrap(),
-                "line": 2,
-                "character": 5
-            }),
+            "rust_analyzer_code_lens",
+            json!({ "file_path": main_path.to_str().unwrap(), "kind": "test" }),
         )
         .await?;
-
-    let Some(content) = response.get("content") else {
-        return Ok(());
-    };
-
-    let Some(text) = content[0].get("text") else {
-        return Ok(());
-    };
-
-    let Some(text_str) = text.as_str() else {
-        return Ok(());
-    };
-
-    // Handle "null" response specially
-    if text_str == "null" {
-        // rust-analyzer returned null - still indexing
-        eprintln!("Got null completion response (rust-analyzer may still be indexing)");
+    let lenses: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    let lenses = lenses.as_array().unwrap();
+    // Empty during initialization is normal for LSP
+    if lenses.is_empty() {
+        eprintln!("Got no code lenses (rust-analyzer may still be indexing)");
         return Ok(());
     }
 
-    let completions: Value = serde_json::from_str(text_str)?;
+    assert!(lenses.iter().all(|lens| lens["kind"] == "test"));
     assert!(
-        completions.is_object() || completions.is_array() || completions.is_null(),
-        "Expected object, array, or null, got: {:?}",
-        completions
+        lenses
+            .iter()
+            .all(|lens| lens["command"] == "rust-analyzer.runSingle"
+                && lens["range"]["line"].is_u64())
     );
+    // Both tests and the module holding them.
+    assert!(lenses.len() >= 2, "Got: {:?}", lenses);
 
     Ok(())
 }
 
-async fn test_format(client: &mut IpcClient, workspace_path: &Path) -> Result<bool> {
-    // Test 1: Format already-formatted file - should return null (no edits needed)
+#[tokio::test]
+async fn test_semantic_tokens() -> Result<()> {
+    let mut client = IpcClient::get_or_create("test-project").await?;
+    let workspace_path = client.workspace_path().to_path_buf();
     let main_path = workspace_path.join("src/main.rs");
+
     let response = client
         .call_tool(
-            "rust_analyzer_format",
-            json!({
-                "file_path": main_path.to_str().unwrap()
-            }),
+            "rust_analyzer_semantic_tokens",
+            json!({ "file_path": main_path.to_str().unwrap() }),
         )
         .await?;
+    let tokens: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    let tokens = tokens.as_array().unwrap();
+    if tokens.is_empty() {
+        eprintln!("Got no semantic tokens (rust-analyzer may still be indexing)");
+        return Ok(());
+    }
 
-    let Some(content) = response.get("content") else {
-        return Ok(false);
-    };
-
-    let Some(text) = content[0].get("text") else {
-        return Ok(false);
-    };
+    // `greet` in `fn greet(name: &str) -> String` (0-indexed line 13).
+    let greet = tokens
+        .iter()
+        .find(|token| token["line"] == 13 && token["character"] == 3)
+        .expect("No token for greet");
+    assert_eq!(greet["length"], 5);
+    assert_eq!(greet["token_type"], "function", "Got: {}", greet);
+    assert!(greet["modifiers"]
+        .as_array()
+        .unwrap()
+        .contains(&json!("declaration")));
 
-    let Some(text_str) = text.as_str() else {
-        return Ok(false);
-    };
+    Ok(())
+}
 
-    // main.rs is already formatted, so should return null
-    if text_str != "null" {
-        eprintln!("Expected null for formatted file, got: {}", text_str);
-        return Ok(false);
-    }
+#[tokio::test]
+async fn test_completion_structured() -> Result<()> {
+    let mut client = IpcClient::get_or_create("test-project").await?;
+    let workspace_path = client.workspace_path().to_path_buf();
+    let main_path = workspace_path.join("src/main.rs");
 
-    // Test 2: Format unformatted file - should return edits
-    let unformatted_path = workspace_path.join("src/unformatted.rs");
+    // Right after `calc.` in `let result = calc.add(2, 3);`
     let response = client
         .call_tool(
-            "rust_analyzer_format",
+            "rust_analyzer_completion",
             json!({
-                "file_path": unformatted_path.to_str().unwrap()
+                "file_path": main_path.to_str().unwrap(),
+                "line": 5,
+                "character": 22,
+                "trigger_character": ".",
+                "structured": true
             }),
         )
         .await?;
-
-    let Some(content) = response.get("content") else {
-        return Ok(false);
-    };
-
-    let Some(text) = content[0].get("text") else {
-        return Ok(false);
-    };
-
-    let Some(text_str) = text.as_str() else {
-        return Ok(false);
-    };
-
-    // unformatted.rs needs formatting, so should return an array of edits
-    if text_str == "null" {
-        eprintln!("Expected edits for unformatted file, got null");
-        return Ok(false);
+    let completions: Value =
+        serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    assert!(completions["is_incomplete"].is_boolean());
+
+    let items = completions["items"].as_array().unwrap();
+    for item in items {
+        let fields: Vec<&String> = item.as_object().unwrap().keys().collect();
+        assert_eq!(
+            fields,
+            [
+                "detail",
+                "documentation",
+                "insert_text",
+                "kind_name",
+                "label",
+                "sort_text"
+            ]
+        );
+        assert!(item["kind_name"].is_null() || item["kind_name"].is_string());
     }
-
-    // Parse and validate it's a non-empty array of edits
-    let edits: Vec<Value> = serde_json::from_str(text_str)?;
-    if edits.is_empty() {
-        eprintln!("Expected non-empty edits for unformatted file");
-        return Ok(false);
+    if let Some(add) = items.iter().find(|item| item["label"] == "add(…)") {
+        assert_eq!(add["kind_name"], "method");
     }
 
-    Ok(true)
+    Ok(())
 }
 
-async fn test_code_actions(client: &mut IpcClient, workspace_path: &Path) -> Result<bool> {
+#[tokio::test]
+async fn test_completion_resolve() -> Result<()> {
+    let mut client = IpcClient::get_or_create("test-project").await?;
+    let workspace_path = client.workspace_path().to_path_buf();
     let main_path = workspace_path.join("src/main.rs");
+
+    // Right after `calc.` in `let result = calc.add(2, 3);`, without resolving anything upfront
     let response = client
         .call_tool(
-            "rust_analyzer_code_actions",
+            "rust_analyzer_completion",
             json!({
                 "file_path": main_path.to_str().unwrap(),
-                "line": 13,
-                "character": 0,
-                "end_line": 16,
-                "end_character": 1
+                "line": 5,
+                "character": 22,
+                "trigger_character": ".",
+                "full": true,
+                "resolve_top_n": 0
             }),
         )
         .await?;
-
-    let Some(content) = response.get("content") else {
-        return Ok(false);
+    let completions: Value =
+        serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    let Some(item) = completions["items"]
+        .as_array()
+        .and_then(|items| items.iter().find(|item| item.get("data").is_some()))
+    else {
+        eprintln!("Got no resolvable completions (rust-analyzer may still be indexing)");
+        return Ok(());
     };
 
-    let Some(text) = content[0].get("text") else {
-        return Ok(false);
-    };
+    let response = client
+        .call_tool("rust_analyzer_completion_resolve", json!({ "item": item }))
+        .await?;
+    let resolved: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    assert_eq!(resolved["label"], item["label"]);
 
-    let Some(text_str) = text.as_str() else {
+    let result = client
+        .call_tool("rust_analyzer_completion_resolve", json!({}))
+        .await;
+    assert!(result.is_err(), "Resolving requires an item");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_references_deduplicated() -> Result<()> {
+    let mut client = IpcClient::get_or_create("test-project").await?;
+    let workspace_path = client.workspace_path().to_path_buf();
+    let main_path = workspace_path.join("src/main.rs");
+
+    // `greet` in `fn greet(name: &str) -> String` (0-indexed line 13), called from `main` and a
+    // test.
+    let args = json!({
+        "file_path": main_path.to_str().unwrap(),
+        "line": 13,
+        "character": 4
+    });
+    let response = client
+        .call_tool("rust_analyzer_references", args.clone())
+        .await?;
+    let references: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    let locations = references["locations"].as_array().unwrap();
+    if locations.is_empty() {
+        eprintln!("Got no references (rust-analyzer may still be indexing)");
+        return Ok(());
+    }
+
+    assert_eq!(references["count"], locations.len());
+    let keys: Vec<(String, u64, u64)> = locations
+        .iter()
+        .map(|location| {
+            (
+                location["uri"].as_str().unwrap().to_string(),
+                location["range"]["start"]["line"].as_u64().unwrap(),
+                location["range"]["start"]["character"].as_u64().unwrap(),
+            )
+        })
+        .collect();
+    let mut sorted = keys.clone();
+    sorted.sort();
+    sorted.dedup();
+    assert_eq!(keys, sorted, "Got: {}", references);
+    // The declaration and both calls.
+    assert!(keys.iter().any(|(_, line, _)| *line == 13));
+    assert!(keys.len() >= 3, "Got: {}", references);
+    assert_eq!(references["total"], keys.len());
+    assert_eq!(references["has_more"], false);
+
+    // The window of the sorted references after the first two.
+    let mut page_args = args.clone();
+    page_args["offset"] = json!(2);
+    page_args["limit"] = json!(2);
+    let response = client
+        .call_tool("rust_analyzer_references", page_args)
+        .await?;
+    let page: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    let end = keys.len().min(4);
+    assert_eq!(page["total"], keys.len(), "Got: {}", page);
+    assert_eq!(page["count"], end - 2);
+    assert_eq!(page["has_more"], keys.len() > 4);
+    assert_eq!(page["locations"], json!(locations[2..end]));
+
+    let mut args = args;
+    args["include_declaration"] = json!(false);
+    let response = client.call_tool("rust_analyzer_references", args).await?;
+    let references: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    assert_eq!(references["count"], keys.len() - 1, "Got: {}", references);
+    assert!(references["locations"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .all(|location| location["range"]["start"]["line"] != 13));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_references_context_lines() -> Result<()> {
+    let mut client = IpcClient::get_or_create("test-project").await?;
+    let workspace_path = client.workspace_path().to_path_buf();
+    let main_path = workspace_path.join("src/main.rs");
+
+    // The `calc` binding, used on the following lines.
+    let response = client
+        .call_tool(
+            "rust_analyzer_references",
+            json!({
+                "file_path": main_path.to_str().unwrap(),
+                "line": 4,
+                "character": 8,
+                "context_lines": 1
+            }),
+        )
+        .await?;
+    let references: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    let Some(references) = references["locations"]
+        .as_array()
+        .filter(|refs| !refs.is_empty())
+    else {
+        eprintln!("Got no references (rust-analyzer may still be indexing)");
+        return Ok(());
+    };
+
+    for reference in references {
+        let line = reference["range"]["start"]["line"].as_u64().unwrap();
+        let snippet = reference["snippet"].as_str().unwrap();
+        assert_eq!(snippet.lines().count(), 3, "Got: {}", snippet);
+        assert!(
+            snippet.contains(&format!("{} | ", line + 1)),
+            "Got: {}",
+            snippet
+        );
+        assert!(snippet.contains("calc"));
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_hover_text() -> Result<()> {
+    let mut client = IpcClient::get_or_create("test-project").await?;
+    let workspace_path = client.workspace_path().to_path_buf();
+    let main_path = workspace_path.join("src/main.rs");
+
+    let response = client
+        .call_tool(
+            "rust_analyzer_hover",
+            json!({
+                "file_path": main_path.to_str().unwrap(),
+                "line": 4,
+                "character": 15,
+                "format": "text"
+            }),
+        )
+        .await?;
+    let hover: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    if hover.is_null() {
+        eprintln!("Got no hover (rust-analyzer may still be indexing)");
+        return Ok(());
+    }
+
+    let text = hover["text"].as_str().unwrap();
+    assert!(!text.contains("```"), "Got: {}", text);
+    assert!(hover.get("contents").is_none());
+    assert!(hover["range"]["start_line"].is_u64());
+
+    // The default markdown, and the raw LSP hover it's collapsed from.
+    let args = json!({
+        "file_path": main_path.to_str().unwrap(),
+        "line": 4,
+        "character": 15
+    });
+    let response = client
+        .call_tool("rust_analyzer_hover", args.clone())
+        .await?;
+    let hover: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    let markdown = hover["markdown"].as_str().unwrap();
+    assert!(markdown.contains("Calculator"), "Got: {}", markdown);
+    assert!(hover["range"]["start"]["line"].is_u64());
+
+    let mut raw_args = args;
+    raw_args["raw"] = json!(true);
+    let response = client.call_tool("rust_analyzer_hover", raw_args).await?;
+    let raw: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    assert!(raw["contents"].is_object(), "Got: {}", raw);
+    assert!(raw.get("markdown").is_none());
+
+    let result = client
+        .call_tool(
+            "rust_analyzer_hover",
+            json!({
+                "file_path": main_path.to_str().unwrap(),
+                "line": 4,
+                "character": 15,
+                "format": "html"
+            }),
+        )
+        .await;
+    assert!(result.is_err(), "Unknown formats are rejected");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_hover_expression() -> Result<()> {
+    let mut client = IpcClient::get_or_create("test-project").await?;
+    let workspace_path = client.workspace_path().to_path_buf();
+    let main_path = workspace_path.join("src/main.rs");
+
+    // `greet("World")` in `let message = greet("World");` (0-indexed line 1).
+    let response = client
+        .call_tool(
+            "rust_analyzer_hover",
+            json!({
+                "file_path": main_path.to_str().unwrap(),
+                "line": 1,
+                "character": 18,
+                "end_line": 1,
+                "end_character": 32
+            }),
+        )
+        .await?;
+    let hover: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    if hover.is_null() {
+        eprintln!("Got no hover (rust-analyzer may still be indexing)");
+        return Ok(());
+    }
+    let markdown = hover["markdown"].as_str().unwrap();
+    assert!(markdown.contains("String"), "Got: {}", markdown);
+
+    let result = client
+        .call_tool(
+            "rust_analyzer_hover",
+            json!({
+                "file_path": main_path.to_str().unwrap(),
+                "line": 1,
+                "character": 18,
+                "end_line": 1
+            }),
+        )
+        .await;
+    assert!(result.is_err(), "Half a range is rejected");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_hover_range() -> Result<()> {
+    let mut client = IpcClient::get_or_create("test-project").await?;
+    let workspace_path = client.workspace_path().to_path_buf();
+    let main_path = workspace_path.join("src/main.rs");
+
+    // The `Calculator` struct and its impl block (0-indexed lines 17-38)
+    let response = client
+        .call_tool(
+            "rust_analyzer_hover_range",
+            json!({
+                "file_path": main_path.to_str().unwrap(),
+                "line": 17,
+                "character": 0,
+                "end_line": 38,
+                "end_character": 1
+            }),
+        )
+        .await?;
+    let result: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+
+    assert_eq!(result["truncated"], false);
+    let hovers = result["hovers"].as_object().unwrap();
+
+    // Empty during initialization is normal for LSP
+    if hovers.is_empty() {
+        eprintln!("Got no hovers (rust-analyzer may still be indexing)");
+        return Ok(());
+    }
+
+    // `Calculator` on line 18 and `add` on line 27
+    assert!(
+        hovers
+            .get("17:7")
+            .is_some_and(|h| h.as_str().unwrap().contains("struct Calculator")),
+        "Got: {:?}",
+        hovers
+    );
+    assert!(
+        hovers
+            .get("26:7")
+            .is_some_and(|h| h.as_str().unwrap().contains("fn add")),
+        "Got: {:?}",
+        hovers
+    );
+    // `greet` is outside of the range
+    assert!(hovers
+        .values()
+        .all(|h| !h.as_str().unwrap().contains("fn greet")));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_goto_symbol() -> Result<()> {
+    let mut client = IpcClient::get_or_create("test-project").await?;
+
+    let response = client
+        .call_tool("rust_analyzer_goto_symbol", json!({ "name": "Calculator" }))
+        .await;
+    let response = match response {
+        Ok(response) => response,
+        // The symbol index may not be ready yet during initialization
+        Err(e) if e.to_string().contains("No symbol named") => {
+            eprintln!("Symbol not found yet (rust-analyzer may still be indexing)");
+            return Ok(());
+        }
+        Err(e) => return Err(e),
+    };
+    let symbol: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+
+    // `Calculator` is a struct defined in main.rs
+    assert_eq!(symbol["kind"], "struct");
+    assert!(symbol["location"]["uri"]
+        .as_str()
+        .unwrap()
+        .ends_with("src/main.rs"));
+    assert!(
+        symbol["hover"].to_string().contains("struct Calculator"),
+        "Hover should show the struct signature, got: {}",
+        symbol["hover"]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_external_file_changes() -> Result<()> {
+    let project = test_support::IsolatedProject::new()?;
+    let client = test_support::MCPTestClient::start(project.path()).await?;
+    client.initialize_and_wait().await?;
+
+    // Add a module behind the server's back, like an editor would.
+    std::fs::write(
+        project.file_path("src/extra.rs"),
+        "pub fn externally_added() {}\n",
+    )?;
+    // In main.rs rather than lib.rs, which the readiness check opened: rust-analyzer ignores the
+    // disk content of open files.
+    let main_path = project.file_path("src/main.rs");
+    let main = std::fs::read_to_string(&main_path)?;
+    std::fs::write(&main_path, format!("mod extra;\n{}", main))?;
+
+    let max_attempts = if is_ci() { 20 } else { 10 };
+    let mut symbols = Value::Null;
+    for _ in 0..max_attempts {
+        tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+        let response = client
+            .call_tool(
+                "rust_analyzer_workspace_symbols",
+                json!({ "query": "externally_added#" }),
+            )
+            .await?;
+        let result: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+        symbols = result["symbols"].clone();
+        if symbols
+            .as_array()
+            .is_some_and(|symbols| !symbols.is_empty())
+        {
+            break;
+        }
+    }
+
+    client.shutdown().await?;
+
+    assert_eq!(symbols[0]["name"], "externally_added", "Got: {}", symbols);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_batch() -> Result<()> {
+    let mut client = IpcClient::get_or_create("test-project").await?;
+    let main_path = client.workspace_path().join("src/main.rs");
+    let main_path = main_path.to_str().unwrap();
+
+    let response = client
+        .call_tool(
+            "rust_analyzer_batch",
+            json!({
+                "calls": [
+                    { "tool": "rust_analyzer_symbols", "arguments": { "file_path": main_path } },
+                    {
+                        "tool": "rust_analyzer_hover",
+                        "arguments": { "file_path": main_path, "line": 4, "character": 15 }
+                    },
+                    { "tool": "rust_analyzer_diagnostics", "arguments": { "file_path": main_path } },
+                    { "tool": "rust_analyzer_hover", "arguments": {} }
+                ]
+            }),
+        )
+        .await?;
+    let results: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    let results = results.as_array().unwrap();
+
+    // One entry per call, in order.
+    assert_eq!(results.len(), 4);
+    assert_eq!(results[0]["tool"], "rust_analyzer_symbols");
+    assert!(results[0]["result"].is_array(), "Got: {}", results[0]);
+    assert_eq!(results[1]["tool"], "rust_analyzer_hover");
+    assert!(results[1].get("error").is_none(), "Got: {}", results[1]);
+    assert_eq!(results[2]["tool"], "rust_analyzer_diagnostics");
+    assert!(
+        results[2]["result"]["summary"].is_object(),
+        "Got: {}",
+        results[2]
+    );
+
+    // The failing call doesn't fail the batch.
+    assert_eq!(results[3]["error"]["code"], -32602);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_workspace_symbols_regex() -> Result<()> {
+    let mut client = IpcClient::get_or_create("test-project").await?;
+
+    let response = client
+        .call_tool(
+            "rust_analyzer_workspace_symbols",
+            json!({ "query": "/^process/" }),
+        )
+        .await?;
+    let result: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    let symbols = &result["symbols"];
+    let names: Vec<&str> = symbols
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|symbol| symbol["name"].as_str().unwrap())
+        .collect();
+
+    // `process` matches exactly, so it comes before `process_value`.
+    assert_eq!(names, ["process", "process_value"], "Got: {}", symbols);
+    assert_eq!(symbols[0]["match"], "exact");
+    assert_eq!(symbols[0]["kind"], "function");
+    assert_eq!(symbols[1]["match"], "prefix");
+    assert_eq!(result["total"], 2);
+    assert_eq!(result["has_more"], false);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_workspace_symbols_pagination() -> Result<()> {
+    let mut client = IpcClient::get_or_create("test-project").await?;
+
+    // Every symbol of the test project, of which there are more than 4.
+    let args = json!({ "query": "/./" });
+    let response = client
+        .call_tool("rust_analyzer_workspace_symbols", args.clone())
+        .await?;
+    let all: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    let total = all["total"].as_u64().unwrap();
+    assert!(total > 4, "Got: {}", all);
+
+    let mut args = args;
+    args["offset"] = json!(2);
+    args["limit"] = json!(2);
+    let response = client
+        .call_tool("rust_analyzer_workspace_symbols", args)
+        .await?;
+    let page: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    assert_eq!(page["total"], total);
+    assert_eq!(page["has_more"], true);
+    assert_eq!(
+        page["symbols"],
+        json!(all["symbols"].as_array().unwrap()[2..4])
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_move_item() -> Result<()> {
+    let mut client = IpcClient::get_or_create("test-project").await?;
+    let workspace_path = client.workspace_path().to_path_buf();
+    let main_path = workspace_path.join("src/main.rs");
+
+    // `greet` on line 14 (0-indexed line 13), right below `main`
+    let response = client
+        .call_tool(
+            "rust_analyzer_move_item",
+            json!({
+                "file_path": main_path.to_str().unwrap(),
+                "line": 13,
+                "character": 4,
+                "end_line": 13,
+                "end_character": 4,
+                "direction": "up"
+            }),
+        )
+        .await?;
+    let edits: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+
+    // null or empty during initialization is normal for LSP
+    let Some(edits) = edits.as_array().filter(|edits| !edits.is_empty()) else {
+        eprintln!("Got no edits (rust-analyzer may still be indexing)");
+        return Ok(());
+    };
+    assert!(
+        edits.iter().any(|edit| edit["newText"]
+            .as_str()
+            .is_some_and(|text| text.contains("fn greet"))),
+        "Moving `greet` up should rewrite it above `main`, got: {:?}",
+        edits
+    );
+
+    let response = client
+        .call_tool(
+            "rust_analyzer_move_item",
+            json!({
+                "file_path": main_path.to_str().unwrap(),
+                "line": 13,
+                "character": 4,
+                "end_line": 13,
+                "end_character": 4,
+                "direction": "sideways"
+            }),
+        )
+        .await;
+    assert!(response.is_err(), "Invalid direction should be rejected");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_format_incomplete_range() -> Result<()> {
+    let mut client = IpcClient::get_or_create("test-project").await?;
+    let workspace_path = client.workspace_path().to_path_buf();
+    let unformatted_path = workspace_path.join("src/unformatted.rs");
+
+    let response = client
+        .call_tool(
+            "rust_analyzer_format",
+            json!({
+                "file_path": unformatted_path.to_str().unwrap(),
+                "start_line": 0,
+                "end_line": 2
+            }),
+        )
+        .await;
+
+    let err = response.expect_err("A partial range should be rejected");
+    assert!(
+        err.to_string().contains("start_character"),
+        "Unexpected error: {}",
+        err
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_format_range() -> Result<()> {
+    let mut client = IpcClient::get_or_create("test-project").await?;
+    let workspace_path = client.workspace_path().to_path_buf();
+    let unformatted_path = workspace_path.join("src/unformatted.rs");
+
+    let response = client
+        .call_tool(
+            "rust_analyzer_format_range",
+            json!({
+                "file_path": unformatted_path.to_str().unwrap(),
+                "start_line": 0,
+                "start_character": 0,
+                "end_line": 2,
+                "end_character": 0
+            }),
+        )
+        .await;
+
+    // Range formatting needs a nightly rustfmt, so an error from rust-analyzer is acceptable.
+    if let Ok(response) = response {
+        let text = response["content"][0]["text"].as_str().unwrap();
+        let edits: Value = serde_json::from_str(text)?;
+        assert!(
+            edits.is_array() || edits.is_null(),
+            "Expected a list of edits, got: {}",
+            edits
+        );
+    }
+
+    let response = client
+        .call_tool(
+            "rust_analyzer_format_range",
+            json!({ "file_path": unformatted_path.to_str().unwrap() }),
+        )
+        .await;
+    assert!(response.is_err(), "A missing range should be rejected");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_format_apply() -> Result<()> {
+    // Work on a copy so the shared project's unformatted file stays unformatted
+    let project = test_support::IsolatedProject::new()?;
+    let client = test_support::MCPTestClient::start(project.path()).await?;
+    client.initialize().await?;
+    let unformatted_path = project.file_path("src/unformatted.rs");
+    let original = std::fs::read_to_string(&unformatted_path)?;
+
+    let response = client
+        .call_tool(
+            "rust_analyzer_format",
+            json!({
+                "file_path": unformatted_path.to_str().unwrap(),
+                "apply": true
+            }),
+        )
+        .await?;
+    let result: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+
+    assert!(result["applied"].is_boolean());
+    assert!(result["edits_count"].is_number());
+
+    let formatted = std::fs::read_to_string(&unformatted_path)?;
+    if result["applied"] == true {
+        assert!(result["edits_count"].as_u64().unwrap() > 0);
+        assert_ne!(
+            formatted, original,
+            "Formatting should have been written to disk"
+        );
+    } else {
+        assert_eq!(result["edits_count"], 0);
+        assert_eq!(formatted, original);
+    }
+
+    client.shutdown().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_list_files() -> Result<()> {
+    let mut client = IpcClient::get_or_create("test-project").await?;
+
+    let response = client
+        .call_tool("rust_analyzer_list_files", json!({}))
+        .await?;
+    let listing: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    let files: Vec<&str> = listing["files"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|f| f.as_str())
+        .collect();
+
+    assert!(files.contains(&"src/lib.rs"), "Got: {:?}", files);
+    assert!(files.contains(&"src/types.rs"), "Got: {:?}", files);
+    assert!(files.iter().all(|f| !f.starts_with("target/")));
+    assert_eq!(listing["count"].as_u64(), Some(files.len() as u64));
+
+    let response = client
+        .call_tool(
+            "rust_analyzer_list_files",
+            json!({ "pattern": "src/t*.rs" }),
+        )
+        .await?;
+    let listing: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    assert_eq!(listing["files"], json!(["src/types.rs"]));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_status() -> Result<()> {
+    let mut client = IpcClient::get_or_create("test-project").await?;
+
+    let response = client.call_tool("rust_analyzer_status", json!({})).await?;
+    let status: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+
+    assert!(
+        matches!(
+            status["status"].as_str(),
+            Some("indexing" | "checking" | "ready" | "idle")
+        ),
+        "Unexpected status: {}",
+        status
+    );
+    assert!(status["progress"].is_array());
+    assert!(status["check_completed"].is_boolean());
+    assert!(status.get("analyzer_status").is_none());
+
+    // rust-analyzer's own report, scoped to a file.
+    let main_path = client.workspace_path().join("src/main.rs");
+    let response = client
+        .call_tool(
+            "rust_analyzer_status",
+            json!({ "file_path": main_path.to_str().unwrap() }),
+        )
+        .await?;
+    let status: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    let report = status["analyzer_status"].as_str().unwrap();
+    assert!(report.contains("test_project"), "Got: {}", report);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_list_changed() -> Result<()> {
+    let project = test_support::IsolatedProject::new()?;
+    let client = test_support::MCPTestClient::start(project.path()).await?;
+
+    let init = client.initialize().await?;
+    let capabilities = &init["capabilities"];
+    assert_eq!(capabilities["tools"]["listChanged"], true);
+    assert_eq!(capabilities["resources"]["listChanged"], true);
+    assert!(capabilities["prompts"].is_object());
+
+    // Changing the workspace changes the resources, and maybe the tools.
+    client.set_workspace(project.path()).await?;
+    let methods: Vec<Value> = client
+        .notifications()
+        .await
+        .into_iter()
+        .map(|notification| notification["method"].clone())
+        .collect();
+    assert!(methods.contains(&json!("notifications/tools/list_changed")));
+    assert!(methods.contains(&json!("notifications/resources/list_changed")));
+
+    client.shutdown().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_prompts() -> Result<()> {
+    let project = test_support::IsolatedProject::new()?;
+    let client = test_support::MCPTestClient::start(project.path()).await?;
+    let init = client.initialize().await?;
+    assert!(init["capabilities"]["prompts"].is_object());
+
+    let list = client.send_request("prompts/list", None).await?;
+    let names: Vec<&str> = list["prompts"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|prompt| prompt["name"].as_str())
+        .collect();
+    assert_eq!(names, ["fix-all-errors", "explain-symbol"]);
+
+    let prompt = client
+        .send_request(
+            "prompts/get",
+            Some(json!({
+                "name": "explain-symbol",
+                "arguments": { "file_path": "src/main.rs", "line": "5", "character": "22" }
+            })),
+        )
+        .await?;
+    let text = prompt["messages"][0]["content"]["text"].as_str().unwrap();
+    assert!(
+        text.contains("line 5, character 22 of src/main.rs"),
+        "Got: {}",
+        text
+    );
+
+    let response = client
+        .send_message(&json!({
+            "jsonrpc": "2.0",
+            "id": 3100,
+            "method": "prompts/get",
+            "params": { "name": "explain-symbol", "arguments": {} }
+        }))
+        .await?;
+    assert_eq!(response["error"]["code"], -32602);
+
+    client.shutdown().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_resources() -> Result<()> {
+    let project = test_support::IsolatedProject::new()?;
+    let client = test_support::MCPTestClient::start(project.path()).await?;
+    let init = client.initialize().await?;
+    assert!(init["capabilities"]["resources"].is_object());
+
+    let list = client.send_request("resources/list", None).await?;
+    let lib = list["resources"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|resource| resource["name"] == "src/lib.rs")
+        .cloned()
+        .expect("lib.rs should be listed");
+    assert_eq!(lib["mimeType"], "text/x-rust");
+
+    let read = client
+        .send_request("resources/read", Some(json!({ "uri": lib["uri"] })))
+        .await?;
+    assert_eq!(read["contents"][0]["uri"], lib["uri"]);
+    assert_eq!(
+        read["contents"][0]["text"],
+        std::fs::read_to_string(project.file_path("src/lib.rs"))?
+    );
+
+    let response = client
+        .send_message(&json!({
+            "jsonrpc": "2.0",
+            "id": 3000,
+            "method": "resources/read",
+            "params": { "uri": "file:///etc/passwd" }
+        }))
+        .await?;
+    assert_eq!(response["error"]["code"], -32002);
+
+    client.shutdown().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_server_status() -> Result<()> {
+    let mut client = IpcClient::get_or_create("test-project").await?;
+
+    let response = client
+        .call_tool("rust_analyzer_server_status", json!({}))
+        .await?;
+    let status: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+
+    // The test project loads fine, but other tests may keep rust-analyzer busy.
+    assert!(
+        matches!(status["status"].as_str(), Some("loading" | "ready")),
+        "Unexpected status: {}",
+        status
+    );
+    assert!(status["message"].is_null());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_completion_while_indexing() -> Result<()> {
+    let project = test_support::IsolatedProject::new()?;
+    let main_path = project.file_path("src/main.rs");
+    let client = test_support::MCPTestClient::start(project.path()).await?;
+    client.initialize().await?;
+
+    // Start of `println!` in `main` (0-indexed line 2), where everything in scope completes
+    let args = json!({
+        "file_path": main_path.to_str().unwrap(),
+        "line": 2,
+        "character": 4
+    });
+
+    // The first call starts rust-analyzer, which may or may not have completions that early, but
+    // is never just `null`.
+    let response = client
+        .call_tool("rust_analyzer_completion", args.clone())
+        .await?;
+    let completions: Value =
+        serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    assert!(
+        completions["status"] == "indexing" || completions["items"].is_array(),
+        "Got: {}",
+        completions
+    );
+
+    client
+        .call_tool_with_timeout(
+            "rust_analyzer_wait_for_analysis",
+            json!({ "timeout_ms": 120000 }),
+            Duration::from_secs(150),
+        )
+        .await?;
+    let response = client.call_tool("rust_analyzer_completion", args).await?;
+    let completions: Value =
+        serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    assert!(
+        completions["items"]
+            .as_array()
+            .is_some_and(|items| !items.is_empty()),
+        "Got: {}",
+        completions
+    );
+
+    client.shutdown().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_wait_for_analysis() -> Result<()> {
+    let project = test_support::IsolatedProject::new()?;
+    let client = test_support::MCPTestClient::start(project.path()).await?;
+    client.initialize().await?;
+
+    let response = client
+        .call_tool_with_timeout(
+            "rust_analyzer_wait_for_analysis",
+            json!({ "timeout_ms": 120000 }),
+            Duration::from_secs(150),
+        )
+        .await?;
+    let result: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    assert_eq!(result["ready"], true, "Got: {}", result);
+    assert_eq!(result["server_status"]["status"], "ready");
+    assert!(!matches!(
+        result["progress"]["status"].as_str(),
+        Some("indexing" | "checking")
+    ));
+
+    // Once analyzed, symbols are there right away.
+    let response = client
+        .call_tool(
+            "rust_analyzer_symbols",
+            json!({ "file_path": project.file_path("src/lib.rs").to_str().unwrap() }),
+        )
+        .await?;
+    let symbols: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    assert!(symbols
+        .as_array()
+        .is_some_and(|symbols| !symbols.is_empty()));
+
+    client.shutdown().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_memory_usage() -> Result<()> {
+    let mut client = IpcClient::get_or_create("test-project").await?;
+
+    let response = client
+        .call_tool("rust_analyzer_memory_usage", json!({}))
+        .await?;
+    let text = response["content"][0]["text"].as_str().unwrap();
+
+    assert!(!text.is_empty(), "Memory usage report should not be empty");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reload_workspace() -> Result<()> {
+    let mut client = IpcClient::get_or_create("test-project").await?;
+
+    let response = client
+        .call_tool("rust_analyzer_reload_workspace", json!({}))
+        .await?;
+    let result: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+
+    assert_eq!(result["reloaded"], true);
+    assert!(result["indexing_complete"].is_boolean());
+    assert!(result["status"]["status"].is_string());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_set_check_command() -> Result<()> {
+    let mut client = IpcClient::get_or_create("test-project").await?;
+
+    let response = client
+        .call_tool(
+            "rust_analyzer_set_check_command",
+            json!({ "command": "clippy" }),
+        )
+        .await?;
+    let text = response["content"][0]["text"].as_str().unwrap();
+    assert_eq!(text, "Check command set to: clippy");
+
+    let result = client
+        .call_tool(
+            "rust_analyzer_set_check_command",
+            json!({ "command": "build" }),
+        )
+        .await;
+    assert!(result.is_err(), "Invalid check command should be rejected");
+
+    // Restore the default for the other tests sharing the client.
+    client
+        .call_tool(
+            "rust_analyzer_set_check_command",
+            json!({ "command": "check" }),
+        )
+        .await?;
+
+    Ok(())
+}
+
+// Helper functions for test_all_lsp_tools
+
+async fn test_symbols(client: &mut IpcClient, workspace_path: &Path) -> Result<()> {
+    let main_path = workspace_path.join("src/main.rs");
+    let response = client
+        .call_tool(
+            "rust_analyzer_symbols",
+            json!({
+                "file_path": main_path.to_str().unwrap()
+            }),
+        )
+        .await?;
+
+    let Some(content) = response.get("content") else {
+        return Err(anyhow::anyhow!("No content in symbols response"));
+    };
+
+    let Some(text) = content[0].get("text") else {
+        return Err(anyhow::anyhow!("No text in symbols response"));
+    };
+
+    let Some(text_str) = text.as_str() else {
+        return Err(anyhow::anyhow!("Text is not a string"));
+    };
+
+    let symbols: Vec<Value> = serde_json::from_str(text_str)?;
+    assert!(!symbols.is_empty(), "Should have symbols in main.rs");
+
+    let symbol_names: Vec<String> = symbols
+        .iter()
+        .filter_map(|s| s.get("name")?.as_str().map(String::from))
+        .collect();
+
+    assert!(
+        symbol_names.contains(&"main".to_string()),
+        "Should have main function"
+    );
+    assert!(
+        symbol_names.contains(&"greet".to_string()),
+        "Should have greet function"
+    );
+    assert!(
+        symbol_names.contains(&"Calculator".to_string()),
+        "Should have Calculator struct"
+    );
+
+    Ok(())
+}
+
+async fn test_definition(client: &mut IpcClient, workspace_path: &Path) -> Result<bool> {
+    let main_path = workspace_path.join("src/main.rs");
+    let response = client
+        .call_tool(
+            "rust_analyzer_definition",
+            json!({
+                "file_path": main_path.to_str().unwrap(),
+                "line": 1,
+                "character": 18
+            }),
+        )
+        .await?;
+
+    let Some(content) = response.get("content") else {
+        return Ok(false);
+    };
+
+    if !content.is_array() || content[0].is_null() {
+        return Ok(false);
+    }
+
+    let Some(text) = content[0].get("text") else {
+        return Ok(false);
+    };
+
+    let Some(text_str) = text.as_str() else {
+        return Ok(false);
+    };
+
+    // null or empty array during initialization is normal for LSP.
+    // We just check that we got a valid response.
+    if text_str == "null" || text_str == "[]" {
+        // This is a valid response during initialization.
+        return Ok(true);
+    }
+
+    // Try to parse as array
+    let Ok(definitions) = serde_json::from_str::<Vec<Value>>(text_str) else {
+        return Ok(false);
+    };
+
+    Ok(!definitions.is_empty())
+}
+
+async fn test_references(client: &mut IpcClient, workspace_path: &Path) -> Result<bool> {
+    let main_path = workspace_path.join("src/main.rs");
+    let response = client
+        .call_tool(
+            "rust_analyzer_references",
+            json!({
+                "file_path": main_path.to_str().unwrap(),
+                "line": 9,
+                "character": 4
+            }),
+        )
+        .await?;
+
+    let Some(content) = response.get("content") else {
+        return Ok(false);
+    };
+
+    let Some(text) = content[0].get("text") else {
+        return Ok(false);
+    };
+
+    let Some(text_str) = text.as_str() else {
+        return Ok(false);
+    };
+
+    let references: Value = serde_json::from_str(text_str)?;
+    Ok(references["count"].as_u64().is_some_and(|count| count > 0))
+}
+
+async fn test_hover(client: &mut IpcClient, workspace_path: &Path) -> Result<bool> {
+    let main_path = workspace_path.join("src/main.rs");
+    let response = client
+        .call_tool(
+            "rust_analyzer_hover",
+            json!({
+                "file_path": main_path.to_str().unwrap(),
+                "line": 4,
+                "character": 15
+            }),
+        )
+        .await?;
+
+    let Some(content) = response.get("content") else {
+        return Ok(false);
+    };
+
+    let Some(text) = content[0].get("text") else {
+        return Ok(false);
+    };
+
+    if text.as_str() == Some("null") {
+        return Ok(false);
+    }
+
+    let Some(text_str) = text.as_str() else {
+        return Ok(false);
+    };
+
+    let hover: Value = serde_json::from_str(text_str)?;
+    Ok(hover["markdown"].is_string())
+}
+
+async fn test_completion(client: &mut IpcClient, workspace_path: &Path) -> Result<()> {
+    let main_path = workspace_path.join("src/main.rs");
+    let response = client
+        .call_tool(
+            "rust_analyzer_completion",
+            json!({
+                "file_path": main_path.to_str().unwrap(),
+                "line": 2,
+                "character": 5
+            }),
+        )
+        .await?;
+
+    let Some(content) = response.get("content") else {
+        return Ok(());
+    };
+
+    let Some(text) = content[0].get("text") else {
+        return Ok(());
+    };
+
+    let Some(text_str) = text.as_str() else {
+        return Ok(());
+    };
+
+    // Handle "null" response specially
+    if text_str == "null" {
+        // rust-analyzer returned null - still indexing
+        eprintln!("Got null completion response (rust-analyzer may still be indexing)");
+        return Ok(());
+    }
+
+    let completions: Value = serde_json::from_str(text_str)?;
+    assert!(
+        completions.is_object() || completions.is_array() || completions.is_null(),
+        "Expected object, array, or null, got: {:?}",
+        completions
+    );
+
+    Ok(())
+}
+
+async fn test_format(client: &mut IpcClient, workspace_path: &Path) -> Result<bool> {
+    // Test 1: Format already-formatted file - should return null (no edits needed)
+    let main_path = workspace_path.join("src/main.rs");
+    let response = client
+        .call_tool(
+            "rust_analyzer_format",
+            json!({
+                "file_path": main_path.to_str().unwrap()
+            }),
+        )
+        .await?;
+
+    let Some(content) = response.get("content") else {
+        return Ok(false);
+    };
+
+    let Some(text) = content[0].get("text") else {
+        return Ok(false);
+    };
+
+    let Some(text_str) = text.as_str() else {
+        return Ok(false);
+    };
+
+    // main.rs is already formatted, so should return null
+    if text_str != "null" {
+        eprintln!("Expected null for formatted file, got: {}", text_str);
+        return Ok(false);
+    }
+
+    // Test 2: Format unformatted file - should return edits
+    let unformatted_path = workspace_path.join("src/unformatted.rs");
+    let response = client
+        .call_tool(
+            "rust_analyzer_format",
+            json!({
+                "file_path": unformatted_path.to_str().unwrap()
+            }),
+        )
+        .await?;
+
+    let Some(content) = response.get("content") else {
+        return Ok(false);
+    };
+
+    let Some(text) = content[0].get("text") else {
+        return Ok(false);
+    };
+
+    let Some(text_str) = text.as_str() else {
+        return Ok(false);
+    };
+
+    // unformatted.rs needs formatting, so should return an array of edits
+    if text_str == "null" {
+        eprintln!("Expected edits for unformatted file, got null");
+        return Ok(false);
+    }
+
+    // Parse and validate it's a non-empty array of edits
+    let edits: Vec<Value> = serde_json::from_str(text_str)?;
+    if edits.is_empty() {
+        eprintln!("Expected non-empty edits for unformatted file");
         return Ok(false);
+    }
+
+    Ok(true)
+}
+
+async fn test_code_actions(client: &mut IpcClient, workspace_path: &Path) -> Result<bool> {
+    let main_path = workspace_path.join("src/main.rs");
+    let response = client
+        .call_tool(
+            "rust_analyzer_code_actions",
+            json!({
+                "file_path": main_path.to_str().unwrap(),
+                "line": 13,
+                "character": 0,
+                "end_line": 16,
+                "end_character": 1
+            }),
+        )
+        .await?;
+
+    let Some(content) = response.get("content") else {
+        return Ok(false);
+    };
+
+    let Some(text) = content[0].get("text") else {
+        return Ok(false);
+    };
+
+    let Some(text_str) = text.as_str() else {
+        return Ok(false);
+    };
+
+    // Check if we got null or empty array
+    if text_str == "null" || text_str == "[]" {
+        return Ok(false);
+    }
+
+    // Try to parse as array to verify it's valid JSON
+    let Ok(_actions) = serde_json::from_str::<Vec<Value>>(text_str) else {
+        return Ok(false);
+    };
+
+    // Even if we get an empty array, that's better than null
+    // Some files genuinely might not have code actions available
+    Ok(true)
+}
+
+#[tokio::test]
+async fn test_rename_file() -> Result<()> {
+    let project = test_support::IsolatedProject::new()?;
+    let client = test_support::MCPTestClient::start(project.path()).await?;
+    client.initialize_and_wait().await?;
+
+    let args = json!({
+        "old_path": "src/utils.rs",
+        "new_path": "src/util/mod.rs"
+    });
+    let response = client.call_tool("rust_analyzer_rename_file", args).await?;
+    let preview: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    assert!(preview.get("edit").is_some());
+    assert!(
+        project.file_path("src/utils.rs").exists(),
+        "Nothing should change without apply"
+    );
+
+    let args = json!({
+        "old_path": "src/utils.rs",
+        "new_path": "src/util/mod.rs",
+        "apply": true
+    });
+    let response = client.call_tool("rust_analyzer_rename_file", args).await?;
+    let result: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    assert_eq!(result["applied"], true);
+
+    assert!(!project.file_path("src/utils.rs").exists());
+    assert!(project.file_path("src/util/mod.rs").exists());
+    if result["edits_count"].as_u64().unwrap_or(0) > 0 {
+        let lib = std::fs::read_to_string(project.file_path("src/lib.rs"))?;
+        assert!(lib.contains("pub mod util;"), "Got: {}", lib);
+    }
+
+    // Renaming onto an existing file is rejected.
+    let args = json!({
+        "old_path": "src/types.rs",
+        "new_path": "src/lib.rs"
+    });
+    assert!(client
+        .call_tool("rust_analyzer_rename_file", args)
+        .await
+        .is_err());
+
+    client.shutdown().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_create_file() -> Result<()> {
+    let project = test_support::IsolatedProject::new()?;
+    let client = test_support::MCPTestClient::start(project.path()).await?;
+    client.initialize().await?;
+
+    // Missing parent directories are created, and the module gets a doc comment by default.
+    let args = json!({
+        "file_path": "src/parsing/tokens.rs",
+        "open_document": true
+    });
+    let response = client.call_tool("rust_analyzer_create_file", args).await?;
+    let result: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    assert_eq!(result["created_path"], "src/parsing/tokens.rs");
+
+    let content = std::fs::read_to_string(project.file_path("src/parsing/tokens.rs"))?;
+    assert_eq!(content, "//! The `tokens` module.\n");
+
+    let response = client
+        .call_tool("rust_analyzer_open_documents", json!({}))
+        .await?;
+    let documents: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    let is_open = documents["documents"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|doc| {
+            doc["uri"]
+                .as_str()
+                .is_some_and(|uri| uri.ends_with("src/parsing/tokens.rs"))
+        });
+    assert!(is_open, "Got: {}", documents);
+
+    // Existing files are never overwritten.
+    let args = json!({
+        "file_path": "src/lib.rs",
+        "content": "fn main() {}\n"
+    });
+    let err = client
+        .call_tool("rust_analyzer_create_file", args)
+        .await
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("File already exists"),
+        "Got: {}",
+        err
+    );
+    let lib = std::fs::read_to_string(project.file_path("src/lib.rs"))?;
+    assert!(lib.contains("pub mod types;"), "Got: {}", lib);
+
+    client.shutdown().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_write_file() -> Result<()> {
+    let project = test_support::IsolatedProject::new()?;
+    let client = test_support::MCPTestClient::start(project.path()).await?;
+    client.initialize_and_wait().await?;
+
+    let version = || async {
+        let response = client
+            .call_tool("rust_analyzer_open_documents", json!({}))
+            .await?;
+        let documents: Value =
+            serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+        let version = documents["documents"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|doc| {
+                doc["uri"]
+                    .as_str()
+                    .is_some_and(|uri| uri.ends_with("src/types.rs"))
+            })
+            .and_then(|doc| doc["version"].as_u64());
+        Ok::<_, anyhow::Error>(version)
+    };
+
+    // Writing an open document sends its new content to rust-analyzer as a new version.
+    client
+        .call_tool(
+            "rust_analyzer_symbols",
+            json!({ "file_path": "src/types.rs" }),
+        )
+        .await?;
+    let before = version().await?.expect("src/types.rs should be open");
+    let content = std::fs::read_to_string(project.file_path("src/types.rs"))?
+        + "\npub const ANSWER: u32 = 42;\n";
+    let args = json!({ "file_path": "src/types.rs", "content": content });
+    let response = client.call_tool("rust_analyzer_write_file", args).await?;
+    let result: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    assert_eq!(result["written"], true);
+    assert_eq!(result["created"], false);
+    assert_eq!(
+        std::fs::read_to_string(project.file_path("src/types.rs"))?,
+        content
+    );
+    let after = version().await?.expect("src/types.rs should still be open");
+    assert!(
+        after > before,
+        "Version {} should be above {}",
+        after,
+        before
+    );
+
+    // A new file is announced to rust-analyzer, so that a module declared for it resolves.
+    let args = json!({
+        "file_path": "src/extra.rs",
+        "content": "pub fn extra() -> u32 {\n    42\n}\n"
+    });
+    let response = client.call_tool("rust_analyzer_write_file", args).await?;
+    let result: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    assert_eq!(result["created"], true);
+
+    let lib =
+        "pub mod extra;\n".to_string() + &std::fs::read_to_string(project.file_path("src/lib.rs"))?;
+    let args = json!({ "file_path": "src/lib.rs", "content": lib });
+    client.call_tool("rust_analyzer_write_file", args).await?;
+
+    // `extra` in `pub mod extra;`.
+    let args = json!({ "file_path": "src/lib.rs", "line": 0, "character": 8 });
+    let response = client.call_tool("rust_analyzer_definition", args).await?;
+    let definition: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    if definition.is_null() {
+        eprintln!("Got null definition (rust-analyzer may still be indexing)");
+    } else {
+        assert!(
+            definition.to_string().contains("src/extra.rs"),
+            "Got: {}",
+            definition
+        );
+    }
+
+    // Writing outside the workspace is rejected, whether through `..` or an absolute path.
+    let outside = std::env::temp_dir().join("rust-analyzer-mcp-outside.rs");
+    for (id, file_path) in [
+        (1, "../outside.rs".to_string()),
+        (2, outside.display().to_string()),
+    ] {
+        let response = client
+            .send_message(&json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "method": "tools/call",
+                "params": {
+                    "name": "rust_analyzer_write_file",
+                    "arguments": { "file_path": file_path, "content": "fn main() {}\n" }
+                }
+            }))
+            .await?;
+        assert_eq!(response["error"]["code"], -32002, "Got: {}", response);
+        assert_eq!(response["error"]["data"]["kind"], "PathEscapesWorkspace");
+    }
+    assert!(!project.path().join("../outside.rs").exists());
+    assert!(!outside.exists());
+
+    client.shutdown().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_read_file() -> Result<()> {
+    let project = test_support::IsolatedProject::new()?;
+    let client = test_support::MCPTestClient::start(project.path()).await?;
+    client.initialize().await?;
+
+    let response = client
+        .call_tool(
+            "rust_analyzer_read_file",
+            json!({ "file_path": "src/lib.rs" }),
+        )
+        .await?;
+    let result: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    let content = std::fs::read_to_string(project.file_path("src/lib.rs"))?;
+    assert_eq!(result["content"], content.as_str());
+    assert_eq!(result["lines"], content.lines().count());
+    assert_eq!(result["bytes"], content.len());
+    assert_eq!(result["encoding"], "utf-8");
+
+    let response = client
+        .send_message(&json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "tools/call",
+            "params": {
+                "name": "rust_analyzer_read_file",
+                "arguments": { "file_path": "../Cargo.toml" }
+            }
+        }))
+        .await?;
+    assert_eq!(response["error"]["code"], -32002, "Got: {}", response);
+    assert_eq!(response["error"]["data"]["kind"], "PathEscapesWorkspace");
+
+    client.shutdown().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_diff_file() -> Result<()> {
+    let project = test_support::IsolatedProject::new()?;
+    let client = test_support::MCPTestClient::start(project.path()).await?;
+    client.initialize().await?;
+
+    // Rename the first module and declare a new one after the second.
+    let content = std::fs::read_to_string(project.file_path("src/lib.rs"))?;
+    let new_content = content
+        .replacen("pub mod types;", "pub mod kinds;", 1)
+        .replacen("pub mod utils;\n", "pub mod utils;\npub mod extra;\n", 1);
+    let args = json!({ "file_path": "src/lib.rs", "new_content": new_content });
+    let response = client.call_tool("rust_analyzer_diff_file", args).await?;
+    let result: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    assert_eq!(result["insertions"], 2, "Got: {}", result);
+    assert_eq!(result["deletions"], 1, "Got: {}", result);
+    assert_eq!(result["changed_lines"], json!([0, 2]));
+    let diff = result["diff"].as_str().unwrap();
+    assert!(diff.contains("-pub mod types;"), "Got: {}", diff);
+    assert!(diff.contains("+pub mod extra;"), "Got: {}", diff);
+
+    // It's only a preview.
+    assert_eq!(
+        std::fs::read_to_string(project.file_path("src/lib.rs"))?,
+        content
+    );
+
+    client.shutdown().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rename() -> Result<()> {
+    let project = test_support::IsolatedProject::new()?;
+    let client = test_support::MCPTestClient::start(project.path()).await?;
+    client.initialize_and_wait().await?;
+
+    // `greet` is defined on line 14 of main.rs and called from `main` and a test.
+    let args = json!({
+        "file_path": "src/main.rs",
+        "line": 13,
+        "character": 3,
+        "new_name": "welcome"
+    });
+    let response = client
+        .call_tool("rust_analyzer_rename", args.clone())
+        .await?;
+    let preview: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    assert_eq!(preview["applied"], false);
+    assert!(preview.get("edit").is_some());
+    let main = std::fs::read_to_string(project.file_path("src/main.rs"))?;
+    assert!(
+        main.contains("fn greet("),
+        "Nothing should change without apply"
+    );
+
+    let mut apply_args = args;
+    apply_args["apply"] = json!(true);
+    let response = client.call_tool("rust_analyzer_rename", apply_args).await?;
+    let result: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    assert_eq!(result["applied"], true);
+
+    let files = result["files"].as_array().unwrap();
+    assert_eq!(files.len(), 1, "Got: {}", result);
+    assert_eq!(files[0]["file"], "src/main.rs");
+    assert!(files[0]["edits"].as_u64().unwrap() >= 2, "Got: {}", result);
+    assert!(
+        files[0]["changed_lines"].as_u64().unwrap() >= 2,
+        "Got: {}",
+        result
+    );
+
+    let main = std::fs::read_to_string(project.file_path("src/main.rs"))?;
+    assert!(main.contains("fn welcome("), "Got: {}", main);
+    assert!(!main.contains("greet("), "Got: {}", main);
+
+    // Invalid identifiers are rejected by rust-analyzer.
+    let args = json!({
+        "file_path": "src/main.rs",
+        "line": 13,
+        "character": 3,
+        "new_name": "not valid"
+    });
+    assert!(client
+        .call_tool("rust_analyzer_rename", args)
+        .await
+        .is_err());
+
+    client.shutdown().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_apply_edits() -> Result<()> {
+    let project = test_support::IsolatedProject::new()?;
+    let client = test_support::MCPTestClient::start(project.path()).await?;
+    client.initialize().await?;
+
+    let utils_uri = format!("file://{}", project.file_path("src/utils.rs").display());
+    let range = |line: u64, character: u64, end_character: u64| {
+        json!({
+            "start": { "line": line, "character": character },
+            "end": { "line": line, "character": end_character }
+        })
+    };
+
+    // Both URIs and paths relative to the workspace root are accepted.
+    let args = json!({
+        "changes": {
+            utils_uri.clone(): [
+                { "range": range(2, 4, 13), "newText": "Handles" },
+                { "range": range(3, 7, 14), "newText": "handle" }
+            ],
+            "src/types.rs": [
+                { "range": range(0, 4, 17), "newText": "Settings" }
+            ]
+        }
+    });
+    let response = client.call_tool("rust_analyzer_apply_edits", args).await?;
+    let result: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    assert_eq!(result["applied"], true);
+    assert_eq!(result["total_edits"], 3);
+    assert_eq!(result["files"].as_array().unwrap().len(), 2);
+
+    let utils = std::fs::read_to_string(project.file_path("src/utils.rs"))?;
+    assert!(
+        utils.contains("/// Handles the given configuration.\npub fn handle("),
+        "Got: {}",
+        utils
+    );
+    let types = std::fs::read_to_string(project.file_path("src/types.rs"))?;
+    assert!(
+        types.starts_with("/// Settings structure"),
+        "Got: {}",
+        types
+    );
+
+    // An out of bounds edit is reported and nothing is written, even to the valid files.
+    let args = json!({
+        "documentChanges": [
+            {
+                "textDocument": { "uri": utils_uri, "version": null },
+                "edits": [{ "range": range(0, 0, 3), "newText": "pub use" }]
+            },
+            {
+                "textDocument": { "uri": "src/types.rs", "version": null },
+                "edits": [
+                    { "range": range(0, 0, 0), "newText": "// " },
+                    { "range": range(500, 0, 0), "newText": "oops" }
+                ]
+            }
+        ]
+    });
+    let err = client
+        .call_tool("rust_analyzer_apply_edits", args)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("src/types.rs"), "Got: {}", err);
+    assert!(err.to_string().contains("Edit 1"), "Got: {}", err);
+    assert_eq!(
+        std::fs::read_to_string(project.file_path("src/utils.rs"))?,
+        utils
+    );
+
+    // Build output can't be edited.
+    std::fs::create_dir_all(project.file_path("target"))?;
+    std::fs::write(project.file_path("target/generated.rs"), "")?;
+    let args = json!({
+        "changes": {
+            "target/generated.rs": [{ "range": range(0, 0, 0), "newText": "fn f() {}" }]
+        }
+    });
+    assert!(client
+        .call_tool("rust_analyzer_apply_edits", args)
+        .await
+        .is_err());
+
+    client.shutdown().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_health_check() -> Result<()> {
+    let project = test_support::IsolatedProject::new()?;
+    let client = test_support::MCPTestClient::start(project.path()).await?;
+    client.initialize().await?;
+
+    // The health check doesn't start rust-analyzer.
+    let response = client
+        .call_tool("rust_analyzer_health_check", json!({}))
+        .await?;
+    let health: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    assert_eq!(health["status"], "not_started");
+    assert_eq!(health["checks"]["process_running"], false);
+
+    client.initialize_and_wait().await?;
+    let response = client
+        .call_tool("rust_analyzer_health_check", json!({}))
+        .await?;
+    let health: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    assert_eq!(health["checks"]["process_running"], true);
+    assert_eq!(health["checks"]["lsp_initialized"], true);
+    assert!(health["open_documents"].as_u64().unwrap() >= 1);
+    assert_eq!(health["pending_requests"], 0);
+    assert!(health["cached_diagnostics"].is_u64());
+
+    // Nor does it restart rust-analyzer after a crash.
+    client.kill_rust_analyzer().await?;
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+    let response = client
+        .call_tool("rust_analyzer_health_check", json!({}))
+        .await?;
+    let health: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    assert_eq!(health["status"], "crashed", "Got: {}", health);
+    assert_eq!(health["checks"]["process_running"], false);
+
+    client.shutdown().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_progress_notifications() -> Result<()> {
+    let project = test_support::IsolatedProject::new()?;
+    let client = test_support::MCPTestClient::start(project.path()).await?;
+    client.initialize_and_wait().await?;
+
+    // Starting rust-analyzer on a fresh workspace involves loading and indexing it.
+    let notifications = client.notifications().await;
+    let progress: Vec<&Value> = notifications
+        .iter()
+        .filter(|n| n["method"] == "$/progress")
+        .map(|n| &n["params"])
+        .collect();
+    assert!(!progress.is_empty(), "Got: {:?}", notifications);
+    assert!(
+        progress
+            .iter()
+            .any(|p| p["kind"] == "begin" && p["title"].is_string()),
+        "Got: {:?}",
+        progress
+    );
+    // Every `end` has the title of the progress it ends.
+    assert!(progress
+        .iter()
+        .filter(|p| p["kind"] == "end")
+        .all(|p| p["title"].is_string()));
+    // Waiting for analysis means rust-analyzer reported being ready.
+    assert!(
+        progress
+            .iter()
+            .any(|p| p["kind"] == "status" && p["status"] == "ready"),
+        "Got: {:?}",
+        progress
+    );
+
+    client.shutdown().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_impls_for_type() -> Result<()> {
+    let project = test_support::IsolatedProject::new()?;
+    let client = test_support::MCPTestClient::start(project.path()).await?;
+    client.initialize_and_wait().await?;
+
+    // `pub struct Config` in types.rs derives Debug and Clone, and has an inherent impl and a
+    // `Default` impl.
+    let args = json!({
+        "file_path": "src/types.rs",
+        "line": 2,
+        "character": 11
+    });
+    let response = client
+        .call_tool("rust_analyzer_impls_for_type", args)
+        .await?;
+    let result: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    let impls = result["impls"].as_array().unwrap();
+
+    let find = |trait_name: Value| impls.iter().find(|i| i["trait"] == trait_name);
+    let inherent = find(Value::Null).unwrap_or_else(|| panic!("Got: {}", result));
+    assert_eq!(inherent["self_type"], "Config");
+    assert_eq!(inherent["file"], "src/types.rs");
+    assert_eq!(inherent["derived"], false);
+    let default = find(json!("Default")).unwrap_or_else(|| panic!("Got: {}", result));
+    assert_eq!(default["header"], "impl Default for Config");
+    if let Some(debug) = find(json!("Debug")) {
+        assert_eq!(debug["derived"], true);
+        assert_eq!(debug["self_type"], "Config");
+    }
+    assert_eq!(result["summary"]["total"], impls.len());
+
+    client.shutdown().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_batch_request() -> Result<()> {
+    let project = test_support::IsolatedProject::new()?;
+    let client = test_support::MCPTestClient::start(project.path()).await?;
+    client.initialize().await?;
+
+    let batch = json!([
+        { "jsonrpc": "2.0", "id": "a", "method": "tools/list" },
+        { "jsonrpc": "2.0", "method": "notifications/initialized" },
+        { "jsonrpc": "2.0", "id": "b", "method": "no/such/method" },
+        { "not": "a request" }
+    ]);
+    let response = client.send_message(&batch).await?;
+    let responses = response
+        .as_array()
+        .expect("Batch should get an array response");
+
+    // The notification doesn't get a response.
+    assert_eq!(responses.len(), 3, "Got: {}", response);
+    assert_eq!(responses[0]["id"], "a");
+    assert!(responses[0]["result"]["tools"].is_array());
+    assert_eq!(responses[1]["id"], "b");
+    assert_eq!(responses[1]["error"]["code"], -32601);
+    assert!(responses[2]["id"].is_null());
+    assert_eq!(responses[2]["error"]["code"], -32600);
+
+    // A plain request still works afterwards.
+    let tools = client.send_request("tools/list", None).await?;
+    assert!(tools["tools"].is_array());
+
+    client.shutdown().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rust_analyzer_version() -> Result<()> {
+    let mut client = IpcClient::get_or_create("test-project").await?;
+
+    let response = client.call_tool("rust_analyzer_version", json!({})).await?;
+    let text = response["content"][0]["text"].as_str().unwrap();
+    assert!(text.starts_with("rust-analyzer "), "Got: {}", text);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_missing_rust_analyzer_is_reported() -> Result<()> {
+    let project = test_support::IsolatedProject::new()?;
+    std::fs::write(
+        project.file_path(".rust-analyzer-mcp.toml"),
+        "rust_analyzer_path = \"/nonexistent/rust-analyzer\"\n",
+    )?;
+
+    let client = test_support::MCPTestClient::start(project.path()).await?;
+    let result = client.initialize().await?;
+
+    let error = result["serverInfo"]["rust_analyzer"]["error"]
+        .as_str()
+        .expect("Missing rust-analyzer should be reported on initialize");
+    assert!(error.contains("rustup component add"), "Got: {}", error);
+
+    let response = client.call_tool("rust_analyzer_version", json!({})).await;
+    assert!(response.is_err());
+
+    client.shutdown().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_open_documents() -> Result<()> {
+    let mut client = IpcClient::get_or_create("test-project").await?;
+    let workspace_path = client.workspace_path().to_path_buf();
+
+    for file in ["src/lib.rs", "src/types.rs"] {
+        let path = workspace_path.join(file);
+        client
+            .call_tool(
+                "rust_analyzer_symbols",
+                json!({ "file_path": path.to_str().unwrap() }),
+            )
+            .await?;
+    }
+
+    let response = client
+        .call_tool("rust_analyzer_open_documents", json!({}))
+        .await?;
+    let result: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    let documents = result["documents"].as_array().unwrap();
+    assert_eq!(result["count"].as_u64(), Some(documents.len() as u64));
+
+    for file in ["src/lib.rs", "src/types.rs"] {
+        let document = documents
+            .iter()
+            .find(|doc| doc["uri"].as_str().is_some_and(|uri| uri.ends_with(file)))
+            .unwrap_or_else(|| panic!("{} should be open, got: {}", file, result));
+        assert!(document["error_count"].is_u64());
+        assert!(document["warning_count"].is_u64());
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_close_document() -> Result<()> {
+    let project = test_support::IsolatedProject::new()?;
+    let client = test_support::MCPTestClient::start(project.path()).await?;
+    client.initialize().await?;
+
+    let types_path = project.file_path("src/types.rs");
+    let args = json!({ "file_path": types_path.to_str().unwrap() });
+    client
+        .call_tool("rust_analyzer_symbols", args.clone())
+        .await?;
+
+    let is_open = |documents: &Value| {
+        documents["documents"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|doc| {
+                doc["uri"]
+                    .as_str()
+                    .is_some_and(|uri| uri.ends_with("src/types.rs"))
+            })
     };
+    let response = client
+        .call_tool("rust_analyzer_open_documents", json!({}))
+        .await?;
+    let documents: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    assert!(is_open(&documents), "Got: {}", documents);
 
-    // Check if we got null or empty array
-    if text_str == "null" || text_str == "[]" {
-        return Ok(false);
+    let response = client
+        .call_tool("rust_analyzer_close_document", args.clone())
+        .await?;
+    let result: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    assert_eq!(result["closed"], true);
+
+    let response = client
+        .call_tool("rust_analyzer_open_documents", json!({}))
+        .await?;
+    let documents: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    assert!(!is_open(&documents), "Got: {}", documents);
+
+    // Closing it again is a no-op.
+    let response = client
+        .call_tool("rust_analyzer_close_document", args)
+        .await?;
+    let result: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    assert_eq!(result["closed"], false);
+
+    client.shutdown().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_workspace_info() -> Result<()> {
+    let mut client = IpcClient::get_or_create("test-project").await?;
+
+    let response = client
+        .call_tool("rust_analyzer_workspace_info", json!({}))
+        .await?;
+    let info: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+
+    let members = info["members"].as_array().unwrap();
+    assert_eq!(members.len(), 1, "Got: {}", info);
+    assert_eq!(members[0]["path"], ".");
+    assert!(members[0]["name"].is_string());
+    assert!(members[0]["dependencies"].is_array());
+    assert!(info.get("analyzer_status").is_some());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_cancel_request() -> Result<()> {
+    let project = test_support::IsolatedProject::new()?;
+    let client = test_support::MCPTestClient::start(project.path()).await?;
+    client.initialize().await?;
+
+    // The first tool call starts rust-analyzer and waits for it to index the workspace, so it's
+    // still in flight when the cancellation arrives.
+    client
+        .write_message(&json!({
+            "jsonrpc": "2.0",
+            "id": 1000,
+            "method": "tools/call",
+            "params": {
+                "name": "rust_analyzer_workspace_diagnostics",
+                "arguments": {}
+            }
+        }))
+        .await?;
+    client
+        .write_message(&json!({
+            "jsonrpc": "2.0",
+            "method": "$/cancelRequest",
+            "params": { "id": 1000 }
+        }))
+        .await?;
+
+    let response = client.next_response().await?;
+    assert_eq!(response["id"], 1000);
+    assert_eq!(response["error"]["code"], -32800);
+
+    // The server keeps serving requests afterwards.
+    let tools = client.send_request("tools/list", None).await?;
+    assert!(tools["tools"].is_array());
+
+    client.shutdown().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_cancel_method() -> Result<()> {
+    let project = test_support::IsolatedProject::new()?;
+    let client = test_support::MCPTestClient::start(project.path()).await?;
+    client.initialize_and_wait().await?;
+
+    // Opening every file of the workspace keeps the call busy long enough to cancel it.
+    client
+        .write_message(&json!({
+            "jsonrpc": "2.0",
+            "id": 2000,
+            "method": "tools/call",
+            "params": {
+                "name": "rust_analyzer_index_workspace",
+                "arguments": {}
+            }
+        }))
+        .await?;
+    client
+        .write_message(&json!({
+            "jsonrpc": "2.0",
+            "id": 2001,
+            "method": "cancel",
+            "params": { "id": 2000 }
+        }))
+        .await?;
+
+    // The cancellation itself is acknowledged, then the call fails.
+    let ack = client.next_response().await?;
+    assert_eq!(ack["id"], 2001);
+    assert!(ack["error"].is_null(), "Got: {}", ack);
+    let response = client.next_response().await?;
+    assert_eq!(response["id"], 2000);
+    assert_eq!(response["error"]["code"], -32800);
+
+    // rust-analyzer was told to drop the work, and keeps answering.
+    let health = client
+        .call_tool("rust_analyzer_health_check", json!({}))
+        .await?;
+    let health: Value = serde_json::from_str(health["content"][0]["text"].as_str().unwrap())?;
+    assert_eq!(health["checks"]["process_running"], true);
+    assert_eq!(health["pending_requests"], 0);
+    let response = client
+        .call_tool(
+            "rust_analyzer_symbols",
+            json!({ "file_path": project.file_path("src/lib.rs").to_str().unwrap() }),
+        )
+        .await?;
+    assert!(response["content"][0]["text"].is_string());
+
+    client.shutdown().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_string_request_ids() -> Result<()> {
+    let project = test_support::IsolatedProject::new()?;
+    let client = test_support::MCPTestClient::start(project.path()).await?;
+    client.initialize().await?;
+
+    // String IDs are echoed back as is.
+    let response = client
+        .send_message(&json!({
+            "jsonrpc": "2.0",
+            "id": "list-1",
+            "method": "tools/list"
+        }))
+        .await?;
+    assert_eq!(response["id"], "list-1");
+    assert!(response["result"]["tools"].is_array());
+
+    // And tool calls with string IDs can be cancelled, without mixing them up with numeric IDs.
+    client
+        .write_message(&json!({
+            "jsonrpc": "2.0",
+            "id": "1",
+            "method": "tools/call",
+            "params": {
+                "name": "rust_analyzer_workspace_diagnostics",
+                "arguments": {}
+            }
+        }))
+        .await?;
+    client
+        .write_message(&json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/cancelled",
+            "params": { "requestId": 1 }
+        }))
+        .await?;
+    client
+        .write_message(&json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/cancelled",
+            "params": { "requestId": "1" }
+        }))
+        .await?;
+
+    let response = client.next_response().await?;
+    assert_eq!(response["id"], "1");
+    assert_eq!(response["error"]["code"], -32800);
+
+    client.shutdown().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_cancel_queued_request() -> Result<()> {
+    let project = test_support::IsolatedProject::new()?;
+    let client = test_support::MCPTestClient::start(project.path()).await?;
+    client.initialize().await?;
+
+    // The second call waits for the first one, which starts rust-analyzer.
+    for (id, name) in [
+        (3000, "rust_analyzer_workspace_diagnostics"),
+        (3001, "rust_analyzer_health_check"),
+    ] {
+        client
+            .write_message(&json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "method": "tools/call",
+                "params": { "name": name, "arguments": {} }
+            }))
+            .await?;
     }
+    client
+        .write_message(&json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/cancelled",
+            "params": { "requestId": 3001 }
+        }))
+        .await?;
 
-    // Try to parse as array to verify it's valid JSON
-    let Ok(_actions) = serde_json::from_str::<Vec<Value>>(text_str) else {
-        return Ok(false);
+    // The queued call is answered right away, while the first one is still in flight.
+    let response = client.next_response().await?;
+    assert_eq!(response["id"], 3001, "Got: {}", response);
+    assert_eq!(response["error"]["code"], -32800);
+
+    client
+        .write_message(&json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/cancelled",
+            "params": { "requestId": 3000 }
+        }))
+        .await?;
+    let response = client.next_response().await?;
+    assert_eq!(response["id"], 3000, "Got: {}", response);
+    assert_eq!(response["error"]["code"], -32800);
+
+    // The cancelled call is never handled.
+    let tools = client.send_request("tools/list", None).await?;
+    assert!(tools["tools"].is_array());
+
+    client.shutdown().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_cancel_during_writes() -> Result<()> {
+    let project = test_support::IsolatedProject::new()?;
+    let client = test_support::MCPTestClient::start(project.path()).await?;
+    client.initialize().await?;
+
+    let types = std::fs::read_to_string(project.file_path("src/types.rs"))?;
+    let utils = std::fs::read_to_string(project.file_path("src/utils.rs"))?;
+    let insert = |text: &str| {
+        json!([{
+            "range": {
+                "start": { "line": 0, "character": 0 },
+                "end": { "line": 0, "character": 0 }
+            },
+            "newText": text
+        }])
     };
+    client
+        .write_message(&json!({
+            "jsonrpc": "2.0",
+            "id": 4000,
+            "method": "tools/call",
+            "params": {
+                "name": "rust_analyzer_apply_edits",
+                "arguments": {
+                    "changes": {
+                        "src/types.rs": insert("// Edited.\n"),
+                        "src/utils.rs": insert("// Edited.\n")
+                    }
+                }
+            }
+        }))
+        .await?;
+    client
+        .write_message(&json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/cancelled",
+            "params": { "requestId": 4000 }
+        }))
+        .await?;
 
-    // Even if we get an empty array, that's better than null
-    // Some files genuinely might not have code actions available
-    Ok(true)
+    // Whenever the cancellation arrives, the edit is either applied completely or not at all.
+    let response = client.next_response().await?;
+    assert_eq!(response["id"], 4000, "Got: {}", response);
+    let types_now = std::fs::read_to_string(project.file_path("src/types.rs"))?;
+    let utils_now = std::fs::read_to_string(project.file_path("src/utils.rs"))?;
+    if response["error"]["code"] == -32800 {
+        assert_eq!(types_now, types);
+        assert_eq!(utils_now, utils);
+    } else {
+        assert!(response["result"].is_object(), "Got: {}", response);
+        assert_eq!(types_now, format!("// Edited.\n{}", types));
+        assert_eq!(utils_now, format!("// Edited.\n{}", utils));
+    }
+
+    client.shutdown().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_symbols_filtered_by_kind() -> Result<()> {
+    let project = test_support::IsolatedProject::new()?;
+    let client = test_support::MCPTestClient::start(project.path()).await?;
+    client.initialize_and_wait().await?;
+
+    // lib.rs has both functions and structs.
+    let lib_path = project.file_path("src/lib.rs");
+    let response = client
+        .call_tool(
+            "rust_analyzer_symbols",
+            json!({
+                "file_path": lib_path.to_str().unwrap(),
+                "flat": true,
+                "kinds": ["struct"]
+            }),
+        )
+        .await?;
+    let symbols: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    let names: Vec<&str> = symbols
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|s| s["name"].as_str())
+        .collect();
+    assert!(names.contains(&"Person"), "Got: {:?}", names);
+    assert!(names.contains(&"Point"), "Got: {:?}", names);
+    assert!(!names.contains(&"add"), "Got: {:?}", names);
+
+    let result = client
+        .call_tool(
+            "rust_analyzer_symbols",
+            json!({
+                "file_path": lib_path.to_str().unwrap(),
+                "kinds": ["structure"]
+            }),
+        )
+        .await;
+    assert!(result.is_err());
+
+    client.shutdown().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_error_categories() -> Result<()> {
+    let project = test_support::IsolatedProject::new()?;
+    let client = test_support::MCPTestClient::start(project.path()).await?;
+    client.initialize().await?;
+
+    let call = |id: u64, arguments: Value| {
+        json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": "tools/call",
+            "params": { "name": "rust_analyzer_symbols", "arguments": arguments }
+        })
+    };
+
+    let response = client
+        .send_message(&call(1, json!({ "file_path": "src/missing.rs" })))
+        .await?;
+    let error = &response["error"];
+    assert_eq!(error["code"], -32001, "Got: {}", response);
+    assert_eq!(error["data"]["kind"], "FileNotFound");
+    assert_eq!(error["data"]["path"], "src/missing.rs");
+    assert!(error["data"]["hint"].is_string());
+
+    let response = client
+        .send_message(&call(2, json!({ "file_path": "../outside.rs" })))
+        .await?;
+    assert_eq!(response["error"]["code"], -32002, "Got: {}", response);
+    assert_eq!(response["error"]["data"]["kind"], "PathEscapesWorkspace");
+
+    let response = client.send_message(&call(3, json!({}))).await?;
+    assert_eq!(response["error"]["code"], -32602, "Got: {}", response);
+    assert_eq!(response["error"]["data"]["kind"], "InvalidArguments");
+
+    client.shutdown().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_definition_in_standard_library() -> Result<()> {
+    let project = test_support::IsolatedProject::new()?;
+    let client = test_support::MCPTestClient::start(project.path()).await?;
+    client.initialize_and_wait().await?;
+
+    let tools = client.send_request("tools/list", None).await?;
+    let definition = tools["tools"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|tool| tool["name"] == "rust_analyzer_definition")
+        .expect("rust_analyzer_definition should be listed");
+    assert_eq!(
+        definition["inputSchema"]["properties"]["include_source"]["type"],
+        "boolean"
+    );
+
+    // `fn greet(name: &str) -> String` on line 13.
+    let main_path = project.file_path("src/main.rs");
+    let content = std::fs::read_to_string(&main_path)?;
+    let line = content.lines().nth(13).unwrap();
+    let character = line.find("String").unwrap();
+
+    let response = client
+        .call_tool(
+            "rust_analyzer_definition",
+            json!({
+                "file_path": main_path.to_str().unwrap(),
+                "line": 13,
+                "character": character,
+                "include_source": true
+            }),
+        )
+        .await?;
+    let result: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    let location = if result.is_array() {
+        &result[0]
+    } else {
+        &result
+    };
+    assert_eq!(location["external"], true, "Got: {}", result);
+    assert_eq!(location["crate"], "alloc", "Got: {}", result);
+    assert_eq!(location["source"], "sysroot");
+    assert!(location["crate_path"]
+        .as_str()
+        .is_some_and(|path| path.ends_with("string.rs")));
+    assert!(location["source_snippet"]
+        .as_str()
+        .is_some_and(|snippet| snippet.contains("struct String")));
+
+    // Definitions in the workspace aren't external.
+    let character = line.find("greet").unwrap();
+    let response = client
+        .call_tool(
+            "rust_analyzer_definition",
+            json!({
+                "file_path": main_path.to_str().unwrap(),
+                "line": 13,
+                "character": character
+            }),
+        )
+        .await?;
+    let result: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    let location = if result.is_array() {
+        &result[0]
+    } else {
+        &result
+    };
+    assert_eq!(location["external"], false, "Got: {}", result);
+    assert!(location.get("source_snippet").is_none());
+
+    // `include_snippet` is an alias of `include_source`.
+    let response = client
+        .call_tool(
+            "rust_analyzer_definition",
+            json!({
+                "file_path": main_path.to_str().unwrap(),
+                "line": 13,
+                "character": character,
+                "include_snippet": true
+            }),
+        )
+        .await?;
+    let result: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    let location = if result.is_array() {
+        &result[0]
+    } else {
+        &result
+    };
+    assert!(location["source_snippet"]
+        .as_str()
+        .is_some_and(|snippet| snippet.contains("fn greet")));
+
+    client.shutdown().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_parent_module() -> Result<()> {
+    let project = test_support::IsolatedProject::new()?;
+    let client = test_support::MCPTestClient::start(project.path()).await?;
+    client.initialize_and_wait().await?;
+
+    // types.rs is declared by `pub mod types;` on the first line of lib.rs.
+    let types_path = project.file_path("src/types.rs");
+    let response = client
+        .call_tool(
+            "rust_analyzer_parent_module",
+            json!({ "file_path": types_path.to_str().unwrap() }),
+        )
+        .await?;
+    let result: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    assert_eq!(result["is_crate_root"], false, "Got: {}", result);
+    assert!(result.get("message").is_none());
+    let location = &result["locations"][0];
+    assert_eq!(location["file"], "src/lib.rs");
+    assert_eq!(location["line"], 0);
+
+    let lib_path = project.file_path("src/lib.rs");
+    let response = client
+        .call_tool(
+            "rust_analyzer_parent_module",
+            json!({ "file_path": lib_path.to_str().unwrap() }),
+        )
+        .await?;
+    let result: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    assert_eq!(result["is_crate_root"], true, "Got: {}", result);
+    assert_eq!(result["locations"], json!([]));
+    assert_eq!(
+        result["message"],
+        "src/lib.rs is a crate root; no module declares it"
+    );
+
+    // A file no module declares isn't a crate root either.
+    std::fs::write(project.file_path("src/orphan.rs"), "pub fn orphan() {}\n")?;
+    let response = client
+        .call_tool(
+            "rust_analyzer_parent_module",
+            json!({ "file_path": "src/orphan.rs" }),
+        )
+        .await?;
+    let result: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    assert_eq!(result["is_crate_root"], false, "Got: {}", result);
+    assert!(result["message"]
+        .as_str()
+        .is_some_and(|message| message.starts_with("No parent module found for src/orphan.rs")));
+
+    client.shutdown().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_child_modules() -> Result<()> {
+    let project = test_support::IsolatedProject::new()?;
+    let client = test_support::MCPTestClient::start(project.path()).await?;
+    client.initialize_and_wait().await?;
+
+    // lib.rs declares `pub mod types;` and `pub mod utils;`.
+    let lib_path = project.file_path("src/lib.rs");
+    let response = client
+        .call_tool(
+            "rust_analyzer_child_modules",
+            json!({ "file_path": lib_path.to_str().unwrap() }),
+        )
+        .await?;
+    let result: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    assert_eq!(result["count"], 2, "Got: {}", result);
+
+    let modules = result["modules"].as_array().unwrap();
+    let types = modules
+        .iter()
+        .find(|m| m["name"] == "types")
+        .expect("No types module");
+    assert_eq!(types["inline"], false);
+    assert_eq!(types["file"], "src/types.rs");
+    assert_eq!(types["declaration"]["line"], 0);
+
+    // The inline `mod tests` of main.rs.
+    let main_path = project.file_path("src/main.rs");
+    let response = client
+        .call_tool(
+            "rust_analyzer_child_modules",
+            json!({ "file_path": main_path.to_str().unwrap() }),
+        )
+        .await?;
+    let result: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    let tests = &result["modules"][0];
+    assert_eq!(tests["name"], "tests", "Got: {}", result);
+    assert_eq!(tests["inline"], true);
+    assert!(tests["file"].is_null());
+    assert_eq!(tests["declaration"]["line"], 40);
+
+    client.shutdown().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_lsp_error_response() -> Result<()> {
+    let project = test_support::IsolatedProject::new()?;
+    let client = test_support::MCPTestClient::start(project.path()).await?;
+    client.initialize_and_wait().await?;
+
+    // rust-analyzer rejects a new name that isn't an identifier, and its error must reach the
+    // caller rather than being mistaken for an empty result.
+    let response = client
+        .send_message(&json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "tools/call",
+            "params": {
+                "name": "rust_analyzer_rename",
+                "arguments": {
+                    "file_path": "src/main.rs",
+                    "line": 13,
+                    "character": 7,
+                    "new_name": "not an identifier"
+                }
+            }
+        }))
+        .await?;
+    assert_eq!(response["error"]["code"], -32004, "Got: {}", response);
+    assert_eq!(response["error"]["data"]["kind"], "LspRequestFailed");
+    assert!(response["error"]["message"]
+        .as_str()
+        .unwrap()
+        .contains("textDocument/rename failed"));
+
+    // A malformed SSR rule isn't a failure though, but the answer to the validation.
+    let response = client
+        .call_tool(
+            "rust_analyzer_ssr",
+            json!({
+                "file_path": "src/main.rs",
+                "line": 0,
+                "character": 0,
+                "query": "foo($a",
+                "parse_only": true
+            }),
+        )
+        .await?;
+    let result: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    assert_eq!(result["valid"], false, "Got: {}", result);
+    assert!(result["error"]
+        .as_str()
+        .is_some_and(|error| error.contains("experimental/ssr failed")));
+
+    client.shutdown().await?;
+
+    Ok(())
 }
@@ -0,0 +1,54 @@
+use anyhow::Result;
+use serde_json::{json, Value};
+use test_support::{IsolatedProject, MCPTestClient};
+
+#[tokio::test]
+async fn test_recovers_after_rust_analyzer_crash() -> Result<()> {
+    let project = IsolatedProject::new()?;
+    let client = MCPTestClient::start(project.path()).await?;
+    client.initialize_and_wait().await?;
+
+    let lib_path = project.file_path("src/lib.rs");
+    let args = json!({ "file_path": lib_path.to_str().unwrap() });
+    client
+        .call_tool("rust_analyzer_symbols", args.clone())
+        .await?;
+
+    client.kill_rust_analyzer().await?;
+    // Give the server a moment to notice that rust-analyzer is gone.
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+
+    // The next call restarts rust-analyzer rather than hanging until a timeout.
+    let response = client.call_tool("rust_analyzer_symbols", args).await?;
+    let symbols: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    assert!(
+        symbols.is_array(),
+        "Expected symbols after the restart, got: {}",
+        symbols
+    );
+
+    let response = client.call_tool("rust_analyzer_status", json!({})).await?;
+    let status: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    assert_eq!(status["restart_count"], 1);
+
+    // The document open before the crash is open again.
+    let response = client
+        .call_tool("rust_analyzer_open_documents", json!({}))
+        .await?;
+    let documents: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+    assert!(
+        documents["documents"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|doc| doc["uri"]
+                .as_str()
+                .is_some_and(|uri| uri.ends_with("src/lib.rs"))),
+        "Got: {}",
+        documents
+    );
+
+    client.shutdown().await?;
+
+    Ok(())
+}
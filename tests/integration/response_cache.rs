@@ -0,0 +1,104 @@
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::time::Duration;
+use test_support::{IsolatedProject, MCPTestClient};
+
+async fn flat_symbol_names(client: &MCPTestClient, file_path: &str) -> Result<Vec<String>> {
+    let response = client
+        .call_tool(
+            "rust_analyzer_symbols",
+            json!({ "file_path": file_path, "flat": true }),
+        )
+        .await?;
+    let symbols: Value = serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+
+    Ok(symbols
+        .as_array()
+        .map(|symbols| {
+            symbols
+                .iter()
+                .filter_map(|s| s["name"].as_str().map(String::from))
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+#[tokio::test]
+async fn test_cached_results_are_invalidated_by_edits() -> Result<()> {
+    let project = IsolatedProject::new()?;
+    let lib_path = project.file_path("src/lib.rs");
+    let lib_path = lib_path.to_str().unwrap();
+
+    let client = MCPTestClient::start(project.path()).await?;
+    client.initialize_and_wait().await?;
+
+    // Repeated calls return the same result, whether from the cache or not
+    let first = flat_symbol_names(&client, lib_path).await?;
+    let second = flat_symbol_names(&client, lib_path).await?;
+    assert_eq!(first, second);
+    assert!(!first.contains(&"cache_probe".to_string()));
+
+    // Writing the file through the server must not leave a stale result behind
+    let content = std::fs::read_to_string(lib_path)? + "\npub fn cache_probe() {}\n";
+    client
+        .call_tool(
+            "rust_analyzer_write_file",
+            json!({ "file_path": lib_path, "content": content }),
+        )
+        .await?;
+    let names = flat_symbol_names(&client, lib_path).await?;
+    assert!(
+        names.contains(&"cache_probe".to_string()),
+        "Symbols should reflect the edit, got: {:?}",
+        names
+    );
+
+    client.shutdown().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_cached_results_are_invalidated_by_external_edits() -> Result<()> {
+    let project = IsolatedProject::new()?;
+    let client = MCPTestClient::start(project.path()).await?;
+    client.initialize_and_wait().await?;
+
+    // `add` in `pub fn add(a: i32, b: i32)`, only referred to by its declaration so far.
+    let args = json!({ "file_path": "src/lib.rs", "line": 36, "character": 7 });
+    let count = || async {
+        let response = client
+            .call_tool("rust_analyzer_references", args.clone())
+            .await?;
+        let references: Value =
+            serde_json::from_str(response["content"][0]["text"].as_str().unwrap())?;
+        Ok::<_, anyhow::Error>(references["count"].as_u64().unwrap_or_default())
+    };
+    let before = count().await?;
+    assert_eq!(count().await?, before);
+
+    // Another file starts using it, edited outside of the server, which the cached result of the
+    // same call must not hide once the change is picked up.
+    let utils_path = project.file_path("src/utils.rs");
+    let utils = std::fs::read_to_string(&utils_path)?
+        + "\npub fn sum() -> i32 {\n    crate::add(1, 2)\n}\n";
+    std::fs::write(&utils_path, utils)?;
+
+    let mut after = before;
+    for _ in 0..50 {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        after = count().await?;
+        if after != before {
+            break;
+        }
+    }
+    assert_eq!(
+        after,
+        before + 1,
+        "The new reference in src/utils.rs should be found"
+    );
+
+    client.shutdown().await?;
+
+    Ok(())
+}
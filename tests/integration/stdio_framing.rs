@@ -0,0 +1,80 @@
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+use std::{process::Stdio, time::Duration};
+use test_support::IsolatedProject;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    process::{ChildStdout, Command},
+};
+
+/// Read a `Content-Length` framed message.
+async fn read_framed(reader: &mut BufReader<ChildStdout>) -> Result<Value> {
+    let mut header = String::new();
+    reader.read_line(&mut header).await?;
+    let length: usize = header
+        .trim()
+        .strip_prefix("Content-Length: ")
+        .ok_or_else(|| anyhow!("Expected a Content-Length header, got: {:?}", header))?
+        .parse()?;
+
+    let mut separator = String::new();
+    reader.read_line(&mut separator).await?;
+    assert_eq!(separator, "\r\n");
+
+    let mut content = vec![0; length];
+    reader.read_exact(&mut content).await?;
+
+    Ok(serde_json::from_slice(&content)?)
+}
+
+#[tokio::test]
+async fn test_content_length_framing_is_mirrored() -> Result<()> {
+    let project = IsolatedProject::new()?;
+    let mut server = Command::new(assert_cmd::cargo::cargo_bin("rust-analyzer-mcp"))
+        .arg(project.path())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()?;
+    let mut stdin = server.stdin.take().unwrap();
+    let mut stdout = BufReader::new(server.stdout.take().unwrap());
+
+    let request = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": "2024-11-05",
+            "capabilities": {},
+            "clientInfo": { "name": "test", "version": "0.1.0" }
+        }
+    })
+    .to_string();
+    let framed = format!("Content-Length: {}\r\n\r\n{}", request.len(), request);
+    stdin.write_all(framed.as_bytes()).await?;
+    stdin.flush().await?;
+
+    let response = tokio::time::timeout(Duration::from_secs(30), read_framed(&mut stdout))
+        .await
+        .map_err(|_| anyhow!("No response to initialize"))??;
+    assert_eq!(response["id"], 1, "Got: {}", response);
+    assert_eq!(
+        response["result"]["serverInfo"]["name"],
+        "rust-analyzer-mcp"
+    );
+
+    // Later messages are framed the same way.
+    let request = json!({ "jsonrpc": "2.0", "id": 2, "method": "tools/list" }).to_string();
+    let framed = format!("Content-Length: {}\r\n\r\n{}", request.len(), request);
+    stdin.write_all(framed.as_bytes()).await?;
+    stdin.flush().await?;
+
+    let response = tokio::time::timeout(Duration::from_secs(30), read_framed(&mut stdout))
+        .await
+        .map_err(|_| anyhow!("No response to tools/list"))??;
+    assert_eq!(response["id"], 2, "Got: {}", response);
+    assert!(response["result"]["tools"].is_array());
+
+    Ok(())
+}
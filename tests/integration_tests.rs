@@ -1,5 +1,10 @@
 mod integration {
+    mod config_file;
     mod diagnostics;
+    mod http_transport;
     mod mcp_server_test;
+    mod recovery;
+    mod response_cache;
+    mod stdio_framing;
     // mod shared_test;  // This test file doesn't exist yet
 }
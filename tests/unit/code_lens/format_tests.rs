@@ -0,0 +1,78 @@
+use rust_analyzer_mcp::code_lens::{code_lens_kind, format_code_lens};
+use serde_json::{json, Value};
+
+fn lens(command: &str, title: &str, arguments: Value) -> Value {
+    json!({
+        "range": {"start": {"line": 4, "character": 0}, "end": {"line": 9, "character": 1}},
+        "command": { "title": title, "command": command, "arguments": arguments }
+    })
+}
+
+fn runnable(label: &str, cargo_command: &str) -> Value {
+    json!([{
+        "label": label,
+        "kind": "cargo",
+        "args": { "cargoArgs": [cargo_command, "--package", "app"], "executableArgs": [] }
+    }])
+}
+
+#[test]
+fn test_kinds() {
+    let test = lens(
+        "rust-analyzer.runSingle",
+        "▶\u{fe0e} Run Test",
+        runnable("test tests::it_works", "test"),
+    );
+    assert_eq!(code_lens_kind(&test), Some("test"));
+
+    let run = lens(
+        "rust-analyzer.runSingle",
+        "▶\u{fe0e} Run",
+        runnable("run app", "run"),
+    );
+    assert_eq!(code_lens_kind(&run), Some("run"));
+
+    let debug = lens(
+        "rust-analyzer.debugSingle",
+        "Debug",
+        runnable("test tests::it_works", "test"),
+    );
+    assert_eq!(code_lens_kind(&debug), Some("debug"));
+
+    let implementations = lens(
+        "rust-analyzer.showReferences",
+        "3 implementations",
+        json!([]),
+    );
+    assert_eq!(code_lens_kind(&implementations), Some("implementation"));
+
+    let references = lens("rust-analyzer.showReferences", "1 reference", json!([]));
+    assert_eq!(code_lens_kind(&references), Some("reference"));
+
+    assert_eq!(
+        code_lens_kind(&lens("editor.action.other", "?", json!([]))),
+        None
+    );
+    // Unresolved lenses have no command yet.
+    assert_eq!(code_lens_kind(&json!({ "range": {}, "data": {} })), None);
+}
+
+#[test]
+fn test_format_code_lens() {
+    let formatted = format_code_lens(&lens(
+        "rust-analyzer.showReferences",
+        "2 implementations",
+        json!(["file:///src/lib.rs"]),
+    ));
+
+    assert_eq!(
+        formatted,
+        json!({
+            "kind": "implementation",
+            "range": { "line": 4, "character": 0 },
+            "title": "2 implementations",
+            "command": "rust-analyzer.showReferences",
+            "arguments": ["file:///src/lib.rs"]
+        })
+    );
+}
@@ -0,0 +1,60 @@
+use rust_analyzer_mcp::completion::{completion_kind_name, structured_completion_item};
+use serde_json::json;
+
+#[test]
+fn test_completion_kind_names() {
+    assert_eq!(completion_kind_name(1), Some("text"));
+    assert_eq!(completion_kind_name(3), Some("function"));
+    assert_eq!(completion_kind_name(22), Some("struct"));
+    assert_eq!(completion_kind_name(25), Some("type_parameter"));
+    assert_eq!(completion_kind_name(0), None);
+    assert_eq!(completion_kind_name(26), None);
+}
+
+#[test]
+fn test_structured_completion_item() {
+    let item = json!({
+        "label": "push",
+        "kind": 2,
+        "detail": "fn(&mut self, T)",
+        "documentation": { "kind": "markdown", "value": "Appends an element." },
+        "sortText": "7fffffff",
+        "textEdit": {
+            "range": {
+                "start": { "line": 3, "character": 6 },
+                "end": { "line": 3, "character": 6 }
+            },
+            "newText": "push(${1:value})"
+        },
+        "data": { "position": {} }
+    });
+
+    assert_eq!(
+        structured_completion_item(&item),
+        json!({
+            "label": "push",
+            "kind_name": "method",
+            "detail": "fn(&mut self, T)",
+            "documentation": "Appends an element.",
+            "insert_text": "push(${1:value})",
+            "sort_text": "7fffffff"
+        })
+    );
+}
+
+#[test]
+fn test_structured_completion_documentation_truncated() {
+    let item = json!({
+        "label": "Vec",
+        "kind": 22,
+        "insertText": "Vec",
+        "documentation": "é".repeat(300)
+    });
+
+    let structured = structured_completion_item(&item);
+    let documentation = structured["documentation"].as_str().unwrap();
+    assert_eq!(documentation.chars().count(), 200);
+    assert!(documentation.ends_with('…'));
+    assert_eq!(structured["insert_text"], "Vec");
+    assert!(structured["detail"].is_null());
+}
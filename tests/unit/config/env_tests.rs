@@ -0,0 +1,80 @@
+use rust_analyzer_mcp::config::{CheckCommand, LspTimeouts, ServerConfig};
+use std::time::Duration;
+
+const VARS: [&str; 7] = [
+    "RA_MCP_LSP_TIMEOUT_SECS",
+    "RA_MCP_DOCUMENT_OPEN_DELAY_MS",
+    "RA_MCP_DIAGNOSTICS_TIMEOUT_MS",
+    "RA_MCP_DIAGNOSTICS_POLL_INTERVAL_MS",
+    "RA_MCP_CHECK_ON_SAVE",
+    "RA_MCP_RESPONSE_CACHE_CAPACITY",
+    "RA_MCP_MAX_OPEN_DOCUMENTS",
+];
+
+fn clear_vars() {
+    for var in VARS {
+        std::env::remove_var(var);
+    }
+}
+
+// The environment is shared by all tests of the process, so everything touching it is done in a
+// single test.
+#[test]
+fn test_server_config_from_env() {
+    clear_vars();
+    assert_eq!(ServerConfig::from_env().unwrap(), ServerConfig::default());
+
+    std::env::set_var("RA_MCP_LSP_TIMEOUT_SECS", "90");
+    std::env::set_var("RA_MCP_DOCUMENT_OPEN_DELAY_MS", "10");
+    std::env::set_var("RA_MCP_DIAGNOSTICS_TIMEOUT_MS", "5000");
+    std::env::set_var("RA_MCP_DIAGNOSTICS_POLL_INTERVAL_MS", "100");
+    std::env::set_var("RA_MCP_CHECK_ON_SAVE", "clippy");
+    std::env::set_var("RA_MCP_RESPONSE_CACHE_CAPACITY", "0");
+    std::env::set_var("RA_MCP_MAX_OPEN_DOCUMENTS", "8");
+    let config = ServerConfig::from_env().unwrap();
+    assert_eq!(
+        config,
+        ServerConfig {
+            lsp_request_timeout: Duration::from_secs(90),
+            timeouts: LspTimeouts::default(),
+            document_open_delay: Duration::from_millis(10),
+            diagnostics_poll_timeout: Duration::from_millis(5000),
+            diagnostics_poll_interval: Duration::from_millis(100),
+            check_on_save: CheckCommand::Clippy,
+            response_cache_capacity: 0,
+            max_open_documents: 8,
+        }
+    );
+
+    std::env::set_var("RA_MCP_CHECK_ON_SAVE", "false");
+    assert_eq!(
+        ServerConfig::from_env().unwrap().check_on_save,
+        CheckCommand::None
+    );
+
+    // Empty variables are ignored
+    std::env::set_var("RA_MCP_LSP_TIMEOUT_SECS", "");
+    assert_eq!(
+        ServerConfig::from_env().unwrap().lsp_request_timeout,
+        ServerConfig::default().lsp_request_timeout
+    );
+
+    std::env::set_var("RA_MCP_LSP_TIMEOUT_SECS", "soon");
+    assert!(ServerConfig::from_env().is_err());
+    std::env::set_var("RA_MCP_LSP_TIMEOUT_SECS", "30");
+    std::env::set_var("RA_MCP_CHECK_ON_SAVE", "build");
+    assert!(ServerConfig::from_env().is_err());
+
+    clear_vars();
+}
+
+#[test]
+fn test_server_config_defaults() {
+    let config = ServerConfig::default();
+
+    assert_eq!(config.lsp_request_timeout, Duration::from_secs(30));
+    assert_eq!(config.document_open_delay, Duration::from_millis(200));
+    assert_eq!(config.diagnostics_poll_timeout, Duration::from_millis(3000));
+    assert_eq!(config.diagnostics_poll_interval, Duration::from_millis(500));
+    assert_eq!(config.check_on_save, CheckCommand::Check);
+}
@@ -0,0 +1,129 @@
+use rust_analyzer_mcp::{
+    config::{CheckCommand, ConfigFile, LspTimeouts, TimeoutCategory, CONFIG_FILE_NAME},
+    ServerOptions,
+};
+use std::{path::PathBuf, time::Duration};
+
+#[test]
+fn test_parse_all_settings() {
+    let config = ConfigFile::parse(
+        r#"
+        rust_analyzer_path = "/opt/rust-analyzer"
+        check_command = "clippy"
+        request_timeout_secs = 120
+        document_open_delay_ms = 50
+        diagnostic_poll_interval_ms = 100
+        compact_responses = true
+        log_lsp_messages = true
+        response_cache_capacity = 20
+        max_open_documents = 5
+        "#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        config,
+        ConfigFile {
+            rust_analyzer_path: Some(PathBuf::from("/opt/rust-analyzer")),
+            check_command: Some(CheckCommand::Clippy),
+            request_timeout_secs: Some(120),
+            document_open_delay_ms: Some(50),
+            diagnostic_poll_interval_ms: Some(100),
+            compact_responses: Some(true),
+            log_lsp_messages: Some(true),
+            response_cache_capacity: Some(20),
+            max_open_documents: Some(5),
+            timeouts: None,
+        }
+    );
+}
+
+#[test]
+fn test_parse_invalid() {
+    // Unknown settings are most likely typos, so they're rejected
+    assert!(ConfigFile::parse("request_timeout = 10").is_err());
+    assert!(ConfigFile::parse("check_command = \"build\"").is_err());
+    assert!(ConfigFile::parse("compact_responses = \"yes\"").is_err());
+}
+
+#[test]
+fn test_parse_timeouts() {
+    let config = ConfigFile::parse(
+        r#"
+        [timeouts]
+        hover_secs = 2
+        workspace_secs = 600
+        "#,
+    )
+    .unwrap();
+    let timeouts = config.timeouts.unwrap();
+
+    assert_eq!(timeouts.get(TimeoutCategory::Hover), Duration::from_secs(2));
+    assert_eq!(
+        timeouts.get(TimeoutCategory::Workspace),
+        Duration::from_secs(600)
+    );
+    // Categories missing from the table keep their default
+    assert_eq!(
+        timeouts.completion_secs,
+        LspTimeouts::default().completion_secs
+    );
+
+    assert!(ConfigFile::parse("[timeouts]\nhover = 2").is_err());
+}
+
+#[test]
+fn test_apply_overrides_only_set_values() {
+    let config = ConfigFile::parse("check_command = \"none\"\nrequest_timeout_secs = 5").unwrap();
+    let mut options = ServerOptions::default();
+    config.apply(&mut options);
+
+    let defaults = ServerOptions::default();
+    assert_eq!(options.config.check_on_save, CheckCommand::None);
+    assert_eq!(options.config.lsp_request_timeout, Duration::from_secs(5));
+    assert_eq!(
+        options.config.document_open_delay,
+        defaults.config.document_open_delay
+    );
+    assert_eq!(options.rust_analyzer_path, None);
+    assert!(!options.compact_responses);
+}
+
+#[test]
+fn test_load() {
+    let workspace = tempfile::tempdir().unwrap();
+
+    // A missing file is the same as an empty one
+    assert_eq!(
+        ConfigFile::load(workspace.path()).unwrap(),
+        ConfigFile::default()
+    );
+
+    std::fs::write(
+        workspace.path().join(CONFIG_FILE_NAME),
+        "rust_analyzer_path = \"bin/rust-analyzer\"",
+    )
+    .unwrap();
+    let config = ConfigFile::load(workspace.path()).unwrap();
+    assert_eq!(
+        config.rust_analyzer_path,
+        Some(workspace.path().join("bin/rust-analyzer"))
+    );
+
+    std::fs::write(workspace.path().join(CONFIG_FILE_NAME), "not toml").unwrap();
+    assert!(ConfigFile::load(workspace.path()).is_err());
+}
+
+#[test]
+fn test_check_command_from_str() {
+    assert_eq!(
+        "check".parse::<CheckCommand>().unwrap(),
+        CheckCommand::Check
+    );
+    assert_eq!(
+        "clippy".parse::<CheckCommand>().unwrap(),
+        CheckCommand::Clippy
+    );
+    assert_eq!("none".parse::<CheckCommand>().unwrap(), CheckCommand::None);
+    assert!("build".parse::<CheckCommand>().is_err());
+}
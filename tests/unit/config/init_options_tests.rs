@@ -0,0 +1,85 @@
+use rust_analyzer_mcp::{
+    config::{load_init_options, merge_json, INIT_OPTIONS_FILE_NAME},
+    lsp::RustAnalyzerClient,
+    ServerOptions,
+};
+use serde_json::json;
+
+#[test]
+fn test_merge_json() {
+    let mut base = json!({
+        "cargo": { "buildScripts": { "enable": true } },
+        "procMacro": { "enable": true },
+        "files": { "exclude": ["target"] }
+    });
+    merge_json(
+        &mut base,
+        &json!({
+            "cargo": { "features": ["foo"] },
+            "procMacro": { "enable": false },
+            "files": { "exclude": ["vendor"] }
+        }),
+    );
+
+    assert_eq!(
+        base,
+        json!({
+            "cargo": { "buildScripts": { "enable": true }, "features": ["foo"] },
+            "procMacro": { "enable": false },
+            // Arrays are replaced rather than concatenated
+            "files": { "exclude": ["vendor"] }
+        })
+    );
+}
+
+// The environment is shared by all tests of the process, so both lookups are tested together.
+#[test]
+fn test_load_init_options() {
+    let workspace = tempfile::tempdir().unwrap();
+    std::env::remove_var("RA_MCP_INIT_OPTIONS");
+
+    assert_eq!(load_init_options(workspace.path()).unwrap(), None);
+
+    std::fs::write(
+        workspace.path().join(INIT_OPTIONS_FILE_NAME),
+        r#"{ "cargo": { "allTargets": false } }"#,
+    )
+    .unwrap();
+    assert_eq!(
+        load_init_options(workspace.path()).unwrap(),
+        Some(json!({ "cargo": { "allTargets": false } }))
+    );
+
+    // The environment variable takes precedence over the workspace file
+    let other = workspace.path().join("other.json");
+    std::fs::write(&other, r#"{ "check": { "command": "clippy" } }"#).unwrap();
+    std::env::set_var("RA_MCP_INIT_OPTIONS", &other);
+    let options = load_init_options(workspace.path());
+    std::env::remove_var("RA_MCP_INIT_OPTIONS");
+    assert_eq!(
+        options.unwrap(),
+        Some(json!({ "check": { "command": "clippy" } }))
+    );
+
+    std::fs::write(workspace.path().join(INIT_OPTIONS_FILE_NAME), "[1, 2]").unwrap();
+    assert!(load_init_options(workspace.path()).is_err());
+    std::fs::write(workspace.path().join(INIT_OPTIONS_FILE_NAME), "{ oops").unwrap();
+    assert!(load_init_options(workspace.path()).is_err());
+}
+
+#[test]
+fn test_init_options_in_initialize_params() {
+    let workspace = tempfile::tempdir().unwrap();
+    let options = ServerOptions {
+        init_options: Some(json!({ "check": { "command": "clippy" } })),
+        ..ServerOptions::default()
+    };
+    let client = RustAnalyzerClient::new(workspace.path().to_path_buf(), options);
+
+    let params = client.initialize_params();
+    let init_options = &params["initializationOptions"];
+    assert_eq!(init_options["check"]["command"], "clippy");
+    // The defaults are preserved
+    assert_eq!(init_options["procMacro"]["enable"], true);
+    assert_eq!(init_options["cargo"]["buildScripts"]["enable"], true);
+}
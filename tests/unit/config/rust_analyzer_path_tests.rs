@@ -0,0 +1,56 @@
+use rust_analyzer_mcp::ServerOptions;
+use std::path::PathBuf;
+
+const MISSING: &str = "/nonexistent/bin/rust-analyzer";
+
+// The environment is shared by all tests of the process, so everything touching
+// `RUST_ANALYZER_PATH` is done in a single test.
+#[test]
+fn test_rust_analyzer_path_precedence() {
+    // Any existing executable will do, the binary isn't run.
+    let exe = std::env::current_exe().unwrap();
+    std::env::remove_var("RUST_ANALYZER_PATH");
+
+    let mut options = ServerOptions::default();
+    options.apply_rust_analyzer_path(None).unwrap();
+    assert_eq!(options.rust_analyzer_path, None);
+
+    // The environment overrides the config file.
+    std::env::set_var("RUST_ANALYZER_PATH", &exe);
+    let mut options = ServerOptions {
+        rust_analyzer_path: Some(PathBuf::from(MISSING)),
+        ..Default::default()
+    };
+    options.apply_rust_analyzer_path(None).unwrap();
+    assert_eq!(options.rust_analyzer_path, Some(exe.clone()));
+
+    // The argument overrides the environment.
+    std::env::set_var("RUST_ANALYZER_PATH", MISSING);
+    let mut options = ServerOptions::default();
+    options.apply_rust_analyzer_path(Some(exe.clone())).unwrap();
+    assert_eq!(options.rust_analyzer_path, Some(exe.clone()));
+
+    // A missing binary is an error naming the setting, rather than a reason to look for another
+    // one.
+    let err = ServerOptions::default()
+        .apply_rust_analyzer_path(None)
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains(MISSING), "Got: {}", err);
+    assert!(err.contains("RUST_ANALYZER_PATH"), "Got: {}", err);
+
+    std::env::set_var("RUST_ANALYZER_PATH", &exe);
+    let err = ServerOptions::default()
+        .apply_rust_analyzer_path(Some(PathBuf::from(MISSING)))
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("--rust-analyzer-path"), "Got: {}", err);
+
+    // Empty variables are ignored.
+    std::env::set_var("RUST_ANALYZER_PATH", "");
+    let mut options = ServerOptions::default();
+    options.apply_rust_analyzer_path(None).unwrap();
+    assert_eq!(options.rust_analyzer_path, None);
+
+    std::env::remove_var("RUST_ANALYZER_PATH");
+}
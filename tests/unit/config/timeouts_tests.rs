@@ -0,0 +1,40 @@
+use rust_analyzer_mcp::config::{LspTimeouts, TimeoutCategory};
+use std::time::Duration;
+
+#[test]
+fn test_parse_category() {
+    assert_eq!(
+        "hover".parse::<TimeoutCategory>().unwrap(),
+        TimeoutCategory::Hover
+    );
+    assert_eq!(
+        "workspace".parse::<TimeoutCategory>().unwrap(),
+        TimeoutCategory::Workspace
+    );
+    assert!("goto".parse::<TimeoutCategory>().is_err());
+}
+
+#[test]
+fn test_set_only_changes_category() {
+    let mut timeouts = LspTimeouts::default();
+    timeouts.set(TimeoutCategory::Formatting, 3);
+
+    assert_eq!(
+        timeouts.get(TimeoutCategory::Formatting),
+        Duration::from_secs(3)
+    );
+    assert_eq!(
+        timeouts,
+        LspTimeouts {
+            formatting_secs: 3,
+            ..LspTimeouts::default()
+        }
+    );
+}
+
+#[test]
+fn test_workspace_outlasts_hover() {
+    let timeouts = LspTimeouts::default();
+
+    assert!(timeouts.get(TimeoutCategory::Workspace) > timeouts.get(TimeoutCategory::Hover));
+}
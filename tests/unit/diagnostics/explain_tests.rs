@@ -0,0 +1,54 @@
+use rust_analyzer_mcp::diagnostics::{clippy_summary, explain_code};
+
+const NEEDLESS_RETURN: &str = "### What it does
+Checks for return statements at the end of a block.
+
+### Why is this bad?
+Removing the `return` and semicolon will make the code
+more rusty.
+
+### Example
+```rust
+fn foo(x: usize) -> usize {
+    return x;
+}
+```";
+
+#[test]
+fn test_clippy_summary() {
+    assert_eq!(
+        clippy_summary(NEEDLESS_RETURN).as_deref(),
+        Some("Checks for return statements at the end of a block.")
+    );
+}
+
+#[test]
+fn test_clippy_summary_without_sections() {
+    assert_eq!(
+        clippy_summary("\nChecks for things.\n").as_deref(),
+        Some("Checks for things.")
+    );
+    assert_eq!(clippy_summary("  \n"), None);
+}
+
+#[tokio::test]
+async fn test_explain_error_code() {
+    let workspace = std::env::temp_dir();
+    let explanation = explain_code(&workspace, "E0308").await.unwrap();
+
+    assert_eq!(explanation["source"], "rustc");
+    assert_eq!(
+        explanation["url"],
+        "https://doc.rust-lang.org/error_codes/E0308.html"
+    );
+    assert!(explanation["explanation"]
+        .as_str()
+        .unwrap()
+        .contains("Expected type did not match the received type."));
+}
+
+#[tokio::test]
+async fn test_explain_unknown_code() {
+    let workspace = std::env::temp_dir();
+    assert!(explain_code(&workspace, "not-a-code").await.is_err());
+}
@@ -0,0 +1,523 @@
+use rust_analyzer_mcp::diagnostics::{
+    add_snippets, code_url, diagnostic_snippet, first_errors, format_diagnostics,
+    format_workspace_diagnostics, parse_severity, DiagnosticGrouping,
+};
+use serde_json::{json, Value};
+use std::path::Path;
+
+#[test]
+fn test_diagnostic_sources_are_preserved() {
+    let result = json!([
+        {
+            "severity": 1,
+            "message": "mismatched types",
+            "code": "E0308",
+            "source": "rustc",
+            "range": {"start": {"line": 1, "character": 0}, "end": {"line": 1, "character": 5}}
+        },
+        {
+            "severity": 2,
+            "message": "redundant clone",
+            "code": "clippy::redundant_clone",
+            "source": "clippy",
+            "range": {"start": {"line": 2, "character": 0}, "end": {"line": 2, "character": 5}}
+        },
+        {
+            "severity": 4,
+            "message": "unused variable",
+            "code": "unused_variables",
+            "source": "rust-analyzer",
+            "range": {"start": {"line": 3, "character": 0}, "end": {"line": 3, "character": 5}}
+        }
+    ]);
+
+    let formatted = format_diagnostics("src/lib.rs", &result, None, None);
+    let diagnostics = formatted["diagnostics"].as_array().unwrap();
+
+    assert_eq!(diagnostics[0]["source"], "rustc");
+    assert_eq!(diagnostics[1]["source"], "clippy");
+    assert_eq!(diagnostics[2]["source"], "rust-analyzer");
+}
+
+#[test]
+fn test_diagnostic_source_inferred_from_code() {
+    let result = json!([
+        {"severity": 1, "message": "mismatched types", "code": "E0308"},
+        {"severity": 2, "message": "redundant clone", "code": "clippy::redundant_clone"},
+        {"severity": 2, "message": "something else"}
+    ]);
+
+    let formatted = format_diagnostics("src/lib.rs", &result, None, None);
+    let diagnostics = formatted["diagnostics"].as_array().unwrap();
+
+    assert_eq!(diagnostics[0]["source"], "rustc");
+    assert_eq!(diagnostics[1]["source"], "clippy");
+    assert_eq!(diagnostics[2]["source"], "rust-analyzer");
+}
+
+#[test]
+fn test_code_urls() {
+    assert_eq!(
+        code_url(&json!("E0308")).as_deref(),
+        Some("https://doc.rust-lang.org/error_codes/E0308.html")
+    );
+    assert_eq!(
+        code_url(&json!("clippy::redundant_clone")).as_deref(),
+        Some("https://rust-lang.github.io/rust-clippy/master/index.html#redundant_clone")
+    );
+    assert_eq!(code_url(&json!("unused_variables")), None);
+    assert_eq!(code_url(&json!("E03")), None);
+    assert_eq!(code_url(&json!(42)), None);
+
+    let formatted = format_diagnostics(
+        "src/lib.rs",
+        &json!([{"severity": 1, "code": "E0425"}]),
+        None,
+        None,
+    );
+    assert_eq!(
+        formatted["diagnostics"][0]["code_url"],
+        "https://doc.rust-lang.org/error_codes/E0425.html"
+    );
+
+    let formatted = format_diagnostics(
+        "src/lib.rs",
+        &json!([{"severity": 1, "message": "oops"}]),
+        None,
+        None,
+    );
+    assert!(formatted["diagnostics"][0]["code_url"].is_null());
+}
+
+#[test]
+fn test_code_descriptions() {
+    let result = json!([
+        {"severity": 1, "message": "cannot find value `x` in this scope", "code": "E0425"},
+        {"severity": 2, "message": "redundant clone", "code": "clippy::redundant_clone"},
+        {"severity": 2, "message": "unused variable", "code": "unused_variables"},
+        {
+            "severity": 1,
+            "message": "mismatched types",
+            "code": "E0308",
+            "codeDescription": {"href": "https://example.com/E0308"}
+        }
+    ]);
+
+    let formatted = format_diagnostics("src/lib.rs", &result, None, None);
+    let diagnostics = formatted["diagnostics"].as_array().unwrap();
+
+    assert_eq!(
+        diagnostics[0]["code_description"],
+        json!({"href": "https://doc.rust-lang.org/error_codes/E0425.html"})
+    );
+    assert_eq!(
+        diagnostics[1]["code_description"],
+        json!({"href": "https://rust-lang.github.io/rust-clippy/master/index.html#redundant_clone"})
+    );
+    assert!(diagnostics[2]["code_description"].is_null());
+    // The link provided by rust-analyzer wins.
+    assert_eq!(
+        diagnostics[3]["code_description"],
+        json!({"href": "https://example.com/E0308"})
+    );
+    assert_eq!(diagnostics[3]["code_url"], "https://example.com/E0308");
+}
+
+#[test]
+fn test_duplicate_diagnostics_are_dropped() {
+    let range = json!({"start": {"line": 4, "character": 8}, "end": {"line": 4, "character": 12}});
+    let other_range =
+        json!({"start": {"line": 9, "character": 8}, "end": {"line": 9, "character": 12}});
+    let result = json!([
+        {"severity": 1, "code": "E0425", "message": "cannot find value `x`", "range": range},
+        // The same error reported again, e.g. for another macro expansion.
+        {"severity": 1, "code": "E0425", "message": "cannot find value `x`", "range": range},
+        {"severity": 1, "code": "E0425", "message": "cannot find value `x`", "range": range},
+        // Same message elsewhere, or with another code, isn't a duplicate.
+        {"severity": 1, "code": "E0425", "message": "cannot find value `x`", "range": other_range},
+        {"severity": 2, "code": "unused", "message": "cannot find value `x`", "range": range}
+    ]);
+
+    let formatted = format_diagnostics("src/lib.rs", &result, None, None);
+
+    assert_eq!(formatted["diagnostics"].as_array().unwrap().len(), 3);
+    assert_eq!(formatted["summary"]["deduplicated"], 2);
+    assert_eq!(formatted["summary"]["errors"], 2);
+    assert_eq!(formatted["summary"]["warnings"], 1);
+
+    let formatted = format_diagnostics("src/lib.rs", &json!(null), None, None);
+    assert_eq!(formatted["summary"]["deduplicated"], 0);
+}
+
+#[test]
+fn test_min_severity() {
+    let result = json!([
+        {"severity": 1, "message": "mismatched types", "code": "E0308"},
+        {"severity": 2, "message": "unused variable", "code": "unused_variables"},
+        {"severity": 2, "message": "unused import", "code": "unused_imports"},
+        {"severity": 4, "message": "consider removing this"},
+        {"message": "no severity"}
+    ]);
+
+    let formatted = format_diagnostics("src/lib.rs", &result, parse_severity("error"), None);
+    let diagnostics = formatted["diagnostics"].as_array().unwrap();
+
+    // Diagnostics without a severity are kept.
+    assert_eq!(diagnostics.len(), 2);
+    assert_eq!(diagnostics[0]["severity"], "error");
+    assert_eq!(diagnostics[1]["severity"], "unknown");
+    // The counts include the diagnostics that aren't shown.
+    assert_eq!(formatted["summary"]["errors"], 1);
+    assert_eq!(formatted["summary"]["warnings"], 2);
+    assert_eq!(formatted["summary"]["hints"], 1);
+    assert_eq!(formatted["summary"]["shown"], 2);
+    assert_eq!(formatted["summary"]["errors_suppressed"], 0);
+    assert_eq!(formatted["summary"]["warnings_suppressed"], 2);
+    assert_eq!(formatted["summary"]["hints_suppressed"], 1);
+
+    let formatted = format_diagnostics("src/lib.rs", &result, parse_severity("warning"), None);
+    assert_eq!(formatted["summary"]["shown"], 4);
+    assert_eq!(formatted["summary"]["warnings_suppressed"], 0);
+    assert_eq!(formatted["summary"]["hints_suppressed"], 1);
+    let formatted = format_diagnostics("src/lib.rs", &result, None, None);
+    assert_eq!(formatted["summary"]["shown"], 5);
+    assert_eq!(formatted["summary"]["hints_suppressed"], 0);
+
+    assert_eq!(parse_severity("hint"), Some(4));
+    assert_eq!(parse_severity("fatal"), None);
+}
+
+#[test]
+fn test_related_information_is_collapsed() {
+    let range = json!({"start": {"line": 1, "character": 17}, "end": {"line": 1, "character": 20}});
+    let related_range =
+        json!({"start": {"line": 1, "character": 11}, "end": {"line": 1, "character": 14}});
+    let related = json!({
+        "location": {"uri": "file:///project/src/lib.rs", "range": related_range},
+        "message": "expected due to this"
+    });
+    let result = json!([
+        {
+            "severity": 1,
+            "code": "E0308",
+            "message": "mismatched types",
+            "range": range,
+            "relatedInformation": [related, related]
+        },
+        {"severity": 2, "message": "unused variable: `x`", "range": related_range}
+    ]);
+
+    let formatted = format_diagnostics("src/lib.rs", &result, None, None);
+    let diagnostics = formatted["diagnostics"].as_array().unwrap();
+
+    assert_eq!(
+        diagnostics[0]["related"],
+        json!([{
+            "uri": "file:///project/src/lib.rs",
+            "range": related_range,
+            "message": "expected due to this"
+        }])
+    );
+    assert!(diagnostics[0].get("relatedInformation").is_none());
+    assert_eq!(diagnostics[1]["related"], json!([]));
+}
+
+#[test]
+fn test_diagnostic_snippet() {
+    let content = "fn main() {\n    let x: i32 = \"a\";\n}\n";
+    let range = json!({"start": {"line": 1, "character": 17}, "end": {"line": 1, "character": 20}});
+
+    assert_eq!(
+        diagnostic_snippet(content, &range).unwrap(),
+        "2 |     let x: i32 = \"a\";\n  |                  ^^^"
+    );
+}
+
+#[test]
+fn test_diagnostic_snippet_multiline() {
+    let content = "fn f() {\n\n    g();\n}\n";
+    // From `{` to the end of `}`, with an empty line in between
+    let range = json!({"start": {"line": 0, "character": 7}, "end": {"line": 3, "character": 1}});
+
+    assert_eq!(
+        diagnostic_snippet(content, &range).unwrap(),
+        "1 | fn f() {\n  |        ^\n2 | \n3 |     g();\n  | ^^^^^^^^\n4 | }\n  | ^"
+    );
+
+    // Long ranges are cut
+    let content = "x\n".repeat(20);
+    let range = json!({"start": {"line": 0, "character": 0}, "end": {"line": 19, "character": 1}});
+    let snippet = diagnostic_snippet(&content, &range).unwrap();
+    assert_eq!(snippet.lines().filter(|l| l.ends_with("| x")).count(), 5);
+    assert!(snippet.ends_with("| ..."));
+}
+
+#[test]
+fn test_diagnostic_snippet_edge_cases() {
+    let content = "\tlet s = \"🦀\"; bad\n";
+
+    // Columns are in UTF-16 code units and tabs are kept for alignment
+    let range = json!({"start": {"line": 0, "character": 15}, "end": {"line": 0, "character": 18}});
+    assert_eq!(
+        diagnostic_snippet(content, &range).unwrap(),
+        "1 | \tlet s = \"🦀\"; bad\n  | \t             ^^^"
+    );
+
+    // An empty range gets a single caret
+    let range = json!({"start": {"line": 0, "character": 1}, "end": {"line": 0, "character": 1}});
+    assert!(diagnostic_snippet(content, &range)
+        .unwrap()
+        .ends_with("| \t^"));
+
+    // Out of bounds
+    let range = json!({"start": {"line": 5, "character": 0}, "end": {"line": 5, "character": 1}});
+    assert_eq!(diagnostic_snippet(content, &range), None);
+    let range = json!({"start": {"line": 0, "character": 15}, "end": {"line": 9, "character": 0}});
+    assert!(diagnostic_snippet(content, &range)
+        .unwrap()
+        .ends_with("^^^"));
+    assert_eq!(diagnostic_snippet(content, &json!(null)), None);
+}
+
+#[test]
+fn test_add_snippets() {
+    let content = "fn main() {\n    let x = 1;\n}\n";
+    let result = json!([{
+        "severity": 2,
+        "message": "unused variable: `x`",
+        "range": {"start": {"line": 1, "character": 8}, "end": {"line": 1, "character": 9}}
+    }]);
+
+    let mut formatted = format_diagnostics("src/main.rs", &result, None, None);
+    add_snippets(&mut formatted, content);
+
+    assert_eq!(
+        formatted["diagnostics"][0]["snippet"],
+        "2 |     let x = 1;\n  |         ^"
+    );
+}
+
+#[test]
+fn test_first_errors() {
+    let range = |line: u32| json!({"start": {"line": line, "character": 4}, "end": {"line": line, "character": 8}});
+    let result = json!({
+        "file:///project/src/main.rs": [
+            {"severity": 1, "message": "mismatched types", "code": "E0308", "range": range(9)},
+            {"severity": 2, "message": "unused variable", "range": range(2)},
+            {"severity": 1, "message": "cannot find value", "code": "E0425", "range": range(3)}
+        ],
+        "file:///project/src/lib.rs": [
+            {"severity": 1, "message": "unresolved import", "code": "E0432", "range": range(0)}
+        ],
+        "file:///project/src/clean.rs": []
+    });
+
+    let errors = first_errors(Path::new("/project"), &result, 2);
+    assert_eq!(errors["total_errors"], 3);
+    assert_eq!(errors["shown"], 2);
+    assert_eq!(
+        errors["errors"],
+        json!([
+            {
+                "file": "src/lib.rs",
+                "line": 0,
+                "character": 4,
+                "message": "unresolved import",
+                "code": "E0432"
+            },
+            {
+                "file": "src/main.rs",
+                "line": 3,
+                "character": 4,
+                "message": "cannot find value",
+                "code": "E0425"
+            }
+        ])
+    );
+
+    let errors = first_errors(Path::new("/project"), &result, 20);
+    assert_eq!(errors["shown"], 3);
+    assert_eq!(errors["errors"][2]["line"], 9);
+}
+
+#[test]
+fn test_workspace_min_severity() {
+    let result = json!({
+        "file:///project/src/lib.rs": [
+            {"severity": 1, "message": "mismatched types"},
+            {"severity": 2, "message": "unused variable"}
+        ],
+        "file:///project/src/main.rs": [
+            {"severity": 2, "message": "unused import"},
+            {"severity": 2, "message": "dead code"}
+        ]
+    });
+
+    let formatted = format_workspace_diagnostics(
+        Path::new("/project"),
+        &result,
+        parse_severity("error"),
+        None,
+    );
+
+    // Files with only warnings are left out, but their warnings are counted.
+    let files = formatted["files"].as_object().unwrap();
+    assert_eq!(files.len(), 1);
+    assert_eq!(files["file:///project/src/lib.rs"]["summary"]["shown"], 1);
+    let summary = &formatted["summary"];
+    assert_eq!(summary["total_files"], 2);
+    assert_eq!(summary["total_warnings"], 3);
+    assert_eq!(summary["shown"], 1);
+    assert_eq!(summary["errors_suppressed"], 0);
+    assert_eq!(summary["warnings_suppressed"], 3);
+}
+
+fn diagnostic(severity: u64, code: &str, line: u64) -> Value {
+    json!({
+        "severity": severity,
+        "code": code,
+        "message": format!("{} on line {}", code, line),
+        "range": {"start": {"line": line, "character": 4}, "end": {"line": line, "character": 8}}
+    })
+}
+
+#[test]
+fn test_workspace_group_by_code() {
+    let result = json!({
+        "file:///project/src/main.rs": [
+            diagnostic(2, "dead_code", 7),
+            diagnostic(1, "E0308", 3)
+        ],
+        "file:///project/src/lib.rs": [
+            diagnostic(2, "dead_code", 20),
+            diagnostic(2, "unused_variables", 2),
+            diagnostic(2, "dead_code", 10),
+            diagnostic(2, "dead_code", 30)
+        ]
+    });
+
+    let formatted = format_workspace_diagnostics(
+        Path::new("/project"),
+        &result,
+        None,
+        Some(DiagnosticGrouping::Code),
+    );
+
+    assert!(formatted.get("files").is_none());
+    let groups = formatted["groups"].as_array().unwrap();
+    let counts: Vec<(&str, u64)> = groups
+        .iter()
+        .map(|group| {
+            (
+                group["key"].as_str().unwrap(),
+                group["count"].as_u64().unwrap(),
+            )
+        })
+        .collect();
+    // The largest group first, the others in the order of their first occurrence.
+    assert_eq!(
+        counts,
+        [("dead_code", 4), ("unused_variables", 1), ("E0308", 1)]
+    );
+
+    // The first 3 occurrences by file and position.
+    let examples = groups[0]["examples"].as_array().unwrap();
+    let locations: Vec<(&str, u64)> = examples
+        .iter()
+        .map(|example| {
+            (
+                example["file"].as_str().unwrap(),
+                example["line"].as_u64().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        locations,
+        [("src/lib.rs", 10), ("src/lib.rs", 20), ("src/lib.rs", 30)]
+    );
+    assert_eq!(examples[0]["character"], 4);
+    assert_eq!(examples[0]["message"], "dead_code on line 10");
+
+    // The summary is the same as without grouping.
+    let ungrouped = format_workspace_diagnostics(Path::new("/project"), &result, None, None);
+    assert_eq!(formatted["summary"], ungrouped["summary"]);
+}
+
+#[test]
+fn test_workspace_group_by_severity_and_file() {
+    let result = json!({
+        "file:///project/src/main.rs": [diagnostic(1, "E0308", 3), diagnostic(2, "dead_code", 7)],
+        "file:///project/src/lib.rs": [diagnostic(1, "E0425", 1)]
+    });
+
+    let by_severity = format_workspace_diagnostics(
+        Path::new("/project"),
+        &result,
+        None,
+        Some(DiagnosticGrouping::Severity),
+    );
+    assert_eq!(by_severity["groups"][0]["key"], "error");
+    assert_eq!(by_severity["groups"][0]["count"], 2);
+    assert_eq!(by_severity["groups"][1]["key"], "warning");
+
+    // Diagnostics below the minimum severity aren't grouped.
+    let by_file = format_workspace_diagnostics(
+        Path::new("/project"),
+        &result,
+        parse_severity("error"),
+        Some(DiagnosticGrouping::File),
+    );
+    let groups = by_file["groups"].as_array().unwrap();
+    assert_eq!(groups.len(), 2);
+    assert_eq!(groups[0]["key"], "src/lib.rs");
+    assert_eq!(groups[1]["key"], "src/main.rs");
+    assert_eq!(groups[1]["count"], 1);
+}
+
+#[test]
+fn test_parse_diagnostic_grouping() {
+    assert_eq!(
+        "code".parse::<DiagnosticGrouping>().unwrap(),
+        DiagnosticGrouping::Code
+    );
+    assert!("rule".parse::<DiagnosticGrouping>().is_err());
+}
+
+#[test]
+fn test_rendered() {
+    let rendered = "error[E0308]: mismatched types\n --> src/lib.rs:2:18\n";
+    let diagnostic = |severity: u64| {
+        json!({
+            "severity": severity,
+            "message": format!("severity {}", severity),
+            "range": {"start": {"line": 1, "character": 17}, "end": {"line": 1, "character": 23}},
+            "data": { "rendered": rendered }
+        })
+    };
+    let result = json!([diagnostic(1), diagnostic(2), diagnostic(4)]);
+
+    // By default only for errors and warnings.
+    let formatted = format_diagnostics("src/lib.rs", &result, None, None);
+    assert_eq!(formatted["diagnostics"][0]["rendered"], rendered);
+    assert_eq!(formatted["diagnostics"][1]["rendered"], rendered);
+    assert!(formatted["diagnostics"][2].get("rendered").is_none());
+
+    let formatted = format_diagnostics("src/lib.rs", &result, None, Some(true));
+    assert_eq!(formatted["diagnostics"][2]["rendered"], rendered);
+
+    let formatted = format_diagnostics("src/lib.rs", &result, None, Some(false));
+    assert!(formatted["diagnostics"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .all(|diag| diag.get("rendered").is_none()));
+
+    // rust-analyzer's own diagnostics don't have any.
+    let formatted = format_diagnostics(
+        "src/lib.rs",
+        &json!([{"severity": 1, "message": "oops"}]),
+        None,
+        Some(true),
+    );
+    assert!(formatted["diagnostics"][0].get("rendered").is_none());
+}
@@ -0,0 +1,91 @@
+use rust_analyzer_mcp::diagnostics::{to_rustc_diagnostic, to_rustc_diagnostics};
+use serde_json::json;
+
+const CONTENT: &str = "fn main() {\n    let x: i32 = \"five\";\n    println!(\"{}\", y);\n}\n";
+
+#[test]
+fn test_error_to_rustc_json() {
+    let diag = json!({
+        "severity": 1,
+        "code": "E0308",
+        "source": "rustc",
+        "message": "mismatched types\nexpected `i32`, found `&str`",
+        "range": {"start": {"line": 1, "character": 17}, "end": {"line": 1, "character": 23}}
+    });
+
+    let message = to_rustc_diagnostic("src/main.rs", CONTENT, &diag);
+    assert_eq!(
+        message,
+        json!({
+            "$message_type": "diagnostic",
+            "message": "mismatched types\nexpected `i32`, found `&str`",
+            "code": {"code": "E0308", "explanation": null},
+            "level": "error",
+            "spans": [{
+                "file_name": "src/main.rs",
+                "byte_start": 29,
+                "byte_end": 35,
+                "line_start": 2,
+                "line_end": 2,
+                "column_start": 18,
+                "column_end": 24,
+                "is_primary": true,
+                "text": [{
+                    "text": "    let x: i32 = \"five\";",
+                    "highlight_start": 18,
+                    "highlight_end": 24
+                }],
+                "label": null,
+                "suggested_replacement": null,
+                "suggestion_applicability": null,
+                "expansion": null
+            }],
+            "children": [],
+            "rendered": "error[E0308]: mismatched types\nexpected `i32`, found `&str`\n --> src/main.rs:2:18\n  |\n2 |     let x: i32 = \"five\";\n  |                  ^^^^^^\n\n"
+        })
+    );
+    assert_eq!(&CONTENT[29..35], "\"five\"");
+}
+
+#[test]
+fn test_rustc_levels_and_notes() {
+    let range = json!({"start": {"line": 2, "character": 19}, "end": {"line": 2, "character": 20}});
+    let diagnostics = vec![
+        json!({
+            "severity": 1,
+            "message": "cannot find value `y` in this scope",
+            "range": range,
+            "relatedInformation": [{
+                "location": {"uri": "file:///project/src/main.rs", "range": range},
+                "message": "a local variable with a similar name exists: `x`"
+            }]
+        }),
+        json!({"severity": 2, "code": "unused_variables", "message": "unused variable", "range": range}),
+        json!({"severity": 4, "message": "consider prefixing with an underscore", "range": range}),
+    ];
+
+    let messages = to_rustc_diagnostics("src/main.rs", CONTENT, &diagnostics, None);
+    let levels: Vec<_> = messages
+        .iter()
+        .map(|m| m["level"].as_str().unwrap())
+        .collect();
+    assert_eq!(levels, vec!["error", "warning", "help"]);
+
+    let error = &messages[0];
+    assert!(error["code"].is_null());
+    assert_eq!(error["children"][0]["level"], "note");
+    assert_eq!(
+        error["children"][0]["message"],
+        "a local variable with a similar name exists: `x`"
+    );
+    assert_eq!(
+        error["rendered"],
+        "error: cannot find value `y` in this scope\n --> src/main.rs:3:20\n  |\n3 |     \
+         println!(\"{}\", y);\n  |                    ^\n  = note: a local variable with a similar \
+         name exists: `x`\n\n"
+    );
+    assert_eq!(messages[1]["code"]["code"], "unused_variables");
+
+    let messages = to_rustc_diagnostics("src/main.rs", CONTENT, &diagnostics, Some(2));
+    assert_eq!(messages.len(), 2);
+}
@@ -0,0 +1,101 @@
+use rust_analyzer_mcp::diagnostics::to_sarif;
+use serde_json::json;
+use std::path::Path;
+
+#[test]
+fn test_workspace_to_sarif() {
+    let result = json!({
+        "file:///workspace/src/main.rs": [{
+            "severity": 1,
+            "code": "E0308",
+            "message": "mismatched types",
+            "range": {"start": {"line": 1, "character": 17}, "end": {"line": 1, "character": 23}}
+        }, {
+            "severity": 2,
+            "code": "unused_variables",
+            "message": "unused variable: `x`",
+            "range": {"start": {"line": 1, "character": 8}, "end": {"line": 1, "character": 9}}
+        }],
+        "file:///workspace/src/lib.rs": [{
+            "severity": 1,
+            "code": "E0308",
+            "message": "mismatched types",
+            "range": {"start": {"line": 4, "character": 0}, "end": {"line": 5, "character": 1}}
+        }]
+    });
+
+    let log = serde_json::to_value(to_sarif(Path::new("/workspace"), &result, Some(1))).unwrap();
+    assert_eq!(
+        log["$schema"],
+        "https://json.schemastore.org/sarif-2.1.0.json"
+    );
+    assert_eq!(log["version"], "2.1.0");
+
+    let runs = log["runs"].as_array().unwrap();
+    assert_eq!(runs.len(), 1);
+    let run = &runs[0];
+    assert_eq!(run["tool"]["driver"]["name"], "rust-analyzer");
+    assert_eq!(
+        run["originalUriBaseIds"]["%SRCROOT%"]["uri"],
+        "file:///workspace/"
+    );
+
+    // Both errors share a rule and the warning is left out.
+    assert_eq!(
+        run["tool"]["driver"]["rules"],
+        json!([{
+            "id": "E0308",
+            "helpUri": "https://doc.rust-lang.org/error_codes/E0308.html"
+        }])
+    );
+    assert_eq!(
+        run["results"],
+        json!([{
+            "ruleId": "E0308",
+            "ruleIndex": 0,
+            "level": "error",
+            "message": {"text": "mismatched types"},
+            "locations": [{
+                "physicalLocation": {
+                    "artifactLocation": {"uri": "src/lib.rs", "uriBaseId": "%SRCROOT%"},
+                    "region": {"startLine": 5, "startColumn": 1, "endLine": 6, "endColumn": 2}
+                }
+            }]
+        }, {
+            "ruleId": "E0308",
+            "ruleIndex": 0,
+            "level": "error",
+            "message": {"text": "mismatched types"},
+            "locations": [{
+                "physicalLocation": {
+                    "artifactLocation": {"uri": "src/main.rs", "uriBaseId": "%SRCROOT%"},
+                    "region": {"startLine": 2, "startColumn": 18, "endLine": 2, "endColumn": 24}
+                }
+            }]
+        }])
+    );
+}
+
+#[test]
+fn test_sarif_without_codes() {
+    let result = json!({
+        "file:///elsewhere/build.rs": [{
+            "severity": 4,
+            "message": "consider using a `let` binding",
+            "range": {"start": {"line": 0, "character": 0}, "end": {"line": 0, "character": 3}}
+        }]
+    });
+
+    let log = serde_json::to_value(to_sarif(Path::new("/workspace"), &result, None)).unwrap();
+    let run = &log["runs"][0];
+    assert_eq!(run["tool"]["driver"]["rules"], json!([]));
+
+    // Files outside of the workspace keep their absolute URI.
+    let sarif_result = &run["results"][0];
+    assert_eq!(sarif_result["level"], "note");
+    assert!(sarif_result.get("ruleId").is_none());
+    assert_eq!(
+        sarif_result["locations"][0]["physicalLocation"]["artifactLocation"],
+        json!({"uri": "file:///elsewhere/build.rs"})
+    );
+}
@@ -0,0 +1,172 @@
+use rust_analyzer_mcp::edits::{
+    apply_text_edits, char_index, line_offset, position_to_offset, range_text, uri_to_path,
+    validate_text_edits, workspace_edit_changes,
+};
+use serde_json::{json, Value};
+use std::path::PathBuf;
+
+#[test]
+fn test_position_to_offset() {
+    let content = "fn main() {\n    let x = 1;\n}\n";
+
+    assert_eq!(position_to_offset(content, 0, 0), Some(0));
+    assert_eq!(position_to_offset(content, 1, 4), Some(16));
+    assert_eq!(position_to_offset(content, 2, 0), Some(27));
+    // Past the end of the line clamps to the line end
+    assert_eq!(position_to_offset(content, 0, 100), Some(11));
+    // Past the last line is out of bounds
+    assert_eq!(position_to_offset(content, 10, 0), None);
+}
+
+#[test]
+fn test_line_offset() {
+    // `é` is 2 bytes but 1 UTF-16 code unit, `🦀` is 4 bytes and 2 code units.
+    let line = "é🦀x";
+    assert_eq!(line_offset(line, 0), 0);
+    assert_eq!(line_offset(line, 1), 2);
+    assert_eq!(line_offset(line, 3), 6);
+    assert_eq!(line_offset(line, 4), 7);
+    assert_eq!(line_offset(line, 100), line.len());
+
+    assert_eq!(char_index(line, 3), 2);
+    assert_eq!(char_index(line, 100), 3);
+}
+
+#[test]
+fn test_position_to_offset_utf16() {
+    // '🦀' is two UTF-16 code units and four UTF-8 bytes
+    let content = "let s = \"🦀\";";
+
+    assert_eq!(position_to_offset(content, 0, 9), Some(9));
+    assert_eq!(position_to_offset(content, 0, 11), Some(13));
+}
+
+#[test]
+fn test_range_text() {
+    let content = "fn foo(a: i32, b: i32) {}\n";
+    let range = json!({
+        "start": {"line": 0, "character": 3},
+        "end": {"line": 0, "character": 6}
+    });
+
+    assert_eq!(range_text(content, &range), Some("foo"));
+}
+
+#[test]
+fn test_workspace_edit_changes() {
+    let edit = json!({
+        "changes": {
+            "file:///tmp/a.rs": [{"range": {}, "newText": "x"}]
+        }
+    });
+    let changes = workspace_edit_changes(&edit);
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].0, "file:///tmp/a.rs");
+
+    let edit = json!({
+        "documentChanges": [
+            {"textDocument": {"uri": "file:///tmp/b.rs", "version": 1}, "edits": [{}, {}]},
+            {"kind": "create", "uri": "file:///tmp/c.rs"}
+        ]
+    });
+    let changes = workspace_edit_changes(&edit);
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].1.len(), 2);
+}
+
+#[test]
+fn test_uri_to_path() {
+    assert_eq!(
+        uri_to_path("file:///tmp/my%20project/src/lib.rs"),
+        Some(PathBuf::from("/tmp/my project/src/lib.rs"))
+    );
+    assert_eq!(uri_to_path("untitled:foo"), None);
+}
+
+fn text_edit(line: u64, character: u64, end_line: u64, end_character: u64, text: &str) -> Value {
+    json!({
+        "range": {
+            "start": { "line": line, "character": character },
+            "end": { "line": end_line, "character": end_character }
+        },
+        "newText": text
+    })
+}
+
+#[test]
+fn test_apply_text_edits() {
+    let content = "fn main(){\nlet x=1;\n}\n";
+    // Given out of order, as LSP doesn't guarantee any order
+    let edits = vec![
+        text_edit(1, 0, 1, 0, "    "),
+        text_edit(0, 9, 0, 9, " "),
+        text_edit(1, 5, 1, 6, " = "),
+    ];
+
+    assert_eq!(
+        apply_text_edits(content, &edits).as_deref(),
+        Some("fn main() {\n    let x = 1;\n}\n")
+    );
+}
+
+#[test]
+fn test_apply_text_edits_same_position() {
+    let edits = vec![text_edit(0, 0, 0, 0, "a"), text_edit(0, 0, 0, 0, "b")];
+
+    assert_eq!(apply_text_edits("c", &edits).as_deref(), Some("abc"));
+}
+
+#[test]
+fn test_apply_text_edits_invalid() {
+    let overlapping = vec![text_edit(0, 0, 0, 3, "x"), text_edit(0, 2, 0, 4, "y")];
+    assert_eq!(apply_text_edits("abcdef", &overlapping), None);
+
+    let out_of_bounds = vec![text_edit(5, 0, 5, 0, "x")];
+    assert_eq!(apply_text_edits("abcdef", &out_of_bounds), None);
+}
+
+#[test]
+fn test_validate_text_edits() {
+    let content = "fn main(){\nlet x=1;\n}\n";
+
+    let valid = vec![
+        text_edit(1, 0, 1, 0, "    "),
+        text_edit(0, 9, 0, 9, " "),
+        text_edit(1, 5, 1, 6, " = "),
+        // Inserting on the empty line after the last newline is fine.
+        text_edit(3, 0, 3, 0, "// end\n"),
+    ];
+    assert!(validate_text_edits(content, &valid).is_ok());
+
+    let err = validate_text_edits(
+        content,
+        &[text_edit(0, 0, 0, 0, ""), text_edit(4, 0, 4, 0, "x")],
+    )
+    .unwrap_err();
+    assert!(
+        err.to_string()
+            .starts_with("Edit 1 is out of bounds: line 4"),
+        "{}",
+        err
+    );
+
+    // apply_text_edits would clamp this one to the end of the line.
+    let err = validate_text_edits(content, &[text_edit(2, 0, 2, 5, "")]).unwrap_err();
+    assert!(err.to_string().contains("character 5"), "{}", err);
+
+    let err = validate_text_edits(content, &[text_edit(1, 4, 1, 2, "")]).unwrap_err();
+    assert!(err.to_string().contains("ends before it starts"), "{}", err);
+
+    let overlapping = vec![text_edit(0, 0, 0, 3, "x"), text_edit(0, 2, 0, 4, "y")];
+    let err = validate_text_edits(content, &overlapping).unwrap_err();
+    assert_eq!(err.to_string(), "Edit 0 overlaps edit 1");
+
+    let err = validate_text_edits(content, &[json!({ "range": {}, "newText": "" })]).unwrap_err();
+    assert!(err.to_string().contains("malformed range"), "{}", err);
+    let err = validate_text_edits(
+        content,
+        &[json!({ "range": text_edit(0, 0, 0, 0, "")["range"] })],
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("no newText"), "{}", err);
+}
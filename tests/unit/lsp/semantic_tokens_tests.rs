@@ -0,0 +1,59 @@
+use rust_analyzer_mcp::lsp::decode_semantic_tokens;
+use serde_json::json;
+
+#[test]
+fn test_decode_semantic_tokens() {
+    let legend = json!({
+        "tokenTypes": ["keyword", "function", "parameter"],
+        "tokenModifiers": ["declaration", "mutable", "unsafe"]
+    });
+    // `fn greet(mut name: String)` on line 2, then `unsafe_call` on line 4.
+    let data = [
+        2, 0, 2, 0, 0, // fn
+        0, 3, 5, 1, 0b1, // greet, 3 characters after `fn`
+        0, 10, 4, 2, 0b11, // name, 10 characters after `greet`
+        2, 4, 11, 1, 0b100, // unsafe_call, at an absolute character on a new line
+    ];
+
+    let tokens = decode_semantic_tokens(&data, &legend);
+
+    assert_eq!(
+        tokens,
+        [
+            json!({ "line": 2, "character": 0, "length": 2, "token_type": "keyword", "modifiers": [] }),
+            json!({
+                "line": 2,
+                "character": 3,
+                "length": 5,
+                "token_type": "function",
+                "modifiers": ["declaration"]
+            }),
+            json!({
+                "line": 2,
+                "character": 13,
+                "length": 4,
+                "token_type": "parameter",
+                "modifiers": ["declaration", "mutable"]
+            }),
+            json!({
+                "line": 4,
+                "character": 4,
+                "length": 11,
+                "token_type": "function",
+                "modifiers": ["unsafe"]
+            }),
+        ]
+    );
+}
+
+#[test]
+fn test_decode_semantic_tokens_outside_legend() {
+    let legend = json!({ "tokenTypes": ["keyword"], "tokenModifiers": [] });
+
+    // An unknown type and modifier, and a trailing incomplete token.
+    let tokens = decode_semantic_tokens(&[0, 1, 3, 7, 0b10, 1, 0], &legend);
+
+    assert_eq!(tokens.len(), 1);
+    assert_eq!(tokens[0]["token_type"], "unknown");
+    assert_eq!(tokens[0]["modifiers"], json!(["unknown"]));
+}
@@ -0,0 +1,43 @@
+use rust_analyzer_mcp::lsp::ServerStatus;
+use serde_json::json;
+
+#[test]
+fn test_server_status_from_params() {
+    assert_eq!(
+        ServerStatus::from_params(&json!({ "health": "ok", "quiescent": false })),
+        ServerStatus::Loading
+    );
+    assert_eq!(
+        ServerStatus::from_params(&json!({ "health": "ok", "quiescent": true })),
+        ServerStatus::Ready
+    );
+    // Warnings (e.g. a failed build script) don't keep the analysis from completing.
+    assert_eq!(
+        ServerStatus::from_params(&json!({
+            "health": "warning",
+            "quiescent": true,
+            "message": "Failed to run build scripts"
+        })),
+        ServerStatus::Ready
+    );
+    assert_eq!(
+        ServerStatus::from_params(&json!({
+            "health": "error",
+            "quiescent": true,
+            "message": "Failed to load workspace"
+        })),
+        ServerStatus::Invalid("Failed to load workspace".to_string())
+    );
+}
+
+#[test]
+fn test_server_status_to_json() {
+    assert_eq!(
+        ServerStatus::Ready.to_json(),
+        json!({ "status": "ready", "message": null })
+    );
+    assert_eq!(
+        ServerStatus::Invalid("needs reload".to_string()).to_json(),
+        json!({ "status": "invalid", "message": "needs reload" })
+    );
+}
@@ -0,0 +1,50 @@
+use rust_analyzer_mcp::lsp::{RustAnalyzerVersion, MIN_RUST_ANALYZER_VERSION};
+
+fn version(major: u64, minor: u64, patch: u64) -> RustAnalyzerVersion {
+    RustAnalyzerVersion {
+        major,
+        minor,
+        patch,
+    }
+}
+
+#[test]
+fn test_parse_rustup_version() {
+    assert_eq!(
+        RustAnalyzerVersion::parse("rust-analyzer 1.80.1 (3f5fd8d 2024-08-06)"),
+        Some(version(1, 80, 1))
+    );
+    assert_eq!(
+        RustAnalyzerVersion::parse("rust-analyzer 1.83.0-nightly (1bc403d 2024-10-11)\n"),
+        Some(version(1, 83, 0))
+    );
+}
+
+#[test]
+fn test_parse_standalone_version() {
+    let parsed =
+        RustAnalyzerVersion::parse("rust-analyzer 0.3.2096-standalone (ad5c5d0 2024-09-02)")
+            .unwrap();
+
+    assert_eq!(parsed, version(0, 3, 2096));
+    assert!(parsed.is_standalone());
+    assert!(parsed.is_supported());
+}
+
+#[test]
+fn test_parse_invalid_version() {
+    assert_eq!(RustAnalyzerVersion::parse(""), None);
+    assert_eq!(RustAnalyzerVersion::parse("rustc 1.80.1"), None);
+    assert_eq!(RustAnalyzerVersion::parse("rust-analyzer"), None);
+    assert_eq!(RustAnalyzerVersion::parse("rust-analyzer 1"), None);
+    assert_eq!(RustAnalyzerVersion::parse("rust-analyzer 1.2.3.4"), None);
+    assert_eq!(RustAnalyzerVersion::parse("rust-analyzer one.two"), None);
+}
+
+#[test]
+fn test_minimum_version() {
+    assert!(MIN_RUST_ANALYZER_VERSION.is_supported());
+    assert!(version(1, 80, 1).is_supported());
+    assert!(!version(1, 63, 9).is_supported());
+    assert_eq!(MIN_RUST_ANALYZER_VERSION.to_string(), "1.64.0");
+}
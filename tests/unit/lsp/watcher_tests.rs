@@ -0,0 +1,25 @@
+use rust_analyzer_mcp::lsp::is_watched;
+use std::path::Path;
+
+#[test]
+fn test_is_watched() {
+    let root = Path::new("/workspace");
+
+    assert!(is_watched(root, Path::new("/workspace/src/lib.rs")));
+    assert!(is_watched(root, Path::new("/workspace/Cargo.toml")));
+    assert!(is_watched(
+        root,
+        Path::new("/workspace/crates/core/Cargo.toml")
+    ));
+    assert!(is_watched(root, Path::new("/workspace/Cargo.lock")));
+
+    // Build output, git internals, other files and files outside of the workspace aren't.
+    assert!(!is_watched(
+        root,
+        Path::new("/workspace/target/debug/build/out.rs")
+    ));
+    assert!(!is_watched(root, Path::new("/workspace/.git/index")));
+    assert!(!is_watched(root, Path::new("/workspace/README.md")));
+    assert!(!is_watched(root, Path::new("/workspace/src/lib.rs.swp")));
+    assert!(!is_watched(root, Path::new("/elsewhere/src/lib.rs")));
+}
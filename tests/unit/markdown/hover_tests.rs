@@ -0,0 +1,64 @@
+use rust_analyzer_mcp::markdown::hover_markdown;
+use serde_json::json;
+
+#[test]
+fn test_hover_markup_content() {
+    let hover = json!({
+        "contents": {
+            "kind": "markdown",
+            "value": "```rust\nfn main()\n```"
+        },
+        "range": {"start": {"line": 0, "character": 3}, "end": {"line": 0, "character": 7}}
+    });
+
+    assert_eq!(
+        hover_markdown(&hover).as_deref(),
+        Some("```rust\nfn main()\n```")
+    );
+
+    let hover = json!({ "contents": { "kind": "plaintext", "value": "fn main()" } });
+    assert_eq!(hover_markdown(&hover).as_deref(), Some("fn main()"));
+}
+
+#[test]
+fn test_hover_marked_string() {
+    let hover = json!({ "contents": "Returns the *sum*." });
+    assert_eq!(
+        hover_markdown(&hover).as_deref(),
+        Some("Returns the *sum*.")
+    );
+
+    // With a language, it's a code block.
+    let hover = json!({ "contents": { "language": "rust", "value": "fn add(a: i32) -> i32" } });
+    assert_eq!(
+        hover_markdown(&hover).as_deref(),
+        Some("```rust\nfn add(a: i32) -> i32\n```")
+    );
+}
+
+#[test]
+fn test_hover_marked_strings() {
+    let hover = json!({
+        "contents": [
+            { "language": "rust", "value": "test_project::Calculator" },
+            { "language": "rust", "value": "pub fn add(&mut self, a: i32, b: i32) -> i32" },
+            "Adds `a` and `b`."
+        ]
+    });
+
+    assert_eq!(
+        hover_markdown(&hover).as_deref(),
+        Some(
+            "```rust\ntest_project::Calculator\n```\n\n\
+             ```rust\npub fn add(&mut self, a: i32, b: i32) -> i32\n```\n\n\
+             Adds `a` and `b`."
+        )
+    );
+}
+
+#[test]
+fn test_hover_without_contents() {
+    assert_eq!(hover_markdown(&json!(null)), None);
+    assert_eq!(hover_markdown(&json!({ "contents": [] })), None);
+    assert_eq!(hover_markdown(&json!({ "contents": 42 })), None);
+}
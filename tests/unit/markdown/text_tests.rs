@@ -0,0 +1,37 @@
+use rust_analyzer_mcp::markdown::markdown_to_text;
+
+#[test]
+fn test_hover_markdown_to_text() {
+    let markdown = "\
+```rust
+test_project::Calculator
+```
+
+```rust
+pub fn add(&mut self, a: i32, b: i32) -> i32
+```
+
+---
+
+Adds `a` and `b`, **keeping** the *result* in [`Calculator::value`](https://docs.rs/x).";
+
+    assert_eq!(
+        markdown_to_text(markdown),
+        "\
+test_project::Calculator
+
+pub fn add(&mut self, a: i32, b: i32) -> i32
+
+Adds a and b, keeping the result in Calculator::value."
+    );
+}
+
+#[test]
+fn test_code_kept_verbatim() {
+    let markdown = "# Safety\n\n```rust\nlet p: *const u8 = **x;\n```\n\n\n\nA __strong__ note";
+
+    assert_eq!(
+        markdown_to_text(markdown),
+        "Safety\n\nlet p: *const u8 = **x;\n\nA strong note"
+    );
+}
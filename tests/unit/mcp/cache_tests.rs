@@ -0,0 +1,76 @@
+use rust_analyzer_mcp::mcp::ResponseCache;
+use serde_json::json;
+use std::time::{Duration, SystemTime};
+
+#[test]
+fn test_cacheable_tools() {
+    assert!(ResponseCache::is_cacheable("rust_analyzer_hover"));
+    assert!(ResponseCache::is_cacheable("rust_analyzer_symbols"));
+    assert!(!ResponseCache::is_cacheable("rust_analyzer_diagnostics"));
+    assert!(!ResponseCache::is_cacheable("rust_analyzer_write_file"));
+}
+
+#[test]
+fn test_get_and_invalidate() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("lib.rs");
+    std::fs::write(&path, "fn main() {}").unwrap();
+
+    let mut cache = ResponseCache::new(10);
+    let args = json!({ "file_path": "lib.rs", "line": 0, "character": 3 });
+    assert_eq!(cache.get("rust_analyzer_hover", &args, 0), None);
+
+    cache.insert("rust_analyzer_hover", &args, &path, 0, json!("hover"));
+    assert_eq!(
+        cache.get("rust_analyzer_hover", &args, 0),
+        Some(json!("hover"))
+    );
+    // Other tools or arguments don't share the entry
+    assert_eq!(cache.get("rust_analyzer_definition", &args, 0), None);
+    let other_args = json!({ "file_path": "lib.rs", "line": 0, "character": 4 });
+    assert_eq!(cache.get("rust_analyzer_hover", &other_args, 0), None);
+
+    // A document change through the server invalidates the entry
+    assert_eq!(cache.get("rust_analyzer_hover", &args, 1), None);
+    assert!(cache.is_empty());
+
+    // So does a modification of the file on disk
+    cache.insert("rust_analyzer_hover", &args, &path, 1, json!("hover"));
+    let file = std::fs::File::options().write(true).open(&path).unwrap();
+    file.set_modified(SystemTime::now() + Duration::from_secs(60))
+        .unwrap();
+    assert_eq!(cache.get("rust_analyzer_hover", &args, 1), None);
+}
+
+#[test]
+fn test_lru_eviction() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("lib.rs");
+    std::fs::write(&path, "").unwrap();
+
+    let mut cache = ResponseCache::new(2);
+    let args = |line: u64| json!({ "file_path": "lib.rs", "line": line, "character": 0 });
+    cache.insert("rust_analyzer_hover", &args(0), &path, 0, json!(0));
+    cache.insert("rust_analyzer_hover", &args(1), &path, 0, json!(1));
+    // Use the first entry so that the second one is the least recently used
+    assert!(cache.get("rust_analyzer_hover", &args(0), 0).is_some());
+    cache.insert("rust_analyzer_hover", &args(2), &path, 0, json!(2));
+
+    assert_eq!(cache.len(), 2);
+    assert!(cache.get("rust_analyzer_hover", &args(0), 0).is_some());
+    assert!(cache.get("rust_analyzer_hover", &args(1), 0).is_none());
+    assert!(cache.get("rust_analyzer_hover", &args(2), 0).is_some());
+}
+
+#[test]
+fn test_disabled() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("lib.rs");
+    std::fs::write(&path, "").unwrap();
+
+    let mut cache = ResponseCache::new(0);
+    let args = json!({ "file_path": "lib.rs" });
+    cache.insert("rust_analyzer_symbols", &args, &path, 0, json!([]));
+
+    assert_eq!(cache.get("rust_analyzer_symbols", &args, 0), None);
+}
@@ -0,0 +1,50 @@
+use rust_analyzer_mcp::mcp::{get_prompt, get_prompts};
+use serde_json::json;
+
+#[test]
+fn test_prompt_arguments_substituted() {
+    let result = get_prompt(
+        "explain-symbol",
+        &json!({ "file_path": "src/lib.rs", "line": 12, "character": "4" }),
+    )
+    .unwrap();
+
+    let text = result["messages"][0]["content"]["text"].as_str().unwrap();
+    assert!(text.contains("line 12, character 4 of src/lib.rs"));
+    assert!(
+        !text.contains('{'),
+        "Unsubstituted placeholder in: {}",
+        text
+    );
+    assert_eq!(result["messages"][0]["role"], "user");
+}
+
+#[test]
+fn test_prompt_errors() {
+    assert!(get_prompt("no-such-prompt", &json!({})).is_err());
+    // `character` is missing.
+    assert!(get_prompt(
+        "explain-symbol",
+        &json!({ "file_path": "src/lib.rs", "line": 12 })
+    )
+    .is_err());
+
+    // Prompts without arguments don't need any.
+    assert!(get_prompt("fix-all-errors", &json!(null)).is_ok());
+}
+
+#[test]
+fn test_prompt_definitions() {
+    for prompt in get_prompts() {
+        let text = get_prompt(
+            &prompt.name,
+            &json!({ "file_path": "a", "line": 1, "character": 2 }),
+        )
+        .unwrap()["messages"][0]["content"]["text"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        // Every placeholder is a declared argument.
+        assert!(!text.contains('{'), "{}: {}", prompt.name, text);
+    }
+}
@@ -0,0 +1,51 @@
+use rust_analyzer_mcp::mcp::{list_resources, read_resource};
+use std::path::PathBuf;
+
+fn test_project() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("test-project")
+        .canonicalize()
+        .unwrap()
+}
+
+#[test]
+fn test_list_resources() {
+    let root = test_project();
+    let list = list_resources(&root);
+    let resources = list["resources"].as_array().unwrap();
+
+    let lib = resources
+        .iter()
+        .find(|resource| resource["name"] == "src/lib.rs")
+        .expect("lib.rs should be listed");
+    assert_eq!(
+        lib["uri"],
+        format!("file://{}", root.join("src/lib.rs").display())
+    );
+    assert_eq!(lib["mimeType"], "text/x-rust");
+    assert!(lib["size"].as_u64().unwrap() > 0);
+
+    // Only Rust sources are listed.
+    assert!(resources
+        .iter()
+        .all(|resource| resource["name"].as_str().unwrap().ends_with(".rs")));
+}
+
+#[test]
+fn test_read_resource() {
+    let root = test_project();
+    let uri = format!("file://{}", root.join("src/lib.rs").display());
+
+    let result = read_resource(&root, &uri).unwrap();
+    let contents = &result["contents"][0];
+    assert_eq!(contents["uri"], uri);
+    assert_eq!(contents["mimeType"], "text/x-rust");
+    assert_eq!(
+        contents["text"],
+        std::fs::read_to_string(root.join("src/lib.rs")).unwrap()
+    );
+
+    assert!(read_resource(&root, "https://example.com/lib.rs").is_err());
+    assert!(read_resource(&root, "file:///etc/passwd").is_err());
+    assert!(read_resource(&root, &format!("file://{}/src/missing.rs", root.display())).is_err());
+}
@@ -0,0 +1,49 @@
+use rust_analyzer_mcp::protocol::{ErrorKind, MCPError, ToolError};
+use serde_json::json;
+use std::collections::HashSet;
+
+#[test]
+fn test_tool_errors_are_categorized() {
+    let error: anyhow::Error = ToolError::new(ErrorKind::LspTimeout, "Request timeout").into();
+    let error = MCPError::from_tool_error(&error.context("Failed to get hover"));
+    assert_eq!(error.code, -32003);
+    assert_eq!(error.message, "Failed to get hover");
+    let data = error.data.unwrap();
+    assert_eq!(data["kind"], "LspTimeout");
+    assert!(data["hint"].is_string());
+    assert!(data.get("path").is_none());
+
+    let io_error = std::io::Error::new(std::io::ErrorKind::NotFound, "No such file");
+    let error = MCPError::from_tool_error(&ToolError::file_read("src/gone.rs", io_error).into());
+    assert_eq!(error.code, -32001);
+    assert_eq!(error.data.as_ref().unwrap()["kind"], "FileNotFound");
+    assert_eq!(error.data.as_ref().unwrap()["path"], "src/gone.rs");
+}
+
+#[test]
+fn test_uncategorized_errors() {
+    let error = MCPError::from_tool_error(&anyhow::anyhow!("Client not initialized"));
+    assert_eq!(error.code, -32603);
+    assert_eq!(error.data.unwrap()["kind"], "InternalError");
+
+    // I/O errors about missing files are recognized without a `ToolError`.
+    let io_error = std::io::Error::new(std::io::ErrorKind::NotFound, "No such file");
+    let error = MCPError::from_tool_error(&anyhow::Error::from(io_error).context("Failed"));
+    assert_eq!(error.data.unwrap()["kind"], json!("FileNotFound"));
+}
+
+#[test]
+fn test_error_codes_are_distinct() {
+    let kinds = [
+        ErrorKind::FileNotFound,
+        ErrorKind::PathEscapesWorkspace,
+        ErrorKind::LspTimeout,
+        ErrorKind::LspRequestFailed,
+        ErrorKind::RustAnalyzerCrashed,
+        ErrorKind::InvalidArguments,
+        ErrorKind::InternalError,
+    ];
+    let codes: HashSet<i32> = kinds.iter().map(|kind| kind.code()).collect();
+    assert_eq!(codes.len(), kinds.len());
+    assert!(codes.iter().all(|code| (-32999..=-32000).contains(code)));
+}
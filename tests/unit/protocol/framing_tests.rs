@@ -0,0 +1,122 @@
+use rust_analyzer_mcp::{
+    config::MAX_MESSAGE_SIZE,
+    protocol::{
+        framing::{read_framed_message, read_message, Framing},
+        MCPRequest,
+    },
+};
+
+const REQUEST: &str = r#"{"jsonrpc":"2.0","id":1,"method":"tools/list","params":{}}"#;
+
+async fn read_request(mut input: &[u8]) -> MCPRequest {
+    let message = read_message(&mut input).await.unwrap().unwrap();
+
+    serde_json::from_str(&message).unwrap()
+}
+
+fn assert_is_request(request: &MCPRequest) {
+    assert_eq!(request.jsonrpc, "2.0");
+    assert_eq!(request.id, Some(serde_json::json!(1)));
+    assert_eq!(request.method, "tools/list");
+    assert_eq!(request.params, Some(serde_json::json!({})));
+}
+
+#[tokio::test]
+async fn test_newline_delimited() {
+    let input = format!("{}\n", REQUEST);
+    assert_is_request(&read_request(input.as_bytes()).await);
+}
+
+#[tokio::test]
+async fn test_content_length_framed() {
+    let input = format!("Content-Length: {}\r\n\r\n{}", REQUEST.len(), REQUEST);
+    assert_is_request(&read_request(input.as_bytes()).await);
+}
+
+#[tokio::test]
+async fn test_content_length_with_other_headers() {
+    let input = format!(
+        "content-length: {}\r\nContent-Type: application/vscode-jsonrpc; charset=utf-8\r\n\r\n{}",
+        REQUEST.len(),
+        REQUEST
+    );
+    assert_is_request(&read_request(input.as_bytes()).await);
+}
+
+#[tokio::test]
+async fn test_mixed_framings_and_blank_lines() {
+    let input = format!(
+        "\n{}\r\n\r\nContent-Length: {}\r\n\r\n{}{}\n",
+        REQUEST,
+        REQUEST.len(),
+        REQUEST,
+        REQUEST
+    );
+    let mut input = input.as_bytes();
+
+    for _ in 0..3 {
+        let message = read_message(&mut input).await.unwrap().unwrap();
+        assert_eq!(message, REQUEST);
+    }
+    assert!(read_message(&mut input).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_invalid_content_length() {
+    let mut input = "Content-Length: many\r\n\r\n{}".as_bytes();
+    assert!(read_message(&mut input).await.is_err());
+}
+
+#[tokio::test]
+async fn test_oversized_content_length() {
+    // Rejected before reading, or allocating room for, the content.
+    let input = format!(
+        "Content-Length: {}\r\n\r\n{}",
+        MAX_MESSAGE_SIZE + 1,
+        REQUEST
+    );
+    let error = read_message(&mut input.as_bytes()).await.unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[tokio::test]
+async fn test_truncated_content() {
+    let input = format!("Content-Length: {}\r\n\r\n{}", REQUEST.len() + 10, REQUEST);
+    assert!(read_message(&mut input.as_bytes()).await.is_err());
+}
+
+#[tokio::test]
+async fn test_framing_reported() {
+    let input = format!(
+        "{}\nContent-Length: {}\r\n\r\n{}",
+        REQUEST,
+        REQUEST.len(),
+        REQUEST
+    );
+    let mut input = input.as_bytes();
+
+    for expected in [Framing::NewlineDelimited, Framing::ContentLength] {
+        let (message, framing) = read_framed_message(&mut input).await.unwrap().unwrap();
+        assert_eq!(message, REQUEST);
+        assert_eq!(framing, expected);
+    }
+}
+
+#[tokio::test]
+async fn test_frame_round_trip() {
+    assert_eq!(
+        Framing::ContentLength.frame("{}"),
+        "Content-Length: 2\r\n\r\n{}"
+    );
+    assert_eq!(Framing::NewlineDelimited.frame("{}"), "{}\n");
+
+    for framing in [Framing::NewlineDelimited, Framing::ContentLength] {
+        let framed = framing.frame(REQUEST);
+        let (message, read) = read_framed_message(&mut framed.as_bytes())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(message, REQUEST);
+        assert_eq!(read, framing);
+    }
+}
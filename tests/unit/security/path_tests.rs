@@ -0,0 +1,98 @@
+use rust_analyzer_mcp::security::{validate_edit_path, validate_workspace_path};
+use std::fs;
+use tempfile::TempDir;
+
+/// A workspace with `src/lib.rs`, next to a file outside of it.
+fn setup() -> (TempDir, std::path::PathBuf) {
+    let dir = TempDir::new().unwrap();
+    let workspace = dir.path().join("workspace");
+    fs::create_dir_all(workspace.join("src")).unwrap();
+    fs::write(workspace.join("src/lib.rs"), "").unwrap();
+    fs::write(dir.path().join("secret.txt"), "").unwrap();
+
+    (dir, workspace)
+}
+
+#[test]
+fn test_paths_inside_workspace() {
+    let (_dir, workspace) = setup();
+    let root = workspace.canonicalize().unwrap();
+
+    assert_eq!(
+        validate_workspace_path(&workspace, "src/lib.rs").unwrap(),
+        root.join("src/lib.rs")
+    );
+    assert_eq!(
+        validate_workspace_path(&workspace, "src/../src/lib.rs").unwrap(),
+        root.join("src/lib.rs")
+    );
+    let absolute = workspace.join("src/lib.rs");
+    assert_eq!(
+        validate_workspace_path(&workspace, absolute.to_str().unwrap()).unwrap(),
+        root.join("src/lib.rs")
+    );
+    // Files and directories that don't exist yet
+    assert_eq!(
+        validate_workspace_path(&workspace, "src/util/mod.rs").unwrap(),
+        root.join("src/util/mod.rs")
+    );
+}
+
+#[test]
+fn test_path_traversal_is_rejected() {
+    let (dir, workspace) = setup();
+
+    for path in [
+        "../secret.txt",
+        "src/../../secret.txt",
+        "../../../../../../etc/passwd",
+        "/etc/passwd",
+        // Doesn't exist, so it can't be canonicalized
+        "src/missing/../../../secret.txt",
+    ] {
+        assert!(
+            validate_workspace_path(&workspace, path).is_err(),
+            "{} should be rejected",
+            path
+        );
+    }
+
+    let outside = dir.path().join("secret.txt");
+    assert!(validate_workspace_path(&workspace, outside.to_str().unwrap()).is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_symlink_out_of_workspace_is_rejected() {
+    let (dir, workspace) = setup();
+    std::os::unix::fs::symlink(dir.path().join("secret.txt"), workspace.join("link.txt")).unwrap();
+    std::os::unix::fs::symlink(dir.path(), workspace.join("outside")).unwrap();
+
+    assert!(validate_workspace_path(&workspace, "link.txt").is_err());
+    assert!(validate_workspace_path(&workspace, "outside/secret.txt").is_err());
+    assert!(validate_workspace_path(&workspace, "outside/new.rs").is_err());
+}
+
+#[test]
+fn test_edits_to_build_output_are_rejected() {
+    let (dir, workspace) = setup();
+    let root = workspace.canonicalize().unwrap();
+    fs::create_dir_all(workspace.join("target/debug/build")).unwrap();
+
+    assert_eq!(
+        validate_edit_path(&workspace, "src/lib.rs").unwrap(),
+        root.join("src/lib.rs")
+    );
+    // Only the workspace's own `target` directory is build output.
+    assert_eq!(
+        validate_edit_path(&workspace, "src/target/mod.rs").unwrap(),
+        root.join("src/target/mod.rs")
+    );
+
+    let err = validate_edit_path(&workspace, "target/debug/build/out.rs").unwrap_err();
+    assert!(err.to_string().contains("build output"), "{}", err);
+    let absolute = workspace.join("target/debug/build/out.rs");
+    assert!(validate_edit_path(&workspace, absolute.to_str().unwrap()).is_err());
+    let outside = dir.path().join("secret.txt");
+    assert!(validate_edit_path(&workspace, outside.to_str().unwrap()).is_err());
+}
@@ -0,0 +1,122 @@
+use rust_analyzer_mcp::sources::{context_excerpt, external_crate, source_excerpt, ExternalCrate};
+use std::path::Path;
+
+#[test]
+fn test_registry_crates() {
+    let path = Path::new(
+        "/home/user/.cargo/registry/src/index.crates.io-6f17d22bba15001f/serde-1.0.200/src/de/mod.rs",
+    );
+    assert_eq!(
+        external_crate(path),
+        Some(ExternalCrate {
+            name: "serde".to_string(),
+            version: Some("1.0.200".to_string()),
+            source: "registry",
+            path: "src/de/mod.rs".to_string(),
+        })
+    );
+
+    // Crate names can contain dashes and digits, and versions can have a pre-release.
+    let path = Path::new("/cargo/registry/src/github.com-1ecc6299db9ec823/md-5-0.10.6/src/lib.rs");
+    let krate = external_crate(path).unwrap();
+    assert_eq!(krate.name, "md-5");
+    assert_eq!(krate.version.as_deref(), Some("0.10.6"));
+
+    let path = Path::new("/cargo/registry/src/index/tokio-util-0.7.0-rc.1/src/lib.rs");
+    let krate = external_crate(path).unwrap();
+    assert_eq!(krate.name, "tokio-util");
+    assert_eq!(krate.version.as_deref(), Some("0.7.0-rc.1"));
+}
+
+#[test]
+fn test_git_and_sysroot_crates() {
+    let path =
+        Path::new("/home/user/.cargo/git/checkouts/hyper-1a2b3c4d5e6f7a8b/0f1e2d3/src/lib.rs");
+    assert_eq!(
+        external_crate(path),
+        Some(ExternalCrate {
+            name: "hyper".to_string(),
+            version: None,
+            source: "git",
+            path: "src/lib.rs".to_string(),
+        })
+    );
+
+    let path = Path::new(
+        "/home/user/.rustup/toolchains/stable-x86_64-unknown-linux-gnu/lib/rustlib/src/rust/library/\
+         core/src/option.rs",
+    );
+    assert_eq!(
+        external_crate(path),
+        Some(ExternalCrate {
+            name: "core".to_string(),
+            version: None,
+            source: "sysroot",
+            path: "src/option.rs".to_string(),
+        })
+    );
+
+    assert_eq!(
+        external_crate(Path::new("/home/user/other-project/src/lib.rs")),
+        None
+    );
+}
+
+#[test]
+fn test_source_excerpt() {
+    let content: String = (1..=30).map(|i| format!("line {}\n", i)).collect();
+
+    assert_eq!(
+        source_excerpt(&content, 4, 5).unwrap(),
+        "3 | line 3\n4 | line 4\n5 | line 5\n6 | line 6\n7 | line 7\n8 | line 8"
+    );
+
+    // Context is clamped to the content, and long ranges are cut off.
+    assert_eq!(source_excerpt(&content, 0, 0).unwrap().lines().count(), 3);
+    assert_eq!(source_excerpt(&content, 29, 29).unwrap().lines().count(), 3);
+    let excerpt = source_excerpt(&content, 10, 25).unwrap();
+    assert_eq!(excerpt.lines().count(), 12);
+    assert!(excerpt.starts_with(" 9 | line 9"));
+
+    assert_eq!(source_excerpt(&content, 30, 30), None);
+}
+
+#[test]
+fn test_context_excerpt() {
+    let content: String = (1..=30).map(|i| format!("line {}\n", i)).collect();
+
+    assert_eq!(context_excerpt(&content, 4, 4, 0).unwrap(), "5 | line 5");
+    assert_eq!(
+        context_excerpt(&content, 4, 5, 1).unwrap(),
+        "4 | line 4\n5 | line 5\n6 | line 6\n7 | line 7"
+    );
+
+    // Unlike source excerpts, long ranges aren't cut off.
+    assert_eq!(
+        context_excerpt(&content, 5, 20, 3).unwrap().lines().count(),
+        22
+    );
+    assert_eq!(
+        context_excerpt(&content, 28, 29, 5)
+            .unwrap()
+            .lines()
+            .count(),
+        7
+    );
+    assert_eq!(context_excerpt(&content, 30, 30, 2), None);
+}
+
+#[test]
+fn test_missing_sources_note() {
+    let path = Path::new("/home/user/.cargo/registry/src/index/serde-1.0.200/src/lib.rs");
+    let note = external_crate(path).unwrap().missing_sources_note();
+    assert!(
+        note.contains("serde") && note.contains("cargo fetch"),
+        "{}",
+        note
+    );
+
+    let path = Path::new("/toolchain/lib/rustlib/src/rust/library/alloc/src/string.rs");
+    let note = external_crate(path).unwrap().missing_sources_note();
+    assert!(note.contains("rust-src"), "{}", note);
+}
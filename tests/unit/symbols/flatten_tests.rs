@@ -0,0 +1,132 @@
+use rust_analyzer_mcp::symbols::flatten_symbols;
+use serde_json::{json, Value};
+
+fn symbol(name: &str, kind: u64, line: u64, children: Value) -> Value {
+    let range = json!({
+        "start": { "line": line, "character": 0 },
+        "end": { "line": line, "character": 10 }
+    });
+    json!({
+        "name": name,
+        "kind": kind,
+        "range": range,
+        "selectionRange": range,
+        "children": children
+    })
+}
+
+fn paths(flat: &[Value]) -> Vec<&str> {
+    flat.iter().filter_map(|s| s["path"].as_str()).collect()
+}
+
+#[test]
+fn test_flatten_document_symbols() {
+    let symbols = json!([
+        symbol("main", 12, 0, json!([])),
+        symbol(
+            "Calculator",
+            23,
+            17,
+            json!([symbol("value", 8, 18, json!([]))])
+        ),
+        symbol(
+            "impl Calculator",
+            19,
+            21,
+            json!([
+                symbol("new", 6, 22, json!([])),
+                symbol("add", 6, 26, json!([]))
+            ])
+        ),
+        symbol(
+            "tests",
+            2,
+            40,
+            json!([symbol("test_greet", 12, 44, json!([]))])
+        ),
+    ]);
+
+    let flat = flatten_symbols(&symbols);
+
+    assert_eq!(
+        paths(&flat),
+        vec![
+            "main",
+            "Calculator",
+            "Calculator::value",
+            "Calculator::new",
+            "Calculator::add",
+            "tests",
+            "tests::test_greet"
+        ]
+    );
+
+    let add = flat
+        .iter()
+        .find(|s| s["path"] == "Calculator::add")
+        .unwrap();
+    assert_eq!(add["name"], "add");
+    assert_eq!(add["container"], "Calculator");
+    assert_eq!(add["line"], 26);
+    assert_eq!(add["character"], 0);
+    assert_eq!(add["kind"], 6);
+
+    let main = &flat[0];
+    assert!(main["container"].is_null());
+}
+
+#[test]
+fn test_flatten_trait_and_generic_impls() {
+    let symbols = json!([
+        symbol(
+            "impl<T: Clone> Display for Wrapper<T>",
+            19,
+            0,
+            json!([symbol("fmt", 6, 1, json!([]))])
+        ),
+        symbol(
+            "impl Default for Config",
+            19,
+            5,
+            json!([symbol("default", 6, 6, json!([]))])
+        ),
+    ]);
+
+    assert_eq!(
+        paths(&flatten_symbols(&symbols)),
+        vec!["Wrapper<T>::fmt", "Config::default"]
+    );
+}
+
+#[test]
+fn test_flatten_symbol_information() {
+    let location = |line: u64| {
+        json!({
+            "uri": "file:///project/src/main.rs",
+            "range": {
+                "start": { "line": line, "character": 0 },
+                "end": { "line": line, "character": 10 }
+            }
+        })
+    };
+    let symbols = json!([
+        { "name": "Calculator", "kind": 23, "location": location(17) },
+        { "name": "impl Calculator", "kind": 19, "location": location(21) },
+        {
+            "name": "add",
+            "kind": 6,
+            "location": location(26),
+            "containerName": "impl Calculator"
+        },
+    ]);
+
+    let flat = flatten_symbols(&symbols);
+
+    assert_eq!(paths(&flat), vec!["Calculator", "Calculator::add"]);
+    assert_eq!(flat[1]["line"], 26);
+}
+
+#[test]
+fn test_flatten_null() {
+    assert!(flatten_symbols(&Value::Null).is_empty());
+}
@@ -0,0 +1,81 @@
+use rust_analyzer_mcp::symbols::{derive_at, impl_header_at, parse_impl_header};
+
+fn trait_impl(trait_name: &str, self_ty: &str) -> Option<(Option<String>, String)> {
+    Some((Some(trait_name.to_string()), self_ty.to_string()))
+}
+
+fn inherent_impl(self_ty: &str) -> Option<(Option<String>, String)> {
+    Some((None, self_ty.to_string()))
+}
+
+#[test]
+fn test_parse_impl_header() {
+    assert_eq!(parse_impl_header("impl Point"), inherent_impl("Point"));
+    assert_eq!(
+        parse_impl_header("impl fmt::Display for Point {"),
+        trait_impl("fmt::Display", "Point")
+    );
+    assert_eq!(
+        parse_impl_header("impl<T: Clone> From<Vec<T>> for Wrapper<T>\nwhere\n    T: Send,\n{"),
+        trait_impl("From<Vec<T>>", "Wrapper<T>")
+    );
+    assert_eq!(
+        parse_impl_header("unsafe impl Send for Handle"),
+        trait_impl("Send", "Handle")
+    );
+    assert_eq!(
+        parse_impl_header("impl !Sync for Handle"),
+        trait_impl("!Sync", "Handle")
+    );
+    assert_eq!(
+        parse_impl_header("impl<T> Cache<T> where T: Hash {"),
+        inherent_impl("Cache<T>")
+    );
+    assert_eq!(parse_impl_header("fn implement()"), None);
+    assert_eq!(parse_impl_header("struct Point"), None);
+}
+
+#[test]
+fn test_impl_header_at() {
+    let content =
+        "struct Point;\n\nimpl Point {\n    fn new() {}\n}\n\nimpl<T> fmt::Display\n    for \
+                   Wrapper<T>\nwhere\n    T: Clone,\n{\n}\n\nunsafe impl Send for Point {}\n";
+
+    let offset = content.find("Point {").unwrap();
+    assert_eq!(impl_header_at(content, offset), Some("impl Point"));
+
+    let offset = content.find("Wrapper").unwrap();
+    assert_eq!(
+        impl_header_at(content, offset),
+        Some("impl<T> fmt::Display\n    for Wrapper<T>\nwhere\n    T: Clone,")
+    );
+
+    let offset = content.rfind("Point").unwrap();
+    assert_eq!(
+        impl_header_at(content, offset),
+        Some("unsafe impl Send for Point")
+    );
+
+    // Not in an impl header, even though there's an impl before.
+    let offset = content.find("new").unwrap();
+    assert_eq!(impl_header_at(content, offset), None);
+    assert_eq!(impl_header_at(content, 7), None);
+}
+
+#[test]
+fn test_derive_at() {
+    let content = "#[derive(Debug, Clone)]\n#[serde(rename_all = \"camelCase\")]\npub struct \
+                   Config {\n    name: String,\n}\n";
+
+    let start = content.find("Clone").unwrap();
+    assert_eq!(
+        derive_at(content, start, start + "Clone".len()),
+        Some(("Clone".to_string(), "Config".to_string()))
+    );
+
+    // Not in a derive attribute.
+    let start = content.find("rename_all").unwrap();
+    assert_eq!(derive_at(content, start, start + "rename_all".len()), None);
+    let start = content.find("name:").unwrap();
+    assert_eq!(derive_at(content, start, start + "name".len()), None);
+}
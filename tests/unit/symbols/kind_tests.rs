@@ -0,0 +1,103 @@
+use rust_analyzer_mcp::symbols::{
+    filter_symbols_by_kind, flatten_symbols, name_symbol_kinds, parse_symbol_kind, symbol_kind_name,
+};
+use serde_json::{json, Value};
+
+fn symbol(name: &str, kind: u64, children: Value) -> Value {
+    let range = json!({
+        "start": { "line": 0, "character": 0 },
+        "end": { "line": 0, "character": 10 }
+    });
+    json!({
+        "name": name,
+        "kind": kind,
+        "range": range,
+        "selectionRange": range,
+        "children": children
+    })
+}
+
+fn names(symbols: &Value) -> Vec<&str> {
+    symbols
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|s| s["name"].as_str())
+        .collect()
+}
+
+#[test]
+fn test_symbol_kind_names() {
+    assert_eq!(symbol_kind_name(1), Some("file"));
+    assert_eq!(symbol_kind_name(23), Some("struct"));
+    assert_eq!(symbol_kind_name(26), Some("type_parameter"));
+    assert_eq!(symbol_kind_name(0), None);
+    assert_eq!(symbol_kind_name(27), None);
+
+    assert_eq!(parse_symbol_kind("struct"), Some(23));
+    assert_eq!(parse_symbol_kind("enum_member"), Some(22));
+    assert_eq!(parse_symbol_kind("trait"), Some(11));
+    assert_eq!(parse_symbol_kind("Struct"), None);
+    for kind in 1..=26 {
+        assert_eq!(
+            parse_symbol_kind(symbol_kind_name(kind).unwrap()),
+            Some(kind)
+        );
+    }
+}
+
+#[test]
+fn test_filter_symbols_by_kind() {
+    let symbols = json!([
+        symbol("main", 12, json!([])),
+        symbol("Shape", 10, json!([symbol("Circle", 22, json!([]))])),
+        symbol(
+            "geometry",
+            2,
+            json!([
+                symbol("Point", 23, json!([symbol("x", 8, json!([]))])),
+                symbol("distance", 12, json!([]))
+            ])
+        ),
+        symbol("helpers", 2, json!([symbol("helper", 12, json!([]))]))
+    ]);
+    let kinds = [
+        parse_symbol_kind("struct").unwrap(),
+        parse_symbol_kind("enum").unwrap(),
+    ];
+
+    // Modules are kept for the structs in them, with only the matching children.
+    let filtered = filter_symbols_by_kind(&symbols, &kinds);
+    assert_eq!(names(&filtered), vec!["Shape", "geometry"]);
+    assert_eq!(names(&filtered[0]["children"]), Vec::<&str>::new());
+    assert_eq!(names(&filtered[1]["children"]), vec!["Point"]);
+    assert_eq!(
+        names(&filtered[1]["children"][0]["children"]),
+        Vec::<&str>::new()
+    );
+
+    // Flat lists are filtered as they are.
+    let flat = json!(flatten_symbols(&symbols));
+    let filtered = filter_symbols_by_kind(&flat, &[parse_symbol_kind("function").unwrap()]);
+    assert_eq!(names(&filtered), vec!["main", "distance", "helper"]);
+}
+
+#[test]
+fn test_name_symbol_kinds() {
+    let mut symbols = json!([
+        symbol("Calculator", 23, json!([symbol("add", 6, json!([]))])),
+        symbol("MAX", 14, json!([])),
+        symbol("Unknown", 99, json!([]))
+    ]);
+    name_symbol_kinds(&mut symbols);
+
+    assert_eq!(symbols[0]["kind"], "struct");
+    assert_eq!(symbols[0]["children"][0]["kind"], "method");
+    assert_eq!(symbols[1]["kind"], "constant");
+    assert_eq!(symbols[2]["kind"], 99);
+
+    // Flat lists too.
+    let mut flat = json!(flatten_symbols(&json!([symbol("process", 12, json!([]))])));
+    name_symbol_kinds(&mut flat);
+    assert_eq!(flat[0]["kind"], "function");
+}
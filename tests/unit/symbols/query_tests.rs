@@ -0,0 +1,62 @@
+use rust_analyzer_mcp::symbols::{MatchQuality, SymbolQuery};
+
+#[test]
+fn test_text_query() {
+    let query = SymbolQuery::parse("Calc#").unwrap();
+    assert_eq!(query.lsp_query(), "Calc#");
+
+    assert_eq!(query.match_quality("Calc"), Some(MatchQuality::Exact));
+    assert_eq!(
+        query.match_quality("Calculator"),
+        Some(MatchQuality::Prefix)
+    );
+    assert_eq!(query.match_quality("calculate"), Some(MatchQuality::Prefix));
+    assert_eq!(
+        query.match_quality("new_calc"),
+        Some(MatchQuality::Contains)
+    );
+    assert_eq!(
+        query.match_quality("CustomAllocator"),
+        Some(MatchQuality::Fuzzy)
+    );
+}
+
+#[test]
+fn test_regex_query() {
+    let query = SymbolQuery::parse("/^test_/").unwrap();
+    assert_eq!(query.lsp_query(), "#");
+    assert_eq!(query.match_quality("test_add"), Some(MatchQuality::Prefix));
+    assert_eq!(query.match_quality("add"), None);
+
+    let query = SymbolQuery::parse("/Trait$/").unwrap();
+    assert_eq!(query.match_quality("Trait"), Some(MatchQuality::Exact));
+    assert_eq!(query.match_quality("MyTrait"), Some(MatchQuality::Contains));
+    assert_eq!(query.match_quality("Traits"), None);
+
+    let query = SymbolQuery::parse("/^/").unwrap();
+    assert_eq!(query.match_quality("anything"), Some(MatchQuality::Fuzzy));
+
+    assert!(SymbolQuery::parse("/(unclosed/").is_err());
+    // A lone slash is text.
+    assert!(matches!(SymbolQuery::parse("/"), Ok(SymbolQuery::Text(_))));
+}
+
+#[test]
+fn test_match_quality_order() {
+    let mut qualities = vec![
+        MatchQuality::Fuzzy,
+        MatchQuality::Contains,
+        MatchQuality::Exact,
+        MatchQuality::Prefix,
+    ];
+    qualities.sort();
+    assert_eq!(
+        qualities,
+        vec![
+            MatchQuality::Exact,
+            MatchQuality::Prefix,
+            MatchQuality::Contains,
+            MatchQuality::Fuzzy
+        ]
+    );
+}
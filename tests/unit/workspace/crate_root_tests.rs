@@ -0,0 +1,94 @@
+use rust_analyzer_mcp::workspace::is_crate_root;
+use std::{fs, path::Path};
+use tempfile::TempDir;
+
+fn write(root: &Path, path: &str, content: &str) {
+    let path = root.join(path);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, content).unwrap();
+}
+
+#[test]
+fn test_default_targets() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path();
+    write(
+        root,
+        "Cargo.toml",
+        "[package]\nname = \"app\"\nversion = \"0.1.0\"\n",
+    );
+    for file in [
+        "src/lib.rs",
+        "src/main.rs",
+        "src/bin/tool.rs",
+        "src/bin/other/main.rs",
+        "examples/demo.rs",
+        "tests/it.rs",
+        "benches/bench/main.rs",
+        "build.rs",
+    ] {
+        write(root, file, "");
+        assert!(is_crate_root(&root.join(file)), "{} is a crate root", file);
+    }
+
+    for file in [
+        "src/utils.rs",
+        "src/bin/other/helpers.rs",
+        "tests/common/mod.rs",
+    ] {
+        write(root, file, "");
+        assert!(
+            !is_crate_root(&root.join(file)),
+            "{} isn't a crate root",
+            file
+        );
+    }
+}
+
+#[test]
+fn test_declared_targets() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path();
+    write(
+        root,
+        "Cargo.toml",
+        r#"
+        [package]
+        name = "app"
+        version = "0.1.0"
+        build = "tools/build.rs"
+
+        [lib]
+        path = "lib/app.rs"
+
+        [[bin]]
+        name = "cli"
+        path = "./cli/main.rs"
+        "#,
+    );
+    for file in ["lib/app.rs", "cli/main.rs", "tools/build.rs"] {
+        write(root, file, "");
+        assert!(is_crate_root(&root.join(file)), "{} is a crate root", file);
+    }
+    assert!(!is_crate_root(&root.join("lib/other.rs")));
+}
+
+#[test]
+fn test_workspace_members() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path();
+    write(
+        root,
+        "Cargo.toml",
+        "[workspace]\nmembers = [\"crates/*\"]\n",
+    );
+    write(
+        root,
+        "crates/core/Cargo.toml",
+        "[package]\nname = \"core\"\nversion = \"0.1.0\"\n",
+    );
+
+    // Targets are relative to the package, not to the workspace.
+    assert!(is_crate_root(&root.join("crates/core/src/lib.rs")));
+    assert!(!is_crate_root(&root.join("src/lib.rs")));
+}
@@ -0,0 +1,172 @@
+use rust_analyzer_mcp::workspace::{workspace_info, workspace_members};
+use serde_json::json;
+use std::{fs, path::Path};
+use tempfile::TempDir;
+
+fn write(root: &Path, path: &str, content: &str) {
+    let path = root.join(path);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, content).unwrap();
+}
+
+#[test]
+fn test_single_package() {
+    let dir = TempDir::new().unwrap();
+    write(
+        dir.path(),
+        "Cargo.toml",
+        r#"
+        [package]
+        name = "app"
+        version = "0.1.0"
+        edition = "2021"
+
+        [dependencies]
+        anyhow = "1.0"
+        tokio = { version = "1", features = ["full"] }
+        local = { path = "../local" }
+        "#,
+    );
+    write(
+        dir.path(),
+        "rust-toolchain.toml",
+        "[toolchain]\nchannel = \"1.80.0\"\n",
+    );
+
+    let info = workspace_info(dir.path()).unwrap();
+
+    assert_eq!(info["rust_edition"], "2021");
+    assert_eq!(info["toolchain"], "1.80.0");
+    let members = info["members"].as_array().unwrap();
+    assert_eq!(members.len(), 1);
+    assert_eq!(members[0]["name"], "app");
+    assert_eq!(members[0]["version"], "0.1.0");
+    assert_eq!(members[0]["path"], ".");
+    assert_eq!(
+        members[0]["dependencies"],
+        json!([
+            { "name": "anyhow", "version": "1.0", "features": [] },
+            { "name": "local", "version": null, "features": [] },
+            { "name": "tokio", "version": "1", "features": ["full"] }
+        ])
+    );
+}
+
+#[test]
+fn test_workspace_with_inherited_fields() {
+    let dir = TempDir::new().unwrap();
+    write(
+        dir.path(),
+        "Cargo.toml",
+        r#"
+        [workspace]
+        members = ["crates/*"]
+        exclude = ["crates/ignored"]
+
+        [workspace.package]
+        version = "2.0.0"
+        edition = "2021"
+
+        [workspace.dependencies]
+        serde = { version = "1.0", features = ["derive"] }
+        log = "0.4"
+        "#,
+    );
+    write(
+        dir.path(),
+        "crates/core/Cargo.toml",
+        r#"
+        [package]
+        name = "core"
+        version.workspace = true
+        edition.workspace = true
+
+        [dependencies]
+        serde = { workspace = true, features = ["rc"] }
+        log.workspace = true
+        "#,
+    );
+    write(
+        dir.path(),
+        "crates/old/Cargo.toml",
+        "[package]\nname = \"old\"\nversion = \"0.1.0\"\n",
+    );
+    write(
+        dir.path(),
+        "crates/ignored/Cargo.toml",
+        "[package]\nname = \"ignored\"\nversion = \"0.1.0\"\n",
+    );
+    write(dir.path(), "rust-toolchain", "nightly-2024-08-01\n");
+
+    let info = workspace_info(dir.path()).unwrap();
+
+    assert_eq!(info["rust_edition"], "2021");
+    assert_eq!(info["toolchain"], "nightly-2024-08-01");
+    let mut members = info["members"].as_array().unwrap().clone();
+    members.sort_by_key(|m| m["name"].as_str().unwrap().to_string());
+    assert_eq!(members.len(), 2, "Got: {:?}", members);
+
+    assert_eq!(members[0]["name"], "core");
+    assert_eq!(members[0]["version"], "2.0.0");
+    assert_eq!(members[0]["edition"], "2021");
+    assert_eq!(members[0]["path"], "crates/core");
+    assert_eq!(
+        members[0]["dependencies"],
+        json!([
+            { "name": "log", "version": "0.4", "features": [] },
+            { "name": "serde", "version": "1.0", "features": ["rc", "derive"] }
+        ])
+    );
+
+    // Cargo's default edition
+    assert_eq!(members[1]["name"], "old");
+    assert_eq!(members[1]["edition"], "2015");
+}
+
+#[test]
+fn test_workspace_members() {
+    let dir = TempDir::new().unwrap();
+    write(
+        dir.path(),
+        "Cargo.toml",
+        r#"
+        [workspace]
+        members = ["app", "crates/*"]
+
+        [workspace.package]
+        edition = "2021"
+        "#,
+    );
+    write(
+        dir.path(),
+        "app/Cargo.toml",
+        "[package]\nname = \"app\"\nversion = \"1.0.0\"\nedition.workspace = true\n",
+    );
+    write(
+        dir.path(),
+        "crates/lib/Cargo.toml",
+        "[package]\nname = \"lib\"\nversion = \"0.2.0\"\nedition = \"2018\"\n\n\
+         [dependencies]\nlog = \"0.4\"\n",
+    );
+
+    let members = workspace_members(dir.path()).unwrap();
+
+    assert_eq!(members["total"], 2);
+    assert_eq!(
+        members["workspace_root"],
+        dir.path().canonicalize().unwrap().display().to_string()
+    );
+    assert_eq!(
+        members["members"],
+        json!([
+            { "name": "app", "version": "1.0.0", "edition": "2021", "path": "app" },
+            { "name": "lib", "version": "0.2.0", "edition": "2018", "path": "crates/lib" }
+        ])
+    );
+}
+
+#[test]
+fn test_missing_manifest() {
+    let dir = TempDir::new().unwrap();
+    assert!(workspace_info(dir.path()).is_err());
+}
@@ -1,6 +1,61 @@
 mod unit {
+    mod code_lens {
+        mod format_tests;
+    }
+    mod completion {
+        mod structured_tests;
+    }
+    mod config {
+        mod env_tests;
+        mod file_tests;
+        mod init_options_tests;
+        mod rust_analyzer_path_tests;
+        mod timeouts_tests;
+    }
+    mod diagnostics {
+        mod explain_tests;
+        mod format_tests;
+        mod rustc_format_tests;
+        mod sarif_tests;
+    }
+    mod edits {
+        mod position_tests;
+    }
+    mod lsp {
+        mod semantic_tokens_tests;
+        mod status_tests;
+        mod version_tests;
+        mod watcher_tests;
+    }
+    mod markdown {
+        mod hover_tests;
+        mod text_tests;
+    }
+    mod mcp {
+        mod cache_tests;
+        mod prompts_tests;
+        mod resources_tests;
+    }
     mod protocol {
+        mod error_tests;
+        mod framing_tests;
         mod request_tests;
         mod tool_tests;
     }
+    mod security {
+        mod path_tests;
+    }
+    mod sources {
+        mod external_tests;
+    }
+    mod symbols {
+        mod flatten_tests;
+        mod impl_tests;
+        mod kind_tests;
+        mod query_tests;
+    }
+    mod workspace {
+        mod crate_root_tests;
+        mod info_tests;
+    }
 }